@@ -1,62 +1,39 @@
 pragma circom 2.0.0;
 
-template AgeVerification() {
-    // Private inputs
-    signal private input age;
-    signal private input threshold;
-
-    // Public inputs
-    signal input publicAgeHash;
-    signal input publicThreshold;
-
-    // Output
-    signal output isAboveThreshold;
-
-    // Verify that the age is above the threshold
-    component isGreater = GreaterThan(32);
-    isGreater.in[0] <== age;
-    isGreater.in[1] <== threshold;
-    isAboveThreshold <== isGreater.out;
-}
-
-template GreaterThan(n) {
-    signal input in[2];
-    signal output out;
-
-    component lt = LessThan(n);
-    lt.in[0] <== in[1];
-    lt.in[1] <== in[0];
-    out <== lt.out;
-}
-
-template LessThan(n) {
-    assert(n <= 252);
-    signal input in[2];
-    signal output out;
-
-    signal n2p[n];
-    n2p[0] <== 1;
-    for (var i = 1; i < n; i++) {
-        n2p[i] <== n2p[i-1] * 2;
-    }
-
-    signal difference[n];
-    difference[0] <== in[0] - in[1];
-    for (var i = 1; i < n; i++) {
-        difference[i] <== difference[i-1] * difference[i-1];
+include "circomlib/circuits/poseidon.circom";
+include "circomlib/circuits/comparators.circom";
+
+// Proves that the age claim of a credential is at least `threshold`, without revealing it.
+//
+// The credential's on-chain commitment is Poseidon(h_1, ..., h_n) over its claim hashes, each
+// h_i = Poseidon(salt_i, value_i), with the age claim first. The circuit recomputes it from the
+// private age claim and the other claim hashes and exposes it, so the program can check the proof
+// is about the credential the presentation references.
+//
+// Public signals, in order: threshold (the request's predicate input), commitment.
+template AgeVerification(nClaims) {
+    signal input age;
+    signal input salt;
+    signal input otherClaims[nClaims - 1];
+
+    signal input threshold;
+    signal input commitment;
+
+    component isOldEnough = GreaterEqThan(32);
+    isOldEnough.in[0] <== age;
+    isOldEnough.in[1] <== threshold;
+    isOldEnough.out === 1;
+
+    component ageClaim = Poseidon(2);
+    ageClaim.inputs[0] <== salt;
+    ageClaim.inputs[1] <== age;
+
+    component claims = Poseidon(nClaims);
+    claims.inputs[0] <== ageClaim.out;
+    for (var i = 1; i < nClaims; i++) {
+        claims.inputs[i] <== otherClaims[i - 1];
     }
-
-    component isNegative = IsNegative();
-    isNegative.in <== difference[n-1];
-    out <== isNegative.out;
-}
-
-template IsNegative() {
-    signal input in;
-    signal output out;
-
-    signal inv <-- in!=0 ? 1/in : 0;
-    out <== -in * inv;
+    commitment === claims.out;
 }
 
-component main = AgeVerification(); 
\ No newline at end of file
+component main {public [threshold, commitment]} = AgeVerification(2);
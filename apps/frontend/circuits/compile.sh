@@ -4,7 +4,7 @@
 mkdir -p build
 
 # Compile the circuit
-circom age_verification.circom --r1cs --wasm --sym -o build
+circom age_verification.circom --r1cs --wasm --sym -l ../node_modules -o build
 
 # Generate the witness
 node build/age_verification_js/generate_witness.js build/age_verification_js/age_verification.wasm input.json build/witness.wtns
@@ -1,10 +1,12 @@
 import { groth16 } from 'snarkjs';
 
+// Claim hashes and the commitment are decimal field elements; see circuits/age_verification.circom
 export interface AgeVerificationInput {
     age: number;
+    salt: string;
+    otherClaims: string[];
     threshold: number;
-    publicAgeHash: string;
-    publicThreshold: number;
+    commitment: string;
 }
 
 export interface Proof {
@@ -14,11 +16,8 @@ export interface Proof {
     protocol: string;
 }
 
-export interface PublicSignals {
-    isAboveThreshold: string;
-    publicAgeHash: string;
-    publicThreshold: string;
-}
+// snarkjs returns the public signals in circuit order: [threshold, commitment]
+export type PublicSignals = string[];
 
 export async function generateAgeVerificationProof(
     input: AgeVerificationInput
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use idenclave_core::AccountLayout;
+
+/// An accreditor's statement that `issuer` may issue credentials of `schema`, stored in the PDA
+/// `[b"accreditation", accreditor, issuer, schema]`. Field order follows
+/// `idenclave_core::Accreditation`.
+#[account]
+#[derive(Default)]
+pub struct Accreditation {
+    pub is_initialized: bool, // 1
+    pub revoked: bool,        // 1
+    pub accreditor: Pubkey,   // 32
+    pub issuer: Pubkey,       // 32
+    pub schema: [u8; 32],     // 32
+    pub expires_at: u64,      // 8
+    pub delegation_depth: u8, // 1, levels below the issuer that may still be accredited for the schema
+}
+
+impl Accreditation {
+    pub const SEED: &'static [u8] = idenclave_core::Accreditation::SEED;
+    pub const LEN: usize = idenclave_core::Accreditation::LEN;
+
+    pub fn to_core(&self) -> idenclave_core::Accreditation {
+        idenclave_core::Accreditation {
+            is_initialized: self.is_initialized,
+            revoked: self.revoked,
+            accreditor: self.accreditor.to_bytes(),
+            issuer: self.issuer.to_bytes(),
+            schema: self.schema,
+            expires_at: self.expires_at,
+            delegation_depth: self.delegation_depth,
+        }
+    }
+}
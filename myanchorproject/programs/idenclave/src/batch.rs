@@ -0,0 +1,136 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use idenclave_core::BatchItemResult;
+
+use crate::credential::{Credential, CredentialState};
+use crate::event::{CredentialOffered, CredentialRevoked};
+use crate::index::CredentialIndex;
+use crate::{index_credential, Identity};
+
+/// One credential of `issue_credential_batch`; the same fields, in the same order, as
+/// `idenclave_core::BatchIssueItem`, so the encoded items are identical to the native program's.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct BatchIssueItem {
+    pub nonce: u64,
+    pub credential_ref: [u8; 32],
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+/// Creates a program-owned PDA funded by `payer`, also when the address was pre-funded,
+/// which would make a plain `create_account` fail (the same handling as `init`).
+fn create_pda<'info>(
+    payer: &AccountInfo<'info>,
+    pda: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(space);
+    let signer_seeds = &[seeds];
+    if pda.lamports() == 0 {
+        let accounts = system_program::CreateAccount { from: payer.clone(), to: pda.clone() };
+        return system_program::create_account(
+            CpiContext::new_with_signer(system_program.clone(), accounts, signer_seeds),
+            required,
+            space as u64,
+            &crate::ID,
+        );
+    }
+    let shortfall = required.saturating_sub(pda.lamports());
+    if shortfall > 0 {
+        let accounts = system_program::Transfer { from: payer.clone(), to: pda.clone() };
+        system_program::transfer(CpiContext::new(system_program.clone(), accounts), shortfall)?;
+    }
+    let accounts = system_program::Allocate { account_to_allocate: pda.clone() };
+    system_program::allocate(CpiContext::new_with_signer(system_program.clone(), accounts, signer_seeds), space as u64)?;
+    let accounts = system_program::Assign { account_to_assign: pda.clone() };
+    system_program::assign(CpiContext::new_with_signer(system_program.clone(), accounts, signer_seeds), &crate::ID)
+}
+
+/// Offers one credential of a batch at its PDA. Problems with the item's own accounts skip it
+/// and are reported in its result; failing to fund the accounts aborts the batch.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn offer_batch_item<'info>(
+    issuer: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    schema: &[u8; 32],
+    item: &BatchIssueItem,
+    identity_info: &'info AccountInfo<'info>,
+    credential_info: &AccountInfo<'info>,
+    index_info: &AccountInfo<'info>,
+) -> Result<BatchItemResult> {
+    let mut identity = match Account::<Identity>::try_from(identity_info) {
+        Ok(identity) if identity_info.is_writable => identity,
+        _ => return Ok(BatchItemResult::InvalidAccount),
+    };
+    let identity_key = identity_info.key();
+    let nonce = item.nonce.to_le_bytes();
+    let credential_seeds: &[&[u8]] = &[Credential::SEED, issuer.as_ref(), identity_key.as_ref(), schema, &nonce];
+    let (credential_key, credential_bump) = Pubkey::find_program_address(credential_seeds, &crate::ID);
+    if credential_info.key() != credential_key || !credential_info.is_writable {
+        return Ok(BatchItemResult::InvalidAccount);
+    }
+    if credential_info.owner == &crate::ID {
+        return Ok(BatchItemResult::AlreadyExists);
+    }
+    let n = identity.credential_count.to_le_bytes();
+    let index_seeds: &[&[u8]] = &[CredentialIndex::SEED, identity_key.as_ref(), &n];
+    let (index_key, index_bump) = Pubkey::find_program_address(index_seeds, &crate::ID);
+    if index_info.key() != index_key || !index_info.is_writable {
+        return Ok(BatchItemResult::InvalidAccount);
+    }
+
+    create_pda(payer, credential_info, system_program, 8 + Credential::LEN, &[credential_seeds, &[&[credential_bump]]].concat())?;
+    create_pda(payer, index_info, system_program, 8 + CredentialIndex::LEN, &[index_seeds, &[&[index_bump]]].concat())?;
+    let mut index = CredentialIndex::default();
+    index_credential(&mut identity, &mut index, identity_key, credential_key)?;
+    index.try_serialize(&mut &mut index_info.try_borrow_mut_data()?[..])?;
+    let credential = Credential {
+        is_initialized: true,
+        pending: true,
+        identity: identity_key,
+        issuer: *issuer,
+        credential_ref: item.credential_ref,
+        issued_at: item.issued_at,
+        expires_at: item.expires_at,
+        ..Default::default()
+    };
+    credential.try_serialize(&mut &mut credential_info.try_borrow_mut_data()?[..])?;
+    identity.exit(&crate::ID)?;
+    emit!(CredentialOffered { credential: credential_key, identity: identity_key, issuer: *issuer });
+    Ok(BatchItemResult::Ok)
+}
+
+/// Revokes one credential of a batch, skipping it if it cannot be revoked.
+pub(crate) fn revoke_batch_item<'info>(issuer: &Pubkey, credential_info: &'info AccountInfo<'info>) -> Result<BatchItemResult> {
+    let mut credential = match Account::<Credential>::try_from(credential_info) {
+        Ok(credential) if credential_info.is_writable => credential,
+        _ => return Ok(BatchItemResult::InvalidAccount),
+    };
+    if credential.issuer != *issuer {
+        return Ok(BatchItemResult::NotIssuer);
+    }
+    if let Err(violation) = credential.check_revoke() {
+        return Ok(violation.into());
+    }
+    credential.revoked = true;
+    credential.suspended = false;
+    credential.exit(&crate::ID)?;
+    emit!(CredentialRevoked { credential: credential_info.key(), issuer: *issuer });
+    Ok(BatchItemResult::Ok)
+}
+
+/// Logs the skipped items of a batch and returns every item's result code; Anchor exposes the
+/// returned `Vec<u8>` as return data, byte for byte what the native program sets.
+pub(crate) fn report_batch(instruction: &str, results: &[BatchItemResult]) -> Vec<u8> {
+    for (i, result) in results.iter().enumerate() {
+        if *result != BatchItemResult::Ok {
+            msg!("[{}] Item {} skipped: {:?}", instruction, i, result);
+        }
+    }
+    let done = results.iter().filter(|result| **result == BatchItemResult::Ok).count();
+    msg!("[{}] {} of {} items processed", instruction, done, results.len());
+    results.iter().map(|result| *result as u8).collect()
+}
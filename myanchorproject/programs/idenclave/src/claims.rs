@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+/// A claim of a credential document disclosed on its own. The same fields as
+/// `idenclave_core::Claim`, which defines the leaf it hashes to.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Claim {
+    pub name: String,
+    pub value: Vec<u8>,
+    pub salt: [u8; 32],
+}
+
+impl Claim {
+    pub fn to_core(&self) -> idenclave_core::Claim {
+        idenclave_core::Claim { name: self.name.clone(), value: self.value.clone(), salt: self.salt }
+    }
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use idenclave_core::merkle::{MerkleHasher, Node};
+
+pub use idenclave_core::CredentialTree;
+
+/// SHA-256 through the `sol_sha256` syscall, the hash of every credential tree.
+pub struct Sha256Hasher;
+
+impl MerkleHasher for Sha256Hasher {
+    fn hashv(parts: &[&[u8]]) -> Node {
+        hashv(parts).to_bytes()
+    }
+}
+
+/// A credential kept as a leaf of a credential tree. The same fields, in the same order, as
+/// `idenclave_core::CompressedCredential`, so its Borsh encoding is the leaf's preimage and
+/// leaves are identical to the native program's.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompressedCredential {
+    pub identity: Pubkey,
+    pub issuer: Pubkey,
+    pub schema: [u8; 32],
+    pub credential_ref: [u8; 32],
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub revoked: bool,
+}
+
+impl CompressedCredential {
+    pub fn to_core(&self) -> idenclave_core::CompressedCredential {
+        idenclave_core::CompressedCredential {
+            identity: self.identity.to_bytes(),
+            issuer: self.issuer.to_bytes(),
+            schema: self.schema,
+            credential_ref: self.credential_ref,
+            issued_at: self.issued_at,
+            expires_at: self.expires_at,
+            revoked: self.revoked,
+        }
+    }
+
+    pub fn leaf_hash(&self) -> Node {
+        self.to_core().leaf_hash::<Sha256Hasher>()
+    }
+}
@@ -1,16 +1,60 @@
 use anchor_lang::prelude::*;
+use idenclave_core::AccountLayout;
 
+/// A verifiable credential issued to an identity.
+/// Fields are declared in the order of the canonical `idenclave_core::Credential` layout,
+/// so the account data after the 8-byte discriminator is identical to the native program's.
 #[account]
+#[derive(Default)]
 pub struct Credential {
-    pub expires_at: i64,           // 8
     pub is_initialized: bool,      // 1
     pub revoked: bool,             // 1
     pub identity: Pubkey,          // 32
     pub issuer: Pubkey,            // 32
     pub credential_ref: [u8; 32],  // 32
-    pub issued_at: i64,            // 8
+    pub issued_at: u64,            // 8
+    pub expires_at: u64,           // 8
+    pub suspended: bool,           // 1
+    pub previous: Pubkey,          // 32
+    pub superseded_by: Pubkey,     // 32
+    pub pending: bool,             // 1
+    pub claims_root: [u8; 32],     // 32, all zeros when the issuer committed to no claims
+    pub commitment: [u8; 32],      // 32, Poseidon commitment proofs bind to, all zeros until set
 }
 
+// Status values and the rules for moving between them live in idenclave-core,
+// shared with the native program.
+pub use idenclave_core::{CredentialState, CredentialStatus};
+
 impl Credential {
-    pub const LEN: usize = 8 + 1 + 1 + 32 + 32 + 32 + 8;
+    pub const LEN: usize = idenclave_core::Credential::LEN;
+    pub const SEED: &'static [u8] = idenclave_core::Credential::SEED;
+
+    /// Address of the credential `issuer` issues to `identity` under `schema` and `nonce`; for a
+    /// credential issued on behalf of an organization, `issuer` is the organization.
+    pub fn find_address(issuer: &Pubkey, identity: &Pubkey, schema: &[u8; 32], nonce: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, issuer.as_ref(), identity.as_ref(), schema, &nonce.to_le_bytes()], &crate::ID)
+    }
+}
+
+impl CredentialState for Credential {
+    fn is_revoked(&self) -> bool {
+        self.revoked
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    fn is_superseded(&self) -> bool {
+        self.superseded_by != Pubkey::default()
+    }
+
+    fn expires_at(&self) -> u64 {
+        self.expires_at
+    }
 }
@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use idenclave_core::{ClaimError, OrganizationError, RuleViolation, ServiceError, TreeError};
+
+#[error_code]
+pub enum IdenclaveError {
+    #[msg("Signer is not the identity authority")]
+    NotAuthority,
+    #[msg("Only recovery address can recover authority")]
+    NotRecovery,
+    #[msg("Signer is not the credential issuer")]
+    NotIssuer,
+    #[msg("Identity was closed by a different authority")]
+    ClosedByDifferentAuthority,
+    #[msg("Identity still has credentials that have not been closed")]
+    ActiveCredentials,
+    #[msg("Credential does not belong to this identity")]
+    IdentityMismatch,
+    #[msg("Credential is already revoked")]
+    AlreadyRevoked,
+    #[msg("Credential state does not allow this operation")]
+    InvalidCredentialState,
+    #[msg("Renewal must extend the expiry")]
+    ExpiryNotExtended,
+    #[msg("Credential offer is not pending")]
+    NotPending,
+    #[msg("Only revoked or expired credentials can be closed")]
+    CredentialStillValid,
+    #[msg("Credential index entry does not match the credential")]
+    IndexMismatch,
+    #[msg("No Ed25519 signature by the issuer over the issuance message precedes this instruction")]
+    MissingIssuerSignature,
+    #[msg("Issuance signature is past its deadline")]
+    IssuanceSignatureExpired,
+    #[msg("Only the issuer can send the refunded rent elsewhere")]
+    RecipientNotIssuer,
+    #[msg("A batch needs between 1 and 32 items, each with all of its accounts")]
+    InvalidBatch,
+    #[msg("Invalid credential tree depth, buffer size or account size")]
+    InvalidTreeParameters,
+    #[msg("Account is not an initialized credential tree")]
+    TreeNotInitialized,
+    #[msg("Credential tree is already initialized")]
+    TreeAlreadyInitialized,
+    #[msg("Signer is not the credential tree authority")]
+    NotTreeAuthority,
+    #[msg("Credential tree is full")]
+    TreeFull,
+    #[msg("Proof does not show the credential in the credential tree")]
+    InvalidProof,
+    #[msg("Compressed credential changed after the proof root")]
+    LeafChanged,
+    #[msg("Signer is not a signing key of the organization")]
+    NotOrganizationKey,
+    #[msg("Key is already a signing key of the organization")]
+    OrganizationKeyAlreadyAuthorized,
+    #[msg("Key is not a signing key of the organization")]
+    OrganizationKeyNotAuthorized,
+    #[msg("Organization has the maximum number of signing keys")]
+    TooManyOrganizationKeys,
+    #[msg("Signer is not the accreditor")]
+    NotAccreditor,
+    #[msg("Accreditation is already revoked")]
+    AccreditationAlreadyRevoked,
+    #[msg("Verifying key is malformed or has too many public inputs")]
+    InvalidVerifyingKey,
+    #[msg("Presentation request lists too many schemas, issuers or predicate inputs")]
+    TooManyRequestEntries,
+    #[msg("Predicate inputs need a circuit taking one more public input, the credential commitment")]
+    InvalidPredicateInputs,
+    #[msg("Presentation request has expired")]
+    PresentationRequestExpired,
+    #[msg("Circuit is not the one the presentation request names")]
+    CircuitMismatch,
+    #[msg("Proof is not a 256-byte Groth16 proof")]
+    MalformedProof,
+    #[msg("Signer is not the verifier")]
+    NotVerifier,
+    #[msg("Credential does not commit to any claims")]
+    NoClaimsRoot,
+    #[msg("Claim name or value is too large")]
+    ClaimTooLarge,
+    #[msg("Proof does not show the claim under the credential's claims root")]
+    InvalidClaimProof,
+    #[msg("A circuit needs a schema whose credential the proof is about")]
+    MissingCircuitSchema,
+    #[msg("Credential claims are already committed")]
+    ClaimsAlreadyCommitted,
+    #[msg("Claim hashes must be one to twelve field elements")]
+    InvalidClaimHashes,
+    #[msg("Identity already has a service with this id")]
+    ServiceAlreadyExists,
+    #[msg("Identity has no service with this id")]
+    ServiceNotFound,
+    #[msg("Identity has the maximum number of services")]
+    TooManyServices,
+    #[msg("Service id, type and endpoint must be set and within their size limits")]
+    InvalidService,
+}
+
+impl From<RuleViolation> for IdenclaveError {
+    fn from(violation: RuleViolation) -> Self {
+        match violation {
+            RuleViolation::AlreadyRevoked => IdenclaveError::AlreadyRevoked,
+            RuleViolation::InvalidState => IdenclaveError::InvalidCredentialState,
+            RuleViolation::ExpiryNotExtended => IdenclaveError::ExpiryNotExtended,
+            RuleViolation::NotPending => IdenclaveError::NotPending,
+            RuleViolation::StillValid => IdenclaveError::CredentialStillValid,
+        }
+    }
+}
+
+impl From<TreeError> for IdenclaveError {
+    fn from(error: TreeError) -> Self {
+        match error {
+            TreeError::InvalidParameters => IdenclaveError::InvalidTreeParameters,
+            TreeError::NotInitialized => IdenclaveError::TreeNotInitialized,
+            TreeError::AlreadyInitialized => IdenclaveError::TreeAlreadyInitialized,
+            TreeError::TreeFull => IdenclaveError::TreeFull,
+            TreeError::LeafChanged => IdenclaveError::LeafChanged,
+            TreeError::IndexOutOfRange | TreeError::RootNotFound | TreeError::InvalidProof | TreeError::OutOfSync => {
+                IdenclaveError::InvalidProof
+            }
+        }
+    }
+}
+
+impl From<OrganizationError> for IdenclaveError {
+    fn from(error: OrganizationError) -> Self {
+        match error {
+            OrganizationError::KeyAlreadyAuthorized => IdenclaveError::OrganizationKeyAlreadyAuthorized,
+            OrganizationError::KeyNotAuthorized => IdenclaveError::OrganizationKeyNotAuthorized,
+            OrganizationError::TooManyKeys => IdenclaveError::TooManyOrganizationKeys,
+        }
+    }
+}
+
+impl From<ServiceError> for IdenclaveError {
+    fn from(error: ServiceError) -> Self {
+        match error {
+            ServiceError::AlreadyExists => IdenclaveError::ServiceAlreadyExists,
+            ServiceError::NotFound => IdenclaveError::ServiceNotFound,
+            ServiceError::TooManyServices => IdenclaveError::TooManyServices,
+            ServiceError::EmptyField | ServiceError::TooLarge | ServiceError::Malformed => IdenclaveError::InvalidService,
+        }
+    }
+}
+
+impl From<ClaimError> for IdenclaveError {
+    fn from(error: ClaimError) -> Self {
+        match error {
+            ClaimError::NoClaimsRoot => IdenclaveError::NoClaimsRoot,
+            ClaimError::ClaimTooLarge => IdenclaveError::ClaimTooLarge,
+            ClaimError::TooManyClaims | ClaimError::Malformed | ClaimError::InvalidProof => IdenclaveError::InvalidClaimProof,
+        }
+    }
+}
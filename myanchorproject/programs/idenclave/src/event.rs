@@ -0,0 +1,169 @@
+use anchor_lang::prelude::*;
+
+use crate::compressed::CompressedCredential;
+
+// Events mirror the native program's `IdentityEvent` variants one for one.
+
+#[event]
+pub struct CredentialIssued {
+    pub credential: Pubkey,
+    pub identity: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct CredentialRevoked {
+    pub credential: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct CredentialSuspended {
+    pub credential: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct CredentialReinstated {
+    pub credential: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct CredentialRenewed {
+    pub credential: Pubkey,
+    pub issuer: Pubkey,
+    pub expires_at: u64,
+}
+
+#[event]
+pub struct CredentialSuperseded {
+    pub credential: Pubkey,
+    pub new_credential: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct CredentialOffered {
+    pub credential: Pubkey,
+    pub identity: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct CredentialAccepted {
+    pub credential: Pubkey,
+    pub identity: Pubkey,
+}
+
+#[event]
+pub struct CredentialOfferRejected {
+    pub credential: Pubkey,
+    pub identity: Pubkey,
+}
+
+#[event]
+pub struct CredentialOfferCancelled {
+    pub credential: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct CredentialClosed {
+    pub credential: Pubkey,
+    pub closed_by: Pubkey,
+}
+
+#[event]
+pub struct CredentialReissued {
+    pub credential: Pubkey,
+    pub issuer: Pubkey,
+    pub previous_ref: [u8; 32],
+    pub credential_ref: [u8; 32],
+    pub expires_at: u64,
+}
+
+#[event]
+pub struct CompressedCredentialIssued {
+    pub tree: Pubkey,
+    pub index: u32,
+    pub root: [u8; 32],
+    pub credential: CompressedCredential,
+}
+
+#[event]
+pub struct CompressedCredentialRevoked {
+    pub tree: Pubkey,
+    pub index: u32,
+    pub root: [u8; 32],
+}
+
+#[event]
+pub struct OrganizationKeyAdded {
+    pub organization: Pubkey,
+    pub key: Pubkey,
+}
+
+#[event]
+pub struct OrganizationKeyRemoved {
+    pub organization: Pubkey,
+    pub key: Pubkey,
+}
+
+#[event]
+pub struct OrganizationKeyRotated {
+    pub organization: Pubkey,
+    pub old_key: Pubkey,
+    pub new_key: Pubkey,
+}
+
+#[event]
+pub struct AccreditationGranted {
+    pub accreditation: Pubkey,
+    pub accreditor: Pubkey,
+    pub issuer: Pubkey,
+    pub schema: [u8; 32],
+    pub expires_at: u64,
+    pub delegation_depth: u8,
+}
+
+#[event]
+pub struct AccreditationRevoked {
+    pub accreditation: Pubkey,
+    pub accreditor: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct PresentationRequested {
+    pub request: Pubkey,
+    pub verifier: Pubkey,
+}
+
+#[event]
+pub struct PresentationResponded {
+    pub request: Pubkey,
+    pub identity: Pubkey,
+    pub result: u8,
+}
+
+#[event]
+pub struct VerificationRecorded {
+    pub receipt: Pubkey,
+    pub verifier: Pubkey,
+    pub credential: Pubkey,
+    pub result: u8,
+}
+
+#[event]
+pub struct VerificationReceiptClosed {
+    pub receipt: Pubkey,
+    pub verifier: Pubkey,
+}
+
+#[event]
+pub struct ClaimsCommitted {
+    pub credential: Pubkey,
+    pub issuer: Pubkey,
+    pub commitment: [u8; 32],
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+use idenclave_core::AccountLayout;
+
+/// Entry `n` of an identity's credential index, stored in the PDA
+/// `[b"cred-index", identity, n (u64 LE)]`. Entries are created when a credential is
+/// issued to the identity and closed together with the credential, so walking
+/// `0..identity.credential_count` lists every live credential of a holder.
+#[account]
+#[derive(Default)]
+pub struct CredentialIndex {
+    pub is_initialized: bool, // 1
+    pub identity: Pubkey,     // 32
+    pub index: u64,           // 8
+    pub credential: Pubkey,   // 32
+}
+
+impl CredentialIndex {
+    pub const SEED: &'static [u8] = idenclave_core::CredentialIndex::SEED;
+    pub const LEN: usize = idenclave_core::CredentialIndex::LEN;
+}
@@ -1,114 +1,1312 @@
+// `#[program]` expands to code calling the deprecated `AccountInfo::realloc` in anchor-lang 0.31
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
+use idenclave_core::AccountLayout;
 
+pub mod accreditation;
+pub mod batch;
+pub mod claims;
+pub mod compressed;
 pub mod credential;
-use crate::credential::Credential;
+pub mod error;
+pub mod event;
+pub mod index;
+pub mod organization;
+pub mod presentation;
+pub mod tombstone;
+pub mod verification;
+use crate::accreditation::Accreditation;
+use crate::batch::BatchIssueItem;
+use crate::claims::Claim;
+use crate::compressed::{CompressedCredential, CredentialTree, Sha256Hasher};
+use crate::credential::{Credential, CredentialState};
+use crate::error::IdenclaveError;
+use crate::event::*;
+use crate::index::CredentialIndex;
+use crate::organization::{acting_issuer, Organization};
+use crate::presentation::{AltBn128, Circuit, PoseidonSyscall, PresentationRequest, PresentationResponse};
+use crate::tombstone::Tombstone;
+use crate::verification::VerificationReceipt;
 
 declare_id!("GhdfjF2uHkx45jWaLTaHLfTeCoEsnAnyi2ZcsHxpCNha");
 
+pub use idenclave_core::{ATTRIBUTES_LEN, MAX_SERVICES, SERVICE_LEN};
+
 #[program]
 pub mod idenclave {
     use super::*;
 
     pub fn register_identity(ctx: Context<RegisterIdentity>) -> Result<()> {
+        // A closed identity can only be brought back by the authority that closed it
+        let tombstone = &ctx.accounts.tombstone;
+        if tombstone.owner == ctx.program_id && !tombstone.data_is_empty() {
+            let tombstone = Tombstone::try_deserialize(&mut &tombstone.try_borrow_data()?[..])?;
+            require!(
+                !tombstone.is_initialized || tombstone.authority == ctx.accounts.authority.key(),
+                IdenclaveError::ClosedByDifferentAuthority
+            );
+        }
         let identity = &mut ctx.accounts.identity;
         identity.authority = *ctx.accounts.authority.key;
         identity.is_initialized = true;
         Ok(())
     }
 
+    pub fn update_authority(ctx: Context<UpdateIdentity>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.identity.authority = new_authority;
+        Ok(())
+    }
+
+    pub fn set_attributes(ctx: Context<UpdateIdentity>, data: Vec<u8>) -> Result<()> {
+        // Same truncation as the native program: attributes are a fixed, zero-padded buffer
+        let data = idenclave_core::truncate_attributes(&data);
+        let mut attributes = [0u8; ATTRIBUTES_LEN];
+        attributes[..data.len()].copy_from_slice(data);
+        ctx.accounts.identity.attributes = attributes;
+        Ok(())
+    }
+
+    pub fn add_service(ctx: Context<UpdateIdentity>, id: String, service_type: String, endpoint: String) -> Result<()> {
+        let service = idenclave_core::Service { id: id.clone(), service_type, endpoint };
+        ctx.accounts.identity.update_services(|identity| identity.add_service(service))?;
+        msg!("[AddService] Service {} added", id);
+        Ok(())
+    }
+
+    pub fn update_service(ctx: Context<UpdateIdentity>, id: String, service_type: String, endpoint: String) -> Result<()> {
+        let service = idenclave_core::Service { id: id.clone(), service_type, endpoint };
+        ctx.accounts.identity.update_services(|identity| identity.update_service(service))?;
+        msg!("[UpdateService] Service {} updated", id);
+        Ok(())
+    }
+
+    pub fn remove_service(ctx: Context<UpdateIdentity>, id: String) -> Result<()> {
+        ctx.accounts.identity.update_services(|identity| identity.remove_service(&id))?;
+        msg!("[RemoveService] Service {} removed", id);
+        Ok(())
+    }
+
+    pub fn set_recovery(ctx: Context<UpdateIdentity>, new_recovery: Pubkey) -> Result<()> {
+        ctx.accounts.identity.recovery = new_recovery;
+        Ok(())
+    }
+
+    pub fn recover_authority(ctx: Context<RecoverAuthority>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.identity.authority = new_authority;
+        msg!("[RecoverAuthority] Authority recovered");
+        Ok(())
+    }
+
     pub fn issue_credential(
         ctx: Context<IssueCredential>,
+        _schema: [u8; 32],
+        _nonce: u64,
         credential_ref: [u8; 32],
-        issued_at: i64,
-        expires_at: i64,
+        issued_at: u64,
+        expires_at: u64,
+        claims_root: Option<[u8; 32]>,
     ) -> Result<()> {
+        let credential_key = ctx.accounts.credential.key();
+        let identity_key = ctx.accounts.identity.key();
+        index_credential(&mut ctx.accounts.identity, &mut ctx.accounts.index, identity_key, credential_key)?;
         let credential = &mut ctx.accounts.credential;
         credential.is_initialized = true;
         credential.revoked = false;
-        credential.identity = ctx.accounts.identity.key();
-        credential.issuer = ctx.accounts.issuer.key();
+        credential.identity = identity_key;
+        credential.issuer = acting_issuer(&ctx.accounts.issuer.key(), &ctx.accounts.organization);
         credential.credential_ref = credential_ref;
         credential.issued_at = issued_at;
         credential.expires_at = expires_at;
+        credential.claims_root = claims_root.unwrap_or_default();
+        emit!(CredentialIssued { credential: credential_key, identity: identity_key, issuer: credential.issuer });
         Ok(())
     }
 
-    pub fn close_identity(ctx: Context<CloseIdentity>) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn issue_credential_signed(
+        ctx: Context<IssueCredentialSigned>,
+        schema: [u8; 32],
+        nonce: u64,
+        credential_ref: [u8; 32],
+        issued_at: u64,
+        expires_at: u64,
+        deadline: u64,
+        claims_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            idenclave_core::unix_now(Clock::get()?.unix_timestamp) <= deadline,
+            IdenclaveError::IssuanceSignatureExpired
+        );
+        let claims_root = claims_root.unwrap_or_default();
+        let credential_key = ctx.accounts.credential.key();
+        let identity_key = ctx.accounts.identity.key();
+        let issuer_key = ctx.accounts.issuer.key();
+        let issuer = acting_issuer(&issuer_key, &ctx.accounts.organization);
+        let message = idenclave_core::IssuanceMessage {
+            program_id: crate::ID.to_bytes(),
+            issuer: issuer.to_bytes(),
+            identity: identity_key.to_bytes(),
+            schema,
+            nonce,
+            credential_ref,
+            issued_at,
+            expires_at,
+            claims_root,
+            deadline,
+        };
+        verify_ed25519_signature(&ctx.accounts.instructions, &issuer_key, &message.to_bytes())?;
+        index_credential(&mut ctx.accounts.identity, &mut ctx.accounts.index, identity_key, credential_key)?;
+        // Without the holder's signature the credential waits for acceptance like an offer
+        let pending = !ctx.accounts.authority.is_signer;
+        let credential = &mut ctx.accounts.credential;
+        credential.is_initialized = true;
+        credential.pending = pending;
+        credential.identity = identity_key;
+        credential.issuer = issuer;
+        credential.credential_ref = credential_ref;
+        credential.issued_at = issued_at;
+        credential.expires_at = expires_at;
+        credential.claims_root = claims_root;
+        if pending {
+            emit!(CredentialOffered { credential: credential_key, identity: identity_key, issuer });
+        } else {
+            emit!(CredentialIssued { credential: credential_key, identity: identity_key, issuer });
+        }
+        msg!("[IssueCredentialSigned] Credential issued at {}", credential_key);
+        Ok(())
+    }
+
+    /// Offers a cohort of credentials in one instruction. The remaining accounts hold
+    /// `[identity, credential, index]` for every item; items that cannot be issued are skipped and
+    /// reported in the returned result codes (`idenclave_core::BatchItemResult`).
+    pub fn issue_credential_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, IssueCredentialBatch<'info>>,
+        schema: [u8; 32],
+        items: Vec<BatchIssueItem>,
+    ) -> Result<Vec<u8>> {
+        require!(!items.is_empty() && items.len() <= idenclave_core::MAX_BATCH_ITEMS, IdenclaveError::InvalidBatch);
+        require!(ctx.remaining_accounts.len() == items.len() * 3, IdenclaveError::InvalidBatch);
+        let issuer = acting_issuer(&ctx.accounts.issuer.key(), &ctx.accounts.organization);
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        // A cohort's holders cannot all sign one transaction, so batch credentials arrive as offers
+        let mut results = Vec::with_capacity(items.len());
+        for (item, accounts) in items.iter().zip(ctx.remaining_accounts.chunks_exact(3)) {
+            results.push(batch::offer_batch_item(
+                &issuer,
+                &payer,
+                &system_program,
+                &schema,
+                item,
+                &accounts[0],
+                &accounts[1],
+                &accounts[2],
+            )?);
+        }
+        Ok(batch::report_batch("IssueCredentialBatch", &results))
+    }
+
+    /// Revokes every credential passed as a remaining account, reporting the ones it skipped.
+    pub fn revoke_credential_batch<'info>(ctx: Context<'_, '_, 'info, 'info, RevokeCredentialBatch<'info>>) -> Result<Vec<u8>> {
+        let credentials = ctx.remaining_accounts;
+        require!(!credentials.is_empty() && credentials.len() <= idenclave_core::MAX_BATCH_ITEMS, IdenclaveError::InvalidBatch);
+        let issuer = acting_issuer(&ctx.accounts.issuer.key(), &ctx.accounts.organization);
+        let mut results = Vec::with_capacity(credentials.len());
+        for credential in credentials {
+            results.push(batch::revoke_batch_item(&issuer, credential)?);
+        }
+        Ok(batch::report_batch("RevokeCredentialBatch", &results))
+    }
+
+    /// Initializes a credential tree in an account the client created with
+    /// `CredentialTree::account_len` bytes, owned by this program; usually too large for `init`.
+    pub fn create_credential_tree(ctx: Context<CreateCredentialTree>, max_depth: u8, max_buffer_size: u8) -> Result<()> {
+        let mut data = ctx.accounts.tree.try_borrow_mut_data()?;
+        CredentialTree::initialize::<Sha256Hasher>(&mut data[..], max_depth, max_buffer_size, &ctx.accounts.authority.key().to_bytes())
+            .map_err(IdenclaveError::from)?;
+        msg!("[CreateCredentialTree] Tree of depth {} created", max_depth);
+        Ok(())
+    }
+
+    pub fn issue_compressed_credential(
+        ctx: Context<IssueCompressedCredential>,
+        schema: [u8; 32],
+        credential_ref: [u8; 32],
+        issued_at: u64,
+        expires_at: u64,
+    ) -> Result<()> {
+        let tree_key = ctx.accounts.tree.key();
+        let mut data = ctx.accounts.tree.try_borrow_mut_data()?;
+        let mut tree = CredentialTree::load(&mut data[..]).map_err(IdenclaveError::from)?;
+        require!(tree.authority() == ctx.accounts.issuer.key().to_bytes(), IdenclaveError::NotTreeAuthority);
+        let credential = CompressedCredential {
+            identity: ctx.accounts.identity.key(),
+            issuer: ctx.accounts.issuer.key(),
+            schema,
+            credential_ref,
+            issued_at,
+            expires_at,
+            revoked: false,
+        };
+        let (index, root) = tree.append::<Sha256Hasher>(&credential.leaf_hash()).map_err(IdenclaveError::from)?;
+        emit!(CompressedCredentialIssued { tree: tree_key, index, root, credential });
+        msg!("[IssueCompressedCredential] Credential issued at leaf {}", index);
+        Ok(())
+    }
+
+    /// Revokes the compressed credential at leaf `index`, proven by `proof` against `root`, which
+    /// may be any of the tree's recent roots.
+    pub fn revoke_compressed_credential(
+        ctx: Context<RevokeCompressedCredential>,
+        root: [u8; 32],
+        index: u32,
+        credential: CompressedCredential,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require_keys_eq!(credential.issuer, ctx.accounts.issuer.key(), IdenclaveError::NotIssuer);
+        credential.to_core().check_revoke().map_err(IdenclaveError::from)?;
+        let tree_key = ctx.accounts.tree.key();
+        let mut data = ctx.accounts.tree.try_borrow_mut_data()?;
+        let mut tree = CredentialTree::load(&mut data[..]).map_err(IdenclaveError::from)?;
+        let revoked = CompressedCredential { revoked: true, ..credential.clone() };
+        let root = tree
+            .replace::<Sha256Hasher>(&root, index, &credential.leaf_hash(), &revoked.leaf_hash(), &proof)
+            .map_err(IdenclaveError::from)?;
+        emit!(CompressedCredentialRevoked { tree: tree_key, index, root });
+        msg!("[RevokeCompressedCredential] Credential at leaf {} revoked", index);
+        Ok(())
+    }
+
+    /// Returns the status of a compressed credential shown to be the current leaf at `index`.
+    pub fn verify_compressed_credential(
+        ctx: Context<VerifyCompressedCredential>,
+        root: [u8; 32],
+        index: u32,
+        credential: CompressedCredential,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<u8> {
+        let data = ctx.accounts.tree.try_borrow_data()?;
+        let tree = CredentialTree::load(&data[..]).map_err(IdenclaveError::from)?;
+        tree.verify::<Sha256Hasher>(&root, index, &credential.leaf_hash(), &proof).map_err(IdenclaveError::from)?;
+        let status = credential.to_core().status(idenclave_core::unix_now(Clock::get()?.unix_timestamp));
+        msg!("[VerifyCompressedCredential] Credential at leaf {} is {:?}", index, status);
+        Ok(status as u8)
+    }
+
+    pub fn revoke_credential(ctx: Context<UpdateCredential>) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
+        credential.check_revoke().map_err(IdenclaveError::from)?;
+        credential.revoked = true;
+        credential.suspended = false;
+        emit!(CredentialRevoked { credential: credential.key(), issuer: credential.issuer });
+        msg!("[RevokeCredential] Credential revoked");
+        Ok(())
+    }
+
+    pub fn query_credential_status(ctx: Context<QueryCredentialStatus>) -> Result<u8> {
+        let credential = &ctx.accounts.credential;
+        let status = credential.status(idenclave_core::unix_now(Clock::get()?.unix_timestamp));
+        msg!("[QueryCredentialStatus] Credential status {:?}", status);
+        // The return value is exposed as return data, like the native program's single status byte
+        Ok(status as u8)
+    }
+
+    pub fn suspend_credential(ctx: Context<UpdateCredential>) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
+        credential.check_suspend().map_err(IdenclaveError::from)?;
+        credential.suspended = true;
+        emit!(CredentialSuspended { credential: credential.key(), issuer: credential.issuer });
+        msg!("[SuspendCredential] Credential suspended");
+        Ok(())
+    }
+
+    pub fn reinstate_credential(ctx: Context<UpdateCredential>) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
+        credential.check_reinstate().map_err(IdenclaveError::from)?;
+        credential.suspended = false;
+        emit!(CredentialReinstated { credential: credential.key(), issuer: credential.issuer });
+        msg!("[ReinstateCredential] Credential reinstated");
+        Ok(())
+    }
+
+    pub fn renew_credential(ctx: Context<UpdateCredential>, expires_at: u64) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
+        credential.check_renew(expires_at).map_err(IdenclaveError::from)?;
+        credential.expires_at = expires_at;
+        emit!(CredentialRenewed { credential: credential.key(), issuer: credential.issuer, expires_at });
+        msg!("[RenewCredential] Credential renewed until {}", expires_at);
+        Ok(())
+    }
+
+    pub fn reissue_credential(
+        ctx: Context<UpdateCredential>,
+        credential_ref: [u8; 32],
+        issued_at: u64,
+        expires_at: u64,
+        claims_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
+        credential.check_reissue().map_err(IdenclaveError::from)?;
+        // The replaced reference is logged so the credential's history can be rebuilt from events
+        let previous_ref = credential.credential_ref;
+        credential.credential_ref = credential_ref;
+        credential.issued_at = issued_at;
+        credential.expires_at = expires_at;
+        // The claims belong to the document being replaced, so they are replaced along with it
+        credential.claims_root = claims_root.unwrap_or_default();
+        credential.commitment = [0u8; 32];
+        emit!(CredentialReissued {
+            credential: credential.key(),
+            issuer: credential.issuer,
+            previous_ref,
+            credential_ref,
+            expires_at,
+        });
+        msg!("[ReissueCredential] Credential reissued");
+        Ok(())
+    }
+
+    pub fn supersede_credential(
+        ctx: Context<SupersedeCredential>,
+        _schema: [u8; 32],
+        _nonce: u64,
+        credential_ref: [u8; 32],
+        issued_at: u64,
+        expires_at: u64,
+        claims_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let old = &ctx.accounts.old_credential;
+        old.check_supersede().map_err(IdenclaveError::from)?;
+        let old_key = old.key();
+        let new_key = ctx.accounts.new_credential.key();
+        let issuer = acting_issuer(&ctx.accounts.issuer.key(), &ctx.accounts.organization);
+        let identity_key = ctx.accounts.identity.key();
+        index_credential(&mut ctx.accounts.identity, &mut ctx.accounts.index, identity_key, new_key)?;
+        let new = &mut ctx.accounts.new_credential;
+        new.is_initialized = true;
+        new.identity = identity_key;
+        new.issuer = issuer;
+        new.credential_ref = credential_ref;
+        new.issued_at = issued_at;
+        new.expires_at = expires_at;
+        new.claims_root = claims_root.unwrap_or_default();
+        new.previous = old_key;
+        ctx.accounts.old_credential.superseded_by = new_key;
+        emit!(CredentialSuperseded { credential: old_key, new_credential: new_key, issuer });
+        msg!("[SupersedeCredential] Credential superseded by {}", new_key);
+        Ok(())
+    }
+
+    pub fn offer_credential(
+        ctx: Context<OfferCredential>,
+        _schema: [u8; 32],
+        _nonce: u64,
+        credential_ref: [u8; 32],
+        issued_at: u64,
+        expires_at: u64,
+        claims_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let credential_key = ctx.accounts.credential.key();
+        let identity_key = ctx.accounts.identity.key();
+        index_credential(&mut ctx.accounts.identity, &mut ctx.accounts.index, identity_key, credential_key)?;
+        let credential = &mut ctx.accounts.credential;
+        credential.is_initialized = true;
+        credential.pending = true;
+        credential.identity = identity_key;
+        credential.issuer = acting_issuer(&ctx.accounts.issuer.key(), &ctx.accounts.organization);
+        credential.credential_ref = credential_ref;
+        credential.issued_at = issued_at;
+        credential.expires_at = expires_at;
+        credential.claims_root = claims_root.unwrap_or_default();
+        emit!(CredentialOffered { credential: credential_key, identity: identity_key, issuer: credential.issuer });
+        msg!("[OfferCredential] Credential offered to {}", identity_key);
+        Ok(())
+    }
+
+    pub fn accept_credential(ctx: Context<AcceptCredential>) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
+        credential.check_accept().map_err(IdenclaveError::from)?;
+        credential.pending = false;
+        emit!(CredentialAccepted { credential: credential.key(), identity: credential.identity });
+        msg!("[AcceptCredential] Credential accepted");
+        Ok(())
+    }
+
+    pub fn reject_credential(ctx: Context<RejectCredential>) -> Result<()> {
+        ctx.accounts.credential.check_withdraw_offer().map_err(IdenclaveError::from)?;
+        // Rent goes to the recipient, which has to be the issuer unless the issuer signs too
+        unindex_credential(&mut ctx.accounts.identity);
+        emit!(CredentialOfferRejected { credential: ctx.accounts.credential.key(), identity: ctx.accounts.identity.key() });
+        msg!("[RejectCredential] Credential offer rejected");
+        Ok(())
+    }
+
+    pub fn cancel_credential_offer(ctx: Context<CancelCredentialOffer>) -> Result<()> {
+        ctx.accounts.credential.check_withdraw_offer().map_err(IdenclaveError::from)?;
+        unindex_credential(&mut ctx.accounts.identity);
+        emit!(CredentialOfferCancelled { credential: ctx.accounts.credential.key(), issuer: ctx.accounts.credential.issuer });
+        msg!("[CancelCredentialOffer] Credential offer cancelled");
         Ok(())
     }
 
     pub fn close_credential(ctx: Context<CloseCredential>) -> Result<()> {
+        let credential = &ctx.accounts.credential;
+        let closer = acting_issuer(&ctx.accounts.closer.key(), &ctx.accounts.organization);
+        // The holder may also close, proven through the identity's current authority
+        if credential.issuer != closer {
+            require_keys_eq!(ctx.accounts.identity.authority, ctx.accounts.closer.key(), IdenclaveError::NotAuthority);
+        }
+        credential
+            .check_close(idenclave_core::unix_now(Clock::get()?.unix_timestamp))
+            .map_err(IdenclaveError::from)?;
+        // Rent goes to the recipient, which has to be the issuer unless the issuer closes
+        unindex_credential(&mut ctx.accounts.identity);
+        emit!(CredentialClosed { credential: credential.key(), closed_by: closer });
+        msg!("[CloseCredential] Credential closed");
+        Ok(())
+    }
+
+    pub fn close_identity(ctx: Context<CloseIdentity>) -> Result<()> {
+        let tombstone = &mut ctx.accounts.tombstone;
+        tombstone.is_initialized = true;
+        tombstone.authority = ctx.accounts.authority.key();
+        msg!("[CloseAccount] Identity closed");
+        Ok(())
+    }
+
+    pub fn create_organization(ctx: Context<CreateOrganization>, keys: Vec<Pubkey>) -> Result<()> {
+        let organization_key = ctx.accounts.organization.key();
+        let organization = &mut ctx.accounts.organization;
+        organization.is_initialized = true;
+        organization.authority = ctx.accounts.authority.key();
+        for key in &keys {
+            organization.update_keys(|organization| organization.add_key(&key.to_bytes()))?;
+        }
+        for key in keys {
+            emit!(OrganizationKeyAdded { organization: organization_key, key });
+        }
+        msg!("[CreateOrganization] Organization created");
+        Ok(())
+    }
+
+    pub fn add_organization_key(ctx: Context<UpdateOrganization>, key: Pubkey) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        organization.update_keys(|organization| organization.add_key(&key.to_bytes()))?;
+        emit!(OrganizationKeyAdded { organization: organization.key(), key });
+        msg!("[AddOrganizationKey] Key {} added", key);
+        Ok(())
+    }
+
+    pub fn remove_organization_key(ctx: Context<UpdateOrganization>, key: Pubkey) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        organization.update_keys(|organization| organization.remove_key(&key.to_bytes()))?;
+        emit!(OrganizationKeyRemoved { organization: organization.key(), key });
+        msg!("[RemoveOrganizationKey] Key {} removed", key);
+        Ok(())
+    }
+
+    pub fn rotate_organization_key(ctx: Context<UpdateOrganization>, old_key: Pubkey, new_key: Pubkey) -> Result<()> {
+        let organization = &mut ctx.accounts.organization;
+        organization.update_keys(|organization| organization.rotate_key(&old_key.to_bytes(), &new_key.to_bytes()))?;
+        emit!(OrganizationKeyRotated { organization: organization.key(), old_key, new_key });
+        msg!("[RotateOrganizationKey] Key {} replaced by {}", old_key, new_key);
+        Ok(())
+    }
+
+    /// Accredits `issuer` for `schema`. Accrediting again updates the terms and lifts a revocation.
+    pub fn accredit(ctx: Context<Accredit>, schema: [u8; 32], expires_at: u64, delegation_depth: u8) -> Result<()> {
+        let accreditor = acting_issuer(&ctx.accounts.accreditor.key(), &ctx.accounts.organization);
+        let accreditation = &mut ctx.accounts.accreditation;
+        accreditation.is_initialized = true;
+        accreditation.revoked = false;
+        accreditation.accreditor = accreditor;
+        accreditation.issuer = ctx.accounts.issuer.key();
+        accreditation.schema = schema;
+        accreditation.expires_at = expires_at;
+        accreditation.delegation_depth = delegation_depth;
+        emit!(AccreditationGranted {
+            accreditation: accreditation.key(),
+            accreditor,
+            issuer: accreditation.issuer,
+            schema,
+            expires_at,
+            delegation_depth,
+        });
+        msg!("[Accredit] {} accredited by {}", accreditation.issuer, accreditor);
+        Ok(())
+    }
+
+    pub fn revoke_accreditation(ctx: Context<RevokeAccreditation>) -> Result<()> {
+        let accreditation = &mut ctx.accounts.accreditation;
+        require!(!accreditation.revoked, IdenclaveError::AccreditationAlreadyRevoked);
+        accreditation.revoked = true;
+        emit!(AccreditationRevoked {
+            accreditation: accreditation.key(),
+            accreditor: accreditation.accreditor,
+            issuer: accreditation.issuer,
+        });
+        msg!("[RevokeAccreditation] Accreditation of {} revoked", accreditation.issuer);
+        Ok(())
+    }
+
+    /// Walks the accreditations passed as remaining accounts, from the one of the credential's
+    /// issuer up to `root`, and returns the status and the path walked as encoded by
+    /// `idenclave_core::AccreditationReport`.
+    pub fn query_accreditation<'info>(
+        ctx: Context<'_, '_, 'info, 'info, QueryAccreditation<'info>>,
+        root: Pubkey,
+        schema: [u8; 32],
+    ) -> Result<Vec<u8>> {
+        let chain = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| Account::<Accreditation>::try_from(account).map(|accreditation| accreditation.to_core()))
+            .collect::<Result<Vec<_>>>()?;
+        let issuer = ctx.accounts.credential.issuer;
+        let now = idenclave_core::unix_now(Clock::get()?.unix_timestamp);
+        let report = idenclave_core::evaluate_chain(&issuer.to_bytes(), &schema, &root.to_bytes(), &chain, now);
+        msg!("[QueryAccreditation] Issuer {} is {:?} after {} links", issuer, report.status, report.path.len() - 1);
+        Ok(report.pack())
+    }
+
+    /// Registers the verifying key `pack`ed by `idenclave_core::VerifyingKey` into a circuit
+    /// account the client allocated. Requests refer to circuits by address, so it never changes.
+    pub fn register_circuit(ctx: Context<RegisterCircuit>, verifying_key: Vec<u8>) -> Result<()> {
+        let verifying_key = idenclave_core::VerifyingKey::unpack(&verifying_key).ok_or(IdenclaveError::InvalidVerifyingKey)?;
+        let circuit = &mut ctx.accounts.circuit;
+        circuit.is_initialized = true;
+        circuit.authority = ctx.accounts.authority.key();
+        circuit.set_verifying_key(&verifying_key);
+        msg!("[RegisterCircuit] Circuit with {} public inputs registered", verifying_key.public_inputs());
+        Ok(())
+    }
+
+    pub fn create_presentation_request(
+        ctx: Context<CreatePresentationRequest>,
+        nonce: u64,
+        expires_at: u64,
+        schemas: Vec<[u8; 32]>,
+        trusted_issuers: Vec<Pubkey>,
+        predicate_inputs: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            schemas.len() <= idenclave_core::MAX_REQUEST_SCHEMAS
+                && trusted_issuers.len() <= idenclave_core::MAX_TRUSTED_ISSUERS
+                && predicate_inputs.len() <= idenclave_core::MAX_PREDICATE_INPUTS,
+            IdenclaveError::TooManyRequestEntries
+        );
+        // The commitment signal of the first schema's credential follows the predicate inputs
+        match &ctx.accounts.circuit {
+            Some(circuit) => {
+                require!(!schemas.is_empty(), IdenclaveError::MissingCircuitSchema);
+                require!(predicate_inputs.len() < circuit.public_inputs as usize, IdenclaveError::InvalidPredicateInputs);
+            }
+            None => require!(predicate_inputs.is_empty(), IdenclaveError::InvalidPredicateInputs),
+        }
+        let request = &mut ctx.accounts.request;
+        request.is_initialized = true;
+        request.verifier = ctx.accounts.verifier.key();
+        request.nonce = nonce;
+        request.expires_at = expires_at;
+        request.schema_count = schemas.len() as u8;
+        request.schemas[..schemas.len()].copy_from_slice(&schemas);
+        request.trusted_issuer_count = trusted_issuers.len() as u8;
+        request.trusted_issuers[..trusted_issuers.len()].copy_from_slice(&trusted_issuers);
+        request.circuit = ctx.accounts.circuit.as_ref().map_or(Pubkey::default(), |circuit| circuit.key());
+        request.predicate_input_count = predicate_inputs.len() as u8;
+        request.predicate_inputs[..predicate_inputs.len()].copy_from_slice(&predicate_inputs);
+        emit!(PresentationRequested { request: request.key(), verifier: request.verifier });
+        msg!("[CreatePresentationRequest] Request {} created", request.key());
+        Ok(())
+    }
+
+    /// Presents the credentials passed as remaining accounts, one per schema of the request at the
+    /// nonce in `nonces`, plus a proof for the request's circuit if it names one. Records and
+    /// returns the `idenclave_core::PresentationResult`; a later response replaces the record.
+    pub fn respond_to_request<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RespondToRequest<'info>>,
+        nonces: Vec<u64>,
+        proof: Option<Vec<u8>>,
+        public_inputs: Vec<[u8; 32]>,
+    ) -> Result<u8> {
+        let now = idenclave_core::unix_now(Clock::get()?.unix_timestamp);
+        let request = ctx.accounts.request.to_core();
+        require!(!request.is_expired(now), IdenclaveError::PresentationRequestExpired);
+
+        let mut verified_inputs = None;
+        if request.has_circuit() {
+            let circuit = ctx.accounts.circuit.as_ref().ok_or(IdenclaveError::CircuitMismatch)?;
+            if let Some(proof) = proof {
+                let proof = idenclave_core::Groth16Proof::unpack(&proof).ok_or(IdenclaveError::MalformedProof)?;
+                if !request.accepts_inputs(&public_inputs) {
+                    msg!("Proof is not for the request's predicate inputs");
+                } else if let Err(err) = idenclave_core::groth16::verify::<AltBn128>(&circuit.verifying_key(), &proof, &public_inputs) {
+                    msg!("Proof does not verify: {}", err);
+                } else {
+                    verified_inputs = Some(public_inputs.as_slice());
+                }
+            }
+        }
+
+        // A credential is shown to be of a schema by sitting at the address derived from it
+        let mut presented = Vec::with_capacity(nonces.len());
+        for ((schema, nonce), account) in request.schemas.iter().zip(&nonces).zip(ctx.remaining_accounts) {
+            let credential = Account::<Credential>::try_from(account)?;
+            let (expected, _) = Credential::find_address(&credential.issuer, &credential.identity, schema, *nonce);
+            presented.push((expected == account.key()).then(|| idenclave_core::PresentedCredential {
+                identity: credential.identity.to_bytes(),
+                issuer: credential.issuer.to_bytes(),
+                status: credential.status(now),
+                commitment: credential.commitment,
+            }));
+        }
+        let identity = ctx.accounts.identity.key();
+        let result = request.evaluate(&identity.to_bytes(), &presented, verified_inputs);
+
+        let response = &mut ctx.accounts.response;
+        response.is_initialized = true;
+        response.request = ctx.accounts.request.key();
+        response.identity = identity;
+        response.result = result as u8;
+        response.responded_at = now;
+        emit!(PresentationResponded { request: response.request, identity, result: result as u8 });
+        msg!("[RespondToRequest] {} responded to {}: {:?}", identity, response.request, result);
+        Ok(result as u8)
+    }
+
+    /// Checks the credential's status and keeps it in a new receipt, optionally with the hash of
+    /// evidence kept off-chain. Returns the status like `query_credential_status`.
+    pub fn record_verification(ctx: Context<RecordVerification>, id: u64, proof_hash: Option<[u8; 32]>) -> Result<u8> {
+        let clock = Clock::get()?;
+        let timestamp = idenclave_core::unix_now(clock.unix_timestamp);
+        let status = ctx.accounts.credential.status(timestamp);
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.is_initialized = true;
+        receipt.verifier = ctx.accounts.verifier.key();
+        receipt.credential = ctx.accounts.credential.key();
+        receipt.id = id;
+        receipt.result = status as u8;
+        receipt.slot = clock.slot;
+        receipt.timestamp = timestamp;
+        receipt.proof_hash = proof_hash.unwrap_or_default();
+        emit!(VerificationRecorded {
+            receipt: receipt.key(),
+            verifier: receipt.verifier,
+            credential: receipt.credential,
+            result: status as u8,
+        });
+        msg!("[RecordVerification] {} found credential {} {:?}", receipt.verifier, receipt.credential, status);
+        Ok(status as u8)
+    }
+
+    pub fn close_verification_receipt(ctx: Context<CloseVerificationReceipt>) -> Result<()> {
+        emit!(VerificationReceiptClosed { receipt: ctx.accounts.receipt.key(), verifier: ctx.accounts.verifier.key() });
+        msg!("[CloseVerificationReceipt] Receipt closed");
         Ok(())
     }
+
+    /// Commits the credential to the claims behind `claim_hashes` (see `idenclave_core::commitment`),
+    /// so proofs in presentations can be bound to it. Set once; re-issuing clears it.
+    pub fn commit_claims(ctx: Context<UpdateCredential>, claim_hashes: Vec<[u8; 32]>) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
+        credential.check_commit().map_err(IdenclaveError::from)?;
+        require!(credential.commitment == [0u8; 32], IdenclaveError::ClaimsAlreadyCommitted);
+        let commitment = idenclave_core::claims_commitment::<PoseidonSyscall>(&claim_hashes).ok_or(IdenclaveError::InvalidClaimHashes)?;
+        credential.commitment = commitment;
+        emit!(ClaimsCommitted { credential: credential.key(), issuer: credential.issuer, commitment });
+        msg!("[CommitClaims] Committed to {} claims", claim_hashes.len());
+        Ok(())
+    }
+
+    pub fn verify_claim(ctx: Context<VerifyClaim>, claim: Claim, index: u32, proof: Vec<[u8; 32]>) -> Result<u8> {
+        let credential = &ctx.accounts.credential;
+        let disclosure = idenclave_core::ClaimProof { claim: claim.to_core(), index, proof };
+        disclosure.verify::<Sha256Hasher>(&credential.claims_root).map_err(IdenclaveError::from)?;
+        // The claim is part of the credential either way; whether it still vouches for it is up to the status
+        let status = credential.status(idenclave_core::unix_now(Clock::get()?.unix_timestamp));
+        msg!("[VerifyClaim] Claim {} verified, credential is {:?}", claim.name, status);
+        Ok(status as u8)
+    }
+}
+
+/// Records a new credential on its identity: fills the freshly created index entry
+/// and bumps the identity's counters.
+pub(crate) fn index_credential(
+    identity: &mut Identity,
+    index: &mut CredentialIndex,
+    identity_key: Pubkey,
+    credential_key: Pubkey,
+) -> Result<()> {
+    index.is_initialized = true;
+    index.identity = identity_key;
+    index.index = identity.credential_count;
+    index.credential = credential_key;
+    identity.credential_count = identity.credential_count.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+    identity.active_credentials = identity.active_credentials.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Requires the instruction before the current one to be an Ed25519 program instruction verifying
+/// `signer`'s signature over exactly `message`; the Ed25519 program has already rejected the
+/// transaction if that signature is invalid.
+fn verify_ed25519_signature(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    use anchor_lang::solana_program::{ed25519_program, sysvar::instructions as instructions_sysvar};
+    let current = instructions_sysvar::load_current_index_checked(instructions)?;
+    let previous = current.checked_sub(1).ok_or(IdenclaveError::MissingIssuerSignature)?;
+    let ix = instructions_sysvar::load_instruction_at_checked(previous as usize, instructions)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, IdenclaveError::MissingIssuerSignature);
+    match idenclave_core::issuance::parse_ed25519_instruction(&ix.data) {
+        Some((pubkey, signed)) if pubkey == signer.to_bytes() && signed == message => Ok(()),
+        _ => err!(IdenclaveError::MissingIssuerSignature),
+    }
+}
+
+/// Counterpart of `index_credential` once the credential and its index entry are closed.
+fn unindex_credential(identity: &mut Identity) {
+    identity.active_credentials = identity.active_credentials.saturating_sub(1);
 }
 
+/// Fields are declared in the order of the canonical `idenclave_core::Identity` layout,
+/// so the account data after the 8-byte discriminator is identical to the native program's.
 #[account]
 pub struct Identity {
-    pub is_initialized: bool,
-    pub authority: Pubkey,
-    // Add other fields as needed (e.g., recovery, attributes)
+    pub is_initialized: bool,                // 1
+    pub authority: Pubkey,                   // 32
+    pub recovery: Pubkey,                    // 32
+    pub attributes: [u8; ATTRIBUTES_LEN],    // 128
+    pub active_credentials: u32,             // 4, credentials referencing this identity that have not been closed yet
+    pub credential_count: u64,               // 8, number of credential index entries ever created (next index to use)
+    pub service_count: u8,                   // 1
+    pub services: [[u8; SERVICE_LEN]; MAX_SERVICES], // 195 * 4, encoded `idenclave_core::Service` slots, the first `service_count` in use
+}
+
+impl Identity {
+    pub const LEN: usize = idenclave_core::Identity::LEN;
+
+    pub fn services(&self) -> Vec<idenclave_core::Service> {
+        self.services[..(self.service_count as usize).min(MAX_SERVICES)].iter().map(|slot| idenclave_core::Service::unpack_slot(slot)).collect()
+    }
+
+    /// Applies a change to the service entries with idenclave-core's rules.
+    pub fn update_services(
+        &mut self,
+        change: impl FnOnce(&mut idenclave_core::Identity) -> std::result::Result<(), idenclave_core::ServiceError>,
+    ) -> Result<()> {
+        let mut identity = idenclave_core::Identity { services: self.services(), ..Default::default() };
+        change(&mut identity).map_err(IdenclaveError::from)?;
+        self.service_count = identity.services.len() as u8;
+        for (i, slot) in self.services.iter_mut().enumerate() {
+            match identity.services.get(i) {
+                Some(service) => service.pack_slot(slot),
+                None => slot.fill(0),
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
 pub struct RegisterIdentity<'info> {
     #[account(
         init,
-        payer = authority,
-        space = 8 + 1 + 32, // 8 for Anchor discriminator, 1 for bool, 32 for Pubkey
+        payer = payer,
+        space = 8 + Identity::LEN, // 8 for Anchor discriminator
         seeds = [b"identity", authority.key().as_ref()],
         bump
     )]
-    pub identity: Account<'info, Identity>,
-    #[account(mut)]
+    pub identity: Box<Account<'info, Identity>>,
     pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: only read if it was created by a previous `close_identity`
+    #[account(seeds = [Tombstone::SEED, identity.key().as_ref()], bump)]
+    pub tombstone: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(credential_ref: [u8; 32])]
+pub struct UpdateIdentity<'info> {
+    #[account(mut, has_one = authority @ IdenclaveError::NotAuthority)]
+    pub identity: Box<Account<'info, Identity>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecoverAuthority<'info> {
+    #[account(mut, constraint = identity.recovery == recovery.key() @ IdenclaveError::NotRecovery)]
+    pub identity: Box<Account<'info, Identity>>,
+    pub recovery: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(schema: [u8; 32], nonce: u64)]
 pub struct IssueCredential<'info> {
     #[account(
         init,
-        payer = issuer,
+        payer = payer,
         space = 8 + Credential::LEN,
-        seeds = [b"credential", credential_ref.as_ref()],
+        seeds = [Credential::SEED, acting_issuer(&issuer.key(), &organization).as_ref(), identity.key().as_ref(), schema.as_ref(), &nonce.to_le_bytes()],
         bump
     )]
     pub credential: Account<'info, Credential>,
+    #[account(mut, has_one = authority @ IdenclaveError::NotAuthority)]
+    pub identity: Box<Account<'info, Identity>>,
+    pub issuer: Signer<'info>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
     #[account(
-        seeds = [b"identity", identity.authority.as_ref()],
-        bump,
-        has_one = authority
+        init,
+        payer = payer,
+        space = 8 + CredentialIndex::LEN,
+        seeds = [CredentialIndex::SEED, identity.key().as_ref(), &identity.credential_count.to_le_bytes()],
+        bump
     )]
-    pub identity: Account<'info, Identity>,
+    pub index: Account<'info, CredentialIndex>,
+    pub system_program: Program<'info, System>,
+    /// Issues on behalf of this organization, which `issuer` must be a current key of
+    #[account(constraint = organization.is_authorized(&issuer.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+#[instruction(schema: [u8; 32], nonce: u64)]
+pub struct IssueCredentialSigned<'info> {
+    // The address binds the signed issuer, identity, schema and nonce, so `init` refuses a replay
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Credential::LEN,
+        seeds = [Credential::SEED, acting_issuer(&issuer.key(), &organization).as_ref(), identity.key().as_ref(), schema.as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub credential: Account<'info, Credential>,
+    #[account(mut, has_one = authority @ IdenclaveError::NotAuthority)]
+    pub identity: Box<Account<'info, Identity>>,
+    /// CHECK: the identity's authority; it only has to sign for the credential to skip the offer stage
+    pub authority: UncheckedAccount<'info>,
+    /// CHECK: does not sign the transaction; its Ed25519 signature is verified through `instructions`
+    pub issuer: UncheckedAccount<'info>,
     #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CredentialIndex::LEN,
+        seeds = [CredentialIndex::SEED, identity.key().as_ref(), &identity.credential_count.to_le_bytes()],
+        bump
+    )]
+    pub index: Account<'info, CredentialIndex>,
+    /// CHECK: the instructions sysvar, checked by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    /// Issues on behalf of this organization, which the signing `issuer` must be a current key of
+    #[account(constraint = organization.is_authorized(&issuer.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCredential<'info> {
+    #[account(mut, constraint = credential.issuer == acting_issuer(&issuer.key(), &organization) @ IdenclaveError::NotIssuer)]
+    pub credential: Account<'info, Credential>,
     pub issuer: Signer<'info>,
-    pub authority: Signer<'info>,
+    /// Acts for this organization, which `issuer` must be a current key of
+    #[account(constraint = organization.is_authorized(&issuer.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+pub struct IssueCredentialBatch<'info> {
+    pub issuer: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
+    /// Offers on behalf of this organization, which `issuer` must be a current key of
+    #[account(constraint = organization.is_authorized(&issuer.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
 }
 
 #[derive(Accounts)]
-pub struct CloseIdentity<'info> {
+pub struct RevokeCredentialBatch<'info> {
+    pub issuer: Signer<'info>,
+    /// Revokes on behalf of this organization, which `issuer` must be a current key of
+    #[account(constraint = organization.is_authorized(&issuer.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCredentialTree<'info> {
+    /// CHECK: allocated by the client for this program; initialized as a `CredentialTree`
+    #[account(mut, owner = crate::ID, rent_exempt = enforce)]
+    pub tree: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct IssueCompressedCredential<'info> {
+    /// CHECK: read and written as a `CredentialTree`, whose authority must be `issuer`
+    #[account(mut, owner = crate::ID)]
+    pub tree: UncheckedAccount<'info>,
+    pub issuer: Signer<'info>,
+    #[account(has_one = authority @ IdenclaveError::NotAuthority)]
+    pub identity: Box<Account<'info, Identity>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeCompressedCredential<'info> {
+    /// CHECK: read and written as a `CredentialTree`
+    #[account(mut, owner = crate::ID)]
+    pub tree: UncheckedAccount<'info>,
+    pub issuer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyCompressedCredential<'info> {
+    /// CHECK: read as a `CredentialTree`
+    #[account(owner = crate::ID)]
+    pub tree: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueryCredentialStatus<'info> {
+    pub credential: Account<'info, Credential>,
+}
+
+#[derive(Accounts)]
+#[instruction(schema: [u8; 32], nonce: u64)]
+pub struct SupersedeCredential<'info> {
     #[account(
         mut,
-        close = authority,
-        seeds = [b"identity", authority.key().as_ref()],
+        constraint = old_credential.issuer == acting_issuer(&issuer.key(), &organization) @ IdenclaveError::NotIssuer,
+        has_one = identity @ IdenclaveError::IdentityMismatch
+    )]
+    pub old_credential: Account<'info, Credential>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Credential::LEN,
+        seeds = [Credential::SEED, acting_issuer(&issuer.key(), &organization).as_ref(), identity.key().as_ref(), schema.as_ref(), &nonce.to_le_bytes()],
         bump
     )]
-    pub identity: Account<'info, Identity>,
+    pub new_credential: Account<'info, Credential>,
+    pub issuer: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
     #[account(mut)]
+    pub identity: Box<Account<'info, Identity>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CredentialIndex::LEN,
+        seeds = [CredentialIndex::SEED, identity.key().as_ref(), &identity.credential_count.to_le_bytes()],
+        bump
+    )]
+    pub index: Account<'info, CredentialIndex>,
+    pub system_program: Program<'info, System>,
+    /// Issues on behalf of this organization, which `issuer` must be a current key of
+    #[account(constraint = organization.is_authorized(&issuer.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+#[instruction(schema: [u8; 32], nonce: u64)]
+pub struct OfferCredential<'info> {
+    #[account(mut)]
+    pub identity: Box<Account<'info, Identity>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Credential::LEN,
+        seeds = [Credential::SEED, acting_issuer(&issuer.key(), &organization).as_ref(), identity.key().as_ref(), schema.as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub credential: Account<'info, Credential>,
+    pub issuer: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CredentialIndex::LEN,
+        seeds = [CredentialIndex::SEED, identity.key().as_ref(), &identity.credential_count.to_le_bytes()],
+        bump
+    )]
+    pub index: Account<'info, CredentialIndex>,
+    pub system_program: Program<'info, System>,
+    /// Issues on behalf of this organization, which `issuer` must be a current key of
+    #[account(constraint = organization.is_authorized(&issuer.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptCredential<'info> {
+    #[account(has_one = authority @ IdenclaveError::NotAuthority)]
+    pub identity: Box<Account<'info, Identity>>,
+    #[account(mut, has_one = identity @ IdenclaveError::IdentityMismatch)]
+    pub credential: Account<'info, Credential>,
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct RejectCredential<'info> {
+    #[account(mut, has_one = authority @ IdenclaveError::NotAuthority)]
+    pub identity: Box<Account<'info, Identity>>,
+    #[account(
+        mut,
+        close = recipient,
+        has_one = identity @ IdenclaveError::IdentityMismatch,
+        has_one = issuer @ IdenclaveError::NotIssuer
+    )]
+    pub credential: Account<'info, Credential>,
+    pub authority: Signer<'info>,
+    /// CHECK: must match `credential.issuer`; it may sign to send the rent elsewhere
+    pub issuer: UncheckedAccount<'info>,
+    /// CHECK: receives the refunded rent; only the issuer may pick an account other than itself
+    #[account(
+        mut,
+        constraint = issuer.is_signer || recipient.key() == issuer.key() @ IdenclaveError::RecipientNotIssuer
+    )]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [CredentialIndex::SEED, identity.key().as_ref(), &index.index.to_le_bytes()],
+        bump,
+        constraint = index.credential == credential.key() @ IdenclaveError::IndexMismatch
+    )]
+    pub index: Account<'info, CredentialIndex>,
+}
+
+#[derive(Accounts)]
+pub struct CancelCredentialOffer<'info> {
+    #[account(
+        mut,
+        close = recipient,
+        constraint = credential.issuer == acting_issuer(&issuer.key(), &organization) @ IdenclaveError::NotIssuer,
+        has_one = identity @ IdenclaveError::IdentityMismatch
+    )]
+    pub credential: Account<'info, Credential>,
+    pub issuer: Signer<'info>,
+    /// CHECK: any account chosen by the issuer may receive the refunded rent
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub identity: Box<Account<'info, Identity>>,
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [CredentialIndex::SEED, identity.key().as_ref(), &index.index.to_le_bytes()],
+        bump,
+        constraint = index.credential == credential.key() @ IdenclaveError::IndexMismatch
+    )]
+    pub index: Account<'info, CredentialIndex>,
+    /// Acts for this organization, which `issuer` must be a current key of
+    #[account(constraint = organization.is_authorized(&issuer.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
 #[derive(Accounts)]
 pub struct CloseCredential<'info> {
     #[account(
         mut,
-        close = issuer,
-        seeds = [b"credential", credential.credential_ref.as_ref()],
+        close = recipient,
+        has_one = issuer @ IdenclaveError::NotIssuer,
+        has_one = identity @ IdenclaveError::IdentityMismatch
+    )]
+    pub credential: Account<'info, Credential>,
+    /// Either the issuer, a key of the issuing organization or the identity's current authority
+    pub closer: Signer<'info>,
+    /// CHECK: must match `credential.issuer`
+    pub issuer: UncheckedAccount<'info>,
+    /// CHECK: receives the refunded rent; only the issuer may pick an account other than itself
+    #[account(
+        mut,
+        constraint = acting_issuer(&closer.key(), &organization) == issuer.key() || recipient.key() == issuer.key()
+            @ IdenclaveError::RecipientNotIssuer
+    )]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub identity: Box<Account<'info, Identity>>,
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [CredentialIndex::SEED, identity.key().as_ref(), &index.index.to_le_bytes()],
+        bump,
+        constraint = index.credential == credential.key() @ IdenclaveError::IndexMismatch
+    )]
+    pub index: Account<'info, CredentialIndex>,
+    /// Acts for this organization, which `closer` must be a current key of
+    #[account(constraint = organization.is_authorized(&closer.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseIdentity<'info> {
+    #[account(
+        mut,
+        close = recipient,
+        has_one = authority @ IdenclaveError::NotAuthority,
+        constraint = identity.active_credentials == 0 @ IdenclaveError::ActiveCredentials
+    )]
+    pub identity: Box<Account<'info, Identity>>,
+    pub authority: Signer<'info>,
+    /// CHECK: any account may receive the identity's lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    // The payer funds the tombstone, the identity's own lamports all go to the recipient
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Tombstone::LEN,
+        seeds = [Tombstone::SEED, identity.key().as_ref()],
+        bump
+    )]
+    pub tombstone: Account<'info, Tombstone>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateOrganization<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Organization::LEN,
+        seeds = [Organization::SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub organization: Account<'info, Organization>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOrganization<'info> {
+    #[account(mut, has_one = authority @ IdenclaveError::NotAuthority)]
+    pub organization: Account<'info, Organization>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(schema: [u8; 32])]
+pub struct Accredit<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Accreditation::LEN,
+        seeds = [Accreditation::SEED, acting_issuer(&accreditor.key(), &organization).as_ref(), issuer.key().as_ref(), schema.as_ref()],
         bump
     )]
+    pub accreditation: Account<'info, Accreditation>,
+    pub accreditor: Signer<'info>,
+    /// CHECK: any account may be accredited, e.g. an issuer organization
+    pub issuer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Accredits on behalf of this organization, which `accreditor` must be a current key of
+    #[account(constraint = organization.is_authorized(&accreditor.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAccreditation<'info> {
+    #[account(mut, constraint = accreditation.accreditor == acting_issuer(&accreditor.key(), &organization) @ IdenclaveError::NotAccreditor)]
+    pub accreditation: Account<'info, Accreditation>,
+    pub accreditor: Signer<'info>,
+    /// Acts for this organization, which `accreditor` must be a current key of
+    #[account(constraint = organization.is_authorized(&accreditor.key()) @ IdenclaveError::NotOrganizationKey)]
+    pub organization: Option<Account<'info, Organization>>,
+}
+
+#[derive(Accounts)]
+pub struct QueryAccreditation<'info> {
     pub credential: Account<'info, Credential>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterCircuit<'info> {
+    #[account(zero)]
+    pub circuit: Box<Account<'info, Circuit>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CreatePresentationRequest<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PresentationRequest::LEN,
+        seeds = [PresentationRequest::SEED, verifier.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub request: Box<Account<'info, PresentationRequest>>,
+    pub verifier: Signer<'info>,
     #[account(mut)]
-    pub issuer: Signer<'info>,
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Circuit responses must carry a proof for
+    pub circuit: Option<Box<Account<'info, Circuit>>>,
+}
+
+#[derive(Accounts)]
+pub struct RespondToRequest<'info> {
+    pub request: Box<Account<'info, PresentationRequest>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PresentationResponse::LEN,
+        seeds = [PresentationResponse::SEED, request.key().as_ref(), identity.key().as_ref()],
+        bump
+    )]
+    pub response: Account<'info, PresentationResponse>,
+    #[account(has_one = authority @ IdenclaveError::NotAuthority)]
+    pub identity: Box<Account<'info, Identity>>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// The circuit the request names, if any
+    #[account(constraint = circuit.key() == request.circuit @ IdenclaveError::CircuitMismatch)]
+    pub circuit: Option<Box<Account<'info, Circuit>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct RecordVerification<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + VerificationReceipt::LEN,
+        seeds = [VerificationReceipt::SEED, verifier.key().as_ref(), credential.key().as_ref(), &id.to_le_bytes()],
+        bump
+    )]
+    pub receipt: Account<'info, VerificationReceipt>,
+    pub verifier: Signer<'info>,
+    pub credential: Account<'info, Credential>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVerificationReceipt<'info> {
+    #[account(mut, close = recipient, has_one = verifier @ IdenclaveError::NotVerifier)]
+    pub receipt: Account<'info, VerificationReceipt>,
+    pub verifier: Signer<'info>,
+    /// CHECK: receives the refunded rent
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyClaim<'info> {
+    pub credential: Account<'info, Credential>,
 }
@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use idenclave_core::{AccountLayout, OrganizationError, MAX_ORGANIZATION_KEYS};
+
+use crate::error::IdenclaveError;
+
+/// An issuer organization, stored in the PDA `[b"organization", authority]`. Any of its current
+/// keys issues and manages credentials that record the organization as their issuer; the
+/// authority manages the keys. Field order follows `idenclave_core::Organization`.
+#[account]
+#[derive(Default)]
+pub struct Organization {
+    pub is_initialized: bool,                    // 1
+    pub authority: Pubkey,                       // 32
+    pub key_count: u8,                           // 1
+    pub keys: [Pubkey; MAX_ORGANIZATION_KEYS],   // 32 * 8, the first `key_count` in use
+}
+
+impl Organization {
+    pub const SEED: &'static [u8] = idenclave_core::Organization::SEED;
+    pub const LEN: usize = idenclave_core::Organization::LEN;
+
+    pub fn is_authorized(&self, key: &Pubkey) -> bool {
+        self.to_core().is_authorized(&key.to_bytes())
+    }
+
+    /// Applies a key change with idenclave-core's rules.
+    pub fn update_keys(
+        &mut self,
+        change: impl FnOnce(&mut idenclave_core::Organization) -> std::result::Result<(), OrganizationError>,
+    ) -> Result<()> {
+        let mut organization = self.to_core();
+        change(&mut organization).map_err(IdenclaveError::from)?;
+        self.key_count = organization.key_count;
+        for (slot, key) in self.keys.iter_mut().zip(organization.keys) {
+            *slot = Pubkey::new_from_array(key);
+        }
+        Ok(())
+    }
+
+    fn to_core(&self) -> idenclave_core::Organization {
+        idenclave_core::Organization {
+            is_initialized: self.is_initialized,
+            authority: self.authority.to_bytes(),
+            key_count: self.key_count,
+            keys: self.keys.map(|key| key.to_bytes()),
+        }
+    }
+}
+
+/// The issuer a credential records when `issuer` signs: the organization it acts for, if any.
+pub fn acting_issuer(issuer: &Pubkey, organization: &Option<Account<Organization>>) -> Pubkey {
+    organization.as_ref().map_or(*issuer, |organization| organization.key())
+}
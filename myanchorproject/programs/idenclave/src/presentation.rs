@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use idenclave_core::groth16::{Scalar, G1};
+use idenclave_core::{
+    AccountLayout, Bn254, PoseidonHasher, VerifyingKey, MAX_PREDICATE_INPUTS, MAX_PUBLIC_INPUTS, MAX_REQUEST_SCHEMAS,
+    MAX_TRUSTED_ISSUERS,
+};
+use solana_bn254::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing};
+
+/// BN254 through the `alt_bn128` syscalls, for verifying presentation proofs.
+pub struct AltBn128;
+
+impl Bn254 for AltBn128 {
+    fn add(a: &G1, b: &G1) -> Option<G1> {
+        alt_bn128_addition(&[&a[..], &b[..]].concat()).ok()?.try_into().ok()
+    }
+
+    fn mul(point: &G1, scalar: &Scalar) -> Option<G1> {
+        alt_bn128_multiplication(&[&point[..], &scalar[..]].concat()).ok()?.try_into().ok()
+    }
+
+    fn pairing_is_one(pairs: &[u8]) -> Option<bool> {
+        let result = alt_bn128_pairing(pairs).ok()?;
+        Some(result.last() == Some(&1) && result[..result.len() - 1].iter().all(|&b| b == 0))
+    }
+}
+
+/// Poseidon through the `sol_poseidon` syscall, for the claim commitments proofs bind to. Off chain
+/// the hash is computed with `light-poseidon`, the implementation behind the syscall.
+pub struct PoseidonSyscall;
+
+impl PoseidonHasher for PoseidonSyscall {
+    #[cfg(target_os = "solana")]
+    fn hashv(inputs: &[Scalar]) -> Option<Scalar> {
+        let inputs: Vec<&[u8]> = inputs.iter().map(|input| &input[..]).collect();
+        let mut hash = [0u8; 32];
+        // Parameters 0 is BN254 with the x^5 S-box, endianness 0 big-endian
+        let result = unsafe {
+            solana_define_syscall::definitions::sol_poseidon(0, 0, inputs.as_ptr() as *const u8, inputs.len() as u64, hash.as_mut_ptr())
+        };
+        (result == 0).then_some(hash)
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn hashv(inputs: &[Scalar]) -> Option<Scalar> {
+        use light_poseidon::{Poseidon, PoseidonBytesHasher};
+        let inputs: Vec<&[u8]> = inputs.iter().map(|input| &input[..]).collect();
+        Poseidon::<ark_bn254::Fr>::new_circom(inputs.len()).ok()?.hash_bytes_be(&inputs).ok()
+    }
+}
+
+/// A circuit presentation requests can require proofs for, registered into an account the client
+/// allocated and never changed afterwards. Field order follows `idenclave_core::Circuit`.
+#[account]
+pub struct Circuit {
+    pub is_initialized: bool,                  // 1
+    pub authority: Pubkey,                     // 32
+    pub public_inputs: u8,                     // 1
+    pub alpha: [u8; 64],                       // 64
+    pub beta: [u8; 128],                       // 128
+    pub gamma: [u8; 128],                      // 128
+    pub delta: [u8; 128],                      // 128
+    pub ic: [[u8; 64]; MAX_PUBLIC_INPUTS + 1], // 64 * 9, the first `public_inputs + 1` in use
+}
+
+impl Circuit {
+    pub const LEN: usize = idenclave_core::Circuit::LEN;
+
+    pub fn set_verifying_key(&mut self, verifying_key: &VerifyingKey) {
+        self.public_inputs = verifying_key.public_inputs() as u8;
+        self.alpha = verifying_key.alpha;
+        self.beta = verifying_key.beta;
+        self.gamma = verifying_key.gamma;
+        self.delta = verifying_key.delta;
+        for (slot, point) in self.ic.iter_mut().zip(&verifying_key.ic) {
+            *slot = *point;
+        }
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey {
+            alpha: self.alpha,
+            beta: self.beta,
+            gamma: self.gamma,
+            delta: self.delta,
+            ic: self.ic[..self.public_inputs as usize + 1].to_vec(),
+        }
+    }
+}
+
+/// A verifier's presentation request, stored in the PDA `[b"presentation-request", verifier, nonce]`.
+/// See `idenclave_core::PresentationRequest` for what a response has to present. Field order
+/// follows it.
+#[account]
+#[derive(Default)]
+pub struct PresentationRequest {
+    pub is_initialized: bool,                               // 1
+    pub verifier: Pubkey,                                   // 32
+    pub nonce: u64,                                         // 8
+    pub expires_at: u64,                                    // 8
+    pub schema_count: u8,                                   // 1
+    pub schemas: [[u8; 32]; MAX_REQUEST_SCHEMAS],           // 32 * 4, the first `schema_count` in use
+    pub trusted_issuer_count: u8,                           // 1
+    pub trusted_issuers: [Pubkey; MAX_TRUSTED_ISSUERS],     // 32 * 8, any issuer when none are in use
+    pub circuit: Pubkey,                                    // 32, the default key for none
+    pub predicate_input_count: u8,                          // 1
+    pub predicate_inputs: [[u8; 32]; MAX_PREDICATE_INPUTS], // 32 * 4
+}
+
+impl PresentationRequest {
+    pub const SEED: &'static [u8] = idenclave_core::PresentationRequest::SEED;
+    pub const LEN: usize = idenclave_core::PresentationRequest::LEN;
+
+    pub fn to_core(&self) -> idenclave_core::PresentationRequest {
+        idenclave_core::PresentationRequest {
+            is_initialized: self.is_initialized,
+            verifier: self.verifier.to_bytes(),
+            nonce: self.nonce,
+            expires_at: self.expires_at,
+            schemas: self.schemas[..self.schema_count as usize].to_vec(),
+            trusted_issuers: self.trusted_issuers[..self.trusted_issuer_count as usize].iter().map(|issuer| issuer.to_bytes()).collect(),
+            circuit: self.circuit.to_bytes(),
+            predicate_inputs: self.predicate_inputs[..self.predicate_input_count as usize].to_vec(),
+        }
+    }
+}
+
+/// The recorded response of an identity to a presentation request, stored in the PDA
+/// `[b"presentation-response", request, identity]`. Field order follows
+/// `idenclave_core::PresentationResponse`.
+#[account]
+#[derive(Default)]
+pub struct PresentationResponse {
+    pub is_initialized: bool, // 1
+    pub request: Pubkey,      // 32
+    pub identity: Pubkey,     // 32
+    pub result: u8,           // 1, an `idenclave_core::PresentationResult`
+    pub responded_at: u64,    // 8
+}
+
+impl PresentationResponse {
+    pub const SEED: &'static [u8] = idenclave_core::PresentationResponse::SEED;
+    pub const LEN: usize = idenclave_core::PresentationResponse::LEN;
+}
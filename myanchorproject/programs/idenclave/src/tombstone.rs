@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+use idenclave_core::AccountLayout;
+
+/// Left behind at `[b"tombstone", identity]` when an identity is closed, so the
+/// identity can only be re-registered by the authority that closed it.
+#[account]
+#[derive(Default)]
+pub struct Tombstone {
+    pub is_initialized: bool, // 1
+    pub authority: Pubkey,    // 32
+}
+
+impl Tombstone {
+    pub const SEED: &'static [u8] = idenclave_core::Tombstone::SEED;
+    pub const LEN: usize = idenclave_core::Tombstone::LEN;
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use idenclave_core::AccountLayout;
+
+/// A verifier's record of having checked a credential, stored in the PDA
+/// `[b"verification-receipt", verifier, credential, id]`. Field order follows
+/// `idenclave_core::VerificationReceipt`.
+#[account]
+#[derive(Default)]
+pub struct VerificationReceipt {
+    pub is_initialized: bool, // 1
+    pub verifier: Pubkey,     // 32
+    pub credential: Pubkey,   // 32
+    pub id: u64,              // 8
+    pub result: u8,           // 1, credential status at the time of the check
+    pub slot: u64,            // 8
+    pub timestamp: u64,       // 8
+    pub proof_hash: [u8; 32], // 32, hash of off-chain evidence, all zeros for none
+}
+
+impl VerificationReceipt {
+    pub const SEED: &'static [u8] = idenclave_core::VerificationReceipt::SEED;
+    pub const LEN: usize = idenclave_core::VerificationReceipt::LEN;
+}
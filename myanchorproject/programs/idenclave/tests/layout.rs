@@ -0,0 +1,292 @@
+use anchor_lang::prelude::*;
+use anchor_lang::AccountSerialize;
+use idenclave::accreditation::Accreditation;
+use idenclave::credential::Credential;
+use idenclave::index::CredentialIndex;
+use idenclave::organization::Organization;
+use idenclave::presentation::{Circuit, PoseidonSyscall, PresentationRequest, PresentationResponse};
+use idenclave::tombstone::Tombstone;
+use idenclave::verification::VerificationReceipt;
+use idenclave::Identity;
+use idenclave_core::AccountLayout;
+
+// Anchor accounts must serialize to the canonical idenclave-core layout, discriminator included.
+fn anchor_bytes<T: AccountSerialize>(account: &T) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn test_identity_layout_matches_core() {
+    let mut attributes = [0u8; idenclave::ATTRIBUTES_LEN];
+    attributes[..3].copy_from_slice(b"abc");
+    let mut identity = Identity {
+        is_initialized: true,
+        authority: Pubkey::new_unique(),
+        recovery: Pubkey::new_unique(),
+        attributes,
+        active_credentials: 2,
+        credential_count: 5,
+        service_count: 0,
+        services: [[0u8; idenclave::SERVICE_LEN]; idenclave::MAX_SERVICES],
+    };
+    let services = vec![
+        idenclave_core::Service::new("messaging", "DIDCommMessaging", "https://example.com/didcomm"),
+        idenclave_core::Service::new("domain", "LinkedDomains", "https://example.com"),
+    ];
+    for service in &services {
+        identity.update_services(|core| core.add_service(service.clone())).unwrap();
+    }
+    let core = idenclave_core::Identity {
+        is_initialized: true,
+        authority: identity.authority.to_bytes(),
+        recovery: identity.recovery.to_bytes(),
+        attributes,
+        active_credentials: 2,
+        credential_count: 5,
+        services: services.clone(),
+    };
+    let mut expected = vec![0u8; idenclave_core::Identity::ACCOUNT_LEN];
+    core.pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&identity), expected);
+    assert_eq!(8 + Identity::LEN, expected.len());
+    assert_eq!(identity.services(), services);
+
+    // Removal leaves the same bytes as in the native program
+    identity.update_services(|core| core.remove_service("messaging")).unwrap();
+    let mut core = core;
+    core.remove_service("messaging").unwrap();
+    core.pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&identity), expected);
+}
+
+#[test]
+fn test_credential_layout_matches_core() {
+    let credential = Credential {
+        is_initialized: true,
+        revoked: false,
+        identity: Pubkey::new_unique(),
+        issuer: Pubkey::new_unique(),
+        credential_ref: [7u8; 32],
+        issued_at: 1_700_000_000,
+        expires_at: 1_800_000_000,
+        suspended: true,
+        previous: Pubkey::new_unique(),
+        superseded_by: Pubkey::new_unique(),
+        pending: true,
+        claims_root: [9u8; 32],
+        commitment: [10u8; 32],
+    };
+    let core = idenclave_core::Credential {
+        is_initialized: true,
+        revoked: false,
+        identity: credential.identity.to_bytes(),
+        issuer: credential.issuer.to_bytes(),
+        credential_ref: [7u8; 32],
+        issued_at: 1_700_000_000,
+        expires_at: 1_800_000_000,
+        suspended: true,
+        previous: credential.previous.to_bytes(),
+        superseded_by: credential.superseded_by.to_bytes(),
+        pending: true,
+        claims_root: [9u8; 32],
+        commitment: [10u8; 32],
+    };
+    let mut expected = vec![0u8; idenclave_core::Credential::ACCOUNT_LEN];
+    core.pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&credential), expected);
+}
+
+#[test]
+fn test_index_and_tombstone_layouts_match_core() {
+    let entry = CredentialIndex {
+        is_initialized: true,
+        identity: Pubkey::new_unique(),
+        index: 3,
+        credential: Pubkey::new_unique(),
+    };
+    let mut expected = vec![0u8; idenclave_core::CredentialIndex::ACCOUNT_LEN];
+    idenclave_core::CredentialIndex {
+        is_initialized: true,
+        identity: entry.identity.to_bytes(),
+        index: 3,
+        credential: entry.credential.to_bytes(),
+    }
+    .pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&entry), expected);
+
+    let tombstone = Tombstone { is_initialized: true, authority: Pubkey::new_unique() };
+    let mut expected = vec![0u8; idenclave_core::Tombstone::ACCOUNT_LEN];
+    idenclave_core::Tombstone { is_initialized: true, authority: tombstone.authority.to_bytes() }
+        .pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&tombstone), expected);
+}
+
+#[test]
+fn test_batch_item_encoding_matches_core() {
+    let item = idenclave::batch::BatchIssueItem { nonce: 3, credential_ref: [4; 32], issued_at: 5, expires_at: 6 };
+    let core = idenclave_core::BatchIssueItem { nonce: 3, credential_ref: [4; 32], issued_at: 5, expires_at: 6 };
+    let mut expected = Vec::new();
+    core.pack(&mut expected);
+    assert_eq!(item.try_to_vec().unwrap(), expected);
+}
+
+#[test]
+fn test_compressed_credential_encoding_matches_core() {
+    let credential = idenclave::compressed::CompressedCredential {
+        identity: Pubkey::new_unique(),
+        issuer: Pubkey::new_unique(),
+        schema: [1; 32],
+        credential_ref: [2; 32],
+        issued_at: 3,
+        expires_at: 4,
+        revoked: true,
+    };
+    let core = credential.to_core();
+    assert_eq!(credential.try_to_vec().unwrap(), core.pack().to_vec());
+    assert_eq!(credential.leaf_hash(), core.leaf_hash::<idenclave::compressed::Sha256Hasher>());
+}
+
+#[test]
+fn test_organization_layout_matches_core() {
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut organization = Organization { is_initialized: true, authority: Pubkey::new_unique(), ..Default::default() };
+    organization.update_keys(|organization| organization.add_key(&first.to_bytes())).unwrap();
+    organization.update_keys(|organization| organization.add_key(&second.to_bytes())).unwrap();
+    organization.update_keys(|organization| organization.remove_key(&first.to_bytes())).unwrap();
+    assert!(organization.is_authorized(&second) && !organization.is_authorized(&first));
+
+    let mut core = idenclave_core::Organization {
+        is_initialized: true,
+        authority: organization.authority.to_bytes(),
+        ..Default::default()
+    };
+    core.add_key(&second.to_bytes()).unwrap();
+    let mut expected = vec![0u8; idenclave_core::Organization::ACCOUNT_LEN];
+    core.pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&organization), expected);
+}
+
+#[test]
+fn test_accreditation_layout_matches_core() {
+    let accreditation = Accreditation {
+        is_initialized: true,
+        revoked: true,
+        accreditor: Pubkey::new_unique(),
+        issuer: Pubkey::new_unique(),
+        schema: [1; 32],
+        expires_at: 2,
+        delegation_depth: 3,
+    };
+    let mut expected = vec![0u8; idenclave_core::Accreditation::ACCOUNT_LEN];
+    accreditation.to_core().pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&accreditation), expected);
+}
+
+#[test]
+fn test_presentation_layouts_match_core() {
+    let verifying_key = idenclave_core::VerifyingKey {
+        alpha: [1; 64],
+        beta: [2; 128],
+        gamma: [3; 128],
+        delta: [4; 128],
+        ic: vec![[5; 64], [6; 64]],
+    };
+    let mut circuit = Circuit {
+        is_initialized: true,
+        authority: Pubkey::new_unique(),
+        public_inputs: 0,
+        alpha: [0; 64],
+        beta: [0; 128],
+        gamma: [0; 128],
+        delta: [0; 128],
+        ic: [[0; 64]; idenclave_core::MAX_PUBLIC_INPUTS + 1],
+    };
+    circuit.set_verifying_key(&verifying_key);
+    assert_eq!(circuit.verifying_key(), verifying_key);
+    let core = idenclave_core::Circuit { is_initialized: true, authority: circuit.authority.to_bytes(), verifying_key };
+    let mut expected = vec![0u8; idenclave_core::Circuit::ACCOUNT_LEN];
+    core.pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&circuit), expected);
+
+    let mut request = PresentationRequest {
+        is_initialized: true,
+        verifier: Pubkey::new_unique(),
+        nonce: 7,
+        expires_at: 8,
+        schema_count: 2,
+        trusted_issuer_count: 1,
+        circuit: Pubkey::new_unique(),
+        predicate_input_count: 1,
+        ..Default::default()
+    };
+    request.schemas[..2].copy_from_slice(&[[1; 32], [2; 32]]);
+    request.trusted_issuers[0] = Pubkey::new_unique();
+    request.predicate_inputs[0] = [3; 32];
+    let mut expected = vec![0u8; idenclave_core::PresentationRequest::ACCOUNT_LEN];
+    request.to_core().pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&request), expected);
+
+    let response = PresentationResponse {
+        is_initialized: true,
+        request: Pubkey::new_unique(),
+        identity: Pubkey::new_unique(),
+        result: 3,
+        responded_at: 9,
+    };
+    let core = idenclave_core::PresentationResponse {
+        is_initialized: true,
+        request: response.request.to_bytes(),
+        identity: response.identity.to_bytes(),
+        result: 3,
+        responded_at: 9,
+    };
+    let mut expected = vec![0u8; idenclave_core::PresentationResponse::ACCOUNT_LEN];
+    core.pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&response), expected);
+}
+
+#[test]
+fn test_verification_receipt_layout_matches_core() {
+    let receipt = VerificationReceipt {
+        is_initialized: true,
+        verifier: Pubkey::new_unique(),
+        credential: Pubkey::new_unique(),
+        id: 1,
+        result: 2,
+        slot: 3,
+        timestamp: 4,
+        proof_hash: [5; 32],
+    };
+    let core = idenclave_core::VerificationReceipt {
+        is_initialized: true,
+        verifier: receipt.verifier.to_bytes(),
+        credential: receipt.credential.to_bytes(),
+        id: 1,
+        result: 2,
+        slot: 3,
+        timestamp: 4,
+        proof_hash: [5; 32],
+    };
+    let mut expected = vec![0u8; idenclave_core::VerificationReceipt::ACCOUNT_LEN];
+    core.pack_account(&mut expected);
+    assert_eq!(anchor_bytes(&receipt), expected);
+}
+
+#[test]
+fn test_poseidon_matches_circomlib() {
+    // Poseidon(1, 2) from circomlib's test vectors, as the program's claim hash of salt 1 and value 2
+    let scalar = |value: u8| {
+        let mut bytes = [0u8; 32];
+        bytes[31] = value;
+        bytes
+    };
+    let expected = [
+        17, 92, 192, 245, 231, 214, 144, 65, 61, 246, 76, 107, 150, 98, 233, 207, 42, 54, 23, 242, 116, 50, 69, 81, 158, 25,
+        96, 122, 68, 23, 24, 154,
+    ];
+    assert_eq!(idenclave_core::claim_hash::<PoseidonSyscall>(&scalar(1), &scalar(2)), Some(expected));
+    assert_eq!(idenclave_core::claims_commitment::<PoseidonSyscall>(&[]), None);
+    assert_eq!(idenclave_core::claims_commitment::<PoseidonSyscall>(&[[0xff; 32]]), None);
+}
@@ -24,10 +24,24 @@ describe("idenclave", () => {
     )[0];
   };
 
-  // Helper function to get PDA for credential
-  const getCredentialPDA = (credentialRef: Uint8Array) => {
+  // Helper function to get PDA for the credential `issuer` gives `identity` under `schema` and `nonce`
+  const getCredentialPDA = (issuer: PublicKey, identity: PublicKey, schema: Uint8Array, nonce: number) => {
     return PublicKey.findProgramAddressSync(
-      [Buffer.from("credential"), credentialRef],
+      [
+        Buffer.from("credential"),
+        issuer.toBuffer(),
+        identity.toBuffer(),
+        schema,
+        new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+  };
+
+  // Helper function to get PDA for entry `n` of an identity's credential index
+  const getCredentialIndexPDA = (identity: PublicKey, n: number) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("cred-index"), identity.toBuffer(), new anchor.BN(n).toArrayLike(Buffer, "le", 8)],
       program.programId
     )[0];
   };
@@ -41,6 +55,8 @@ describe("idenclave", () => {
         .accounts({
           identity: identityPDA,
           authority: authority.publicKey,
+          recipient: authority.publicKey,
+          payer: provider.wallet.publicKey,
         })
         .rpc();
     } catch (err) {
@@ -58,6 +74,8 @@ describe("idenclave", () => {
         .accounts({
           identity: identityPDA,
           authority: authority.publicKey,
+          recipient: authority.publicKey,
+          payer: provider.wallet.publicKey,
         })
         .rpc();
     } catch (err) {
@@ -74,6 +92,7 @@ describe("idenclave", () => {
         .accounts({
           identity: identityPDA,
           authority: authority.publicKey,
+          payer: provider.wallet.publicKey,
           systemProgram: SystemProgram.programId,
         })
         .signers([authority])
@@ -93,6 +112,7 @@ describe("idenclave", () => {
           .accounts({
             identity: identityPDA,
             authority: unauthorized.publicKey,
+            payer: unauthorized.publicKey,
             systemProgram: SystemProgram.programId,
           })
           .signers([unauthorized])
@@ -110,24 +130,31 @@ describe("idenclave", () => {
     new TextEncoder().encode(`test-cred-${Date.now()}`).forEach((byte, i) => {
       if (i < 32) credentialRef[i] = byte;
     });
+    const schema = new Uint8Array(32).fill(1);
+    const nonce = 0;
     const issuedAt = Math.floor(Date.now() / 1000);
     const expiresAt = issuedAt + 365 * 24 * 60 * 60; // 1 year from now
 
     it("Successfully issues a credential", async () => {
       const identityPDA = getIdentityPDA(authority.publicKey);
-      const credentialPDA = getCredentialPDA(credentialRef);
+      const credentialPDA = getCredentialPDA(issuer.publicKey, identityPDA, schema, nonce);
 
       await program.methods
         .issueCredential(
+          Array.from(schema),
+          new anchor.BN(nonce),
           Array.from(credentialRef),
           new anchor.BN(issuedAt),
-          new anchor.BN(expiresAt)
+          new anchor.BN(expiresAt),
+          null
         )
         .accounts({
           credential: credentialPDA,
           identity: identityPDA,
           issuer: issuer.publicKey,
           authority: authority.publicKey,
+          payer: provider.wallet.publicKey,
+          index: getCredentialIndexPDA(identityPDA, 0),
           systemProgram: SystemProgram.programId,
         })
         .signers([issuer, authority])
@@ -141,32 +168,48 @@ describe("idenclave", () => {
       expect(credentialAccount.issuedAt.toNumber()).to.equal(issuedAt);
       expect(credentialAccount.expiresAt.toNumber()).to.equal(expiresAt);
 
-      // Clean up the credential after the test
+      // Clean up the credential after the test; only revoked or expired credentials can be closed
+      await program.methods
+        .revokeCredential()
+        .accounts({
+          credential: credentialPDA,
+          issuer: issuer.publicKey,
+        })
+        .rpc();
       await program.methods
         .closeCredential()
         .accounts({
           credential: credentialPDA,
+          closer: issuer.publicKey,
           issuer: issuer.publicKey,
+          recipient: issuer.publicKey,
+          identity: identityPDA,
+          index: getCredentialIndexPDA(identityPDA, 0),
         })
         .rpc();
     });
 
     it("Fails to issue credential with unauthorized issuer", async () => {
       const identityPDA = getIdentityPDA(authority.publicKey);
-      const credentialPDA = getCredentialPDA(credentialRef);
+      const credentialPDA = getCredentialPDA(unauthorized.publicKey, identityPDA, schema, nonce + 1);
 
       try {
         await program.methods
           .issueCredential(
+            Array.from(schema),
+            new anchor.BN(nonce + 1),
             Array.from(credentialRef),
             new anchor.BN(issuedAt),
-            new anchor.BN(expiresAt)
+            new anchor.BN(expiresAt),
+            null
           )
           .accounts({
             credential: credentialPDA,
             identity: identityPDA,
             issuer: unauthorized.publicKey,
             authority: authority.publicKey,
+            payer: provider.wallet.publicKey,
+            index: getCredentialIndexPDA(identityPDA, 1),
             systemProgram: SystemProgram.programId,
           })
           .signers([unauthorized, authority])
@@ -179,20 +222,25 @@ describe("idenclave", () => {
 
     it("Fails to issue credential with unauthorized authority", async () => {
       const identityPDA = getIdentityPDA(authority.publicKey);
-      const credentialPDA = getCredentialPDA(credentialRef);
+      const credentialPDA = getCredentialPDA(issuer.publicKey, identityPDA, schema, nonce + 1);
 
       try {
         await program.methods
           .issueCredential(
+            Array.from(schema),
+            new anchor.BN(nonce + 1),
             Array.from(credentialRef),
             new anchor.BN(issuedAt),
-            new anchor.BN(expiresAt)
+            new anchor.BN(expiresAt),
+            null
           )
           .accounts({
             credential: credentialPDA,
             identity: identityPDA,
             issuer: issuer.publicKey,
             authority: unauthorized.publicKey,
+            payer: provider.wallet.publicKey,
+            index: getCredentialIndexPDA(identityPDA, 1),
             systemProgram: SystemProgram.programId,
           })
           .signers([issuer, unauthorized])
@@ -0,0 +1,18 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Idenclave } from "../target/types/idenclave";
+import { AnchorIdenclaveClient } from "./shared/anchorClient";
+import { NativeIdenclaveClient } from "./shared/nativeClient";
+import { runIdenclaveSuite } from "./shared/suite";
+
+// The same scenarios run against both deployments so their semantics cannot drift apart.
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+runIdenclaveSuite("anchor", provider.connection, () => {
+  return new AnchorIdenclaveClient(anchor.workspace.Idenclave as Program<Idenclave>);
+});
+
+runIdenclaveSuite("native", provider.connection, () => {
+  return new NativeIdenclaveClient(provider.connection, provider.wallet.payer);
+});
@@ -0,0 +1,362 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Idenclave } from "../../target/types/idenclave";
+import {
+  BatchIssuance,
+  batchComputeBudget,
+  batchIndexes,
+  BatchOutcome,
+  batchResults,
+  CredentialState,
+  CredentialStatus,
+  decodeServices,
+  fetchCredentialCounts,
+  IdenclaveClient,
+  IdentityState,
+  Issuance,
+  issuanceSignature,
+  ISSUE_ITEM_COMPUTE,
+  REVOKE_ITEM_COMPUTE,
+  SCHEMA,
+  ServiceEntry,
+  splitBatch,
+} from "./client";
+
+// Drives the Anchor deployment through its generated client.
+export class AnchorIdenclaveClient implements IdenclaveClient {
+  constructor(private program: Program<Idenclave>) {}
+
+  private pda(seeds: (Buffer | Uint8Array)[]): PublicKey {
+    return PublicKey.findProgramAddressSync(seeds, this.program.programId)[0];
+  }
+
+  private indexPDA(identity: PublicKey, n: number): PublicKey {
+    return this.pda([Buffer.from("cred-index"), identity.toBuffer(), new anchor.BN(n).toArrayLike(Buffer, "le", 8)]);
+  }
+
+  private credentialPDA(issuer: PublicKey, identity: PublicKey, nonce: number, schema: Buffer = SCHEMA): PublicKey {
+    return this.pda([
+      Buffer.from("credential"),
+      issuer.toBuffer(),
+      identity.toBuffer(),
+      schema,
+      new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+    ]);
+  }
+
+  private async indexOf(credential: PublicKey): Promise<PublicKey> {
+    const entries = await this.program.account.credentialIndex.all([
+      { memcmp: { offset: 8 + 1 + 32 + 8, bytes: credential.toBase58() } },
+    ]);
+    return entries[0].publicKey;
+  }
+
+  async registerIdentity(authority: Keypair): Promise<PublicKey> {
+    const identity = this.pda([Buffer.from("identity"), authority.publicKey.toBuffer()]);
+    await this.program.methods
+      .registerIdentity()
+      .accountsPartial({
+        identity,
+        authority: authority.publicKey,
+        payer: this.program.provider.publicKey,
+        tombstone: this.pda([Buffer.from("tombstone"), identity.toBuffer()]),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+    return identity;
+  }
+
+  async updateAuthority(identity: PublicKey, authority: Keypair, newAuthority: PublicKey) {
+    await this.program.methods
+      .updateAuthority(newAuthority)
+      .accountsPartial({ identity, authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+  }
+
+  async setAttributes(identity: PublicKey, authority: Keypair, data: Buffer) {
+    await this.program.methods
+      .setAttributes(data)
+      .accountsPartial({ identity, authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+  }
+
+  async setRecovery(identity: PublicKey, authority: Keypair, recovery: PublicKey) {
+    await this.program.methods
+      .setRecovery(recovery)
+      .accountsPartial({ identity, authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+  }
+
+  async addService(identity: PublicKey, authority: Keypair, service: ServiceEntry) {
+    await this.program.methods
+      .addService(service.id, service.type, service.endpoint)
+      .accountsPartial({ identity, authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+  }
+
+  async updateService(identity: PublicKey, authority: Keypair, service: ServiceEntry) {
+    await this.program.methods
+      .updateService(service.id, service.type, service.endpoint)
+      .accountsPartial({ identity, authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+  }
+
+  async removeService(identity: PublicKey, authority: Keypair, id: string) {
+    await this.program.methods
+      .removeService(id)
+      .accountsPartial({ identity, authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+  }
+
+  async recoverAuthority(identity: PublicKey, recovery: Keypair, newAuthority: PublicKey) {
+    await this.program.methods
+      .recoverAuthority(newAuthority)
+      .accountsPartial({ identity, recovery: recovery.publicKey })
+      .signers([recovery])
+      .rpc();
+  }
+
+  async closeIdentity(identity: PublicKey, authority: Keypair, recipient: PublicKey) {
+    await this.program.methods
+      .closeIdentity()
+      .accountsPartial({
+        identity,
+        authority: authority.publicKey,
+        recipient,
+        payer: this.program.provider.publicKey,
+        tombstone: this.pda([Buffer.from("tombstone"), identity.toBuffer()]),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+  }
+
+  async fetchIdentity(identity: PublicKey): Promise<IdentityState> {
+    const account = await this.program.account.identity.fetch(identity);
+    return {
+      authority: account.authority,
+      recovery: account.recovery,
+      attributes: Buffer.from(account.attributes),
+      activeCredentials: account.activeCredentials,
+      credentialCount: account.credentialCount.toNumber(),
+      services: decodeServices(account.serviceCount, account.services.map((slot) => Buffer.from(slot))),
+    };
+  }
+
+  async issueCredential(
+    identity: PublicKey,
+    authority: Keypair,
+    issuer: Keypair,
+    credentialRef: Buffer,
+    issuedAt: number,
+    expiresAt: number
+  ): Promise<PublicKey> {
+    const { credentialCount } = await this.fetchIdentity(identity);
+    const credential = this.credentialPDA(issuer.publicKey, identity, credentialCount);
+    await this.program.methods
+      .issueCredential(
+        Array.from(SCHEMA),
+        new anchor.BN(credentialCount),
+        Array.from(credentialRef),
+        new anchor.BN(issuedAt),
+        new anchor.BN(expiresAt),
+        null
+      )
+      .accountsPartial({
+        credential,
+        identity,
+        issuer: issuer.publicKey,
+        authority: authority.publicKey,
+        payer: this.program.provider.publicKey,
+        index: this.indexPDA(identity, credentialCount),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([issuer, authority])
+      .rpc();
+    return credential;
+  }
+
+  async issueCredentialSigned(issuance: Issuance, issuer: Keypair, holder?: Keypair): Promise<PublicKey> {
+    const { identity } = issuance;
+    const { authority, credentialCount } = await this.fetchIdentity(identity);
+    const credential = this.credentialPDA(issuance.issuer, identity, issuance.nonce, issuance.schema);
+    await this.program.methods
+      .issueCredentialSigned(
+        Array.from(issuance.schema),
+        new anchor.BN(issuance.nonce),
+        Array.from(issuance.credentialRef),
+        new anchor.BN(issuance.issuedAt),
+        new anchor.BN(issuance.expiresAt),
+        new anchor.BN(issuance.deadline),
+        issuance.claimsRoot ? Array.from(issuance.claimsRoot) : null
+      )
+      .accountsPartial({
+        credential,
+        identity,
+        authority,
+        issuer: issuance.issuer,
+        payer: this.program.provider.publicKey,
+        index: this.indexPDA(identity, credentialCount),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      // `authority` is not declared a signer, so the holder's signature is requested through a duplicate meta
+      .remainingAccounts(holder ? [{ pubkey: authority, isSigner: true, isWritable: false }] : [])
+      .preInstructions([issuanceSignature(this.program.programId, issuance, issuer)])
+      .signers(holder ? [holder] : [])
+      .rpc();
+    return credential;
+  }
+
+  private async sendBatch(ixs: TransactionInstruction[], issuer: Keypair) {
+    const signature = await this.program.provider.sendAndConfirm(new Transaction().add(...ixs), [issuer], {
+      commitment: "confirmed",
+    });
+    return await batchResults(this.program.provider.connection, signature);
+  }
+
+  private async issueBatchIx(issuer: PublicKey, schema: Buffer, items: BatchIssuance[], counts: Map<string, number>) {
+    const indexes = batchIndexes(items.map((item) => item.identity), counts);
+    return await this.program.methods
+      .issueCredentialBatch(
+        Array.from(schema),
+        items.map((item) => ({
+          nonce: new anchor.BN(item.nonce),
+          credentialRef: Array.from(item.credentialRef),
+          issuedAt: new anchor.BN(item.issuedAt),
+          expiresAt: new anchor.BN(item.expiresAt),
+        }))
+      )
+      .accountsPartial({ issuer, payer: this.program.provider.publicKey, systemProgram: SystemProgram.programId })
+      .remainingAccounts(
+        items.flatMap((item, i) =>
+          [item.identity, this.credentialPDA(issuer, item.identity, item.nonce, schema), this.indexPDA(item.identity, indexes[i])].map(
+            (pubkey) => ({ pubkey, isSigner: false, isWritable: true })
+          )
+        )
+      )
+      .instruction();
+  }
+
+  async issueCredentialBatch(issuer: Keypair, schema: Buffer, items: BatchIssuance[]): Promise<BatchOutcome[]> {
+    const outcomes: BatchOutcome[] = [];
+    const chunks = await splitBatch(items, this.program.provider.publicKey, ISSUE_ITEM_COMPUTE, (chunk) =>
+      this.issueBatchIx(issuer.publicKey, schema, chunk, new Map())
+    );
+    for (const chunk of chunks) {
+      // Index entries are taken in order, so read every holder's next entry right before sending
+      const counts = await fetchCredentialCounts(this, chunk.map((item) => item.identity));
+      const results = await this.sendBatch(
+        [batchComputeBudget(chunk.length, ISSUE_ITEM_COMPUTE), await this.issueBatchIx(issuer.publicKey, schema, chunk, counts)],
+        issuer
+      );
+      chunk.forEach((item, i) =>
+        outcomes.push({ credential: this.credentialPDA(issuer.publicKey, item.identity, item.nonce, schema), result: results[i] })
+      );
+    }
+    return outcomes;
+  }
+
+  async revokeCredentialBatch(issuer: Keypair, credentials: PublicKey[]): Promise<BatchOutcome[]> {
+    const outcomes: BatchOutcome[] = [];
+    const build = (chunk: PublicKey[]) =>
+      this.program.methods
+        .revokeCredentialBatch()
+        .accountsPartial({ issuer: issuer.publicKey })
+        .remainingAccounts(chunk.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .instruction();
+    for (const chunk of await splitBatch(credentials, this.program.provider.publicKey, REVOKE_ITEM_COMPUTE, build)) {
+      const results = await this.sendBatch([batchComputeBudget(chunk.length, REVOKE_ITEM_COMPUTE), await build(chunk)], issuer);
+      chunk.forEach((credential, i) => outcomes.push({ credential, result: results[i] }));
+    }
+    return outcomes;
+  }
+
+  async revokeCredential(credential: PublicKey, issuer: Keypair) {
+    await this.program.methods
+      .revokeCredential()
+      .accountsPartial({ credential, issuer: issuer.publicKey })
+      .signers([issuer])
+      .rpc();
+  }
+
+  async suspendCredential(credential: PublicKey, issuer: Keypair) {
+    await this.program.methods
+      .suspendCredential()
+      .accountsPartial({ credential, issuer: issuer.publicKey })
+      .signers([issuer])
+      .rpc();
+  }
+
+  async reinstateCredential(credential: PublicKey, issuer: Keypair) {
+    await this.program.methods
+      .reinstateCredential()
+      .accountsPartial({ credential, issuer: issuer.publicKey })
+      .signers([issuer])
+      .rpc();
+  }
+
+  async renewCredential(credential: PublicKey, issuer: Keypair, expiresAt: number) {
+    await this.program.methods
+      .renewCredential(new anchor.BN(expiresAt))
+      .accountsPartial({ credential, issuer: issuer.publicKey })
+      .signers([issuer])
+      .rpc();
+  }
+
+  async reissueCredential(
+    credential: PublicKey,
+    issuer: Keypair,
+    credentialRef: Buffer,
+    issuedAt: number,
+    expiresAt: number
+  ) {
+    await this.program.methods
+      .reissueCredential(Array.from(credentialRef), new anchor.BN(issuedAt), new anchor.BN(expiresAt), null)
+      .accountsPartial({ credential, issuer: issuer.publicKey })
+      .signers([issuer])
+      .rpc();
+  }
+
+  async closeCredential(credential: PublicKey, closer: Keypair, recipient?: PublicKey) {
+    const state = await this.fetchCredential(credential);
+    await this.program.methods
+      .closeCredential()
+      .accountsPartial({
+        credential,
+        closer: closer.publicKey,
+        issuer: state.issuer,
+        recipient: recipient ?? state.issuer,
+        identity: state.identity,
+        index: await this.indexOf(credential),
+      })
+      .signers([closer])
+      .rpc();
+  }
+
+  async queryCredentialStatus(credential: PublicKey): Promise<CredentialStatus> {
+    return await this.program.methods.queryCredentialStatus().accountsPartial({ credential }).view();
+  }
+
+  async fetchCredential(credential: PublicKey): Promise<CredentialState> {
+    const account = await this.program.account.credential.fetch(credential);
+    return {
+      revoked: account.revoked,
+      suspended: account.suspended,
+      pending: account.pending,
+      identity: account.identity,
+      issuer: account.issuer,
+      credentialRef: Buffer.from(account.credentialRef),
+      issuedAt: account.issuedAt.toNumber(),
+      expiresAt: account.expiresAt.toNumber(),
+    };
+  }
+}
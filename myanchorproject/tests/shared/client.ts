@@ -0,0 +1,277 @@
+import { BN } from "@coral-xyz/anchor";
+import {
+  ComputeBudgetProgram,
+  Connection,
+  Ed25519Program,
+  Keypair,
+  PublicKey,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+
+// Schema of the credentials issued through `issueCredential`. Both programs derive a credential's
+// address from its schema and nonce; the identity's credential count serves as the nonce, so every
+// issuance gets a fresh address.
+export const SCHEMA = Buffer.alloc(32, 1);
+
+// Program-agnostic view of an identity, decoded from either deployment.
+export interface IdentityState {
+  authority: PublicKey;
+  recovery: PublicKey;
+  attributes: Buffer;
+  activeCredentials: number;
+  credentialCount: number;
+  services: ServiceEntry[];
+}
+
+// A DID service entry of an identity; see `Service` in idenclave-core.
+export interface ServiceEntry {
+  id: string;
+  type: string;
+  endpoint: string;
+}
+
+export interface CredentialState {
+  revoked: boolean;
+  suspended: boolean;
+  pending: boolean;
+  identity: PublicKey;
+  issuer: PublicKey;
+  credentialRef: Buffer;
+  issuedAt: number;
+  expiresAt: number;
+}
+
+// Mirrors `CredentialStatus` in both programs.
+export enum CredentialStatus {
+  Valid = 0,
+  Expired = 1,
+  Revoked = 2,
+  Suspended = 3,
+  Superseded = 4,
+  Pending = 5,
+}
+
+// Terms an issuer signs offline for `IssueCredentialSigned`; see `IssuanceMessage` in idenclave-core.
+export interface Issuance {
+  // The signing key, or the organization it signs for
+  issuer: PublicKey;
+  identity: PublicKey;
+  schema: Buffer;
+  nonce: number;
+  credentialRef: Buffer;
+  issuedAt: number;
+  expiresAt: number;
+  // Root of the credential's selectively disclosable claims, if it has any
+  claimsRoot?: Buffer;
+  deadline: number;
+}
+
+const u64 = (n: number) => new BN(n).toArrayLike(Buffer, "le", 8);
+
+// Canonical issuance message, byte for byte what both programs rebuild and check.
+export function issuanceMessage(programId: PublicKey, issuance: Issuance): Buffer {
+  return Buffer.concat([
+    Buffer.from("idenclave:issue:v2"),
+    programId.toBuffer(),
+    issuance.issuer.toBuffer(),
+    issuance.identity.toBuffer(),
+    issuance.schema,
+    u64(issuance.nonce),
+    issuance.credentialRef,
+    u64(issuance.issuedAt),
+    u64(issuance.expiresAt),
+    issuance.claimsRoot ?? Buffer.alloc(32),
+    u64(issuance.deadline),
+  ]);
+}
+
+// The Ed25519 program instruction that has to precede `IssueCredentialSigned`. Signing happens
+// here with the issuer's keypair only to stand in for the issuer's offline signer.
+export function issuanceSignature(programId: PublicKey, issuance: Issuance, issuer: Keypair): TransactionInstruction {
+  return Ed25519Program.createInstructionWithPrivateKey({
+    privateKey: issuer.secretKey,
+    message: issuanceMessage(programId, issuance),
+  });
+}
+
+// Size limits of a service entry's id, type and endpoint, and the most entries an identity holds.
+export const SERVICE_FIELD_LENS = [32, 32, 128];
+export const MAX_SERVICES = 4;
+export const SERVICE_LEN = SERVICE_FIELD_LENS.reduce((len, field) => len + 1 + field, 0);
+
+// Encoding of a service entry in `AddService`/`UpdateService`: each field's length, then its bytes.
+export function encodeService(service: ServiceEntry): Buffer {
+  return Buffer.concat(
+    [service.id, service.type, service.endpoint].map((field) => {
+      const bytes = Buffer.from(field, "utf8");
+      return Buffer.concat([Buffer.from([bytes.length]), bytes]);
+    })
+  );
+}
+
+// Decodes the first `count` of the identity's service slots: per field a length, then a zero-padded buffer.
+export function decodeServices(count: number, slots: Buffer[]): ServiceEntry[] {
+  return slots.slice(0, Math.min(count, MAX_SERVICES)).map((slot) => {
+    let offset = 0;
+    const [id, type, endpoint] = SERVICE_FIELD_LENS.map((max) => {
+      const field = slot.subarray(offset + 1, offset + 1 + Math.min(slot[offset], max)).toString("utf8");
+      offset += 1 + max;
+      return field;
+    });
+    return { id, type, endpoint };
+  });
+}
+
+// Mirrors `BatchItemResult` in idenclave-core.
+export enum BatchItemResult {
+  Ok = 0,
+  InvalidAccount = 1,
+  NotIssuer = 2,
+  AlreadyExists = 3,
+  AlreadyRevoked = 4,
+  InvalidState = 5,
+}
+
+// One credential of a batch issuance; it is offered at the credential PDA for the batch's schema and `nonce`.
+export interface BatchIssuance {
+  identity: PublicKey;
+  nonce: number;
+  credentialRef: Buffer;
+  issuedAt: number;
+  expiresAt: number;
+}
+
+export interface BatchOutcome {
+  credential: PublicKey;
+  result: BatchItemResult;
+}
+
+// Limits a batch has to fit in: the programs' item cap, the packet size of a legacy transaction and
+// the compute a transaction may request. The per-item costs are generous estimates; issuing an item
+// creates two accounts and derives two PDAs.
+export const MAX_BATCH_ITEMS = 32;
+const MAX_TRANSACTION_SIZE = 1232;
+const MAX_COMPUTE_UNITS = 1_400_000;
+const BATCH_BASE_COMPUTE = 20_000;
+export const ISSUE_ITEM_COMPUTE = 40_000;
+export const REVOKE_ITEM_COMPUTE = 8_000;
+
+// Encoding of `BatchIssueItem`, shared by both programs.
+export function batchIssueItem(item: BatchIssuance): Buffer {
+  return Buffer.concat([u64(item.nonce), item.credentialRef, u64(item.issuedAt), u64(item.expiresAt)]);
+}
+
+// Raises the compute limit for a batch of `items` items.
+export function batchComputeBudget(items: number, computePerItem: number): TransactionInstruction {
+  return ComputeBudgetProgram.setComputeUnitLimit({
+    units: Math.min(MAX_COMPUTE_UNITS, BATCH_BASE_COMPUTE + items * computePerItem),
+  });
+}
+
+// Splits `items` into the largest chunks whose batch instruction, as made by `build`, still fits in
+// one transaction paid by `payer`, within the compute limit and the programs' item cap.
+export async function splitBatch<T>(
+  items: T[],
+  payer: PublicKey,
+  computePerItem: number,
+  build: (chunk: T[]) => Promise<TransactionInstruction>
+): Promise<T[][]> {
+  const maxItems = Math.min(MAX_BATCH_ITEMS, Math.floor((MAX_COMPUTE_UNITS - BATCH_BASE_COMPUTE) / computePerItem));
+  const fits = async (chunk: T[]) => {
+    const tx = new Transaction({ feePayer: payer, recentBlockhash: PublicKey.default.toBase58() }).add(
+      batchComputeBudget(chunk.length, computePerItem),
+      await build(chunk)
+    );
+    const message = tx.compileMessage();
+    // Signature count (a one-byte compact length here), the signatures, then the message
+    return 1 + 64 * message.header.numRequiredSignatures + message.serialize().length <= MAX_TRANSACTION_SIZE;
+  };
+  const chunks: T[][] = [];
+  let chunk: T[] = [];
+  for (const item of items) {
+    if (chunk.length > 0 && (chunk.length === maxItems || !(await fits([...chunk, item])))) {
+      chunks.push(chunk);
+      chunk = [];
+    }
+    chunk.push(item);
+  }
+  if (chunk.length > 0) {
+    chunks.push(chunk);
+  }
+  return chunks;
+}
+
+// The index entry each item of a batch takes, given every holder's current credential count.
+// Items are assumed to succeed; skipped items shift the entries of later items for the same holder.
+export function batchIndexes(identities: PublicKey[], counts: Map<string, number>): number[] {
+  const next = new Map(counts);
+  return identities.map((identity) => {
+    const n = next.get(identity.toBase58()) ?? 0;
+    next.set(identity.toBase58(), n + 1);
+    return n;
+  });
+}
+
+export async function fetchCredentialCounts(client: IdenclaveClient, identities: PublicKey[]): Promise<Map<string, number>> {
+  const counts = new Map<string, number>();
+  for (const identity of identities) {
+    if (!counts.has(identity.toBase58())) {
+      counts.set(identity.toBase58(), (await client.fetchIdentity(identity)).credentialCount);
+    }
+  }
+  return counts;
+}
+
+// Reads the per-item results a batch instruction left as return data: a u32 LE count, then one code per item.
+export async function batchResults(connection: Connection, signature: string): Promise<BatchItemResult[]> {
+  const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+  const data = Buffer.from(tx.meta.returnData.data[0], "base64");
+  return Array.from(data.subarray(4, 4 + data.readUInt32LE(0)));
+}
+
+// The operations the shared suite exercises. Each implementation builds the
+// instructions for its own program; every method throws if the transaction fails.
+export interface IdenclaveClient {
+  registerIdentity(authority: Keypair): Promise<PublicKey>;
+  updateAuthority(identity: PublicKey, authority: Keypair, newAuthority: PublicKey): Promise<void>;
+  setAttributes(identity: PublicKey, authority: Keypair, data: Buffer): Promise<void>;
+  setRecovery(identity: PublicKey, authority: Keypair, recovery: PublicKey): Promise<void>;
+  recoverAuthority(identity: PublicKey, recovery: Keypair, newAuthority: PublicKey): Promise<void>;
+  addService(identity: PublicKey, authority: Keypair, service: ServiceEntry): Promise<void>;
+  updateService(identity: PublicKey, authority: Keypair, service: ServiceEntry): Promise<void>;
+  removeService(identity: PublicKey, authority: Keypair, id: string): Promise<void>;
+  closeIdentity(identity: PublicKey, authority: Keypair, recipient: PublicKey): Promise<void>;
+  fetchIdentity(identity: PublicKey): Promise<IdentityState>;
+
+  issueCredential(
+    identity: PublicKey,
+    authority: Keypair,
+    issuer: Keypair,
+    credentialRef: Buffer,
+    issuedAt: number,
+    expiresAt: number
+  ): Promise<PublicKey>;
+  // Submitted by the client's own payer; the credential is only live right away if `holder` signs too.
+  issueCredentialSigned(issuance: Issuance, issuer: Keypair, holder?: Keypair): Promise<PublicKey>;
+  // Batches are split over as many transactions as needed; outcomes are in item order. Batch issuance
+  // only offers: every credential stays pending until its holder accepts it, so credentials for
+  // holders who already consented go through `issueCredential` or `issueCredentialSigned` instead.
+  issueCredentialBatch(issuer: Keypair, schema: Buffer, items: BatchIssuance[]): Promise<BatchOutcome[]>;
+  revokeCredentialBatch(issuer: Keypair, credentials: PublicKey[]): Promise<BatchOutcome[]>;
+  revokeCredential(credential: PublicKey, issuer: Keypair): Promise<void>;
+  suspendCredential(credential: PublicKey, issuer: Keypair): Promise<void>;
+  reinstateCredential(credential: PublicKey, issuer: Keypair): Promise<void>;
+  renewCredential(credential: PublicKey, issuer: Keypair, expiresAt: number): Promise<void>;
+  reissueCredential(
+    credential: PublicKey,
+    issuer: Keypair,
+    credentialRef: Buffer,
+    issuedAt: number,
+    expiresAt: number
+  ): Promise<void>;
+  // Rent is refunded to `recipient`, which defaults to the issuer; only the issuer may pick another account.
+  closeCredential(credential: PublicKey, closer: Keypair, recipient?: PublicKey): Promise<void>;
+  queryCredentialStatus(credential: PublicKey): Promise<CredentialStatus>;
+  fetchCredential(credential: PublicKey): Promise<CredentialState>;
+}
@@ -0,0 +1,380 @@
+import { BN } from "@coral-xyz/anchor";
+import {
+  Connection,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Transaction,
+  TransactionInstruction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  BatchIssuance,
+  batchComputeBudget,
+  batchIndexes,
+  batchIssueItem,
+  BatchOutcome,
+  batchResults,
+  CredentialState,
+  CredentialStatus,
+  decodeServices,
+  encodeService,
+  fetchCredentialCounts,
+  IdenclaveClient,
+  IdentityState,
+  Issuance,
+  issuanceSignature,
+  ISSUE_ITEM_COMPUTE,
+  MAX_SERVICES,
+  REVOKE_ITEM_COMPUTE,
+  SCHEMA,
+  SERVICE_LEN,
+  ServiceEntry,
+  splitBatch,
+} from "./client";
+
+// Address the native program (packages/idenclave) is loaded at by the test validator.
+export const NATIVE_PROGRAM_ID = new PublicKey("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K");
+
+// Every account starts with the same 8-byte discriminator Anchor uses for the type.
+const DISCRIMINATOR_LEN = 8;
+const IDENTITY_LEN = DISCRIMINATOR_LEN + 1 + 32 + 32 + 128 + 4 + 8 + 1 + SERVICE_LEN * MAX_SERVICES;
+const CREDENTIAL_INDEX_LEN = DISCRIMINATOR_LEN + 1 + 32 + 8 + 32;
+
+const u64 = (n: number) => new BN(n).toArrayLike(Buffer, "le", 8);
+const readU64 = (data: Buffer, offset: number) => new BN(data.subarray(offset, offset + 8), "le").toNumber();
+
+// Drives the native deployment with hand-encoded `IdentityInstruction`s, using
+// keypair-addressed identity accounts like the Rust tests do.
+export class NativeIdenclaveClient implements IdenclaveClient {
+  constructor(private connection: Connection, private payer: Keypair, private programId = NATIVE_PROGRAM_ID) {}
+
+  private pda(seeds: Buffer[]): PublicKey {
+    return PublicKey.findProgramAddressSync(seeds, this.programId)[0];
+  }
+
+  private ix(keys: [PublicKey, boolean, boolean][], data: Buffer): TransactionInstruction {
+    return new TransactionInstruction({
+      programId: this.programId,
+      keys: keys.map(([pubkey, isSigner, isWritable]) => ({ pubkey, isSigner, isWritable })),
+      data,
+    });
+  }
+
+  private async send(ixs: TransactionInstruction[], signers: Keypair[]): Promise<string> {
+    const tx = new Transaction().add(...ixs);
+    const unique = [this.payer, ...signers].filter(
+      (s, i, all) => all.findIndex((o) => o.publicKey.equals(s.publicKey)) === i
+    );
+    return await sendAndConfirmTransaction(this.connection, tx, unique, { commitment: "confirmed" });
+  }
+
+  private credentialPDA(issuer: PublicKey, identity: PublicKey, schema: Buffer, nonce: number): PublicKey {
+    return this.pda([Buffer.from("credential"), issuer.toBuffer(), identity.toBuffer(), schema, u64(nonce)]);
+  }
+
+  private async createAccount(account: Keypair, space: number): Promise<TransactionInstruction> {
+    return SystemProgram.createAccount({
+      fromPubkey: this.payer.publicKey,
+      newAccountPubkey: account.publicKey,
+      lamports: await this.connection.getMinimumBalanceForRentExemption(space),
+      space,
+      programId: this.programId,
+    });
+  }
+
+  private async indexOf(credential: PublicKey): Promise<PublicKey> {
+    const entries = await this.connection.getProgramAccounts(this.programId, {
+      filters: [{ dataSize: CREDENTIAL_INDEX_LEN }, { memcmp: { offset: DISCRIMINATOR_LEN + 1 + 32 + 8, bytes: credential.toBase58() } }],
+    });
+    return entries[0].pubkey;
+  }
+
+  async registerIdentity(authority: Keypair): Promise<PublicKey> {
+    const identity = Keypair.generate();
+    await this.send(
+      [
+        await this.createAccount(identity, IDENTITY_LEN),
+        this.ix(
+          [
+            [identity.publicKey, false, true],
+            [authority.publicKey, true, true],
+            [this.pda([Buffer.from("tombstone"), identity.publicKey.toBuffer()]), false, false],
+          ],
+          Buffer.from([0])
+        ),
+      ],
+      [authority, identity]
+    );
+    return identity.publicKey;
+  }
+
+  private async identityUpdate(identity: PublicKey, signer: Keypair, data: Buffer) {
+    await this.send(
+      [this.ix([[identity, false, true], [signer.publicKey, true, false]], data)],
+      [signer]
+    );
+  }
+
+  async updateAuthority(identity: PublicKey, authority: Keypair, newAuthority: PublicKey) {
+    await this.identityUpdate(identity, authority, Buffer.concat([Buffer.from([1]), newAuthority.toBuffer()]));
+  }
+
+  async setAttributes(identity: PublicKey, authority: Keypair, data: Buffer) {
+    await this.identityUpdate(identity, authority, Buffer.concat([Buffer.from([3]), data]));
+  }
+
+  async setRecovery(identity: PublicKey, authority: Keypair, recovery: PublicKey) {
+    await this.identityUpdate(identity, authority, Buffer.concat([Buffer.from([4]), recovery.toBuffer()]));
+  }
+
+  async recoverAuthority(identity: PublicKey, recovery: Keypair, newAuthority: PublicKey) {
+    await this.identityUpdate(identity, recovery, Buffer.concat([Buffer.from([5]), newAuthority.toBuffer()]));
+  }
+
+  async addService(identity: PublicKey, authority: Keypair, service: ServiceEntry) {
+    await this.identityUpdate(identity, authority, Buffer.concat([Buffer.from([40]), encodeService(service)]));
+  }
+
+  async updateService(identity: PublicKey, authority: Keypair, service: ServiceEntry) {
+    await this.identityUpdate(identity, authority, Buffer.concat([Buffer.from([41]), encodeService(service)]));
+  }
+
+  async removeService(identity: PublicKey, authority: Keypair, id: string) {
+    await this.identityUpdate(identity, authority, Buffer.concat([Buffer.from([42]), Buffer.from(id, "utf8")]));
+  }
+
+  async closeIdentity(identity: PublicKey, authority: Keypair, recipient: PublicKey) {
+    await this.send(
+      [
+        this.ix(
+          [
+            [identity, false, true],
+            [authority.publicKey, true, false],
+            [recipient, false, true],
+            [this.pda([Buffer.from("tombstone"), identity.toBuffer()]), false, true],
+            [this.payer.publicKey, true, true],
+            [SystemProgram.programId, false, false],
+          ],
+          Buffer.from([2])
+        ),
+      ],
+      [authority]
+    );
+  }
+
+  async fetchIdentity(identity: PublicKey): Promise<IdentityState> {
+    const account = await this.connection.getAccountInfo(identity);
+    const data = account.data.subarray(DISCRIMINATOR_LEN);
+    return {
+      authority: new PublicKey(data.subarray(1, 33)),
+      recovery: new PublicKey(data.subarray(33, 65)),
+      attributes: Buffer.from(data.subarray(65, 193)),
+      activeCredentials: data.readUInt32LE(193),
+      credentialCount: readU64(data, 197),
+      services: decodeServices(
+        data[205],
+        Array.from({ length: MAX_SERVICES }, (_, i) => data.subarray(206 + i * SERVICE_LEN, 206 + (i + 1) * SERVICE_LEN))
+      ),
+    };
+  }
+
+  async issueCredential(
+    identity: PublicKey,
+    authority: Keypair,
+    issuer: Keypair,
+    credentialRef: Buffer,
+    issuedAt: number,
+    expiresAt: number
+  ): Promise<PublicKey> {
+    const { credentialCount } = await this.fetchIdentity(identity);
+    const credential = this.credentialPDA(issuer.publicKey, identity, SCHEMA, credentialCount);
+    const index = this.pda([Buffer.from("cred-index"), identity.toBuffer(), u64(credentialCount)]);
+    await this.send(
+      [
+        this.ix(
+          [
+            [identity, false, true],
+            [authority.publicKey, true, false],
+            [credential, false, true],
+            [issuer.publicKey, true, false],
+            [this.payer.publicKey, true, true],
+            [index, false, true],
+            [SystemProgram.programId, false, false],
+          ],
+          Buffer.concat([Buffer.from([6]), SCHEMA, u64(credentialCount), credentialRef, u64(issuedAt), u64(expiresAt)])
+        ),
+      ],
+      [authority, issuer]
+    );
+    return credential;
+  }
+
+  async issueCredentialSigned(issuance: Issuance, issuer: Keypair, holder?: Keypair): Promise<PublicKey> {
+    const { identity } = issuance;
+    const { authority, credentialCount } = await this.fetchIdentity(identity);
+    const credential = this.credentialPDA(issuance.issuer, identity, issuance.schema, issuance.nonce);
+    await this.send(
+      [
+        issuanceSignature(this.programId, issuance, issuer),
+        this.ix(
+          [
+            [identity, false, true],
+            [authority, holder !== undefined, false],
+            [credential, false, true],
+            [issuance.issuer, false, false],
+            [this.payer.publicKey, true, true],
+            [this.pda([Buffer.from("cred-index"), identity.toBuffer(), u64(credentialCount)]), false, true],
+            [SYSVAR_INSTRUCTIONS_PUBKEY, false, false],
+            [SystemProgram.programId, false, false],
+          ],
+          Buffer.concat([
+            Buffer.from([19]),
+            issuance.schema,
+            u64(issuance.nonce),
+            issuance.credentialRef,
+            u64(issuance.issuedAt),
+            u64(issuance.expiresAt),
+            u64(issuance.deadline),
+            issuance.claimsRoot ?? Buffer.alloc(0),
+          ])
+        ),
+      ],
+      holder ? [holder] : []
+    );
+    return credential;
+  }
+
+  private issueBatchIx(issuer: PublicKey, schema: Buffer, items: BatchIssuance[], counts: Map<string, number>) {
+    const indexes = batchIndexes(items.map((item) => item.identity), counts);
+    return this.ix(
+      [
+        [issuer, true, false],
+        [this.payer.publicKey, true, true],
+        [SystemProgram.programId, false, false],
+        ...items.flatMap((item, i): [PublicKey, boolean, boolean][] => [
+          [item.identity, false, true],
+          [this.credentialPDA(issuer, item.identity, schema, item.nonce), false, true],
+          [this.pda([Buffer.from("cred-index"), item.identity.toBuffer(), u64(indexes[i])]), false, true],
+        ]),
+      ],
+      Buffer.concat([Buffer.from([20]), schema, ...items.map(batchIssueItem)])
+    );
+  }
+
+  async issueCredentialBatch(issuer: Keypair, schema: Buffer, items: BatchIssuance[]): Promise<BatchOutcome[]> {
+    const outcomes: BatchOutcome[] = [];
+    const chunks = await splitBatch(items, this.payer.publicKey, ISSUE_ITEM_COMPUTE, async (chunk) =>
+      this.issueBatchIx(issuer.publicKey, schema, chunk, new Map())
+    );
+    for (const chunk of chunks) {
+      // Index entries are taken in order, so read every holder's next entry right before sending
+      const counts = await fetchCredentialCounts(this, chunk.map((item) => item.identity));
+      const signature = await this.send(
+        [batchComputeBudget(chunk.length, ISSUE_ITEM_COMPUTE), this.issueBatchIx(issuer.publicKey, schema, chunk, counts)],
+        [issuer]
+      );
+      const results = await batchResults(this.connection, signature);
+      chunk.forEach((item, i) =>
+        outcomes.push({ credential: this.credentialPDA(issuer.publicKey, item.identity, schema, item.nonce), result: results[i] })
+      );
+    }
+    return outcomes;
+  }
+
+  async revokeCredentialBatch(issuer: Keypair, credentials: PublicKey[]): Promise<BatchOutcome[]> {
+    const outcomes: BatchOutcome[] = [];
+    const build = async (chunk: PublicKey[]) =>
+      this.ix([[issuer.publicKey, true, false], ...chunk.map((c): [PublicKey, boolean, boolean] => [c, false, true])], Buffer.from([21]));
+    for (const chunk of await splitBatch(credentials, this.payer.publicKey, REVOKE_ITEM_COMPUTE, build)) {
+      const signature = await this.send([batchComputeBudget(chunk.length, REVOKE_ITEM_COMPUTE), await build(chunk)], [issuer]);
+      const results = await batchResults(this.connection, signature);
+      chunk.forEach((credential, i) => outcomes.push({ credential, result: results[i] }));
+    }
+    return outcomes;
+  }
+
+  private async credentialUpdate(credential: PublicKey, issuer: Keypair, data: Buffer) {
+    await this.send(
+      [this.ix([[credential, false, true], [issuer.publicKey, true, false]], data)],
+      [issuer]
+    );
+  }
+
+  async revokeCredential(credential: PublicKey, issuer: Keypair) {
+    await this.credentialUpdate(credential, issuer, Buffer.from([7]));
+  }
+
+  async suspendCredential(credential: PublicKey, issuer: Keypair) {
+    await this.credentialUpdate(credential, issuer, Buffer.from([9]));
+  }
+
+  async reinstateCredential(credential: PublicKey, issuer: Keypair) {
+    await this.credentialUpdate(credential, issuer, Buffer.from([10]));
+  }
+
+  async renewCredential(credential: PublicKey, issuer: Keypair, expiresAt: number) {
+    await this.credentialUpdate(credential, issuer, Buffer.concat([Buffer.from([11]), u64(expiresAt)]));
+  }
+
+  async reissueCredential(
+    credential: PublicKey,
+    issuer: Keypair,
+    credentialRef: Buffer,
+    issuedAt: number,
+    expiresAt: number
+  ) {
+    await this.credentialUpdate(
+      credential,
+      issuer,
+      Buffer.concat([Buffer.from([18]), credentialRef, u64(issuedAt), u64(expiresAt)])
+    );
+  }
+
+  async closeCredential(credential: PublicKey, closer: Keypair, recipient?: PublicKey) {
+    const state = await this.fetchCredential(credential);
+    await this.send(
+      [
+        this.ix(
+          [
+            [credential, false, true],
+            [closer.publicKey, true, false],
+            [state.issuer, false, false],
+            [recipient ?? state.issuer, false, true],
+            [state.identity, false, true],
+            [await this.indexOf(credential), false, true],
+          ],
+          Buffer.from([17])
+        ),
+      ],
+      [closer]
+    );
+  }
+
+  async queryCredentialStatus(credential: PublicKey): Promise<CredentialStatus> {
+    const tx = new Transaction().add(this.ix([[credential, false, false]], Buffer.from([8])));
+    tx.feePayer = this.payer.publicKey;
+    tx.recentBlockhash = (await this.connection.getLatestBlockhash()).blockhash;
+    const { value } = await this.connection.simulateTransaction(tx, [this.payer]);
+    if (value.err) {
+      throw new Error(`QueryCredentialStatus failed: ${JSON.stringify(value.err)}`);
+    }
+    return Buffer.from(value.returnData.data[0], "base64")[0];
+  }
+
+  async fetchCredential(credential: PublicKey): Promise<CredentialState> {
+    const account = await this.connection.getAccountInfo(credential);
+    const data = account.data.subarray(DISCRIMINATOR_LEN);
+    return {
+      revoked: data[1] !== 0,
+      identity: new PublicKey(data.subarray(2, 34)),
+      issuer: new PublicKey(data.subarray(34, 66)),
+      credentialRef: Buffer.from(data.subarray(66, 98)),
+      issuedAt: readU64(data, 98),
+      expiresAt: readU64(data, 106),
+      suspended: data[114] !== 0,
+      pending: data[179] !== 0,
+    };
+  }
+}
@@ -0,0 +1,239 @@
+import { Connection, Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { BatchItemResult, CredentialStatus, IdenclaveClient } from "./client";
+
+const fund = async (connection: Connection, ...keypairs: Keypair[]) => {
+  for (const keypair of keypairs) {
+    const signature = await connection.requestAirdrop(keypair.publicKey, 2 * LAMPORTS_PER_SOL);
+    await connection.confirmTransaction(signature, "confirmed");
+  }
+};
+
+const expectFailure = async (action: Promise<unknown>) => {
+  try {
+    await action;
+  } catch (err) {
+    expect(err).to.be.instanceOf(Error);
+    return;
+  }
+  expect.fail("Expected transaction to fail");
+};
+
+const randomRef = () => Keypair.generate().publicKey.toBuffer();
+
+// Scenarios that both the Anchor and the native deployment must pass unchanged.
+export function runIdenclaveSuite(name: string, connection: Connection, makeClient: () => IdenclaveClient) {
+  describe(`idenclave parity (${name})`, () => {
+    const client = makeClient();
+    const authority = Keypair.generate();
+    const issuer = Keypair.generate();
+    const recovery = Keypair.generate();
+    const stranger = Keypair.generate();
+    const now = Math.floor(Date.now() / 1000);
+    let identity: PublicKey;
+
+    before(async () => {
+      await fund(connection, authority, issuer, recovery, stranger);
+      identity = await client.registerIdentity(authority);
+    });
+
+    describe("Identity management", () => {
+      it("Registers with empty recovery, attributes and counters", async () => {
+        const state = await client.fetchIdentity(identity);
+        expect(state.authority.equals(authority.publicKey)).to.be.true;
+        expect(state.recovery.equals(PublicKey.default)).to.be.true;
+        expect(state.attributes.equals(Buffer.alloc(128))).to.be.true;
+        expect(state.activeCredentials).to.equal(0);
+        expect(state.credentialCount).to.equal(0);
+        expect(state.services).to.deep.equal([]);
+      });
+
+      it("Sets attributes, truncated to 128 bytes", async () => {
+        const data = Buffer.alloc(140, 7);
+        await client.setAttributes(identity, authority, data);
+        const state = await client.fetchIdentity(identity);
+        expect(state.attributes.equals(data.subarray(0, 128))).to.be.true;
+      });
+
+      it("Adds, updates and removes DID service entries within their limits", async () => {
+        const messaging = { id: "messaging", type: "DIDCommMessaging", endpoint: "https://example.com/didcomm" };
+        const domain = { id: "domain", type: "LinkedDomains", endpoint: "https://example.com" };
+        await client.addService(identity, authority, messaging);
+        await client.addService(identity, authority, domain);
+        await expectFailure(client.addService(identity, authority, messaging));
+        await expectFailure(client.addService(identity, authority, { ...domain, id: "profile", endpoint: "x".repeat(129) }));
+        expect((await client.fetchIdentity(identity)).services).to.deep.equal([messaging, domain]);
+
+        const moved = { ...messaging, endpoint: "https://example.org/didcomm" };
+        await client.updateService(identity, authority, moved);
+        await client.removeService(identity, authority, "domain");
+        await expectFailure(client.removeService(identity, authority, "domain"));
+        expect((await client.fetchIdentity(identity)).services).to.deep.equal([moved]);
+      });
+
+      it("Only the authority can change the identity", async () => {
+        await expectFailure(client.setAttributes(identity, stranger, Buffer.from([1])));
+        await expectFailure(client.removeService(identity, stranger, "messaging"));
+        await expectFailure(client.setRecovery(identity, stranger, stranger.publicKey));
+        await expectFailure(client.updateAuthority(identity, stranger, stranger.publicKey));
+      });
+
+      it("Rotates the authority and recovers it with the recovery key", async () => {
+        const rotated = Keypair.generate();
+        await client.setRecovery(identity, authority, recovery.publicKey);
+        await client.updateAuthority(identity, authority, rotated.publicKey);
+        expect((await client.fetchIdentity(identity)).authority.equals(rotated.publicKey)).to.be.true;
+        await expectFailure(client.setAttributes(identity, authority, Buffer.from([1])));
+
+        await expectFailure(client.recoverAuthority(identity, stranger, stranger.publicKey));
+        await client.recoverAuthority(identity, recovery, authority.publicKey);
+        expect((await client.fetchIdentity(identity)).authority.equals(authority.publicKey)).to.be.true;
+      });
+    });
+
+    describe("Credential lifecycle", () => {
+      let credential: PublicKey;
+
+      it("Issues a credential and indexes it on the identity", async () => {
+        const ref = randomRef();
+        credential = await client.issueCredential(identity, authority, issuer, ref, now, now + 3600);
+        const state = await client.fetchCredential(credential);
+        expect(state.identity.equals(identity)).to.be.true;
+        expect(state.issuer.equals(issuer.publicKey)).to.be.true;
+        expect(state.credentialRef.equals(ref)).to.be.true;
+        expect(state.issuedAt).to.equal(now);
+        expect(state.expiresAt).to.equal(now + 3600);
+        expect(await client.queryCredentialStatus(credential)).to.equal(CredentialStatus.Valid);
+        const identityState = await client.fetchIdentity(identity);
+        expect(identityState.activeCredentials).to.equal(1);
+        expect(identityState.credentialCount).to.equal(1);
+      });
+
+      it("Suspends and reinstates", async () => {
+        await expectFailure(client.suspendCredential(credential, stranger));
+        await client.suspendCredential(credential, issuer);
+        expect(await client.queryCredentialStatus(credential)).to.equal(CredentialStatus.Suspended);
+        await expectFailure(client.suspendCredential(credential, issuer));
+        await client.reinstateCredential(credential, issuer);
+        expect(await client.queryCredentialStatus(credential)).to.equal(CredentialStatus.Valid);
+      });
+
+      it("Renews only forwards", async () => {
+        await expectFailure(client.renewCredential(credential, issuer, now + 60));
+        await client.renewCredential(credential, issuer, now + 7200);
+        expect((await client.fetchCredential(credential)).expiresAt).to.equal(now + 7200);
+      });
+
+      it("Re-issues in place without touching the index", async () => {
+        const ref = randomRef();
+        await expectFailure(client.reissueCredential(credential, stranger, ref, now, now + 7200));
+        await client.reissueCredential(credential, issuer, ref, now + 1, now + 7200);
+        const state = await client.fetchCredential(credential);
+        expect(state.credentialRef.equals(ref)).to.be.true;
+        expect(state.issuedAt).to.equal(now + 1);
+        expect(state.identity.equals(identity)).to.be.true;
+        expect((await client.fetchIdentity(identity)).credentialCount).to.equal(1);
+      });
+
+      it("Cannot close an identity that still has credentials", async () => {
+        await expectFailure(client.closeIdentity(identity, authority, authority.publicKey));
+      });
+
+      it("Refuses to close a valid credential", async () => {
+        await expectFailure(client.closeCredential(credential, issuer));
+      });
+
+      it("Revokes, then the issuer closes the credential and picks the rent recipient", async () => {
+        await expectFailure(client.revokeCredential(credential, stranger));
+        await client.revokeCredential(credential, issuer);
+        expect(await client.queryCredentialStatus(credential)).to.equal(CredentialStatus.Revoked);
+        await expectFailure(client.revokeCredential(credential, issuer));
+        await expectFailure(client.renewCredential(credential, issuer, now + 9000));
+        await expectFailure(client.reissueCredential(credential, issuer, randomRef(), now, now + 9000));
+        await expectFailure(client.closeCredential(credential, stranger));
+        const recipient = Keypair.generate().publicKey;
+        await client.closeCredential(credential, issuer, recipient);
+        expect(await connection.getAccountInfo(credential)).to.be.null;
+        expect(await connection.getBalance(recipient)).to.be.greaterThan(0);
+      });
+
+      it("Lets the holder close an expired credential", async () => {
+        const expired = await client.issueCredential(identity, authority, issuer, randomRef(), now - 7200, now - 3600);
+        expect(await client.queryCredentialStatus(expired)).to.equal(CredentialStatus.Expired);
+        await expectFailure(client.closeCredential(expired, authority, authority.publicKey));
+        await client.closeCredential(expired, authority);
+        const state = await client.fetchIdentity(identity);
+        expect(state.activeCredentials).to.equal(0);
+        expect(state.credentialCount).to.equal(2);
+      });
+
+      it("Accepts an issuer-signed issuance from a relayer exactly once", async () => {
+        const issuance = {
+          issuer: issuer.publicKey,
+          identity,
+          schema: Buffer.alloc(32, 9),
+          nonce: 1,
+          credentialRef: randomRef(),
+          issuedAt: now,
+          expiresAt: now + 3600,
+          deadline: now + 600,
+        };
+        const offered = await client.issueCredentialSigned(issuance, issuer);
+        expect(await client.queryCredentialStatus(offered)).to.equal(CredentialStatus.Pending);
+        await expectFailure(client.issueCredentialSigned(issuance, issuer));
+        await expectFailure(client.issueCredentialSigned({ ...issuance, nonce: 2 }, stranger));
+        await expectFailure(client.issueCredentialSigned({ ...issuance, nonce: 2, deadline: now - 1 }, issuer));
+
+        const live = await client.issueCredentialSigned({ ...issuance, nonce: 2 }, issuer, authority);
+        expect(await client.queryCredentialStatus(live)).to.equal(CredentialStatus.Valid);
+        for (const credential of [offered, live]) {
+          await client.revokeCredential(credential, issuer);
+          await client.closeCredential(credential, issuer);
+        }
+      });
+
+      it("Offers and revokes a cohort in batches, reporting skipped items", async () => {
+        const cohort = await client.registerIdentity(stranger);
+        const schema = Buffer.alloc(32, 7);
+        const item = (holder: PublicKey, nonce: number) => ({
+          identity: holder,
+          nonce,
+          credentialRef: randomRef(),
+          issuedAt: now,
+          expiresAt: now + 3600,
+        });
+        // More items than fit in one transaction, alternating between two holders
+        const items = [...Array(12).keys()].map((n) => item(n % 2 ? cohort : identity, n));
+        const issued = await client.issueCredentialBatch(issuer, schema, items);
+        expect(issued.map((outcome) => outcome.result)).to.deep.equal(items.map(() => BatchItemResult.Ok));
+        for (const { credential } of issued) {
+          expect(await client.queryCredentialStatus(credential)).to.equal(CredentialStatus.Pending);
+        }
+        expect((await client.fetchIdentity(cohort)).activeCredentials).to.equal(6);
+        const [repeat] = await client.issueCredentialBatch(issuer, schema, [item(identity, 0)]);
+        expect(repeat.result).to.equal(BatchItemResult.AlreadyExists);
+
+        const credentials = issued.map((outcome) => outcome.credential);
+        const [foreign] = await client.revokeCredentialBatch(stranger, [credentials[0]]);
+        expect(foreign.result).to.equal(BatchItemResult.NotIssuer);
+        const revoked = await client.revokeCredentialBatch(issuer, [...credentials, credentials[0]]);
+        expect(revoked.map((outcome) => outcome.result)).to.deep.equal([
+          ...credentials.map(() => BatchItemResult.Ok),
+          BatchItemResult.AlreadyRevoked,
+        ]);
+        for (const credential of credentials) {
+          expect(await client.queryCredentialStatus(credential)).to.equal(CredentialStatus.Revoked);
+          await client.closeCredential(credential, issuer);
+        }
+      });
+
+      it("Closes the identity once no credentials remain", async () => {
+        const recipient = Keypair.generate().publicKey;
+        await expectFailure(client.closeIdentity(identity, stranger, recipient));
+        await client.closeIdentity(identity, authority, recipient);
+        expect(await connection.getAccountInfo(identity)).to.be.null;
+        expect(await connection.getBalance(recipient)).to.be.greaterThan(0);
+      });
+    });
+  });
+}
@@ -0,0 +1,156 @@
+use crate::{read_key, read_u64, AccountLayout, Key, LayoutError};
+
+/// Most accreditations a chain from a credential's issuer to a root of trust may pass through.
+pub const MAX_ACCREDITATION_CHAIN: usize = 8;
+
+/// An accreditor's statement that `issuer` is trusted to issue credentials of `schema`, stored in
+/// the PDA `[b"accreditation", accreditor, issuer, schema]`.
+///
+/// `delegation_depth` is how many further levels the issuer may accredit others for the same
+/// schema: 0 lets it only issue credentials, 1 also lets issuers it accredits issue, and so on.
+///
+/// Layout (LEN = 107): is_initialized (1), revoked (1), accreditor (32), issuer (32),
+/// schema (32), expires_at (8), delegation_depth (1).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Accreditation {
+    pub is_initialized: bool,
+    pub revoked: bool,
+    pub accreditor: Key,
+    pub issuer: Key,
+    pub schema: [u8; 32],
+    pub expires_at: u64,
+    pub delegation_depth: u8,
+}
+
+impl Accreditation {
+    pub const SEED: &'static [u8] = b"accreditation";
+}
+
+impl AccountLayout for Accreditation {
+    const DISCRIMINATOR: [u8; 8] = [33, 207, 31, 177, 201, 70, 235, 37];
+    const LEN: usize = 1 + 1 + 32 + 32 + 32 + 8 + 1;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1] = self.revoked as u8;
+        dst[2..34].copy_from_slice(&self.accreditor);
+        dst[34..66].copy_from_slice(&self.issuer);
+        dst[66..98].copy_from_slice(&self.schema);
+        dst[98..106].copy_from_slice(&self.expires_at.to_le_bytes());
+        dst[106] = self.delegation_depth;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        Ok(Accreditation {
+            is_initialized: src[0] != 0,
+            revoked: src[1] != 0,
+            accreditor: read_key(src, 2),
+            issuer: read_key(src, 34),
+            schema: read_key(src, 66),
+            expires_at: read_u64(src, 98),
+            delegation_depth: src[106],
+        })
+    }
+}
+
+/// Outcome of walking an issuer's accreditations up to a root of trust.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccreditationStatus {
+    /// The chain leads to the root
+    Accredited = 0,
+    /// The chain does not lead to the root: a link accredits someone else, is for another
+    /// schema or is missing, or the chain is longer than `MAX_ACCREDITATION_CHAIN`
+    Untrusted = 1,
+    Revoked = 2,
+    Expired = 3,
+    /// A link accredits an accreditor beyond the delegation depth it was granted
+    DelegationExceeded = 4,
+}
+
+impl TryFrom<u8> for AccreditationStatus {
+    type Error = u8;
+
+    fn try_from(code: u8) -> Result<Self, u8> {
+        Ok(match code {
+            0 => AccreditationStatus::Accredited,
+            1 => AccreditationStatus::Untrusted,
+            2 => AccreditationStatus::Revoked,
+            3 => AccreditationStatus::Expired,
+            4 => AccreditationStatus::DelegationExceeded,
+            other => return Err(other),
+        })
+    }
+}
+
+/// The status of a chain and the keys it walked, from the issuer up to the root, or up to the
+/// last accreditor reached before the walk stopped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccreditationReport {
+    pub status: AccreditationStatus,
+    pub path: Vec<Key>,
+}
+
+impl AccreditationReport {
+    /// Encoding returned by `QueryAccreditation`: status (1), path length (1), then the path keys.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(2 + 32 * self.path.len());
+        buf.push(self.status as u8);
+        buf.push(self.path.len() as u8);
+        for key in &self.path {
+            buf.extend_from_slice(key);
+        }
+        buf
+    }
+
+    pub fn unpack(src: &[u8]) -> Option<Self> {
+        let (&status, rest) = src.split_first()?;
+        let (&len, keys) = rest.split_first()?;
+        if keys.len() != len as usize * 32 {
+            return None;
+        }
+        Some(AccreditationReport {
+            status: AccreditationStatus::try_from(status).ok()?,
+            path: keys.chunks_exact(32).map(|key| read_key(key, 0)).collect(),
+        })
+    }
+}
+
+/// Walks `chain` from `issuer` up to `root` for credentials of `schema` at `now`.
+///
+/// `chain[0]` accredits the issuer, and each following link accredits the previous link's
+/// accreditor, until one is made by `root`. The link at position `n` must grant a delegation depth
+/// of at least `n`. An issuer that is the root itself needs no links. Links after the one reaching
+/// the root are ignored.
+pub fn evaluate_chain(issuer: &Key, schema: &[u8; 32], root: &Key, chain: &[Accreditation], now: u64) -> AccreditationReport {
+    let mut path = vec![*issuer];
+    let report = |status, path| AccreditationReport { status, path };
+    if issuer == root {
+        return report(AccreditationStatus::Accredited, path);
+    }
+    if chain.len() > MAX_ACCREDITATION_CHAIN {
+        return report(AccreditationStatus::Untrusted, path);
+    }
+    for (depth, link) in chain.iter().enumerate() {
+        if !link.is_initialized || link.issuer != path[depth] || link.schema != *schema {
+            return report(AccreditationStatus::Untrusted, path);
+        }
+        if link.revoked {
+            return report(AccreditationStatus::Revoked, path);
+        }
+        if now > link.expires_at {
+            return report(AccreditationStatus::Expired, path);
+        }
+        if (link.delegation_depth as usize) < depth {
+            return report(AccreditationStatus::DelegationExceeded, path);
+        }
+        path.push(link.accreditor);
+        if link.accreditor == *root {
+            return report(AccreditationStatus::Accredited, path);
+        }
+    }
+    report(AccreditationStatus::Untrusted, path)
+}
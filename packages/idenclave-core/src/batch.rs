@@ -0,0 +1,104 @@
+use crate::{read_u64, RuleViolation};
+
+/// Upper bound on the items of one `IssueCredentialBatch` or `RevokeCredentialBatch`. Transaction
+/// size usually limits a batch further; clients split larger cohorts over several transactions.
+pub const MAX_BATCH_ITEMS: usize = 32;
+
+/// One credential of an `IssueCredentialBatch`. The batch's schema and this nonce select the
+/// credential PDA `[b"credential", issuer, identity, schema, nonce]`, like signed issuance. Batch
+/// credentials are always offers, pending until the holder accepts them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchIssueItem {
+    pub nonce: u64,
+    pub credential_ref: [u8; 32],
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+impl BatchIssueItem {
+    pub const LEN: usize = 8 + 32 + 8 + 8;
+
+    pub fn pack(&self, dst: &mut Vec<u8>) {
+        dst.extend_from_slice(&self.nonce.to_le_bytes());
+        dst.extend_from_slice(&self.credential_ref);
+        dst.extend_from_slice(&self.issued_at.to_le_bytes());
+        dst.extend_from_slice(&self.expires_at.to_le_bytes());
+    }
+
+    pub fn unpack(src: &[u8]) -> Self {
+        let mut credential_ref = [0u8; 32];
+        credential_ref.copy_from_slice(&src[8..40]);
+        BatchIssueItem { nonce: read_u64(src, 0), credential_ref, issued_at: read_u64(src, 40), expires_at: read_u64(src, 48) }
+    }
+
+    /// Reads back-to-back items. `None` unless there are between 1 and `MAX_BATCH_ITEMS` whole items.
+    pub fn unpack_all(src: &[u8]) -> Option<Vec<Self>> {
+        let count = src.len() / Self::LEN;
+        if count == 0 || count > MAX_BATCH_ITEMS || count * Self::LEN != src.len() {
+            return None;
+        }
+        Some(src.chunks_exact(Self::LEN).map(Self::unpack).collect())
+    }
+}
+
+/// Outcome of a single batch item. A problem with one item's accounts or state skips that item
+/// and is reported here instead of failing the whole batch.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchItemResult {
+    Ok = 0,
+    /// The item's accounts are not the expected (writable) identity, credential or index accounts
+    InvalidAccount = 1,
+    /// The credential was issued by someone else
+    NotIssuer = 2,
+    /// A credential already exists at the item's address
+    AlreadyExists = 3,
+    AlreadyRevoked = 4,
+    /// The credential's state does not allow the operation
+    InvalidState = 5,
+}
+
+impl From<RuleViolation> for BatchItemResult {
+    fn from(violation: RuleViolation) -> Self {
+        match violation {
+            RuleViolation::AlreadyRevoked => BatchItemResult::AlreadyRevoked,
+            _ => BatchItemResult::InvalidState,
+        }
+    }
+}
+
+impl TryFrom<u8> for BatchItemResult {
+    type Error = u8;
+
+    fn try_from(code: u8) -> Result<Self, u8> {
+        Ok(match code {
+            0 => BatchItemResult::Ok,
+            1 => BatchItemResult::InvalidAccount,
+            2 => BatchItemResult::NotIssuer,
+            3 => BatchItemResult::AlreadyExists,
+            4 => BatchItemResult::AlreadyRevoked,
+            5 => BatchItemResult::InvalidState,
+            other => return Err(other),
+        })
+    }
+}
+
+/// Return data of a batch instruction: a u32 LE count followed by one result code per item, in
+/// item order. This is the Borsh encoding of the `Vec<u8>` the Anchor program returns.
+pub fn encode_results(results: &[BatchItemResult]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4 + results.len());
+    data.extend_from_slice(&(results.len() as u32).to_le_bytes());
+    data.extend(results.iter().map(|result| *result as u8));
+    data
+}
+
+pub fn decode_results(data: &[u8]) -> Option<Vec<BatchItemResult>> {
+    if data.len() < 4 {
+        return None;
+    }
+    let (count, codes) = data.split_at(4);
+    if u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize != codes.len() {
+        return None;
+    }
+    codes.iter().map(|code| BatchItemResult::try_from(*code).ok()).collect()
+}
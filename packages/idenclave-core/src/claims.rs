@@ -0,0 +1,161 @@
+use crate::merkle::{compute_root, MerkleHasher, Node, TreeMirror, EMPTY_LEAF};
+use crate::{read_key, ClaimError};
+
+/// Prefix of every claim leaf hash, so a claim can never be mistaken for an inner node or for
+/// a compressed credential leaf.
+pub const CLAIM_DOMAIN: &[u8] = b"idenclave:claim:v1";
+/// Deepest claims tree: a credential commits to at most 256 claims.
+pub const MAX_CLAIMS_DEPTH: usize = 8;
+pub const MAX_CLAIM_NAME_LEN: usize = 64;
+pub const MAX_CLAIM_VALUE_LEN: usize = 256;
+
+/// One claim of a credential document, such as `birthdate` = `1990-01-01`. The random salt keeps
+/// the claims root from revealing claims that are never disclosed, even ones with few possible
+/// values.
+///
+/// Encoded as name length (u8) + name + value length (u16 LE) + value + salt (32); the leaf hash
+/// covers `CLAIM_DOMAIN` and that encoding.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Claim {
+    pub name: String,
+    pub value: Vec<u8>,
+    pub salt: [u8; 32],
+}
+
+impl Claim {
+    pub fn new(name: &str, value: &[u8], salt: [u8; 32]) -> Self {
+        Claim { name: name.to_string(), value: value.to_vec(), salt }
+    }
+
+    fn check_size(&self) -> Result<(), ClaimError> {
+        if self.name.len() > MAX_CLAIM_NAME_LEN || self.value.len() > MAX_CLAIM_VALUE_LEN {
+            return Err(ClaimError::ClaimTooLarge);
+        }
+        Ok(())
+    }
+
+    /// Encodes the claim. Names and values over their size limits are refused by `ClaimSet::new`,
+    /// `unpack` and `ClaimProof::verify`, so they never make it into a tree or a proof.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(1 + self.name.len() + 2 + self.value.len() + 32);
+        buf.push(self.name.len() as u8);
+        buf.extend_from_slice(self.name.as_bytes());
+        buf.extend_from_slice(&(self.value.len() as u16).to_le_bytes());
+        buf.extend_from_slice(&self.value);
+        buf.extend_from_slice(&self.salt);
+        buf
+    }
+
+    /// Decodes a claim from the front of `src` and returns it with the bytes after it.
+    pub fn unpack(src: &[u8]) -> Result<(Self, &[u8]), ClaimError> {
+        let (&name_len, rest) = src.split_first().ok_or(ClaimError::Malformed)?;
+        let name_len = name_len as usize;
+        if rest.len() < name_len + 2 {
+            return Err(ClaimError::Malformed);
+        }
+        let name = std::str::from_utf8(&rest[..name_len]).map_err(|_| ClaimError::Malformed)?.to_string();
+        let value_len = u16::from_le_bytes([rest[name_len], rest[name_len + 1]]) as usize;
+        let rest = &rest[name_len + 2..];
+        if rest.len() < value_len + 32 {
+            return Err(ClaimError::Malformed);
+        }
+        let claim = Claim { name, value: rest[..value_len].to_vec(), salt: read_key(rest, value_len) };
+        claim.check_size()?;
+        Ok((claim, &rest[value_len + 32..]))
+    }
+
+    pub fn leaf_hash<H: MerkleHasher>(&self) -> Node {
+        H::hashv(&[CLAIM_DOMAIN, &self.pack()])
+    }
+}
+
+/// A single disclosed claim with its position in the claims tree and the proof (one sibling
+/// per level, leaf level first) against a credential's `claims_root`.
+///
+/// Encoded as the claim (see `Claim`) + index (u32 LE) + proof (32 bytes per node).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClaimProof {
+    pub claim: Claim,
+    pub index: u32,
+    pub proof: Vec<Node>,
+}
+
+impl ClaimProof {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = self.claim.pack();
+        buf.extend_from_slice(&self.index.to_le_bytes());
+        for node in &self.proof {
+            buf.extend_from_slice(node);
+        }
+        buf
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, ClaimError> {
+        let (claim, rest) = Claim::unpack(input)?;
+        if rest.len() < 4 || (rest.len() - 4) % 32 != 0 || (rest.len() - 4) / 32 > MAX_CLAIMS_DEPTH {
+            return Err(ClaimError::Malformed);
+        }
+        let index = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+        let proof = rest[4..].chunks_exact(32).map(|node| read_key(node, 0)).collect();
+        Ok(ClaimProof { claim, index, proof })
+    }
+
+    /// Checks that the claim is one of those `claims_root` commits to. The index has to fit the
+    /// proof's depth, so every claim has exactly one valid proof.
+    pub fn verify<H: MerkleHasher>(&self, claims_root: &Node) -> Result<(), ClaimError> {
+        if *claims_root == EMPTY_LEAF {
+            return Err(ClaimError::NoClaimsRoot);
+        }
+        self.claim.check_size()?;
+        if self.proof.len() > MAX_CLAIMS_DEPTH || u64::from(self.index) >> self.proof.len() != 0 {
+            return Err(ClaimError::InvalidProof);
+        }
+        if compute_root::<H>(&self.claim.leaf_hash::<H>(), self.index, &self.proof) != *claims_root {
+            return Err(ClaimError::InvalidProof);
+        }
+        Ok(())
+    }
+}
+
+/// The claims of one credential document and the tree committing to them. The issuer builds it
+/// to get the `claims_root` it stores on the credential; the holder keeps it to disclose claims
+/// one at a time. Leaves are in claim order, in the smallest tree that holds them all.
+pub struct ClaimSet<H> {
+    claims: Vec<Claim>,
+    tree: TreeMirror<H>,
+}
+
+impl<H: MerkleHasher> ClaimSet<H> {
+    pub fn new(claims: Vec<Claim>) -> Result<Self, ClaimError> {
+        if claims.len() > 1 << MAX_CLAIMS_DEPTH {
+            return Err(ClaimError::TooManyClaims);
+        }
+        let mut tree = TreeMirror::new(claims.len().next_power_of_two().trailing_zeros() as usize);
+        for claim in &claims {
+            claim.check_size()?;
+            tree.append(claim.leaf_hash::<H>());
+        }
+        Ok(ClaimSet { claims, tree })
+    }
+
+    /// Root to store on the credential; all zeros, meaning no claims, for an empty set.
+    pub fn root(&self) -> Node {
+        self.tree.root()
+    }
+
+    pub fn claims(&self) -> &[Claim] {
+        &self.claims
+    }
+
+    /// Proof disclosing the claim at `index`.
+    pub fn proof(&self, index: u32) -> Option<ClaimProof> {
+        let claim = self.claims.get(index as usize)?.clone();
+        Some(ClaimProof { claim, index, proof: self.tree.proof(index) })
+    }
+
+    /// Proof disclosing the first claim called `name`.
+    pub fn disclose(&self, name: &str) -> Option<ClaimProof> {
+        let index = self.claims.iter().position(|claim| claim.name == name)?;
+        self.proof(index as u32)
+    }
+}
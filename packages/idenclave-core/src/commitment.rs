@@ -0,0 +1,32 @@
+use crate::Scalar;
+
+/// Most claim hashes a credential commitment covers: the widest Poseidon the syscall supports.
+pub const MAX_COMMITTED_CLAIMS: usize = 12;
+
+/// Poseidon over the BN254 scalar field with the circom parameters (x^5 S-box), on 32-byte
+/// big-endian field elements, as computed by Solana's `sol_poseidon` syscall and by circomlib's
+/// `Poseidon(n)` template. Each program implements this with its own syscall binding. `None`
+/// means the number of inputs is not supported or an input is not a field element.
+pub trait PoseidonHasher {
+    fn hashv(inputs: &[Scalar]) -> Option<Scalar>;
+}
+
+/// Hash of one claim for a credential commitment: `Poseidon(salt, value)`, with the claim value
+/// encoded as a field element and a random salt so the hash reveals nothing about it.
+pub fn claim_hash<P: PoseidonHasher>(salt: &Scalar, value: &Scalar) -> Option<Scalar> {
+    P::hashv(&[*salt, *value])
+}
+
+/// Commitment to a credential's claims: `Poseidon(h_1, ..., h_n)` over the claim hashes, in the
+/// order the circuits proving about the credential expect them.
+///
+/// The issuer publishes only the claim hashes and the program computes the commitment from them,
+/// so the stored commitment is known to cover exactly those claims. A proof binds to the
+/// credential by recomputing the commitment from the salts and values it keeps private and
+/// exposing it as a public signal.
+pub fn claims_commitment<P: PoseidonHasher>(claim_hashes: &[Scalar]) -> Option<Scalar> {
+    if claim_hashes.is_empty() || claim_hashes.len() > MAX_COMMITTED_CLAIMS {
+        return None;
+    }
+    P::hashv(claim_hashes)
+}
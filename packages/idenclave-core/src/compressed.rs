@@ -0,0 +1,151 @@
+use crate::merkle::{MerkleHasher, Node, TreeMirror};
+use crate::{read_key, read_u64, CredentialState, Key, LayoutError, TreeError};
+
+/// Prefix of every compressed credential leaf hash, so a leaf can never be mistaken for an
+/// inner node or for a hash of anything else.
+pub const LEAF_DOMAIN: &[u8] = b"idenclave:leaf:v1";
+
+/// A credential kept as a leaf of a `CredentialTree` instead of in its own account. Only the
+/// leaf hash is stored on chain; the full credential is logged when it is issued and has to be
+/// presented, with a proof, to revoke or verify it.
+///
+/// Layout (LEN = 145):
+///
+/// | offset   | field          |
+/// |----------|----------------|
+/// | 0..32    | identity       |
+/// | 32..64   | issuer         |
+/// | 64..96   | schema         |
+/// | 96..128  | credential_ref |
+/// | 128      | issued_at      |
+/// | 136      | expires_at     |
+/// | 144      | revoked        |
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompressedCredential {
+    pub identity: Key,
+    pub issuer: Key,
+    pub schema: [u8; 32],
+    pub credential_ref: [u8; 32],
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub revoked: bool,
+}
+
+impl CompressedCredential {
+    pub const LEN: usize = 32 + 32 + 32 + 32 + 8 + 8 + 1;
+
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut dst = [0u8; Self::LEN];
+        dst[0..32].copy_from_slice(&self.identity);
+        dst[32..64].copy_from_slice(&self.issuer);
+        dst[64..96].copy_from_slice(&self.schema);
+        dst[96..128].copy_from_slice(&self.credential_ref);
+        dst[128..136].copy_from_slice(&self.issued_at.to_le_bytes());
+        dst[136..144].copy_from_slice(&self.expires_at.to_le_bytes());
+        dst[144] = self.revoked as u8;
+        dst
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        Ok(CompressedCredential {
+            identity: read_key(src, 0),
+            issuer: read_key(src, 32),
+            schema: read_key(src, 64),
+            credential_ref: read_key(src, 96),
+            issued_at: read_u64(src, 128),
+            expires_at: read_u64(src, 136),
+            revoked: src[144] != 0,
+        })
+    }
+
+    pub fn leaf_hash<H: MerkleHasher>(&self) -> Node {
+        H::hashv(&[LEAF_DOMAIN, &self.pack()])
+    }
+}
+
+/// Compressed credentials can only be issued directly and revoked: they are never pending,
+/// suspended or superseded.
+impl CredentialState for CompressedCredential {
+    fn is_revoked(&self) -> bool {
+        self.revoked
+    }
+
+    fn is_pending(&self) -> bool {
+        false
+    }
+
+    fn is_suspended(&self) -> bool {
+        false
+    }
+
+    fn is_superseded(&self) -> bool {
+        false
+    }
+
+    fn expires_at(&self) -> u64 {
+        self.expires_at
+    }
+}
+
+/// Off-chain copy of one credential tree, rebuilt by replaying the program's
+/// `CompressedCredentialIssued` and `CompressedCredentialRevoked` events in order. Every event
+/// carries the tree's new root, which the mirror checks its own root against, so a missed or
+/// reordered event is noticed instead of producing proofs the program rejects.
+pub struct CredentialTreeMirror<H> {
+    tree: TreeMirror<H>,
+    credentials: Vec<CompressedCredential>,
+}
+
+impl<H: MerkleHasher> CredentialTreeMirror<H> {
+    pub fn new(max_depth: usize) -> Self {
+        CredentialTreeMirror { tree: TreeMirror::new(max_depth), credentials: Vec::new() }
+    }
+
+    pub fn issued(&mut self, index: u32, credential: CompressedCredential, root: &Node) -> Result<(), TreeError> {
+        if index != self.tree.len() {
+            return Err(TreeError::OutOfSync);
+        }
+        self.tree.append(credential.leaf_hash::<H>());
+        self.credentials.push(credential);
+        self.check_root(root)
+    }
+
+    pub fn revoked(&mut self, index: u32, root: &Node) -> Result<(), TreeError> {
+        let credential = self.credentials.get_mut(index as usize).ok_or(TreeError::OutOfSync)?;
+        credential.revoked = true;
+        let leaf = credential.leaf_hash::<H>();
+        self.tree.set(index, leaf);
+        self.check_root(root)
+    }
+
+    fn check_root(&self, root: &Node) -> Result<(), TreeError> {
+        if self.tree.root() != *root {
+            return Err(TreeError::OutOfSync);
+        }
+        Ok(())
+    }
+
+    pub fn root(&self) -> Node {
+        self.tree.root()
+    }
+
+    pub fn len(&self) -> u32 {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn credential(&self, index: u32) -> Option<&CompressedCredential> {
+        self.credentials.get(index as usize)
+    }
+
+    /// Proof of the credential at `index` against the current root.
+    pub fn proof(&self, index: u32) -> Vec<Node> {
+        self.tree.proof(index)
+    }
+}
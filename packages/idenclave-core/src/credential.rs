@@ -0,0 +1,237 @@
+use crate::{read_key, read_u64, AccountLayout, Key, LayoutError, RuleViolation};
+
+/// Canonical credential layout (LEN = 244):
+///
+/// | offset | field          |
+/// |--------|----------------|
+/// | 0      | is_initialized |
+/// | 1      | revoked        |
+/// | 2..34  | identity       |
+/// | 34..66 | issuer         |
+/// | 66..98 | credential_ref |
+/// | 98     | issued_at      |
+/// | 106    | expires_at     |
+/// | 114    | suspended      |
+/// | 115    | previous       |
+/// | 147    | superseded_by  |
+/// | 179    | pending        |
+/// | 180    | claims_root    |
+/// | 212    | commitment     |
+///
+/// Offsets are within the body, which follows the 8-byte discriminator (see `AccountLayout`).
+/// `claims_root` commits to the credential's individually salted claims (see `claims`), all
+/// zeros when the issuer did not commit to any. `commitment` is the Poseidon commitment proofs
+/// about the credential bind to (see `commitment`), all zeros until the issuer sets it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Credential {
+    pub is_initialized: bool,
+    pub revoked: bool,
+    pub identity: Key,
+    pub issuer: Key,
+    pub credential_ref: [u8; 32],
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub suspended: bool,
+    pub previous: Key,
+    pub superseded_by: Key,
+    pub pending: bool,
+    pub claims_root: [u8; 32],
+    pub commitment: [u8; 32],
+}
+
+impl Credential {
+    /// Prefix of a credential PDA `[b"credential", issuer, identity, schema, nonce (u64 LE)]`.
+    /// The issuer and identity are part of the address, so nobody else can take it first.
+    pub const SEED: &'static [u8] = b"credential";
+
+    pub fn has_claims_root(&self) -> bool {
+        self.claims_root != [0; 32]
+    }
+
+    pub fn has_commitment(&self) -> bool {
+        self.commitment != [0; 32]
+    }
+}
+
+impl AccountLayout for Credential {
+    const DISCRIMINATOR: [u8; 8] = [145, 44, 68, 220, 67, 46, 100, 135];
+    const LEN: usize = 1 + 1 + 32 + 32 + 32 + 8 + 8 + 1 + 32 + 32 + 1 + 32 + 32;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1] = self.revoked as u8;
+        dst[2..34].copy_from_slice(&self.identity);
+        dst[34..66].copy_from_slice(&self.issuer);
+        dst[66..98].copy_from_slice(&self.credential_ref);
+        dst[98..106].copy_from_slice(&self.issued_at.to_le_bytes());
+        dst[106..114].copy_from_slice(&self.expires_at.to_le_bytes());
+        dst[114] = self.suspended as u8;
+        dst[115..147].copy_from_slice(&self.previous);
+        dst[147..179].copy_from_slice(&self.superseded_by);
+        dst[179] = self.pending as u8;
+        dst[180..212].copy_from_slice(&self.claims_root);
+        dst[212..244].copy_from_slice(&self.commitment);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        Ok(Credential {
+            is_initialized: src[0] != 0,
+            revoked: src[1] != 0,
+            identity: read_key(src, 2),
+            issuer: read_key(src, 34),
+            credential_ref: read_key(src, 66),
+            issued_at: read_u64(src, 98),
+            expires_at: read_u64(src, 106),
+            suspended: src[114] != 0,
+            previous: read_key(src, 115),
+            superseded_by: read_key(src, 147),
+            pending: src[179] != 0,
+            claims_root: read_key(src, 180),
+            commitment: read_key(src, 212),
+        })
+    }
+}
+
+/// Status of a credential as reported by `QueryCredentialStatus`.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CredentialStatus {
+    Valid = 0,
+    Expired = 1,
+    Revoked = 2,
+    Suspended = 3,
+    Superseded = 4,
+    Pending = 5,
+}
+
+/// Status evaluation and validation rules, implemented by every credential representation
+/// (this crate's `Credential` and each program's own account type) so they all behave the same.
+pub trait CredentialState {
+    fn is_revoked(&self) -> bool;
+    fn is_pending(&self) -> bool;
+    fn is_suspended(&self) -> bool;
+    fn is_superseded(&self) -> bool;
+    fn expires_at(&self) -> u64;
+
+    /// Evaluates the credential status at the given unix timestamp.
+    /// Revocation takes precedence over a pending offer, then superseding, then suspension, then expiry.
+    fn status(&self, now: u64) -> CredentialStatus {
+        if self.is_revoked() {
+            CredentialStatus::Revoked
+        } else if self.is_pending() {
+            CredentialStatus::Pending
+        } else if self.is_superseded() {
+            CredentialStatus::Superseded
+        } else if self.is_suspended() {
+            CredentialStatus::Suspended
+        } else if now > self.expires_at() {
+            CredentialStatus::Expired
+        } else {
+            CredentialStatus::Valid
+        }
+    }
+
+    fn check_revoke(&self) -> Result<(), RuleViolation> {
+        if self.is_revoked() {
+            return Err(RuleViolation::AlreadyRevoked);
+        }
+        Ok(())
+    }
+
+    /// A revoked credential is final and cannot be suspended; suspending twice is an error.
+    fn check_suspend(&self) -> Result<(), RuleViolation> {
+        if self.is_revoked() || self.is_suspended() || self.is_superseded() {
+            return Err(RuleViolation::InvalidState);
+        }
+        Ok(())
+    }
+
+    fn check_reinstate(&self) -> Result<(), RuleViolation> {
+        if self.is_revoked() || !self.is_suspended() || self.is_superseded() {
+            return Err(RuleViolation::InvalidState);
+        }
+        Ok(())
+    }
+
+    /// Renewal only ever extends validity; shortening is done by revoking or superseding.
+    fn check_renew(&self, expires_at: u64) -> Result<(), RuleViolation> {
+        if self.is_revoked() || self.is_superseded() {
+            return Err(RuleViolation::InvalidState);
+        }
+        if expires_at <= self.expires_at() {
+            return Err(RuleViolation::ExpiryNotExtended);
+        }
+        Ok(())
+    }
+
+    fn check_supersede(&self) -> Result<(), RuleViolation> {
+        if self.is_revoked() || self.is_pending() || self.is_superseded() {
+            return Err(RuleViolation::InvalidState);
+        }
+        Ok(())
+    }
+
+    /// Re-issuing rewrites a live credential in place; revoked and superseded ones stay final.
+    fn check_reissue(&self) -> Result<(), RuleViolation> {
+        if self.is_revoked() || self.is_pending() || self.is_superseded() {
+            return Err(RuleViolation::InvalidState);
+        }
+        Ok(())
+    }
+
+    /// Claims can be committed to while the credential is live or still offered; revoked and
+    /// superseded credentials stay final.
+    fn check_commit(&self) -> Result<(), RuleViolation> {
+        if self.is_revoked() || self.is_superseded() {
+            return Err(RuleViolation::InvalidState);
+        }
+        Ok(())
+    }
+
+    fn check_accept(&self) -> Result<(), RuleViolation> {
+        if !self.is_pending() || self.is_revoked() {
+            return Err(RuleViolation::NotPending);
+        }
+        Ok(())
+    }
+
+    /// Rejecting or cancelling an offer.
+    fn check_withdraw_offer(&self) -> Result<(), RuleViolation> {
+        if !self.is_pending() {
+            return Err(RuleViolation::NotPending);
+        }
+        Ok(())
+    }
+
+    fn check_close(&self, now: u64) -> Result<(), RuleViolation> {
+        if !self.is_revoked() && now <= self.expires_at() {
+            return Err(RuleViolation::StillValid);
+        }
+        Ok(())
+    }
+}
+
+impl CredentialState for Credential {
+    fn is_revoked(&self) -> bool {
+        self.revoked
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    fn is_superseded(&self) -> bool {
+        self.superseded_by != Key::default()
+    }
+
+    fn expires_at(&self) -> u64 {
+        self.expires_at
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+
+/// Account data that does not match the expected layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// The data is too short to hold the layout
+    TooShort,
+    /// The account holds a different type
+    WrongDiscriminator,
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LayoutError::TooShort => "account data is too short for the expected layout",
+            LayoutError::WrongDiscriminator => "account holds a different type",
+        })
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// A credential state transition that the validation rules refuse.
+/// Each program maps these onto its own error type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleViolation {
+    /// The credential is already revoked
+    AlreadyRevoked,
+    /// The credential's revoked/suspended/superseded/pending flags do not allow the operation
+    InvalidState,
+    /// A renewal must move the expiry forward
+    ExpiryNotExtended,
+    /// The operation needs a pending offer
+    NotPending,
+    /// Only revoked or expired credentials can be closed
+    StillValid,
+}
+
+impl fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RuleViolation::AlreadyRevoked => "credential is already revoked",
+            RuleViolation::InvalidState => "credential state does not allow this operation",
+            RuleViolation::ExpiryNotExtended => "renewal must extend the expiry",
+            RuleViolation::NotPending => "credential offer is not pending",
+            RuleViolation::StillValid => "only revoked or expired credentials can be closed",
+        })
+    }
+}
+
+impl std::error::Error for RuleViolation {}
+
+/// A change to an organization's signing keys that cannot be applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrganizationError {
+    /// The key is already one of the organization's signing keys
+    KeyAlreadyAuthorized,
+    /// The key is not one of the organization's signing keys
+    KeyNotAuthorized,
+    /// The organization already has `MAX_ORGANIZATION_KEYS` signing keys
+    TooManyKeys,
+}
+
+impl fmt::Display for OrganizationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OrganizationError::KeyAlreadyAuthorized => "key is already authorized",
+            OrganizationError::KeyNotAuthorized => "key is not authorized",
+            OrganizationError::TooManyKeys => "organization has the maximum number of keys",
+        })
+    }
+}
+
+impl std::error::Error for OrganizationError {}
+
+/// A change to an identity's DID service entries that cannot be applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceError {
+    /// The identity already has a service with this id
+    AlreadyExists,
+    /// The identity has no service with this id
+    NotFound,
+    /// The identity already has `MAX_SERVICES` services
+    TooManyServices,
+    /// The id, type or endpoint is empty
+    EmptyField,
+    /// The id, type or endpoint is over its size limit
+    TooLarge,
+    /// The encoded service is not well formed
+    Malformed,
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ServiceError::AlreadyExists => "identity already has a service with this id",
+            ServiceError::NotFound => "identity has no service with this id",
+            ServiceError::TooManyServices => "identity has the maximum number of services",
+            ServiceError::EmptyField => "service id, type and endpoint must not be empty",
+            ServiceError::TooLarge => "service id, type or endpoint is too large",
+            ServiceError::Malformed => "malformed service",
+        })
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+/// Why a Groth16 proof was not accepted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofError {
+    /// The number of public inputs does not match the verifying key
+    WrongInputCount,
+    /// A public input is not reduced modulo the scalar field
+    InputOutOfField,
+    /// A proof or verifying key point is not on the curve
+    InvalidPoint,
+    /// The pairing check failed
+    Rejected,
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProofError::WrongInputCount => "wrong number of public inputs",
+            ProofError::InputOutOfField => "public input is not a field element",
+            ProofError::InvalidPoint => "invalid curve point",
+            ProofError::Rejected => "proof does not verify",
+        })
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+/// A credential tree operation that cannot be applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeError {
+    /// Depth or buffer size out of range, or an account of the wrong size for them
+    InvalidParameters,
+    /// The account is not an initialized credential tree
+    NotInitialized,
+    /// The account is already an initialized credential tree
+    AlreadyInitialized,
+    /// Every leaf of the tree is taken
+    TreeFull,
+    /// The leaf index has not been appended yet
+    IndexOutOfRange,
+    /// The proof's root is not among the tree's recent roots
+    RootNotFound,
+    /// The proof does not lead from the leaf to its root
+    InvalidProof,
+    /// The leaf was changed after the proof's root
+    LeafChanged,
+    /// A mirror replaying events ended up with a different root than the tree reported
+    OutOfSync,
+}
+
+impl fmt::Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TreeError::InvalidParameters => "invalid tree depth, buffer size or account size",
+            TreeError::NotInitialized => "account is not an initialized credential tree",
+            TreeError::AlreadyInitialized => "credential tree is already initialized",
+            TreeError::TreeFull => "credential tree is full",
+            TreeError::IndexOutOfRange => "leaf index has not been appended yet",
+            TreeError::RootNotFound => "proof root is not among the recent roots",
+            TreeError::InvalidProof => "proof does not match the leaf and root",
+            TreeError::LeafChanged => "leaf changed after the proof root",
+            TreeError::OutOfSync => "mirror root differs from the tree root",
+        })
+    }
+}
+
+impl std::error::Error for TreeError {}
+
+/// A disclosed claim that could not be checked against a credential's claims root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClaimError {
+    /// The credential does not commit to any claims
+    NoClaimsRoot,
+    /// More claims than a claims tree holds
+    TooManyClaims,
+    /// A claim name or value over its size limit
+    ClaimTooLarge,
+    /// The claim or its proof is not well formed
+    Malformed,
+    /// The proof does not lead from the claim to the claims root
+    InvalidProof,
+}
+
+impl fmt::Display for ClaimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ClaimError::NoClaimsRoot => "credential has no claims root",
+            ClaimError::TooManyClaims => "too many claims",
+            ClaimError::ClaimTooLarge => "claim name or value is too large",
+            ClaimError::Malformed => "malformed claim or proof",
+            ClaimError::InvalidProof => "claim is not committed to by the claims root",
+        })
+    }
+}
+
+impl std::error::Error for ClaimError {}
@@ -0,0 +1,210 @@
+use crate::{read_key, AccountLayout, Key, LayoutError, ProofError};
+
+/// A BN254 G1 point: x and y, 32-byte big-endian each. All zeros is the point at infinity.
+pub type G1 = [u8; 64];
+/// A BN254 G2 point: x.c1, x.c0, y.c1, y.c0, 32-byte big-endian each (the EIP-197 order).
+pub type G2 = [u8; 128];
+/// An element of the BN254 scalar field, 32-byte big-endian.
+pub type Scalar = [u8; 32];
+
+/// Most public inputs a registered circuit may have.
+pub const MAX_PUBLIC_INPUTS: usize = 8;
+
+/// Modulus of the BN254 base field, which G1 coordinates live in.
+const BASE_FIELD_MODULUS: [u8; 32] = [
+    48, 100, 78, 114, 225, 49, 160, 41, 184, 80, 69, 182, 129, 129, 88, 93, 151, 129, 106, 145, 104, 113, 202, 141, 60,
+    32, 140, 22, 216, 124, 253, 71,
+];
+/// Modulus of the BN254 scalar field, which public inputs must be reduced by.
+const SCALAR_FIELD_MODULUS: [u8; 32] = [
+    48, 100, 78, 114, 225, 49, 160, 41, 184, 80, 69, 182, 129, 129, 88, 93, 40, 51, 232, 72, 121, 185, 112, 145, 67,
+    225, 245, 147, 240, 0, 0, 1,
+];
+
+/// The BN254 operations a Groth16 verification needs, in the encoding of Solana's `alt_bn128`
+/// syscalls. Each program implements this with its own syscall bindings. `None` means the
+/// input was not a valid point.
+pub trait Bn254 {
+    fn add(a: &G1, b: &G1) -> Option<G1>;
+    fn mul(point: &G1, scalar: &Scalar) -> Option<G1>;
+    /// Whether the product of the pairings of the 192-byte (G1, G2) pairs in `pairs` is one.
+    fn pairing_is_one(pairs: &[u8]) -> Option<bool>;
+}
+
+/// A Groth16 proof (LEN = 256): a (64), b (128), c (64).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Groth16Proof {
+    pub a: G1,
+    pub b: G2,
+    pub c: G1,
+}
+
+impl Groth16Proof {
+    pub const LEN: usize = 64 + 128 + 64;
+
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        buf[..64].copy_from_slice(&self.a);
+        buf[64..192].copy_from_slice(&self.b);
+        buf[192..].copy_from_slice(&self.c);
+        buf
+    }
+
+    pub fn unpack(src: &[u8]) -> Option<Self> {
+        if src.len() != Self::LEN {
+            return None;
+        }
+        Some(Groth16Proof {
+            a: src[..64].try_into().unwrap(),
+            b: src[64..192].try_into().unwrap(),
+            c: src[192..].try_into().unwrap(),
+        })
+    }
+}
+
+/// A Groth16 verifying key. `ic` holds one point more than the circuit has public inputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifyingKey {
+    pub alpha: G1,
+    pub beta: G2,
+    pub gamma: G2,
+    pub delta: G2,
+    pub ic: Vec<G1>,
+}
+
+impl Default for VerifyingKey {
+    fn default() -> Self {
+        VerifyingKey { alpha: [0; 64], beta: [0; 128], gamma: [0; 128], delta: [0; 128], ic: Vec::new() }
+    }
+}
+
+impl VerifyingKey {
+    /// Number of public inputs a proof for this key carries.
+    pub fn public_inputs(&self) -> usize {
+        self.ic.len().saturating_sub(1)
+    }
+
+    /// Encoding used in instruction data: alpha, beta, gamma, delta, the number of public inputs
+    /// (1), then the `ic` points.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(64 + 3 * 128 + 1 + 64 * self.ic.len());
+        buf.extend_from_slice(&self.alpha);
+        buf.extend_from_slice(&self.beta);
+        buf.extend_from_slice(&self.gamma);
+        buf.extend_from_slice(&self.delta);
+        buf.push(self.public_inputs() as u8);
+        for point in &self.ic {
+            buf.extend_from_slice(point);
+        }
+        buf
+    }
+
+    /// Reads `pack`'s encoding. `None` unless it is exactly one key with at most
+    /// `MAX_PUBLIC_INPUTS` public inputs.
+    pub fn unpack(src: &[u8]) -> Option<Self> {
+        let inputs = *src.get(448)? as usize;
+        if inputs > MAX_PUBLIC_INPUTS || src.len() != 449 + 64 * (inputs + 1) {
+            return None;
+        }
+        Some(VerifyingKey {
+            alpha: src[..64].try_into().unwrap(),
+            beta: src[64..192].try_into().unwrap(),
+            gamma: src[192..320].try_into().unwrap(),
+            delta: src[320..448].try_into().unwrap(),
+            ic: src[449..].chunks_exact(64).map(|point| point.try_into().unwrap()).collect(),
+        })
+    }
+}
+
+/// Checks `proof` for `public_inputs` against `vk`:
+/// `e(-a, b) * e(alpha, beta) * e(ic[0] + sum(input_i * ic[i + 1]), gamma) * e(c, delta) == 1`.
+pub fn verify<B: Bn254>(vk: &VerifyingKey, proof: &Groth16Proof, public_inputs: &[Scalar]) -> Result<(), ProofError> {
+    if vk.ic.is_empty() || public_inputs.len() != vk.public_inputs() {
+        return Err(ProofError::WrongInputCount);
+    }
+    let mut prepared = vk.ic[0];
+    for (input, point) in public_inputs.iter().zip(&vk.ic[1..]) {
+        // Inputs at or above the modulus would let one proof verify for several input values
+        if *input >= SCALAR_FIELD_MODULUS {
+            return Err(ProofError::InputOutOfField);
+        }
+        let term = B::mul(point, input).ok_or(ProofError::InvalidPoint)?;
+        prepared = B::add(&prepared, &term).ok_or(ProofError::InvalidPoint)?;
+    }
+    let mut pairs = Vec::with_capacity(4 * 192);
+    for (g1, g2) in [(negate(&proof.a), &proof.b), (vk.alpha, &vk.beta), (prepared, &vk.gamma), (proof.c, &vk.delta)] {
+        pairs.extend_from_slice(&g1);
+        pairs.extend_from_slice(g2);
+    }
+    match B::pairing_is_one(&pairs) {
+        Some(true) => Ok(()),
+        Some(false) => Err(ProofError::Rejected),
+        None => Err(ProofError::InvalidPoint),
+    }
+}
+
+/// `-point`, i.e. `(x, q - y)`. The point at infinity is its own negation.
+fn negate(point: &G1) -> G1 {
+    if *point == [0u8; 64] {
+        return *point;
+    }
+    let mut negated = *point;
+    let mut borrow = 0u16;
+    for i in (0..32).rev() {
+        let difference = BASE_FIELD_MODULUS[i] as i16 - point[32 + i] as i16 - borrow as i16;
+        negated[32 + i] = difference.rem_euclid(256) as u8;
+        borrow = (difference < 0) as u16;
+    }
+    negated
+}
+
+/// A circuit registered for presentation requests to require proofs of.
+///
+/// Layout (LEN = 1058): is_initialized (1), authority (32), public input count (1), alpha (64),
+/// beta (128), gamma (128), delta (128), then `MAX_PUBLIC_INPUTS + 1` `ic` slots (64 each), the
+/// first `public input count + 1` in use. Circuits cannot be changed once registered, so a
+/// request keeps meaning what the verifier asked for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Circuit {
+    pub is_initialized: bool,
+    pub authority: Key,
+    pub verifying_key: VerifyingKey,
+}
+
+impl AccountLayout for Circuit {
+    const DISCRIMINATOR: [u8; 8] = [113, 209, 5, 225, 233, 216, 248, 61];
+    const LEN: usize = 1 + 32 + 1 + 64 + 3 * 128 + 64 * (MAX_PUBLIC_INPUTS + 1);
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let vk = &self.verifying_key;
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(&self.authority);
+        dst[33] = vk.public_inputs() as u8;
+        dst[34..98].copy_from_slice(&vk.alpha);
+        dst[98..226].copy_from_slice(&vk.beta);
+        dst[226..354].copy_from_slice(&vk.gamma);
+        dst[354..482].copy_from_slice(&vk.delta);
+        dst[482..Self::LEN].fill(0);
+        for (i, point) in vk.ic.iter().take(MAX_PUBLIC_INPUTS + 1).enumerate() {
+            dst[482 + i * 64..546 + i * 64].copy_from_slice(point);
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        let points = if src[0] != 0 { (src[33] as usize).min(MAX_PUBLIC_INPUTS) + 1 } else { 0 };
+        Ok(Circuit {
+            is_initialized: src[0] != 0,
+            authority: read_key(src, 1),
+            verifying_key: VerifyingKey {
+                alpha: src[34..98].try_into().unwrap(),
+                beta: src[98..226].try_into().unwrap(),
+                gamma: src[226..354].try_into().unwrap(),
+                delta: src[354..482].try_into().unwrap(),
+                ic: (0..points).map(|i| src[482 + i * 64..546 + i * 64].try_into().unwrap()).collect(),
+            },
+        })
+    }
+}
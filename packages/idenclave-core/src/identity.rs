@@ -0,0 +1,141 @@
+use crate::{read_key, read_u64, AccountLayout, Key, LayoutError, Service, ServiceError, MAX_SERVICES, SERVICE_LEN};
+
+/// Length of the fixed, zero-padded attributes buffer.
+pub const ATTRIBUTES_LEN: usize = 128;
+
+/// Canonical identity layout (LEN = 986):
+///
+/// | offset  | field              |
+/// |---------|--------------------|
+/// | 0       | is_initialized     |
+/// | 1..33   | authority          |
+/// | 33..65  | recovery           |
+/// | 65..193 | attributes         |
+/// | 193     | active_credentials |
+/// | 197     | credential_count   |
+/// | 205     | service_count      |
+/// | 206..   | services           |
+///
+/// Offsets are within the body, which follows the 8-byte discriminator (see `AccountLayout`).
+/// `services` is `MAX_SERVICES` slots of `SERVICE_LEN` bytes (see `Service`), the first
+/// `service_count` in use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Identity {
+    pub is_initialized: bool,
+    pub authority: Key,
+    pub recovery: Key,
+    pub attributes: [u8; ATTRIBUTES_LEN],
+    pub active_credentials: u32, // Credentials referencing this identity that have not been closed yet
+    pub credential_count: u64,   // Number of credential index entries ever created (next index to use)
+    pub services: Vec<Service>,  // DID service entries in the order they were added, at most MAX_SERVICES
+}
+
+impl Default for Identity {
+    fn default() -> Self {
+        Self {
+            is_initialized: false,
+            authority: Key::default(),
+            recovery: Key::default(),
+            attributes: [0u8; ATTRIBUTES_LEN],
+            active_credentials: 0,
+            credential_count: 0,
+            services: Vec::new(),
+        }
+    }
+}
+
+impl Identity {
+    /// Attributes with the zero padding trimmed.
+    pub fn attributes(&self) -> &[u8] {
+        let len = self.attributes.iter().rposition(|&b| b != 0).map_or(0, |pos| pos + 1);
+        &self.attributes[..len]
+    }
+
+    /// Stores `data` as the attributes, truncated to `ATTRIBUTES_LEN` and zero padded.
+    pub fn set_attributes(&mut self, data: &[u8]) {
+        let data = truncate_attributes(data);
+        self.attributes = [0u8; ATTRIBUTES_LEN];
+        self.attributes[..data.len()].copy_from_slice(data);
+    }
+
+    pub fn service(&self, id: &str) -> Option<&Service> {
+        self.services.iter().find(|service| service.id == id)
+    }
+
+    pub fn add_service(&mut self, service: Service) -> Result<(), ServiceError> {
+        service.check()?;
+        if self.service(&service.id).is_some() {
+            return Err(ServiceError::AlreadyExists);
+        }
+        if self.services.len() >= MAX_SERVICES {
+            return Err(ServiceError::TooManyServices);
+        }
+        self.services.push(service);
+        Ok(())
+    }
+
+    /// Replaces the type and endpoint of the service with the same id, keeping its position.
+    pub fn update_service(&mut self, service: Service) -> Result<(), ServiceError> {
+        service.check()?;
+        let existing = self.services.iter_mut().find(|existing| existing.id == service.id).ok_or(ServiceError::NotFound)?;
+        *existing = service;
+        Ok(())
+    }
+
+    /// Removes a service; the remaining services keep their order.
+    pub fn remove_service(&mut self, id: &str) -> Result<(), ServiceError> {
+        let position = self.services.iter().position(|service| service.id == id).ok_or(ServiceError::NotFound)?;
+        self.services.remove(position);
+        Ok(())
+    }
+}
+
+impl AccountLayout for Identity {
+    const DISCRIMINATOR: [u8; 8] = [58, 132, 5, 12, 176, 164, 85, 112];
+    const LEN: usize = 1 + 32 + 32 + ATTRIBUTES_LEN + 4 + 8 + 1 + SERVICE_LEN * MAX_SERVICES;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(&self.authority);
+        dst[33..65].copy_from_slice(&self.recovery);
+        dst[65..193].copy_from_slice(&self.attributes);
+        dst[193..197].copy_from_slice(&self.active_credentials.to_le_bytes());
+        dst[197..205].copy_from_slice(&self.credential_count.to_le_bytes());
+        let services = &self.services[..self.services.len().min(MAX_SERVICES)];
+        dst[205] = services.len() as u8;
+        for (i, slot) in dst[206..Self::LEN].chunks_exact_mut(SERVICE_LEN).enumerate() {
+            match services.get(i) {
+                Some(service) => service.pack_slot(slot),
+                None => slot.fill(0),
+            }
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        let mut attributes = [0u8; ATTRIBUTES_LEN];
+        attributes.copy_from_slice(&src[65..193]);
+        let mut active_credentials = [0u8; 4];
+        active_credentials.copy_from_slice(&src[193..197]);
+        Ok(Identity {
+            is_initialized: src[0] != 0,
+            authority: read_key(src, 1),
+            recovery: read_key(src, 33),
+            attributes,
+            active_credentials: u32::from_le_bytes(active_credentials),
+            credential_count: read_u64(src, 197),
+            services: src[206..Self::LEN]
+                .chunks_exact(SERVICE_LEN)
+                .take((src[205] as usize).min(MAX_SERVICES))
+                .map(Service::unpack_slot)
+                .collect(),
+        })
+    }
+}
+
+/// Attribute data longer than the fixed buffer is silently truncated.
+pub fn truncate_attributes(data: &[u8]) -> &[u8] {
+    &data[..data.len().min(ATTRIBUTES_LEN)]
+}
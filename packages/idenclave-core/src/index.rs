@@ -0,0 +1,40 @@
+use crate::{read_key, read_u64, AccountLayout, Key, LayoutError};
+
+/// Entry `n` of an identity's credential index, stored in the PDA
+/// `[b"cred-index", identity, n (u64 LE)]`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CredentialIndex {
+    pub is_initialized: bool,
+    pub identity: Key,
+    pub index: u64,
+    pub credential: Key,
+}
+
+impl CredentialIndex {
+    pub const SEED: &'static [u8] = b"cred-index";
+}
+
+impl AccountLayout for CredentialIndex {
+    const DISCRIMINATOR: [u8; 8] = [249, 168, 238, 238, 249, 220, 234, 44];
+    // LEN: is_initialized (1) + identity (32) + index (8) + credential (32)
+    const LEN: usize = 1 + 32 + 8 + 32;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(&self.identity);
+        dst[33..41].copy_from_slice(&self.index.to_le_bytes());
+        dst[41..73].copy_from_slice(&self.credential);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        Ok(CredentialIndex {
+            is_initialized: src[0] != 0,
+            identity: read_key(src, 1),
+            index: read_u64(src, 33),
+            credential: read_key(src, 41),
+        })
+    }
+}
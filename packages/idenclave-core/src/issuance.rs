@@ -0,0 +1,107 @@
+use crate::Key;
+
+/// Prefix of every issuance message, so an issuer key signing other payloads can never
+/// produce something that passes as an issuance.
+pub const ISSUANCE_DOMAIN: &[u8] = b"idenclave:issue:v2";
+
+/// What an issuer signs offline to authorize `IssueCredentialSigned`.
+///
+/// The credential is created at the PDA `[b"credential", issuer, identity, schema, nonce]`, so a
+/// signed message can be used for exactly one credential: submitting it again targets the same,
+/// already initialized address. `deadline` bounds how long the signature stays usable, which also
+/// covers the window after that credential has been closed. The program id is part of the
+/// message so a signature for one deployment cannot be replayed on another. `claims_root` commits
+/// to the credential's selectively disclosable claims, all zeroes when it has none. A key signing
+/// on behalf of an organization names the organization as `issuer`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IssuanceMessage {
+    pub program_id: Key,
+    pub issuer: Key,
+    pub identity: Key,
+    pub schema: Key,
+    pub nonce: u64,
+    pub credential_ref: [u8; 32],
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub claims_root: [u8; 32],
+    pub deadline: u64,
+}
+
+impl IssuanceMessage {
+    pub const LEN: usize = ISSUANCE_DOMAIN.len() + 32 * 6 + 8 * 4;
+
+    /// Canonical encoding: the domain, then every field in declaration order, integers little endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::LEN);
+        buf.extend_from_slice(ISSUANCE_DOMAIN);
+        buf.extend_from_slice(&self.program_id);
+        buf.extend_from_slice(&self.issuer);
+        buf.extend_from_slice(&self.identity);
+        buf.extend_from_slice(&self.schema);
+        buf.extend_from_slice(&self.nonce.to_le_bytes());
+        buf.extend_from_slice(&self.credential_ref);
+        buf.extend_from_slice(&self.issued_at.to_le_bytes());
+        buf.extend_from_slice(&self.expires_at.to_le_bytes());
+        buf.extend_from_slice(&self.claims_root);
+        buf.extend_from_slice(&self.deadline.to_le_bytes());
+        buf
+    }
+}
+
+// Ed25519 program instruction data: a signature count and a padding byte, then one 14-byte
+// offsets record per signature (seven u16: signature offset and instruction index, public key
+// offset and instruction index, message offset, message size and instruction index).
+const OFFSETS_START: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const DATA_START: usize = OFFSETS_START + OFFSETS_LEN;
+// An instruction index of u16::MAX refers to the Ed25519 instruction's own data.
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Builds Ed25519 program instruction data verifying a single `signature` by `pubkey` over
+/// `message`, with everything stored inline. Used by relayers and tests to submit signed issuance.
+pub fn ed25519_instruction_data(pubkey: &Key, signature: &[u8; 64], message: &[u8]) -> Vec<u8> {
+    let public_key_offset = DATA_START;
+    let signature_offset = public_key_offset + 32;
+    let message_offset = signature_offset + 64;
+    let mut data = Vec::with_capacity(message_offset + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for field in [
+        signature_offset as u16,
+        CURRENT_INSTRUCTION,
+        public_key_offset as u16,
+        CURRENT_INSTRUCTION,
+        message_offset as u16,
+        message.len() as u16,
+        CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey);
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    data
+}
+
+/// Reads the public key and message out of Ed25519 program instruction data carrying exactly one
+/// signature. Data referring to other instructions is rejected, since then the bytes checked by the
+/// Ed25519 program are not the ones seen here. The Ed25519 program itself verifies the signature.
+pub fn parse_ed25519_instruction(data: &[u8]) -> Option<(Key, &[u8])> {
+    if data.len() < DATA_START || data[0] != 1 {
+        return None;
+    }
+    let field = |i: usize| {
+        let at = OFFSETS_START + 2 * i;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    if field(1) != CURRENT_INSTRUCTION || field(3) != CURRENT_INSTRUCTION || field(6) != CURRENT_INSTRUCTION {
+        return None;
+    }
+    let public_key_offset = field(2) as usize;
+    let message_offset = field(4) as usize;
+    let message_len = field(5) as usize;
+    let pubkey = data.get(public_key_offset..public_key_offset + 32)?;
+    let message = data.get(message_offset..message_offset + message_len)?;
+    let mut key = [0u8; 32];
+    key.copy_from_slice(pubkey);
+    Some((key, message))
+}
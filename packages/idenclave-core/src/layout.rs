@@ -0,0 +1,42 @@
+use crate::LayoutError;
+
+/// Byte layout of an idenclave account.
+///
+/// Every account starts with an 8-byte discriminator naming its type, followed by `LEN` bytes
+/// of body. The discriminators are the ones Anchor derives (`sha256("account:<Name>")[..8]`),
+/// so native and Anchor accounts are byte-for-byte identical and an account of one type can
+/// never be read as another.
+pub trait AccountLayout: Sized + Default {
+    const DISCRIMINATOR: [u8; 8];
+    /// Length of the body, without the discriminator.
+    const LEN: usize;
+    /// Full account size: discriminator followed by the body.
+    const ACCOUNT_LEN: usize = 8 + Self::LEN;
+
+    /// Writes the body only.
+    fn pack_into_slice(&self, dst: &mut [u8]);
+
+    /// Reads the body only.
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError>;
+
+    /// Writes the discriminator and the body.
+    fn pack_account(&self, dst: &mut [u8]) {
+        dst[..8].copy_from_slice(&Self::DISCRIMINATOR);
+        self.pack_into_slice(&mut dst[8..]);
+    }
+
+    /// Reads a whole account. A zeroed discriminator is a freshly allocated account and reads as
+    /// the default (uninitialized) value; any other discriminator must match this type.
+    fn unpack_account(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::ACCOUNT_LEN {
+            return Err(LayoutError::TooShort);
+        }
+        if src[..8] == [0u8; 8] {
+            return Ok(Self::default());
+        }
+        if src[..8] != Self::DISCRIMINATOR {
+            return Err(LayoutError::WrongDiscriminator);
+        }
+        Self::unpack_from_slice(&src[8..])
+    }
+}
@@ -0,0 +1,68 @@
+//! Canonical state of the idenclave programs.
+//!
+//! Both the native program (`packages/idenclave`) and the Anchor program
+//! (`myanchorproject/programs/idenclave`) take their account layouts, credential status
+//! evaluation and validation rules from here. The crate has no Solana dependency so it can be
+//! used with either program's `solana-program` version; keys are plain 32-byte arrays.
+
+pub mod accreditation;
+pub mod batch;
+pub mod claims;
+pub mod commitment;
+pub mod compressed;
+pub mod credential;
+pub mod error;
+pub mod groth16;
+pub mod identity;
+pub mod index;
+pub mod issuance;
+pub mod layout;
+pub mod merkle;
+pub mod organization;
+pub mod presentation;
+pub mod service;
+pub mod tombstone;
+pub mod verification;
+
+pub use accreditation::{evaluate_chain, Accreditation, AccreditationReport, AccreditationStatus, MAX_ACCREDITATION_CHAIN};
+pub use batch::{BatchIssueItem, BatchItemResult, MAX_BATCH_ITEMS};
+pub use claims::{Claim, ClaimProof, ClaimSet, MAX_CLAIMS_DEPTH};
+pub use commitment::{claim_hash, claims_commitment, PoseidonHasher, MAX_COMMITTED_CLAIMS};
+pub use compressed::{CompressedCredential, CredentialTreeMirror};
+pub use credential::{Credential, CredentialState, CredentialStatus};
+pub use error::{ClaimError, LayoutError, OrganizationError, ProofError, RuleViolation, ServiceError, TreeError};
+pub use groth16::{Bn254, Circuit, Groth16Proof, Scalar, VerifyingKey, MAX_PUBLIC_INPUTS};
+pub use identity::{truncate_attributes, Identity, ATTRIBUTES_LEN};
+pub use index::CredentialIndex;
+pub use issuance::IssuanceMessage;
+pub use layout::AccountLayout;
+pub use merkle::{CredentialTree, MerkleHasher, TreeMirror};
+pub use organization::{Organization, MAX_ORGANIZATION_KEYS};
+pub use presentation::{
+    PresentationRequest, PresentationResponse, PresentationResult, PresentedCredential, MAX_PREDICATE_INPUTS,
+    MAX_REQUEST_SCHEMAS, MAX_TRUSTED_ISSUERS,
+};
+pub use service::{Service, MAX_SERVICES, SERVICE_LEN};
+pub use tombstone::Tombstone;
+pub use verification::VerificationReceipt;
+
+/// Raw public key bytes.
+pub type Key = [u8; 32];
+
+/// Converts a `Clock::unix_timestamp` to the unsigned timestamps stored in accounts.
+/// Timestamps before the epoch are clamped to zero instead of wrapping around.
+pub fn unix_now(unix_timestamp: i64) -> u64 {
+    unix_timestamp.max(0) as u64
+}
+
+pub(crate) fn read_key(src: &[u8], offset: usize) -> Key {
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&src[offset..offset + 32]);
+    key
+}
+
+pub(crate) fn read_u64(src: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&src[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
@@ -0,0 +1,403 @@
+use crate::{read_key, read_u64, Key, TreeError};
+
+/// A node (or leaf hash) of a credential tree.
+pub type Node = [u8; 32];
+
+/// Hash function of the credential trees. Both programs use SHA-256 through the `sol_sha256`
+/// syscall; keeping it a parameter leaves this crate without dependencies.
+pub trait MerkleHasher {
+    fn hashv(parts: &[&[u8]]) -> Node;
+}
+
+/// Leaf value of an index nothing has been appended to.
+pub const EMPTY_LEAF: Node = [0; 32];
+/// Deepest supported tree: a little over a million leaves.
+pub const MAX_TREE_DEPTH: usize = 20;
+/// Most recent changes kept, and so how many changes a proof can be behind and still be used.
+pub const MAX_BUFFER_SIZE: usize = 64;
+
+pub fn hash_pair<H: MerkleHasher>(left: &Node, right: &Node) -> Node {
+    H::hashv(&[left, right])
+}
+
+/// Roots of empty subtrees: `empty[h]` is the root of an empty subtree of height `h`.
+pub fn empty_nodes<H: MerkleHasher>(max_depth: usize) -> Vec<Node> {
+    let mut empty = Vec::with_capacity(max_depth + 1);
+    empty.push(EMPTY_LEAF);
+    for h in 0..max_depth {
+        empty.push(hash_pair::<H>(&empty[h], &empty[h]));
+    }
+    empty
+}
+
+/// Hashes `leaf` at `index` up through `proof` (the sibling at every level, leaf level first),
+/// writing the node at every level into `path` (`path[0]` is the leaf), and returns the root.
+fn walk<H: MerkleHasher>(leaf: &Node, index: u32, proof: &[Node], path: &mut [Node]) -> Node {
+    let mut node = *leaf;
+    for (level, sibling) in proof.iter().enumerate() {
+        path[level] = node;
+        node = if index >> level & 1 == 1 { hash_pair::<H>(sibling, &node) } else { hash_pair::<H>(&node, sibling) };
+    }
+    node
+}
+
+/// Root of the tree `proof` places `leaf` at `index` in.
+pub fn compute_root<H: MerkleHasher>(leaf: &Node, index: u32, proof: &[Node]) -> Node {
+    let mut path = vec![EMPTY_LEAF; proof.len()];
+    walk::<H>(leaf, index, proof, &mut path)
+}
+
+/// Level at which the paths of two different leaves are siblings: one below where they join.
+fn critical_level(a: u32, b: u32) -> usize {
+    (31 - (a ^ b).leading_zeros()) as usize
+}
+
+/// Concurrent Merkle tree of credential leaves, read and updated in place in account data.
+///
+/// Besides the root, the tree keeps a ring buffer of its most recent changes (root, the path of
+/// the changed leaf and its index). A proof made against any of those roots can be fast-forwarded
+/// through the later changes, so several clients can update leaves in the same slot without
+/// their proofs going stale. Appends need no proof: the tree keeps the proof of the next index.
+///
+/// Layout after the 8-byte discriminator:
+///
+/// | offset  | field                                   |
+/// |---------|-----------------------------------------|
+/// | 0       | is_initialized                          |
+/// | 1       | max_depth                               |
+/// | 2       | max_buffer_size                         |
+/// | 3..35   | authority                               |
+/// | 35      | sequence_number (u64)                   |
+/// | 43      | active_index (u32)                      |
+/// | 47      | buffer_size (u32)                       |
+/// | 51      | next_index (u32)                        |
+/// | 55      | proof of next_index (max_depth nodes)   |
+/// | ..      | changelog (max_buffer_size entries of root, path (max_depth nodes), index (u32)) |
+pub struct CredentialTree<D> {
+    data: D,
+}
+
+impl CredentialTree<&[u8]> {
+    /// `sha256("account:CredentialTree")[..8]`, as for every other account.
+    pub const DISCRIMINATOR: [u8; 8] = [183, 223, 137, 17, 243, 224, 47, 215];
+    pub const HEADER_LEN: usize = 1 + 1 + 1 + 32 + 8 + 4 + 4 + 4;
+
+    /// Full account size (discriminator included) of a tree with the given parameters.
+    pub fn account_len(max_depth: u8, max_buffer_size: u8) -> usize {
+        let (depth, buffer) = (max_depth as usize, max_buffer_size as usize);
+        8 + Self::HEADER_LEN + depth * 32 + buffer * changelog_len(depth)
+    }
+}
+
+fn changelog_len(depth: usize) -> usize {
+    32 + depth * 32 + 4
+}
+
+fn read_u32(src: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([src[offset], src[offset + 1], src[offset + 2], src[offset + 3]])
+}
+
+fn valid_parameters(max_depth: u8, max_buffer_size: u8) -> bool {
+    (1..=MAX_TREE_DEPTH).contains(&(max_depth as usize)) && (1..=MAX_BUFFER_SIZE).contains(&(max_buffer_size as usize))
+}
+
+const BODY: usize = 8;
+const IS_INITIALIZED: usize = BODY;
+const MAX_DEPTH: usize = BODY + 1;
+const MAX_BUFFER: usize = BODY + 2;
+const AUTHORITY: usize = BODY + 3;
+const SEQUENCE_NUMBER: usize = BODY + 35;
+const ACTIVE_INDEX: usize = BODY + 43;
+const BUFFER_SIZE: usize = BODY + 47;
+const NEXT_INDEX: usize = BODY + 51;
+const RIGHTMOST_PROOF: usize = BODY + 55;
+
+impl<D: AsRef<[u8]>> CredentialTree<D> {
+    /// Opens an initialized tree.
+    pub fn load(data: D) -> Result<Self, TreeError> {
+        let src = data.as_ref();
+        if src.len() < RIGHTMOST_PROOF || src[..8] != CredentialTree::DISCRIMINATOR || src[IS_INITIALIZED] == 0 {
+            return Err(TreeError::NotInitialized);
+        }
+        let (max_depth, max_buffer_size) = (src[MAX_DEPTH], src[MAX_BUFFER]);
+        if !valid_parameters(max_depth, max_buffer_size) || src.len() != CredentialTree::account_len(max_depth, max_buffer_size) {
+            return Err(TreeError::InvalidParameters);
+        }
+        Ok(CredentialTree { data })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.bytes()[MAX_DEPTH] as usize
+    }
+
+    pub fn max_buffer_size(&self) -> usize {
+        self.bytes()[MAX_BUFFER] as usize
+    }
+
+    /// The only key allowed to add or change leaves.
+    pub fn authority(&self) -> Key {
+        read_key(self.bytes(), AUTHORITY)
+    }
+
+    /// Number of changes applied since the tree was created.
+    pub fn sequence_number(&self) -> u64 {
+        read_u64(self.bytes(), SEQUENCE_NUMBER)
+    }
+
+    /// Number of leaves appended so far, which is also the index of the next one.
+    pub fn next_index(&self) -> u32 {
+        read_u32(self.bytes(), NEXT_INDEX)
+    }
+
+    pub fn capacity(&self) -> u64 {
+        1u64 << self.max_depth()
+    }
+
+    fn active_index(&self) -> usize {
+        read_u32(self.bytes(), ACTIVE_INDEX) as usize
+    }
+
+    fn buffer_size(&self) -> usize {
+        read_u32(self.bytes(), BUFFER_SIZE) as usize
+    }
+
+    fn changelog_offset(&self, slot: usize) -> usize {
+        RIGHTMOST_PROOF + self.max_depth() * 32 + slot * changelog_len(self.max_depth())
+    }
+
+    fn changelog_root(&self, slot: usize) -> Node {
+        read_key(self.bytes(), self.changelog_offset(slot))
+    }
+
+    fn changelog_path_node(&self, slot: usize, level: usize) -> Node {
+        read_key(self.bytes(), self.changelog_offset(slot) + 32 + level * 32)
+    }
+
+    fn changelog_index(&self, slot: usize) -> u32 {
+        read_u32(self.bytes(), self.changelog_offset(slot) + 32 + self.max_depth() * 32)
+    }
+
+    fn rightmost_proof(&self) -> Vec<Node> {
+        (0..self.max_depth()).map(|level| read_key(self.bytes(), RIGHTMOST_PROOF + level * 32)).collect()
+    }
+
+    pub fn root(&self) -> Node {
+        self.changelog_root(self.active_index())
+    }
+
+    /// Checks that `leaf` is the current leaf at `index`, given a proof against `root`, which may
+    /// be the current root or any root still in the changelog.
+    pub fn verify<H: MerkleHasher>(&self, root: &Node, index: u32, leaf: &Node, proof: &[Node]) -> Result<(), TreeError> {
+        let mut proof = proof.to_vec();
+        self.fast_forward::<H>(root, index, leaf, &mut proof)
+    }
+
+    /// Verifies `proof` against `root` and updates it in place to a proof against the current
+    /// root, replaying every change made since `root`. Fails if the leaf itself was changed.
+    fn fast_forward<H: MerkleHasher>(&self, root: &Node, index: u32, leaf: &Node, proof: &mut [Node]) -> Result<(), TreeError> {
+        if index >= self.next_index() {
+            return Err(TreeError::IndexOutOfRange);
+        }
+        if proof.len() != self.max_depth() {
+            return Err(TreeError::InvalidProof);
+        }
+        // Newest first: 0 is the current root, `behind` changes were made after the matching one
+        let (size, active) = (self.buffer_size(), self.active_index());
+        let slot = |behind: usize| (active + self.max_buffer_size() - behind) % self.max_buffer_size();
+        let behind = (0..size).find(|behind| self.changelog_root(slot(*behind)) == *root).ok_or(TreeError::RootNotFound)?;
+        if compute_root::<H>(leaf, index, proof) != *root {
+            return Err(TreeError::InvalidProof);
+        }
+        for later in (0..behind).rev() {
+            let changed = self.changelog_index(slot(later));
+            if changed == index {
+                return Err(TreeError::LeafChanged);
+            }
+            let level = critical_level(index, changed);
+            proof[level] = self.changelog_path_node(slot(later), level);
+        }
+        Ok(())
+    }
+}
+
+impl<D: AsRef<[u8]> + AsMut<[u8]>> CredentialTree<D> {
+    /// Initializes an empty tree in a zeroed account of `CredentialTree::account_len` bytes.
+    pub fn initialize<H: MerkleHasher>(mut data: D, max_depth: u8, max_buffer_size: u8, authority: &Key) -> Result<Self, TreeError> {
+        if !valid_parameters(max_depth, max_buffer_size) || data.as_ref().len() != CredentialTree::account_len(max_depth, max_buffer_size) {
+            return Err(TreeError::InvalidParameters);
+        }
+        let dst = data.as_mut();
+        if dst[..8] != [0u8; 8] {
+            return Err(TreeError::AlreadyInitialized);
+        }
+        dst[..8].copy_from_slice(&CredentialTree::DISCRIMINATOR);
+        dst[IS_INITIALIZED] = 1;
+        dst[MAX_DEPTH] = max_depth;
+        dst[MAX_BUFFER] = max_buffer_size;
+        dst[AUTHORITY..AUTHORITY + 32].copy_from_slice(authority);
+        let mut tree = CredentialTree { data };
+        let empty = empty_nodes::<H>(max_depth as usize);
+        tree.write_rightmost_proof(&empty[..max_depth as usize]);
+        // The first changelog entry is the empty tree, so proofs against it can be fast-forwarded
+        tree.write_changelog(0, &empty[max_depth as usize], &empty[..max_depth as usize], 0);
+        tree.write_u32(BUFFER_SIZE, 1);
+        Ok(tree)
+    }
+
+    /// Appends `leaf` at the next index and returns that index and the new root.
+    pub fn append<H: MerkleHasher>(&mut self, leaf: &Node) -> Result<(u32, Node), TreeError> {
+        let index = self.next_index();
+        if index as u64 >= self.capacity() {
+            return Err(TreeError::TreeFull);
+        }
+        let depth = self.max_depth();
+        let mut rightmost = self.rightmost_proof();
+        let mut path = vec![EMPTY_LEAF; depth];
+        let root = walk::<H>(leaf, index, &rightmost, &mut path);
+        self.push_changelog(&root, &path, index);
+        self.write_u32(NEXT_INDEX, index + 1);
+
+        // Proof of the following index: empty to the right of it up to the level where the
+        // leaf just appended becomes its left sibling, unchanged above
+        let level = (index + 1).trailing_zeros() as usize;
+        if level < depth {
+            let empty = empty_nodes::<H>(level);
+            rightmost[..level].copy_from_slice(&empty[..level]);
+            rightmost[level] = path[level];
+            self.write_rightmost_proof(&rightmost);
+        }
+        Ok((index, root))
+    }
+
+    /// Replaces the leaf at `index`, proven to be `old_leaf` by `proof` against `root`, with
+    /// `new_leaf` and returns the new root.
+    pub fn replace<H: MerkleHasher>(&mut self, root: &Node, index: u32, old_leaf: &Node, new_leaf: &Node, proof: &[Node]) -> Result<Node, TreeError> {
+        let mut proof = proof.to_vec();
+        self.fast_forward::<H>(root, index, old_leaf, &mut proof)?;
+        let mut path = vec![EMPTY_LEAF; self.max_depth()];
+        let new_root = walk::<H>(new_leaf, index, &proof, &mut path);
+        self.push_changelog(&new_root, &path, index);
+
+        // The changed leaf is left of the next index, and its path a sibling of that index's
+        let next = self.next_index();
+        if next as u64 != self.capacity() {
+            let level = critical_level(index, next);
+            let mut rightmost = self.rightmost_proof();
+            rightmost[level] = path[level];
+            self.write_rightmost_proof(&rightmost);
+        }
+        Ok(new_root)
+    }
+
+    fn write_u32(&mut self, offset: usize, value: u32) {
+        self.data.as_mut()[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_rightmost_proof(&mut self, proof: &[Node]) {
+        for (level, node) in proof.iter().enumerate() {
+            let offset = RIGHTMOST_PROOF + level * 32;
+            self.data.as_mut()[offset..offset + 32].copy_from_slice(node);
+        }
+    }
+
+    fn write_changelog(&mut self, slot: usize, root: &Node, path: &[Node], index: u32) {
+        let offset = self.changelog_offset(slot);
+        let dst = self.data.as_mut();
+        dst[offset..offset + 32].copy_from_slice(root);
+        for (level, node) in path.iter().enumerate() {
+            dst[offset + 32 + level * 32..offset + 64 + level * 32].copy_from_slice(node);
+        }
+        let end = offset + 32 + path.len() * 32;
+        dst[end..end + 4].copy_from_slice(&index.to_le_bytes());
+    }
+
+    fn push_changelog(&mut self, root: &Node, path: &[Node], index: u32) {
+        let slot = (self.active_index() + 1) % self.max_buffer_size();
+        self.write_changelog(slot, root, path, index);
+        self.write_u32(ACTIVE_INDEX, slot as u32);
+        let size = (self.buffer_size() + 1).min(self.max_buffer_size());
+        self.write_u32(BUFFER_SIZE, size as u32);
+        let sequence_number = self.sequence_number() + 1;
+        self.data.as_mut()[SEQUENCE_NUMBER..SEQUENCE_NUMBER + 8].copy_from_slice(&sequence_number.to_le_bytes());
+    }
+}
+
+/// Complete off-chain copy of a credential tree, for building proofs. Only the appended part of
+/// every level is stored; everything to the right of it is empty.
+pub struct TreeMirror<H> {
+    empty: Vec<Node>,
+    /// `levels[0]` are the leaves, `levels[max_depth]` holds the root once anything is appended
+    levels: Vec<Vec<Node>>,
+    hasher: std::marker::PhantomData<H>,
+}
+
+impl<H: MerkleHasher> TreeMirror<H> {
+    pub fn new(max_depth: usize) -> Self {
+        TreeMirror { empty: empty_nodes::<H>(max_depth), levels: vec![Vec::new(); max_depth + 1], hasher: std::marker::PhantomData }
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn len(&self) -> u32 {
+        self.levels[0].len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    pub fn leaf(&self, index: u32) -> Option<Node> {
+        self.levels[0].get(index as usize).copied()
+    }
+
+    fn node(&self, level: usize, position: usize) -> Node {
+        self.levels[level].get(position).copied().unwrap_or(self.empty[level])
+    }
+
+    pub fn root(&self) -> Node {
+        self.node(self.max_depth(), 0)
+    }
+
+    /// Appends a leaf and returns its index.
+    pub fn append(&mut self, leaf: Node) -> u32 {
+        let index = self.len();
+        self.levels[0].push(leaf);
+        self.update(index);
+        index
+    }
+
+    /// Changes an appended leaf; `false` if nothing was appended at `index` yet.
+    pub fn set(&mut self, index: u32, leaf: Node) -> bool {
+        match self.levels[0].get_mut(index as usize) {
+            Some(node) => *node = leaf,
+            None => return false,
+        }
+        self.update(index);
+        true
+    }
+
+    fn update(&mut self, index: u32) {
+        let mut position = index as usize;
+        for level in 0..self.max_depth() {
+            let parent = hash_pair::<H>(&self.node(level, position & !1), &self.node(level, position | 1));
+            position >>= 1;
+            let nodes = &mut self.levels[level + 1];
+            if position == nodes.len() {
+                nodes.push(parent);
+            } else {
+                nodes[position] = parent;
+            }
+        }
+    }
+
+    /// Proof of the leaf at `index` against the current root.
+    pub fn proof(&self, index: u32) -> Vec<Node> {
+        (0..self.max_depth()).map(|level| self.node(level, (index as usize >> level) ^ 1)).collect()
+    }
+}
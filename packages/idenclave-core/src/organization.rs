@@ -0,0 +1,92 @@
+use crate::{read_key, AccountLayout, Key, LayoutError, OrganizationError};
+
+/// Most signing keys an organization can authorize at once.
+pub const MAX_ORGANIZATION_KEYS: usize = 8;
+
+/// An issuer organization, stored in the PDA `[b"organization", authority]`.
+///
+/// Credentials issued on its behalf record the organization's address as their issuer, and any
+/// key it currently authorizes may manage them. Rotating a key therefore keeps every credential
+/// issued with the old key manageable. The authority manages the key set and is not itself a
+/// signing key unless it adds itself.
+///
+/// Layout (LEN = 290): is_initialized (1), authority (32), key_count (1), then
+/// `MAX_ORGANIZATION_KEYS` key slots (32 each), the first `key_count` in use.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Organization {
+    pub is_initialized: bool,
+    pub authority: Key,
+    pub key_count: u8,
+    pub keys: [Key; MAX_ORGANIZATION_KEYS],
+}
+
+impl Organization {
+    pub const SEED: &'static [u8] = b"organization";
+
+    /// The keys currently authorized to issue and manage credentials.
+    pub fn keys(&self) -> &[Key] {
+        &self.keys[..(self.key_count as usize).min(MAX_ORGANIZATION_KEYS)]
+    }
+
+    pub fn is_authorized(&self, key: &Key) -> bool {
+        self.keys().contains(key)
+    }
+
+    pub fn add_key(&mut self, key: &Key) -> Result<(), OrganizationError> {
+        if self.is_authorized(key) {
+            return Err(OrganizationError::KeyAlreadyAuthorized);
+        }
+        let count = self.keys().len();
+        if count == MAX_ORGANIZATION_KEYS {
+            return Err(OrganizationError::TooManyKeys);
+        }
+        self.keys[count] = *key;
+        self.key_count = count as u8 + 1;
+        Ok(())
+    }
+
+    /// Removes a key; the remaining keys keep their order.
+    pub fn remove_key(&mut self, key: &Key) -> Result<(), OrganizationError> {
+        let position = self.keys().iter().position(|k| k == key).ok_or(OrganizationError::KeyNotAuthorized)?;
+        let count = self.keys().len();
+        self.keys.copy_within(position + 1..count, position);
+        self.keys[count - 1] = Key::default();
+        self.key_count = count as u8 - 1;
+        Ok(())
+    }
+
+    /// Replaces `old` with `new` in place, so there is no moment where neither is authorized.
+    pub fn rotate_key(&mut self, old: &Key, new: &Key) -> Result<(), OrganizationError> {
+        if self.is_authorized(new) {
+            return Err(OrganizationError::KeyAlreadyAuthorized);
+        }
+        let position = self.keys().iter().position(|k| k == old).ok_or(OrganizationError::KeyNotAuthorized)?;
+        self.keys[position] = *new;
+        Ok(())
+    }
+}
+
+impl AccountLayout for Organization {
+    const DISCRIMINATOR: [u8; 8] = [145, 38, 152, 251, 91, 57, 118, 160];
+    const LEN: usize = 1 + 32 + 1 + 32 * MAX_ORGANIZATION_KEYS;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(&self.authority);
+        dst[33] = self.key_count;
+        for (i, key) in self.keys.iter().enumerate() {
+            dst[34 + i * 32..66 + i * 32].copy_from_slice(key);
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        let mut keys = [Key::default(); MAX_ORGANIZATION_KEYS];
+        for (i, key) in keys.iter_mut().enumerate() {
+            *key = read_key(src, 34 + i * 32);
+        }
+        Ok(Organization { is_initialized: src[0] != 0, authority: read_key(src, 1), key_count: src[33], keys })
+    }
+}
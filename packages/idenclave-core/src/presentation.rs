@@ -0,0 +1,233 @@
+use crate::{read_key, read_u64, AccountLayout, CredentialStatus, Key, LayoutError, Scalar};
+
+/// Most credential schemas a presentation request can require.
+pub const MAX_REQUEST_SCHEMAS: usize = 4;
+/// Most issuers a presentation request can trust.
+pub const MAX_TRUSTED_ISSUERS: usize = 8;
+/// Most public inputs a presentation request can fix for its circuit.
+pub const MAX_PREDICATE_INPUTS: usize = 4;
+
+/// A verifier's request for a presentation, stored in the PDA
+/// `[b"presentation-request", verifier, nonce (u64 LE)]`.
+///
+/// A response must present a valid credential of every schema in `schemas`, issued by one of
+/// `trusted_issuers` (any issuer when there are none). With a `circuit` (all zeros for none) it
+/// must also carry a proof for that circuit whose public inputs start with `predicate_inputs`,
+/// the values the verifier fixes, such as an age threshold. The next public input is the
+/// commitment signal: it has to equal the stored commitment of the credential presented for the
+/// first schema, so the proof is about that credential's claims.
+///
+/// Layout (LEN = 596): is_initialized (1), verifier (32), nonce (8), expires_at (8),
+/// schema_count (1), `MAX_REQUEST_SCHEMAS` schema slots (32 each), issuer_count (1),
+/// `MAX_TRUSTED_ISSUERS` issuer slots (32 each), circuit (32), predicate_input_count (1),
+/// `MAX_PREDICATE_INPUTS` input slots (32 each).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PresentationRequest {
+    pub is_initialized: bool,
+    pub verifier: Key,
+    pub nonce: u64,
+    pub expires_at: u64,
+    pub schemas: Vec<[u8; 32]>,
+    pub trusted_issuers: Vec<Key>,
+    pub circuit: Key,
+    pub predicate_inputs: Vec<Scalar>,
+}
+
+/// A credential presented for one of a request's schemas, already shown to be of that schema.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PresentedCredential {
+    pub identity: Key,
+    pub issuer: Key,
+    pub status: CredentialStatus,
+    /// The credential's Poseidon commitment, all zeros for none
+    pub commitment: Scalar,
+}
+
+/// Outcome of a response, recorded for the verifier to read.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentationResult {
+    Passed = 0,
+    /// A required schema has no credential shown to be of that schema
+    MissingCredential = 1,
+    /// A credential belongs to another identity than the responding one
+    WrongIdentity = 2,
+    /// A credential's issuer is not among the request's trusted issuers
+    UntrustedIssuer = 3,
+    /// A credential is not valid (revoked, expired, suspended, ...)
+    InvalidCredential = 4,
+    /// The proof is missing, does not start with the request's inputs or does not verify
+    InvalidProof = 5,
+    /// The proof's commitment signal is not the commitment of the credential it has to be about
+    CommitmentMismatch = 6,
+}
+
+impl TryFrom<u8> for PresentationResult {
+    type Error = u8;
+
+    fn try_from(code: u8) -> Result<Self, u8> {
+        Ok(match code {
+            0 => PresentationResult::Passed,
+            1 => PresentationResult::MissingCredential,
+            2 => PresentationResult::WrongIdentity,
+            3 => PresentationResult::UntrustedIssuer,
+            4 => PresentationResult::InvalidCredential,
+            5 => PresentationResult::InvalidProof,
+            6 => PresentationResult::CommitmentMismatch,
+            other => return Err(other),
+        })
+    }
+}
+
+impl PresentationRequest {
+    pub const SEED: &'static [u8] = b"presentation-request";
+
+    pub fn has_circuit(&self) -> bool {
+        self.circuit != Key::default()
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now > self.expires_at
+    }
+
+    pub fn trusts(&self, issuer: &Key) -> bool {
+        self.trusted_issuers.is_empty() || self.trusted_issuers.contains(issuer)
+    }
+
+    /// Whether a proof's public inputs start with the inputs the verifier fixed.
+    pub fn accepts_inputs(&self, public_inputs: &[Scalar]) -> bool {
+        public_inputs.starts_with(&self.predicate_inputs)
+    }
+
+    /// Position of the commitment signal among a proof's public inputs, right after the
+    /// predicate inputs. A circuit needs more public inputs than this.
+    pub fn commitment_input(&self) -> usize {
+        self.predicate_inputs.len()
+    }
+
+    /// Evaluates a response from `identity`. `presented` holds one entry per schema of the
+    /// request, `None` where no credential was shown to be of that schema; `verified_inputs`
+    /// are the public inputs of the response's proof if it checks out, and are ignored without
+    /// a circuit.
+    pub fn evaluate(&self, identity: &Key, presented: &[Option<PresentedCredential>], verified_inputs: Option<&[Scalar]>) -> PresentationResult {
+        if presented.len() != self.schemas.len() {
+            return PresentationResult::MissingCredential;
+        }
+        for credential in presented {
+            let Some(credential) = credential else {
+                return PresentationResult::MissingCredential;
+            };
+            if credential.identity != *identity {
+                return PresentationResult::WrongIdentity;
+            }
+            if !self.trusts(&credential.issuer) {
+                return PresentationResult::UntrustedIssuer;
+            }
+            if credential.status != CredentialStatus::Valid {
+                return PresentationResult::InvalidCredential;
+            }
+        }
+        if self.has_circuit() {
+            let Some(inputs) = verified_inputs else {
+                return PresentationResult::InvalidProof;
+            };
+            let commitment = presented.first().copied().flatten().map(|credential| credential.commitment);
+            match (commitment, inputs.get(self.commitment_input())) {
+                (Some(commitment), Some(signal)) if commitment != Scalar::default() && commitment == *signal => {}
+                _ => return PresentationResult::CommitmentMismatch,
+            }
+        }
+        PresentationResult::Passed
+    }
+}
+
+impl AccountLayout for PresentationRequest {
+    const DISCRIMINATOR: [u8; 8] = [134, 179, 27, 219, 120, 217, 117, 125];
+    const LEN: usize = 1 + 32 + 8 + 8 + 1 + 32 * MAX_REQUEST_SCHEMAS + 1 + 32 * MAX_TRUSTED_ISSUERS + 32 + 1 + 32 * MAX_PREDICATE_INPUTS;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[..Self::LEN].fill(0);
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(&self.verifier);
+        dst[33..41].copy_from_slice(&self.nonce.to_le_bytes());
+        dst[41..49].copy_from_slice(&self.expires_at.to_le_bytes());
+        let mut offset = 49;
+        for (values, max) in [(&self.schemas, MAX_REQUEST_SCHEMAS), (&self.trusted_issuers, MAX_TRUSTED_ISSUERS)] {
+            dst[offset] = values.len().min(max) as u8;
+            for (i, value) in values.iter().take(max).enumerate() {
+                dst[offset + 1 + i * 32..offset + 33 + i * 32].copy_from_slice(value);
+            }
+            offset += 1 + 32 * max;
+        }
+        dst[offset..offset + 32].copy_from_slice(&self.circuit);
+        offset += 32;
+        dst[offset] = self.predicate_inputs.len().min(MAX_PREDICATE_INPUTS) as u8;
+        for (i, input) in self.predicate_inputs.iter().take(MAX_PREDICATE_INPUTS).enumerate() {
+            dst[offset + 1 + i * 32..offset + 33 + i * 32].copy_from_slice(input);
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        let list = |offset: usize, max: usize| -> Vec<[u8; 32]> {
+            (0..(src[offset] as usize).min(max)).map(|i| read_key(src, offset + 1 + i * 32)).collect()
+        };
+        let issuers = 50 + 32 * MAX_REQUEST_SCHEMAS;
+        let circuit = issuers + 1 + 32 * MAX_TRUSTED_ISSUERS;
+        Ok(PresentationRequest {
+            is_initialized: src[0] != 0,
+            verifier: read_key(src, 1),
+            nonce: read_u64(src, 33),
+            expires_at: read_u64(src, 41),
+            schemas: list(49, MAX_REQUEST_SCHEMAS),
+            trusted_issuers: list(issuers, MAX_TRUSTED_ISSUERS),
+            circuit: read_key(src, circuit),
+            predicate_inputs: list(circuit + 32, MAX_PREDICATE_INPUTS),
+        })
+    }
+}
+
+/// The recorded response of `identity` to a request, stored in the PDA
+/// `[b"presentation-response", request, identity]`. A later response replaces it.
+///
+/// Layout (LEN = 74): is_initialized (1), request (32), identity (32), result (1), responded_at (8).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PresentationResponse {
+    pub is_initialized: bool,
+    pub request: Key,
+    pub identity: Key,
+    pub result: u8,
+    pub responded_at: u64,
+}
+
+impl PresentationResponse {
+    pub const SEED: &'static [u8] = b"presentation-response";
+}
+
+impl AccountLayout for PresentationResponse {
+    const DISCRIMINATOR: [u8; 8] = [243, 194, 42, 222, 39, 37, 47, 153];
+    const LEN: usize = 1 + 32 + 32 + 1 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(&self.request);
+        dst[33..65].copy_from_slice(&self.identity);
+        dst[65] = self.result;
+        dst[66..74].copy_from_slice(&self.responded_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        Ok(PresentationResponse {
+            is_initialized: src[0] != 0,
+            request: read_key(src, 1),
+            identity: read_key(src, 33),
+            result: src[65],
+            responded_at: read_u64(src, 66),
+        })
+    }
+}
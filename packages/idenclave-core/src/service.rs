@@ -0,0 +1,107 @@
+use crate::ServiceError;
+
+/// Most service entries an identity can hold.
+pub const MAX_SERVICES: usize = 4;
+pub const MAX_SERVICE_ID_LEN: usize = 32;
+pub const MAX_SERVICE_TYPE_LEN: usize = 32;
+pub const MAX_SERVICE_ENDPOINT_LEN: usize = 128;
+/// Size of one service slot in the identity account: each field is a length (u8) followed by a
+/// zero-padded buffer of its maximum length.
+pub const SERVICE_LEN: usize = 1 + MAX_SERVICE_ID_LEN + 1 + MAX_SERVICE_TYPE_LEN + 1 + MAX_SERVICE_ENDPOINT_LEN;
+
+/// One `service` entry of an identity's DID Document, such as a `DIDCommMessaging` endpoint or a
+/// `LinkedDomains` origin. `id` is the fragment the entry is known by within the document
+/// (`messaging` for `did:sol:<identity>#messaging`) and is unique per identity.
+///
+/// In instructions it is encoded as each field's length (u8) followed by its UTF-8 bytes, in the
+/// order id, type, endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Service {
+    pub id: String,
+    pub service_type: String,
+    pub endpoint: String,
+}
+
+impl Service {
+    pub fn new(id: &str, service_type: &str, endpoint: &str) -> Self {
+        Service { id: id.to_string(), service_type: service_type.to_string(), endpoint: endpoint.to_string() }
+    }
+
+    /// Every field must be set and within its size limit.
+    pub fn check(&self) -> Result<(), ServiceError> {
+        if self.id.is_empty() || self.service_type.is_empty() || self.endpoint.is_empty() {
+            return Err(ServiceError::EmptyField);
+        }
+        if self.id.len() > MAX_SERVICE_ID_LEN
+            || self.service_type.len() > MAX_SERVICE_TYPE_LEN
+            || self.endpoint.len() > MAX_SERVICE_ENDPOINT_LEN
+        {
+            return Err(ServiceError::TooLarge);
+        }
+        Ok(())
+    }
+
+    /// Encodes the service for an instruction. Fields over their size limits are refused by
+    /// `unpack`, so they never reach an identity.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(3 + self.id.len() + self.service_type.len() + self.endpoint.len());
+        for field in [&self.id, &self.service_type, &self.endpoint] {
+            buf.push(field.len() as u8);
+            buf.extend_from_slice(field.as_bytes());
+        }
+        buf
+    }
+
+    /// Decodes a service and checks it; the whole input must be consumed.
+    pub fn unpack(src: &[u8]) -> Result<Self, ServiceError> {
+        let (id, rest) = unpack_field(src)?;
+        let (service_type, rest) = unpack_field(rest)?;
+        let (endpoint, rest) = unpack_field(rest)?;
+        if !rest.is_empty() {
+            return Err(ServiceError::Malformed);
+        }
+        let service = Service { id, service_type, endpoint };
+        service.check()?;
+        Ok(service)
+    }
+
+    /// Writes the service into a `SERVICE_LEN` slot. Fields over their size limits are truncated;
+    /// `Identity::add_service` and `Identity::update_service` refuse them in the first place.
+    pub fn pack_slot(&self, dst: &mut [u8]) {
+        dst[..SERVICE_LEN].fill(0);
+        let mut offset = 0;
+        for (field, max) in [(&self.id, MAX_SERVICE_ID_LEN), (&self.service_type, MAX_SERVICE_TYPE_LEN), (&self.endpoint, MAX_SERVICE_ENDPOINT_LEN)] {
+            let bytes = &field.as_bytes()[..field.len().min(max)];
+            dst[offset] = bytes.len() as u8;
+            dst[offset + 1..offset + 1 + bytes.len()].copy_from_slice(bytes);
+            offset += 1 + max;
+        }
+    }
+
+    /// Reads a `SERVICE_LEN` slot. Lengths are clamped to the buffers and invalid UTF-8 is
+    /// replaced, so arbitrary account data still decodes.
+    pub fn unpack_slot(src: &[u8]) -> Self {
+        let type_offset = 1 + MAX_SERVICE_ID_LEN;
+        let endpoint_offset = type_offset + 1 + MAX_SERVICE_TYPE_LEN;
+        Service {
+            id: read_slot_field(src, 0, MAX_SERVICE_ID_LEN),
+            service_type: read_slot_field(src, type_offset, MAX_SERVICE_TYPE_LEN),
+            endpoint: read_slot_field(src, endpoint_offset, MAX_SERVICE_ENDPOINT_LEN),
+        }
+    }
+}
+
+fn read_slot_field(src: &[u8], offset: usize, max: usize) -> String {
+    let len = (src[offset] as usize).min(max);
+    String::from_utf8_lossy(&src[offset + 1..offset + 1 + len]).into_owned()
+}
+
+fn unpack_field(src: &[u8]) -> Result<(String, &[u8]), ServiceError> {
+    let (&len, rest) = src.split_first().ok_or(ServiceError::Malformed)?;
+    let len = len as usize;
+    if rest.len() < len {
+        return Err(ServiceError::Malformed);
+    }
+    let field = std::str::from_utf8(&rest[..len]).map_err(|_| ServiceError::Malformed)?;
+    Ok((field.to_string(), &rest[len..]))
+}
@@ -0,0 +1,31 @@
+use crate::{read_key, AccountLayout, Key, LayoutError};
+
+/// Left behind at `[b"tombstone", identity]` when an identity is closed, so the
+/// identity can only be re-registered by the authority that closed it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tombstone {
+    pub is_initialized: bool,
+    pub authority: Key,
+}
+
+impl Tombstone {
+    pub const SEED: &'static [u8] = b"tombstone";
+}
+
+impl AccountLayout for Tombstone {
+    const DISCRIMINATOR: [u8; 8] = [45, 187, 252, 155, 232, 114, 36, 22];
+    // LEN: is_initialized (1) + authority (32)
+    const LEN: usize = 1 + 32;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(&self.authority);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        Ok(Tombstone { is_initialized: src[0] != 0, authority: read_key(src, 1) })
+    }
+}
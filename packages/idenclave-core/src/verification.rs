@@ -0,0 +1,62 @@
+use crate::{read_key, read_u64, AccountLayout, Key, LayoutError};
+
+/// A verifier's durable record of having checked a credential, stored in the PDA
+/// `[b"verification-receipt", verifier, credential, id (u64 LE)]`. `id` is chosen by the verifier,
+/// so each check gets its own receipt. Receipts are never changed; the verifier can close them.
+///
+/// `result` is the `CredentialStatus` the credential had when it was checked, and `proof_hash`
+/// optionally commits to evidence the verifier kept off-chain, all zeros for none.
+///
+/// Layout (LEN = 122): is_initialized (1), verifier (32), credential (32), id (8), result (1),
+/// slot (8), timestamp (8), proof_hash (32).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerificationReceipt {
+    pub is_initialized: bool,
+    pub verifier: Key,
+    pub credential: Key,
+    pub id: u64,
+    pub result: u8,
+    pub slot: u64,
+    pub timestamp: u64,
+    pub proof_hash: [u8; 32],
+}
+
+impl VerificationReceipt {
+    pub const SEED: &'static [u8] = b"verification-receipt";
+
+    pub fn has_proof_hash(&self) -> bool {
+        self.proof_hash != [0; 32]
+    }
+}
+
+impl AccountLayout for VerificationReceipt {
+    const DISCRIMINATOR: [u8; 8] = [32, 125, 3, 222, 137, 31, 118, 83];
+    const LEN: usize = 1 + 32 + 32 + 8 + 1 + 8 + 8 + 32;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1..33].copy_from_slice(&self.verifier);
+        dst[33..65].copy_from_slice(&self.credential);
+        dst[65..73].copy_from_slice(&self.id.to_le_bytes());
+        dst[73] = self.result;
+        dst[74..82].copy_from_slice(&self.slot.to_le_bytes());
+        dst[82..90].copy_from_slice(&self.timestamp.to_le_bytes());
+        dst[90..122].copy_from_slice(&self.proof_hash);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, LayoutError> {
+        if src.len() < Self::LEN {
+            return Err(LayoutError::TooShort);
+        }
+        Ok(VerificationReceipt {
+            is_initialized: src[0] != 0,
+            verifier: read_key(src, 1),
+            credential: read_key(src, 33),
+            id: read_u64(src, 65),
+            result: src[73],
+            slot: read_u64(src, 74),
+            timestamp: read_u64(src, 82),
+            proof_hash: read_key(src, 90),
+        })
+    }
+}
@@ -0,0 +1,44 @@
+use idenclave_core::{evaluate_chain, AccountLayout, Accreditation, AccreditationReport, AccreditationStatus};
+
+const ROOT: [u8; 32] = [1; 32];
+const UNIVERSITY: [u8; 32] = [2; 32];
+const FACULTY: [u8; 32] = [3; 32];
+const DEGREE: [u8; 32] = [9; 32];
+
+fn link(accreditor: [u8; 32], issuer: [u8; 32], delegation_depth: u8) -> Accreditation {
+    Accreditation { is_initialized: true, accreditor, issuer, schema: DEGREE, expires_at: 100, delegation_depth, ..Default::default() }
+}
+
+#[test]
+fn test_accreditation_chain() {
+    // A government accredits a university, which accredits one of its faculties
+    let chain = [link(UNIVERSITY, FACULTY, 0), link(ROOT, UNIVERSITY, 1)];
+    let report = evaluate_chain(&FACULTY, &DEGREE, &ROOT, &chain, 50);
+    assert_eq!(report, AccreditationReport { status: AccreditationStatus::Accredited, path: vec![FACULTY, UNIVERSITY, ROOT] });
+    assert_eq!(AccreditationReport::unpack(&report.pack()), Some(report));
+
+    // The root issues directly; a chain to another root is not trusted
+    assert_eq!(evaluate_chain(&ROOT, &DEGREE, &ROOT, &[], 50).path, vec![ROOT]);
+    let report = evaluate_chain(&FACULTY, &DEGREE, &[7; 32], &chain, 50);
+    assert_eq!(report.status, AccreditationStatus::Untrusted);
+    assert_eq!(report.path, vec![FACULTY, UNIVERSITY, ROOT]);
+
+    // The university may issue but not accredit further without delegation depth
+    let chain = [link(UNIVERSITY, FACULTY, 0), link(ROOT, UNIVERSITY, 0)];
+    let report = evaluate_chain(&FACULTY, &DEGREE, &ROOT, &chain, 50);
+    assert_eq!(report, AccreditationReport { status: AccreditationStatus::DelegationExceeded, path: vec![FACULTY, UNIVERSITY] });
+    assert_eq!(evaluate_chain(&UNIVERSITY, &DEGREE, &ROOT, &chain[1..], 50).status, AccreditationStatus::Accredited);
+
+    // Every link must be live, for the schema, and connect to the previous one
+    let chain = [link(UNIVERSITY, FACULTY, 0), Accreditation { revoked: true, ..link(ROOT, UNIVERSITY, 1) }];
+    assert_eq!(evaluate_chain(&FACULTY, &DEGREE, &ROOT, &chain, 50).status, AccreditationStatus::Revoked);
+    let chain = [link(UNIVERSITY, FACULTY, 0), link(ROOT, UNIVERSITY, 1)];
+    assert_eq!(evaluate_chain(&FACULTY, &DEGREE, &ROOT, &chain, 101).status, AccreditationStatus::Expired);
+    assert_eq!(evaluate_chain(&FACULTY, &[8; 32], &ROOT, &chain, 50).status, AccreditationStatus::Untrusted);
+    assert_eq!(evaluate_chain(&FACULTY, &DEGREE, &ROOT, &chain[1..], 50).status, AccreditationStatus::Untrusted);
+    assert_eq!(evaluate_chain(&FACULTY, &DEGREE, &ROOT, &chain[..1], 50).status, AccreditationStatus::Untrusted);
+
+    let mut data = vec![0u8; Accreditation::ACCOUNT_LEN];
+    chain[1].pack_account(&mut data);
+    assert_eq!(Accreditation::unpack_account(&data), Ok(chain[1].clone()));
+}
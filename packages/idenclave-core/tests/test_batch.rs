@@ -0,0 +1,37 @@
+use idenclave_core::batch::{decode_results, encode_results};
+use idenclave_core::{BatchIssueItem, BatchItemResult, RuleViolation, MAX_BATCH_ITEMS};
+
+#[test]
+fn test_batch_items_roundtrip() {
+    let items: Vec<BatchIssueItem> = (0..3)
+        .map(|n| BatchIssueItem { nonce: n, credential_ref: [n as u8; 32], issued_at: 100 + n, expires_at: 200 + n })
+        .collect();
+    let mut data = Vec::new();
+    for item in &items {
+        item.pack(&mut data);
+    }
+    assert_eq!(data.len(), 3 * BatchIssueItem::LEN);
+    assert_eq!(BatchIssueItem::unpack_all(&data), Some(items));
+
+    // Partial items, empty batches and oversized batches are refused
+    assert_eq!(BatchIssueItem::unpack_all(&data[..data.len() - 1]), None);
+    assert_eq!(BatchIssueItem::unpack_all(&[]), None);
+    let mut oversized = Vec::new();
+    for _ in 0..=MAX_BATCH_ITEMS {
+        BatchIssueItem::default().pack(&mut oversized);
+    }
+    assert_eq!(BatchIssueItem::unpack_all(&oversized), None);
+}
+
+#[test]
+fn test_batch_results_encoding() {
+    let results = [BatchItemResult::Ok, BatchItemResult::AlreadyExists, BatchItemResult::NotIssuer];
+    let data = encode_results(&results);
+    assert_eq!(data, vec![3, 0, 0, 0, 0, 3, 2]);
+    assert_eq!(decode_results(&data), Some(results.to_vec()));
+    assert_eq!(decode_results(&[1, 0, 0, 0]), None);
+    assert_eq!(decode_results(&[1, 0, 0, 0, 9]), None);
+
+    assert_eq!(BatchItemResult::from(RuleViolation::AlreadyRevoked), BatchItemResult::AlreadyRevoked);
+    assert_eq!(BatchItemResult::from(RuleViolation::NotPending), BatchItemResult::InvalidState);
+}
@@ -0,0 +1,111 @@
+use idenclave_core::merkle::Node;
+use idenclave_core::{Claim, ClaimError, ClaimProof, ClaimSet, MerkleHasher, MAX_CLAIMS_DEPTH};
+use sha2::{Digest, Sha256};
+
+struct Sha256Hasher;
+
+impl MerkleHasher for Sha256Hasher {
+    fn hashv(parts: &[&[u8]]) -> Node {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+}
+
+type H = Sha256Hasher;
+
+fn claims() -> Vec<Claim> {
+    vec![
+        Claim::new("given_name", b"Ada", [1; 32]),
+        Claim::new("family_name", b"Lovelace", [2; 32]),
+        Claim::new("birthdate", b"1815-12-10", [3; 32]),
+        Claim::new("nationality", b"GB", [4; 32]),
+        Claim::new("over_18", b"true", [5; 32]),
+    ]
+}
+
+#[test]
+fn test_every_claim_verifies_against_the_root() {
+    let set = ClaimSet::<H>::new(claims()).unwrap();
+    let root = set.root();
+    for index in 0..5 {
+        let proof = set.proof(index).unwrap();
+        assert_eq!(proof.proof.len(), 3);
+        assert_eq!(proof.verify::<H>(&root), Ok(()));
+        assert_eq!(ClaimProof::unpack(&proof.pack()).unwrap(), proof);
+    }
+    assert!(set.proof(5).is_none());
+    assert_eq!(set.disclose("birthdate").unwrap().index, 2);
+    assert!(set.disclose("address").is_none());
+}
+
+#[test]
+fn test_tampered_disclosures_are_rejected() {
+    let set = ClaimSet::<H>::new(claims()).unwrap();
+    let root = set.root();
+    let proof = set.disclose("over_18").unwrap();
+
+    let mut changed = proof.clone();
+    changed.claim.value = b"false".to_vec();
+    assert_eq!(changed.verify::<H>(&root), Err(ClaimError::InvalidProof));
+
+    // Without the salt a verifier cannot confirm a guessed value
+    let mut unsalted = proof.clone();
+    unsalted.claim.salt = [0; 32];
+    assert_eq!(unsalted.verify::<H>(&root), Err(ClaimError::InvalidProof));
+
+    let mut moved = proof.clone();
+    moved.index = 3;
+    assert_eq!(moved.verify::<H>(&root), Err(ClaimError::InvalidProof));
+
+    // Indices beyond the proof's depth would alias real leaves
+    let mut aliased = proof.clone();
+    aliased.index += 8;
+    assert_eq!(aliased.verify::<H>(&root), Err(ClaimError::InvalidProof));
+
+    assert_eq!(proof.verify::<H>(&[0; 32]), Err(ClaimError::NoClaimsRoot));
+    let other = ClaimSet::<H>::new(claims()[..4].to_vec()).unwrap();
+    assert_eq!(proof.verify::<H>(&other.root()), Err(ClaimError::InvalidProof));
+}
+
+#[test]
+fn test_claim_set_sizes() {
+    assert_eq!(ClaimSet::<H>::new(Vec::new()).unwrap().root(), [0; 32]);
+
+    // A single claim is its own root
+    let single = ClaimSet::<H>::new(claims()[..1].to_vec()).unwrap();
+    assert_eq!(single.root(), claims()[0].leaf_hash::<H>());
+    assert_eq!(single.proof(0).unwrap().verify::<H>(&single.root()), Ok(()));
+
+    let many: Vec<Claim> = (0..1 << MAX_CLAIMS_DEPTH).map(|n| Claim::new(&format!("claim_{}", n), b"", [0; 32])).collect();
+    let set = ClaimSet::<H>::new(many.clone()).unwrap();
+    assert_eq!(set.proof(255).unwrap().verify::<H>(&set.root()), Ok(()));
+    let mut too_many = many;
+    too_many.push(Claim::default());
+    assert_eq!(ClaimSet::<H>::new(too_many).err(), Some(ClaimError::TooManyClaims));
+
+    let large = Claim::new("photo", &[0; 257], [0; 32]);
+    assert_eq!(ClaimSet::<H>::new(vec![large.clone()]).err(), Some(ClaimError::ClaimTooLarge));
+    let mut data = large.pack();
+    data.extend_from_slice(&0u32.to_le_bytes());
+    assert_eq!(ClaimProof::unpack(&data), Err(ClaimError::ClaimTooLarge));
+}
+
+#[test]
+fn test_malformed_proofs() {
+    let proof = ClaimSet::<H>::new(claims()).unwrap().proof(1).unwrap();
+    let data = proof.pack();
+    assert_eq!(ClaimProof::unpack(&data[..data.len() - 1]), Err(ClaimError::Malformed));
+    assert_eq!(ClaimProof::unpack(&data[..10]), Err(ClaimError::Malformed));
+    assert_eq!(ClaimProof::unpack(&[]), Err(ClaimError::Malformed));
+
+    let mut deep = data.clone();
+    deep.extend_from_slice(&[0; 32 * (MAX_CLAIMS_DEPTH - 2)]);
+    assert_eq!(ClaimProof::unpack(&deep), Err(ClaimError::Malformed));
+
+    let mut not_utf8 = data;
+    not_utf8[1] = 0xff;
+    assert_eq!(ClaimProof::unpack(&not_utf8), Err(ClaimError::Malformed));
+}
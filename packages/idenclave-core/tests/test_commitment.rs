@@ -0,0 +1,31 @@
+use std::cell::RefCell;
+
+use idenclave_core::{claim_hash, claims_commitment, PoseidonHasher, Scalar, MAX_COMMITTED_CLAIMS};
+
+thread_local! {
+    static CALLS: RefCell<Vec<Vec<Scalar>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records its inputs and "hashes" them to their count.
+struct Recorder;
+
+impl PoseidonHasher for Recorder {
+    fn hashv(inputs: &[Scalar]) -> Option<Scalar> {
+        CALLS.with(|calls| calls.borrow_mut().push(inputs.to_vec()));
+        Some([inputs.len() as u8; 32])
+    }
+}
+
+#[test]
+fn test_commitment_inputs() {
+    assert_eq!(claim_hash::<Recorder>(&[1; 32], &[2; 32]), Some([2; 32]));
+    assert_eq!(claims_commitment::<Recorder>(&[[3; 32], [4; 32], [5; 32]]), Some([3; 32]));
+    let calls = CALLS.with(|calls| calls.borrow().clone());
+    assert_eq!(calls, vec![vec![[1; 32], [2; 32]], vec![[3; 32], [4; 32], [5; 32]]]);
+
+    // Poseidon takes one to twelve inputs; other counts never reach the hasher
+    assert_eq!(claims_commitment::<Recorder>(&[]), None);
+    assert_eq!(claims_commitment::<Recorder>(&[[1; 32]; MAX_COMMITTED_CLAIMS + 1]), None);
+    assert_eq!(claims_commitment::<Recorder>(&[[1; 32]; MAX_COMMITTED_CLAIMS]), Some([12; 32]));
+    assert_eq!(CALLS.with(|calls| calls.borrow().len()), 3);
+}
@@ -0,0 +1,103 @@
+use idenclave_core::{
+    unix_now, AccountLayout, Credential, CredentialState, CredentialStatus, Identity, LayoutError, RuleViolation,
+};
+
+fn credential(expires_at: u64) -> Credential {
+    Credential { is_initialized: true, expires_at, ..Default::default() }
+}
+
+#[test]
+fn test_status_precedence() {
+    let mut cred = credential(100);
+    assert_eq!(cred.status(100), CredentialStatus::Valid);
+    assert_eq!(cred.status(101), CredentialStatus::Expired);
+    cred.suspended = true;
+    assert_eq!(cred.status(101), CredentialStatus::Suspended);
+    cred.superseded_by = [1u8; 32];
+    assert_eq!(cred.status(101), CredentialStatus::Superseded);
+    cred.pending = true;
+    assert_eq!(cred.status(101), CredentialStatus::Pending);
+    cred.revoked = true;
+    assert_eq!(cred.status(101), CredentialStatus::Revoked);
+}
+
+#[test]
+fn test_transition_rules() {
+    let mut cred = credential(100);
+    assert_eq!(cred.check_reinstate(), Err(RuleViolation::InvalidState));
+    assert_eq!(cred.check_suspend(), Ok(()));
+    assert_eq!(cred.check_renew(100), Err(RuleViolation::ExpiryNotExtended));
+    assert_eq!(cred.check_renew(101), Ok(()));
+    assert_eq!(cred.check_accept(), Err(RuleViolation::NotPending));
+    assert_eq!(cred.check_close(100), Err(RuleViolation::StillValid));
+    assert_eq!(cred.check_close(101), Ok(()));
+    assert_eq!(cred.check_reissue(), Ok(()));
+
+    cred.suspended = true;
+    assert_eq!(cred.check_suspend(), Err(RuleViolation::InvalidState));
+    assert_eq!(cred.check_reinstate(), Ok(()));
+
+    cred.revoked = true;
+    assert_eq!(cred.check_revoke(), Err(RuleViolation::AlreadyRevoked));
+    assert_eq!(cred.check_renew(200), Err(RuleViolation::InvalidState));
+    assert_eq!(cred.check_supersede(), Err(RuleViolation::InvalidState));
+    assert_eq!(cred.check_reissue(), Err(RuleViolation::InvalidState));
+    assert_eq!(cred.check_close(0), Ok(()));
+}
+
+#[test]
+fn test_layout_roundtrip() {
+    let cred = Credential {
+        is_initialized: true,
+        identity: [1u8; 32],
+        issuer: [2u8; 32],
+        credential_ref: [3u8; 32],
+        issued_at: 10,
+        expires_at: 20,
+        previous: [4u8; 32],
+        pending: true,
+        claims_root: [5u8; 32],
+        commitment: [6u8; 32],
+        ..Default::default()
+    };
+    let mut data = [0u8; Credential::LEN];
+    cred.pack_into_slice(&mut data);
+    assert_eq!(data[179], 1);
+    assert_eq!(&data[180..212], &[5u8; 32]);
+    assert_eq!(&data[212..244], &[6u8; 32]);
+    assert_eq!(Credential::unpack_from_slice(&data).unwrap(), cred);
+    assert!(Credential::unpack_from_slice(&data[..Credential::LEN - 1]).is_err());
+
+    let mut identity = Identity { is_initialized: true, credential_count: 9, ..Default::default() };
+    identity.set_attributes(&[5u8; 200]);
+    assert_eq!(identity.attributes(), &[5u8; 128][..]);
+    let mut data = [0u8; Identity::LEN];
+    identity.pack_into_slice(&mut data);
+    assert_eq!(Identity::unpack_from_slice(&data).unwrap(), identity);
+}
+
+#[test]
+fn test_discriminators() {
+    let cred = credential(100);
+    let mut data = [0u8; Credential::ACCOUNT_LEN];
+    // A freshly allocated account reads as uninitialized
+    assert!(!Credential::unpack_account(&data).unwrap().is_initialized);
+    cred.pack_account(&mut data);
+    assert_eq!(data[..8], Credential::DISCRIMINATOR);
+    assert_eq!(Credential::unpack_account(&data).unwrap(), cred);
+
+    // An identity can never be read as a credential, nor the other way round
+    let mut identity_data = [0u8; Identity::ACCOUNT_LEN];
+    Identity { is_initialized: true, ..Default::default() }.pack_account(&mut identity_data);
+    assert_eq!(Credential::unpack_account(&identity_data), Err(LayoutError::WrongDiscriminator));
+    assert_eq!(Identity::unpack_account(&data), Err(LayoutError::TooShort));
+    let mut padded = data.to_vec();
+    padded.resize(Identity::ACCOUNT_LEN, 0);
+    assert_eq!(Identity::unpack_account(&padded), Err(LayoutError::WrongDiscriminator));
+}
+
+#[test]
+fn test_unix_now_clamps_negative_timestamps() {
+    assert_eq!(unix_now(-5), 0);
+    assert_eq!(unix_now(1_700_000_000), 1_700_000_000);
+}
@@ -0,0 +1,47 @@
+use idenclave_core::issuance::{ed25519_instruction_data, parse_ed25519_instruction, ISSUANCE_DOMAIN};
+use idenclave_core::IssuanceMessage;
+
+#[test]
+fn test_issuance_message_encoding() {
+    let message = IssuanceMessage {
+        program_id: [1; 32],
+        issuer: [2; 32],
+        identity: [3; 32],
+        schema: [4; 32],
+        nonce: 5,
+        credential_ref: [6; 32],
+        issued_at: 7,
+        expires_at: 8,
+        claims_root: [10; 32],
+        deadline: 9,
+    };
+    let bytes = message.to_bytes();
+    assert_eq!(bytes.len(), IssuanceMessage::LEN);
+    assert!(bytes.starts_with(ISSUANCE_DOMAIN));
+    assert_eq!(&bytes[ISSUANCE_DOMAIN.len() + 128..ISSUANCE_DOMAIN.len() + 136], &5u64.to_le_bytes());
+    assert_eq!(&bytes[bytes.len() - 40..bytes.len() - 8], &[10; 32]);
+    assert_eq!(&bytes[bytes.len() - 8..], &9u64.to_le_bytes());
+
+    // Any field change yields a different message
+    let other = IssuanceMessage { nonce: 6, ..message.clone() };
+    assert_ne!(other.to_bytes(), bytes);
+    let other = IssuanceMessage { claims_root: [0; 32], ..message.clone() };
+    assert_ne!(other.to_bytes(), bytes);
+}
+
+#[test]
+fn test_ed25519_instruction_roundtrip() {
+    let data = ed25519_instruction_data(&[7; 32], &[8; 64], b"hello");
+    let (pubkey, message) = parse_ed25519_instruction(&data).unwrap();
+    assert_eq!(pubkey, [7; 32]);
+    assert_eq!(message, b"hello");
+
+    // Only a single inline signature is accepted
+    let mut two = data.clone();
+    two[0] = 2;
+    assert!(parse_ed25519_instruction(&two).is_none());
+    let mut elsewhere = data.clone();
+    elsewhere[4..6].copy_from_slice(&0u16.to_le_bytes());
+    assert!(parse_ed25519_instruction(&elsewhere).is_none());
+    assert!(parse_ed25519_instruction(&data[..data.len() - 1]).is_none());
+}
@@ -0,0 +1,143 @@
+use idenclave_core::merkle::{compute_root, empty_nodes, Node};
+use idenclave_core::{CompressedCredential, CredentialState, CredentialStatus, CredentialTree, CredentialTreeMirror, MerkleHasher, TreeError, TreeMirror};
+use sha2::{Digest, Sha256};
+
+struct Sha256Hasher;
+
+impl MerkleHasher for Sha256Hasher {
+    fn hashv(parts: &[&[u8]]) -> Node {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+}
+
+type H = Sha256Hasher;
+
+fn leaf(n: u8) -> Node {
+    [n; 32]
+}
+
+#[test]
+fn test_append_matches_mirror() {
+    let mut data = vec![0u8; CredentialTree::account_len(3, 4)];
+    let mut tree = CredentialTree::initialize::<H>(&mut data[..], 3, 4, &[9; 32]).unwrap();
+    let mut mirror = TreeMirror::<H>::new(3);
+    assert_eq!(tree.root(), empty_nodes::<H>(3)[3]);
+    assert_eq!(tree.root(), mirror.root());
+
+    for n in 0..8 {
+        let (index, root) = tree.append::<H>(&leaf(n + 1)).unwrap();
+        assert_eq!(index, mirror.append(leaf(n + 1)));
+        assert_eq!(root, mirror.root());
+        assert_eq!(tree.root(), root);
+    }
+    assert_eq!(tree.append::<H>(&leaf(100)), Err(TreeError::TreeFull));
+    assert_eq!(tree.next_index(), 8);
+    assert_eq!(tree.sequence_number(), 8);
+    assert_eq!(tree.authority(), [9; 32]);
+
+    // Every leaf verifies against the current root with the mirror's proofs
+    for index in 0..8 {
+        let proof = mirror.proof(index);
+        assert_eq!(compute_root::<H>(&leaf(index as u8 + 1), index, &proof), mirror.root());
+        tree.verify::<H>(&mirror.root(), index, &leaf(index as u8 + 1), &proof).unwrap();
+    }
+    assert_eq!(tree.verify::<H>(&mirror.root(), 3, &leaf(1), &mirror.proof(3)), Err(TreeError::InvalidProof));
+
+    // A tree can only be initialized once, and only with an account of the right size
+    assert_eq!(CredentialTree::initialize::<H>(&mut data[..], 3, 4, &[9; 32]).err(), Some(TreeError::AlreadyInitialized));
+    let mut short = vec![0u8; CredentialTree::account_len(3, 4) - 1];
+    assert_eq!(CredentialTree::initialize::<H>(&mut short[..], 3, 4, &[9; 32]).err(), Some(TreeError::InvalidParameters));
+    assert_eq!(CredentialTree::load(&short[..]).err(), Some(TreeError::NotInitialized));
+    assert_eq!(CredentialTree::load(&data[..]).unwrap().next_index(), 8);
+}
+
+#[test]
+fn test_concurrent_replacements_fast_forward() {
+    let mut data = vec![0u8; CredentialTree::account_len(4, 8)];
+    let mut tree = CredentialTree::initialize::<H>(&mut data[..], 4, 8, &[9; 32]).unwrap();
+    let mut mirror = TreeMirror::<H>::new(4);
+    for n in 0..6 {
+        tree.append::<H>(&leaf(n + 1)).unwrap();
+        mirror.append(leaf(n + 1));
+    }
+
+    // Several clients prove their leaves against the same root and submit one after another
+    let root = mirror.root();
+    let proofs: Vec<Vec<Node>> = (0..6).map(|index| mirror.proof(index)).collect();
+    for index in [4, 0, 5, 1] {
+        let new_root = tree.replace::<H>(&root, index, &leaf(index as u8 + 1), &leaf(50 + index as u8), &proofs[index as usize]).unwrap();
+        mirror.set(index, leaf(50 + index as u8));
+        assert_eq!(new_root, mirror.root());
+    }
+
+    // Appends in between are fast-forwarded as well, and keep appending correctly after replacements
+    tree.append::<H>(&leaf(7)).unwrap();
+    mirror.append(leaf(7));
+    let new_root = tree.replace::<H>(&root, 2, &leaf(3), &leaf(52), &proofs[2]).unwrap();
+    mirror.set(2, leaf(52));
+    assert_eq!(new_root, mirror.root());
+    tree.append::<H>(&leaf(8)).unwrap();
+    mirror.append(leaf(8));
+    assert_eq!(tree.root(), mirror.root());
+
+    // A stale proof of a leaf changed since cannot be used, nor one for an index not appended yet
+    assert_eq!(tree.replace::<H>(&root, 0, &leaf(1), &leaf(99), &proofs[0]), Err(TreeError::LeafChanged));
+    assert_eq!(tree.verify::<H>(&mirror.root(), 8, &[0; 32], &mirror.proof(8)), Err(TreeError::IndexOutOfRange));
+    tree.verify::<H>(&root, 3, &leaf(4), &proofs[3]).unwrap();
+
+    // Once the root falls out of the changelog, the proof has to be rebuilt
+    for _ in 0..8 {
+        let current = mirror.root();
+        tree.replace::<H>(&current, 7, &mirror.leaf(7).unwrap(), &leaf(tree.sequence_number() as u8), &mirror.proof(7)).unwrap();
+        mirror.set(7, leaf(tree.sequence_number() as u8 - 1));
+    }
+    assert_eq!(tree.root(), mirror.root());
+    assert_eq!(tree.verify::<H>(&root, 3, &leaf(4), &proofs[3]), Err(TreeError::RootNotFound));
+    tree.verify::<H>(&mirror.root(), 3, &leaf(4), &mirror.proof(3)).unwrap();
+}
+
+#[test]
+fn test_compressed_credentials_mirror() {
+    let credential = CompressedCredential {
+        identity: [1; 32],
+        issuer: [2; 32],
+        schema: [3; 32],
+        credential_ref: [4; 32],
+        issued_at: 100,
+        expires_at: 200,
+        revoked: false,
+    };
+    let packed = credential.pack();
+    assert_eq!(packed.len(), CompressedCredential::LEN);
+    assert_eq!(CompressedCredential::unpack(&packed), Ok(credential.clone()));
+    let revoked = CompressedCredential { revoked: true, ..credential.clone() };
+    assert_ne!(revoked.leaf_hash::<H>(), credential.leaf_hash::<H>());
+    assert_eq!(credential.status(150), CredentialStatus::Valid);
+    assert_eq!(credential.status(250), CredentialStatus::Expired);
+    assert_eq!(revoked.status(150), CredentialStatus::Revoked);
+
+    // Replaying the program's changes yields its roots
+    let mut data = vec![0u8; CredentialTree::account_len(5, 4)];
+    let mut tree = CredentialTree::initialize::<H>(&mut data[..], 5, 4, &[2; 32]).unwrap();
+    let mut mirror = CredentialTreeMirror::<H>::new(5);
+    for n in 0..3 {
+        let issued = CompressedCredential { credential_ref: [n; 32], ..credential.clone() };
+        let (index, root) = tree.append::<H>(&issued.leaf_hash::<H>()).unwrap();
+        mirror.issued(index, issued, &root).unwrap();
+    }
+    let old = mirror.credential(1).unwrap().clone();
+    let revoked = CompressedCredential { revoked: true, ..old.clone() };
+    let root = tree.replace::<H>(&mirror.root(), 1, &old.leaf_hash::<H>(), &revoked.leaf_hash::<H>(), &mirror.proof(1)).unwrap();
+    mirror.revoked(1, &root).unwrap();
+    assert!(mirror.credential(1).unwrap().revoked);
+    tree.verify::<H>(&mirror.root(), 1, &revoked.leaf_hash::<H>(), &mirror.proof(1)).unwrap();
+
+    // Missed events are detected
+    let (index, root) = tree.append::<H>(&credential.leaf_hash::<H>()).unwrap();
+    assert_eq!(mirror.issued(index + 1, credential.clone(), &root), Err(TreeError::OutOfSync));
+    assert_eq!(mirror.revoked(0, &root), Err(TreeError::OutOfSync));
+}
@@ -0,0 +1,35 @@
+use idenclave_core::{AccountLayout, Organization, OrganizationError, MAX_ORGANIZATION_KEYS};
+
+#[test]
+fn test_organization_keys() {
+    let mut organization = Organization { is_initialized: true, authority: [1; 32], ..Default::default() };
+    assert!(organization.keys().is_empty());
+    organization.add_key(&[2; 32]).unwrap();
+    organization.add_key(&[3; 32]).unwrap();
+    organization.add_key(&[4; 32]).unwrap();
+    assert_eq!(organization.add_key(&[3; 32]), Err(OrganizationError::KeyAlreadyAuthorized));
+    assert!(organization.is_authorized(&[3; 32]));
+    // The authority only manages keys; it is not a signing key itself
+    assert!(!organization.is_authorized(&[1; 32]));
+
+    // Removal keeps the order of the remaining keys and frees the slot
+    organization.remove_key(&[2; 32]).unwrap();
+    assert_eq!(organization.keys(), &[[3; 32], [4; 32]]);
+    assert_eq!(organization.keys[2], [0; 32]);
+    assert_eq!(organization.remove_key(&[2; 32]), Err(OrganizationError::KeyNotAuthorized));
+
+    // Rotation replaces a key in place
+    organization.rotate_key(&[3; 32], &[5; 32]).unwrap();
+    assert_eq!(organization.keys(), &[[5; 32], [4; 32]]);
+    assert_eq!(organization.rotate_key(&[3; 32], &[6; 32]), Err(OrganizationError::KeyNotAuthorized));
+    assert_eq!(organization.rotate_key(&[5; 32], &[4; 32]), Err(OrganizationError::KeyAlreadyAuthorized));
+
+    for n in 0..(MAX_ORGANIZATION_KEYS - 2) as u8 {
+        organization.add_key(&[10 + n; 32]).unwrap();
+    }
+    assert_eq!(organization.add_key(&[99; 32]), Err(OrganizationError::TooManyKeys));
+
+    let mut data = vec![0u8; Organization::ACCOUNT_LEN];
+    organization.pack_account(&mut data);
+    assert_eq!(Organization::unpack_account(&data), Ok(organization));
+}
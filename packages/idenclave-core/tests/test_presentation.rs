@@ -0,0 +1,122 @@
+use std::cell::RefCell;
+
+use idenclave_core::groth16::{self, G1};
+use idenclave_core::{
+    AccountLayout, Bn254, Circuit, CredentialStatus, Groth16Proof, PresentationRequest, PresentationResponse,
+    PresentationResult, PresentedCredential, ProofError, Scalar, VerifyingKey,
+};
+
+thread_local! {
+    static PAIRING_INPUT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records the pairing input instead of computing anything.
+struct Recorder;
+
+impl Bn254 for Recorder {
+    fn add(a: &G1, _: &G1) -> Option<G1> {
+        Some(*a)
+    }
+
+    fn mul(point: &G1, _: &Scalar) -> Option<G1> {
+        Some(*point)
+    }
+
+    fn pairing_is_one(pairs: &[u8]) -> Option<bool> {
+        PAIRING_INPUT.with(|input| *input.borrow_mut() = pairs.to_vec());
+        Some(true)
+    }
+}
+
+fn verifying_key(inputs: usize) -> VerifyingKey {
+    VerifyingKey { alpha: [1; 64], beta: [2; 128], gamma: [3; 128], delta: [4; 128], ic: vec![[5; 64]; inputs + 1] }
+}
+
+#[test]
+fn test_groth16_encodings_and_checks() {
+    let vk = verifying_key(2);
+    assert_eq!(VerifyingKey::unpack(&vk.pack()), Some(vk.clone()));
+    assert_eq!(VerifyingKey::unpack(&vk.pack()[1..]), None);
+    assert_eq!(VerifyingKey::unpack(&verifying_key(9).pack()), None);
+
+    let mut a = [0u8; 64];
+    a[31] = 1;
+    a[63] = 2;
+    let proof = Groth16Proof { a, b: [6; 128], c: [7; 64] };
+    assert_eq!(Groth16Proof::unpack(&proof.pack()), Some(proof.clone()));
+
+    assert_eq!(groth16::verify::<Recorder>(&vk, &proof, &[[0; 32]]), Err(ProofError::WrongInputCount));
+    assert_eq!(groth16::verify::<Recorder>(&vk, &proof, &[[0; 32], [0xff; 32]]), Err(ProofError::InputOutOfField));
+    groth16::verify::<Recorder>(&vk, &proof, &[[0; 32], [1; 32]]).unwrap();
+    // a enters the pairing negated: y becomes q - 2
+    let pairs = PAIRING_INPUT.with(|input| input.borrow().clone());
+    assert_eq!(pairs.len(), 4 * 192);
+    assert_eq!(&pairs[..32], &a[..32]);
+    let q_minus_2 = hex("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45");
+    assert_eq!(&pairs[32..64], &q_minus_2[..]);
+    assert_eq!(&pairs[64..192], &[6; 128]);
+
+    let circuit = Circuit { is_initialized: true, authority: [8; 32], verifying_key: vk };
+    let mut data = vec![0u8; Circuit::ACCOUNT_LEN];
+    circuit.pack_account(&mut data);
+    assert_eq!(Circuit::unpack_account(&data), Ok(circuit));
+}
+
+#[test]
+fn test_presentation_evaluation() {
+    let identity = [1; 32];
+    let request = PresentationRequest {
+        is_initialized: true,
+        verifier: [2; 32],
+        nonce: 7,
+        expires_at: 100,
+        schemas: vec![[3; 32], [4; 32]],
+        trusted_issuers: vec![[5; 32]],
+        circuit: [6; 32],
+        predicate_inputs: vec![[18; 32]],
+    };
+    let valid = PresentedCredential { identity, issuer: [5; 32], status: CredentialStatus::Valid, commitment: [7; 32] };
+    let both = [Some(valid), Some(valid)];
+    let inputs: &[Scalar] = &[[18; 32], [7; 32]];
+    assert_eq!(request.evaluate(&identity, &both, Some(inputs)), PresentationResult::Passed);
+    assert_eq!(request.evaluate(&identity, &both, None), PresentationResult::InvalidProof);
+    assert_eq!(request.evaluate(&identity, &both[..1], Some(inputs)), PresentationResult::MissingCredential);
+    assert_eq!(request.evaluate(&identity, &[Some(valid), None], Some(inputs)), PresentationResult::MissingCredential);
+    assert_eq!(request.evaluate(&[9; 32], &both, Some(inputs)), PresentationResult::WrongIdentity);
+    let untrusted = PresentedCredential { issuer: [9; 32], ..valid };
+    assert_eq!(request.evaluate(&identity, &[Some(valid), Some(untrusted)], Some(inputs)), PresentationResult::UntrustedIssuer);
+    let revoked = PresentedCredential { status: CredentialStatus::Revoked, ..valid };
+    assert_eq!(request.evaluate(&identity, &[Some(revoked), Some(valid)], Some(inputs)), PresentationResult::InvalidCredential);
+
+    // The commitment signal follows the predicate inputs and binds the proof to the first schema's credential
+    assert_eq!(request.commitment_input(), 1);
+    assert_eq!(request.evaluate(&identity, &both, Some(&[[18; 32], [8; 32]])), PresentationResult::CommitmentMismatch);
+    assert_eq!(request.evaluate(&identity, &both, Some(&[[18; 32]])), PresentationResult::CommitmentMismatch);
+    let other = PresentedCredential { commitment: [8; 32], ..valid };
+    assert_eq!(request.evaluate(&identity, &[Some(other), Some(valid)], Some(inputs)), PresentationResult::CommitmentMismatch);
+    assert_eq!(request.evaluate(&identity, &[Some(valid), Some(other)], Some(inputs)), PresentationResult::Passed);
+    // Credentials the issuer never committed have nothing a proof could bind to
+    let uncommitted = PresentedCredential { commitment: [0; 32], ..valid };
+    assert_eq!(request.evaluate(&identity, &[Some(uncommitted), Some(valid)], Some(&[[18; 32], [0; 32]])), PresentationResult::CommitmentMismatch);
+    assert_eq!(PresentationResult::try_from(6), Ok(PresentationResult::CommitmentMismatch));
+
+    // Without trusted issuers or a circuit, any issuer's valid credential passes and proofs are not needed
+    let open = PresentationRequest { trusted_issuers: Vec::new(), circuit: [0; 32], ..request.clone() };
+    assert_eq!(open.evaluate(&identity, &[Some(untrusted), Some(uncommitted)], None), PresentationResult::Passed);
+
+    assert!(request.accepts_inputs(&[[18; 32], [1; 32]]));
+    assert!(!request.accepts_inputs(&[[17; 32], [1; 32]]));
+    assert!(!request.is_expired(100) && request.is_expired(101));
+
+    let mut data = vec![0u8; PresentationRequest::ACCOUNT_LEN];
+    request.pack_account(&mut data);
+    assert_eq!(PresentationRequest::unpack_account(&data), Ok(request));
+    let response = PresentationResponse { is_initialized: true, request: [1; 32], identity, result: 3, responded_at: 4 };
+    let mut data = vec![0u8; PresentationResponse::ACCOUNT_LEN];
+    response.pack_account(&mut data);
+    assert_eq!(PresentationResponse::unpack_account(&data), Ok(response));
+}
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
@@ -0,0 +1,57 @@
+use idenclave_core::service::{MAX_SERVICE_ENDPOINT_LEN, MAX_SERVICE_ID_LEN};
+use idenclave_core::{AccountLayout, Identity, Service, ServiceError, MAX_SERVICES};
+
+#[test]
+fn test_identity_services() {
+    let mut identity = Identity { is_initialized: true, ..Default::default() };
+    let messaging = Service::new("messaging", "DIDCommMessaging", "https://example.com/didcomm");
+    identity.add_service(messaging.clone()).unwrap();
+    identity.add_service(Service::new("domain", "LinkedDomains", "https://example.com")).unwrap();
+    assert_eq!(identity.add_service(messaging.clone()), Err(ServiceError::AlreadyExists));
+    assert_eq!(identity.service("messaging"), Some(&messaging));
+
+    // Updates replace an entry in place; unknown ids are refused
+    let moved = Service::new("messaging", "DIDCommMessaging", "https://example.org/didcomm");
+    identity.update_service(moved.clone()).unwrap();
+    assert_eq!(identity.services[0], moved);
+    assert_eq!(identity.update_service(Service::new("profile", "Profile", "https://x")), Err(ServiceError::NotFound));
+
+    // Size limits and empty fields
+    let long_id = "a".repeat(MAX_SERVICE_ID_LEN + 1);
+    assert_eq!(identity.add_service(Service::new(&long_id, "Profile", "https://x")), Err(ServiceError::TooLarge));
+    let long_endpoint = format!("https://{}", "a".repeat(MAX_SERVICE_ENDPOINT_LEN));
+    assert_eq!(identity.update_service(Service::new("domain", "LinkedDomains", &long_endpoint)), Err(ServiceError::TooLarge));
+    assert_eq!(identity.add_service(Service::new("profile", "", "https://x")), Err(ServiceError::EmptyField));
+
+    for n in 0..MAX_SERVICES - 2 {
+        identity.add_service(Service::new(&format!("extra-{}", n), "Profile", "https://x")).unwrap();
+    }
+    assert_eq!(identity.add_service(Service::new("profile", "Profile", "https://x")), Err(ServiceError::TooManyServices));
+
+    let mut data = vec![0u8; Identity::ACCOUNT_LEN];
+    identity.pack_account(&mut data);
+    assert_eq!(Identity::unpack_account(&data), Ok(identity.clone()));
+
+    // Removal keeps the order of the remaining entries and frees the slot
+    identity.remove_service("messaging").unwrap();
+    assert_eq!(identity.remove_service("messaging"), Err(ServiceError::NotFound));
+    assert_eq!(identity.services[0].id, "domain");
+    identity.pack_account(&mut data);
+    let unpacked = Identity::unpack_account(&data).unwrap();
+    assert_eq!(unpacked.services.len(), MAX_SERVICES - 1);
+    assert_eq!(unpacked, identity);
+}
+
+#[test]
+fn test_service_encoding() {
+    let service = Service::new("messaging", "DIDCommMessaging", "https://example.com/didcomm");
+    let encoded = service.pack();
+    assert_eq!(&encoded[..10], b"\x09messaging");
+    assert_eq!(Service::unpack(&encoded), Ok(service));
+    assert_eq!(Service::unpack(&encoded[..encoded.len() - 1]), Err(ServiceError::Malformed));
+    assert_eq!(Service::unpack(&[encoded.as_slice(), &[0]].concat()), Err(ServiceError::Malformed));
+    assert_eq!(Service::unpack(b"\x01a\x01b\x00"), Err(ServiceError::EmptyField));
+    assert_eq!(Service::unpack(b"\x01\xff\x01b\x01c"), Err(ServiceError::Malformed));
+    let long_id = Service::new(&"a".repeat(MAX_SERVICE_ID_LEN + 1), "Profile", "https://x");
+    assert_eq!(Service::unpack(&long_id.pack()), Err(ServiceError::TooLarge));
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+/// A snarkjs file that cannot be used, or a proof that does not verify.
+#[derive(Debug)]
+pub enum SnarkjsError {
+    /// The file is not JSON of the expected shape
+    Json(serde_json::Error),
+    /// The proof or key is not Groth16 over BN254 (snarkjs's `bn128`)
+    Unsupported(String),
+    /// A number is not a decimal element of its field
+    InvalidNumber(String),
+    /// A point is not on the curve or not in its prime-order subgroup
+    InvalidPoint,
+    /// The number of public signals does not match the verifying key
+    WrongInputCount,
+    /// The proof does not verify
+    Rejected,
+}
+
+impl fmt::Display for SnarkjsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnarkjsError::Json(err) => write!(f, "malformed snarkjs JSON: {}", err),
+            SnarkjsError::Unsupported(what) => write!(f, "unsupported {}, expected groth16 over bn128", what),
+            SnarkjsError::InvalidNumber(number) => write!(f, "{:?} is not a field element", number),
+            SnarkjsError::InvalidPoint => f.write_str("point is not on the curve or not in its subgroup"),
+            SnarkjsError::WrongInputCount => f.write_str("public signal count does not match the verifying key"),
+            SnarkjsError::Rejected => f.write_str("proof does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for SnarkjsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnarkjsError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for SnarkjsError {
+    fn from(err: serde_json::Error) -> Self {
+        SnarkjsError::Json(err)
+    }
+}
@@ -0,0 +1,133 @@
+//! Groth16 proofs from snarkjs, checked off-chain and encoded for the idenclave programs.
+//!
+//! The frontend proves with snarkjs `groth16.fullProve`, which writes `proof.json` and
+//! `public.json`; `snarkjs zkey export verificationkey` writes `verification_key.json`. This
+//! crate reads all three for BN254, verifies proofs with arkworks, and converts them to the
+//! encodings in `idenclave_core::groth16` that `RegisterCircuit` and `RespondToRequest` take,
+//! so a backend can check a proof before submitting it and get the same answer as the program.
+
+pub mod error;
+mod snarkjs;
+
+pub use error::SnarkjsError;
+
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField};
+use idenclave_core::groth16::{G1, G2};
+use idenclave_core::Scalar;
+
+/// A Groth16 proof read from snarkjs's `proof.json`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Proof(pub ark_groth16::Proof<Bn254>);
+
+impl Proof {
+    pub fn from_json(json: &str) -> Result<Self, SnarkjsError> {
+        let proof: snarkjs::ProofJson = serde_json::from_str(json)?;
+        snarkjs::check_scheme(&proof.protocol, &proof.curve)?;
+        Ok(Proof(ark_groth16::Proof {
+            a: snarkjs::g1(&proof.pi_a)?,
+            b: snarkjs::g2(&proof.pi_b)?,
+            c: snarkjs::g1(&proof.pi_c)?,
+        }))
+    }
+
+    /// The proof as `RespondToRequest` takes it.
+    pub fn to_program(&self) -> idenclave_core::Groth16Proof {
+        idenclave_core::Groth16Proof { a: encode_g1(&self.0.a), b: encode_g2(&self.0.b), c: encode_g1(&self.0.c) }
+    }
+}
+
+/// A Groth16 verifying key read from snarkjs's `verification_key.json`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerifyingKey(pub ark_groth16::VerifyingKey<Bn254>);
+
+impl VerifyingKey {
+    pub fn from_json(json: &str) -> Result<Self, SnarkjsError> {
+        let vk: snarkjs::VerifyingKeyJson = serde_json::from_str(json)?;
+        snarkjs::check_scheme(&vk.protocol, &vk.curve)?;
+        if vk.ic.len() != vk.n_public + 1 {
+            return Err(SnarkjsError::WrongInputCount);
+        }
+        Ok(VerifyingKey(ark_groth16::VerifyingKey {
+            alpha_g1: snarkjs::g1(&vk.vk_alpha_1)?,
+            beta_g2: snarkjs::g2(&vk.vk_beta_2)?,
+            gamma_g2: snarkjs::g2(&vk.vk_gamma_2)?,
+            delta_g2: snarkjs::g2(&vk.vk_delta_2)?,
+            gamma_abc_g1: vk.ic.iter().map(snarkjs::g1).collect::<Result<_, _>>()?,
+        }))
+    }
+
+    /// Number of public signals a proof for this key carries.
+    pub fn public_inputs(&self) -> usize {
+        self.0.gamma_abc_g1.len().saturating_sub(1)
+    }
+
+    /// The key as `RegisterCircuit` takes it (after `idenclave_core::VerifyingKey::pack`). The
+    /// program refuses keys with more than `idenclave_core::MAX_PUBLIC_INPUTS` public inputs.
+    pub fn to_program(&self) -> idenclave_core::VerifyingKey {
+        idenclave_core::VerifyingKey {
+            alpha: encode_g1(&self.0.alpha_g1),
+            beta: encode_g2(&self.0.beta_g2),
+            gamma: encode_g2(&self.0.gamma_g2),
+            delta: encode_g2(&self.0.delta_g2),
+            ic: self.0.gamma_abc_g1.iter().map(encode_g1).collect(),
+        }
+    }
+}
+
+/// The public signals read from snarkjs's `public.json`: outputs first, then public inputs, in
+/// the order the circuit declares them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicSignals(pub Vec<Fr>);
+
+impl PublicSignals {
+    pub fn from_json(json: &str) -> Result<Self, SnarkjsError> {
+        let signals: Vec<String> = serde_json::from_str(json)?;
+        Ok(PublicSignals(signals.iter().map(|signal| snarkjs::field(signal)).collect::<Result<_, _>>()?))
+    }
+
+    /// The signals as the public inputs `RespondToRequest` takes.
+    pub fn to_program(&self) -> Vec<Scalar> {
+        self.0.iter().map(|signal| encode_field(*signal)).collect()
+    }
+}
+
+/// Checks `proof` for `public` against `vk`, as `snarkjs groth16 verify` does.
+pub fn verify(vk: &VerifyingKey, proof: &Proof, public: &PublicSignals) -> Result<(), SnarkjsError> {
+    if public.0.len() != vk.public_inputs() {
+        return Err(SnarkjsError::WrongInputCount);
+    }
+    let prepared = ark_groth16::prepare_verifying_key(&vk.0);
+    match ark_groth16::Groth16::<Bn254>::verify_proof(&prepared, &proof.0, &public.0) {
+        Ok(true) => Ok(()),
+        _ => Err(SnarkjsError::Rejected),
+    }
+}
+
+fn encode_field<F: PrimeField>(value: F) -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(&value.into_bigint().to_bytes_be());
+    buf
+}
+
+/// x then y; the point at infinity is all zeros, as in the `alt_bn128` syscalls.
+fn encode_g1(point: &G1Affine) -> G1 {
+    let mut buf = [0u8; 64];
+    if let Some((x, y)) = point.xy() {
+        buf[..32].copy_from_slice(&encode_field(*x));
+        buf[32..].copy_from_slice(&encode_field(*y));
+    }
+    buf
+}
+
+/// x.c1, x.c0, y.c1, y.c0: the reverse of snarkjs's coefficient order within each coordinate.
+fn encode_g2(point: &G2Affine) -> G2 {
+    let mut buf = [0u8; 128];
+    if let Some((x, y)) = point.xy() {
+        for (i, coefficient) in [x.c1, x.c0, y.c1, y.c0].into_iter().enumerate() {
+            buf[i * 32..(i + 1) * 32].copy_from_slice(&encode_field(coefficient));
+        }
+    }
+    buf
+}
@@ -0,0 +1,95 @@
+//! The JSON shapes snarkjs writes and their conversion to arkworks types.
+//!
+//! Numbers are decimal strings. Points are projective: G1 as `[x, y, z]` and G2 as
+//! `[[x.c0, x.c1], [y.c0, y.c1], [z.c0, z.c1]]`, with `z` one for affine points and zero for
+//! the point at infinity, which is all snarkjs ever writes.
+
+use crate::SnarkjsError;
+use ark_bn254::{Fq, Fq2, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{PrimeField, Zero};
+use num_bigint::BigUint;
+use serde::Deserialize;
+
+pub(crate) type G1Json = [String; 3];
+pub(crate) type G2Json = [[String; 2]; 3];
+
+/// `proof.json`. `pi_a`, `pi_b` and `pi_c` are the proof's a, b and c.
+#[derive(Deserialize)]
+pub(crate) struct ProofJson {
+    pub pi_a: G1Json,
+    pub pi_b: G2Json,
+    pub pi_c: G1Json,
+    pub protocol: String,
+    pub curve: String,
+}
+
+/// `verification_key.json`. snarkjs also writes `vk_alphabeta_12`, which is derived from
+/// alpha and beta and not needed here.
+#[derive(Deserialize)]
+pub(crate) struct VerifyingKeyJson {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub n_public: usize,
+    pub vk_alpha_1: G1Json,
+    pub vk_beta_2: G2Json,
+    pub vk_gamma_2: G2Json,
+    pub vk_delta_2: G2Json,
+    #[serde(rename = "IC")]
+    pub ic: Vec<G1Json>,
+}
+
+pub(crate) fn check_scheme(protocol: &str, curve: &str) -> Result<(), SnarkjsError> {
+    if protocol != "groth16" {
+        return Err(SnarkjsError::Unsupported(format!("protocol {:?}", protocol)));
+    }
+    if curve != "bn128" {
+        return Err(SnarkjsError::Unsupported(format!("curve {:?}", curve)));
+    }
+    Ok(())
+}
+
+/// Parses a decimal field element. Values at or above the modulus are refused rather than
+/// reduced, so every element has exactly one accepted spelling.
+pub(crate) fn field<F: PrimeField>(number: &str) -> Result<F, SnarkjsError> {
+    let invalid = || SnarkjsError::InvalidNumber(number.to_string());
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let value = BigUint::parse_bytes(number.as_bytes(), 10).ok_or_else(invalid)?;
+    let bigint = F::BigInt::try_from(value).map_err(|_| invalid())?;
+    F::from_bigint(bigint).ok_or_else(invalid)
+}
+
+pub(crate) fn g1(point: &G1Json) -> Result<G1Affine, SnarkjsError> {
+    let [x, y, z] = [field::<Fq>(&point[0])?, field(&point[1])?, field(&point[2])?];
+    if z.is_zero() {
+        return Ok(G1Affine::zero());
+    }
+    if z != Fq::from(1u64) {
+        return Err(SnarkjsError::InvalidPoint);
+    }
+    let point = G1Affine::new_unchecked(x, y);
+    // BN254 G1 has cofactor one, so being on the curve is enough
+    if !point.is_on_curve() {
+        return Err(SnarkjsError::InvalidPoint);
+    }
+    Ok(point)
+}
+
+pub(crate) fn g2(point: &G2Json) -> Result<G2Affine, SnarkjsError> {
+    let coordinate = |c: &[String; 2]| -> Result<Fq2, SnarkjsError> { Ok(Fq2::new(field(&c[0])?, field(&c[1])?)) };
+    let [x, y, z] = [coordinate(&point[0])?, coordinate(&point[1])?, coordinate(&point[2])?];
+    if z.is_zero() {
+        return Ok(G2Affine::zero());
+    }
+    if z != Fq2::from(1u64) {
+        return Err(SnarkjsError::InvalidPoint);
+    }
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(SnarkjsError::InvalidPoint);
+    }
+    Ok(point)
+}
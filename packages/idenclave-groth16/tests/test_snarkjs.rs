@@ -0,0 +1,91 @@
+use idenclave::presentation::AltBn128;
+use idenclave_groth16::{verify, Proof, PublicSignals, SnarkjsError, VerifyingKey};
+
+const PROOF: &str = include_str!("fixtures/multiplier/proof.json");
+const PUBLIC: &str = include_str!("fixtures/multiplier/public.json");
+const VERIFYING_KEY: &str = include_str!("fixtures/multiplier/verification_key.json");
+
+/// Base field modulus: one past the largest coordinate.
+const BASE_MODULUS: &str = "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+/// Scalar field modulus: one past the largest public signal.
+const SCALAR_MODULUS: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+/// The largest public signal, `SCALAR_MODULUS - 1`.
+const LARGEST_SIGNAL: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495616";
+
+#[test]
+fn test_verifies_snarkjs_proof() {
+    // Proof that 33 is a product, for the Multiplier in apps/frontend/circuits/test.circom
+    let vk = VerifyingKey::from_json(VERIFYING_KEY).unwrap();
+    let proof = Proof::from_json(PROOF).unwrap();
+    let public = PublicSignals::from_json(PUBLIC).unwrap();
+    assert_eq!(vk.public_inputs(), 1);
+    assert!(verify(&vk, &proof, &public).is_ok());
+
+    let wrong = PublicSignals::from_json(r#"["34"]"#).unwrap();
+    assert!(matches!(verify(&vk, &proof, &wrong), Err(SnarkjsError::Rejected)));
+    let too_many = PublicSignals::from_json(r#"["33", "1"]"#).unwrap();
+    assert!(matches!(verify(&vk, &proof, &too_many), Err(SnarkjsError::WrongInputCount)));
+}
+
+#[test]
+fn test_program_encoding_agrees_with_off_chain_verification() {
+    let vk = VerifyingKey::from_json(VERIFYING_KEY).unwrap();
+    let proof = Proof::from_json(PROOF).unwrap();
+
+    // The program's verifier, through the same alt_bn128 calls it makes on-chain
+    let program_vk = idenclave_core::VerifyingKey::unpack(&vk.to_program().pack()).unwrap();
+    let program_proof = idenclave_core::Groth16Proof::unpack(&proof.to_program().pack()).unwrap();
+    let largest = format!("[{:?}]", LARGEST_SIGNAL);
+    for (signals, accepted) in [(r#"["33"]"#, true), (r#"["34"]"#, false), (r#"["0"]"#, false), (largest.as_str(), false)] {
+        let public = PublicSignals::from_json(signals).unwrap();
+        let on_chain = idenclave_core::groth16::verify::<AltBn128>(&program_vk, &program_proof, &public.to_program());
+        assert_eq!(on_chain.is_ok(), accepted, "program verdict for {}", signals);
+        assert_eq!(verify(&vk, &proof, &public).is_ok(), accepted, "off-chain verdict for {}", signals);
+    }
+
+    let mut thirty_three = [0u8; 32];
+    thirty_three[31] = 33;
+    assert_eq!(PublicSignals::from_json(PUBLIC).unwrap().to_program(), vec![thirty_three]);
+
+    // The largest signal is passed on as is, just below the modulus the program checks inputs against
+    let mut largest_bytes = [
+        0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+        0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x00,
+    ];
+    assert_eq!(PublicSignals::from_json(&largest).unwrap().to_program(), vec![largest_bytes]);
+    largest_bytes[31] += 1;
+    let on_chain = idenclave_core::groth16::verify::<AltBn128>(&program_vk, &program_proof, &[largest_bytes]);
+    assert_eq!(on_chain, Err(idenclave_core::ProofError::InputOutOfField));
+}
+
+#[test]
+fn test_rejects_malformed_files() {
+    assert!(matches!(Proof::from_json("{}"), Err(SnarkjsError::Json(_))));
+    assert!(matches!(
+        Proof::from_json(&PROOF.replace("bn128", "bls12381")),
+        Err(SnarkjsError::Unsupported(_))
+    ));
+    assert!(matches!(
+        VerifyingKey::from_json(&VERIFYING_KEY.replace("\"nPublic\": 1", "\"nPublic\": 2")),
+        Err(SnarkjsError::WrongInputCount)
+    ));
+
+    // Numbers must be canonical decimal field elements
+    for signal in [SCALAR_MODULUS, BASE_MODULUS, "-1", "0x21", "", "3.0"] {
+        let json = format!("[{:?}]", signal);
+        assert!(matches!(PublicSignals::from_json(&json), Err(SnarkjsError::InvalidNumber(_))), "{}", signal);
+    }
+
+    let proof: serde_json::Value = serde_json::from_str(PROOF).unwrap();
+    let mut off_field = proof.clone();
+    off_field["pi_a"][0] = BASE_MODULUS.into();
+    assert!(matches!(Proof::from_json(&off_field.to_string()), Err(SnarkjsError::InvalidNumber(_))));
+
+    // Points must be on the curve
+    let mut off_curve = proof.clone();
+    off_curve["pi_a"][1] = "1".into();
+    assert!(matches!(Proof::from_json(&off_curve.to_string()), Err(SnarkjsError::InvalidPoint)));
+    let mut off_curve = proof;
+    off_curve["pi_b"][0][0] = "1".into();
+    assert!(matches!(Proof::from_json(&off_curve.to_string()), Err(SnarkjsError::InvalidPoint)));
+}
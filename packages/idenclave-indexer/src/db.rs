@@ -0,0 +1,398 @@
+use idenclave_core::{CredentialState, CredentialStatus};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS identities (
+    address TEXT PRIMARY KEY,
+    authority TEXT NOT NULL,
+    recovery TEXT NOT NULL,
+    active_credentials INTEGER NOT NULL,
+    credential_count INTEGER NOT NULL,
+    closed INTEGER NOT NULL DEFAULT 0,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS issuers (
+    address TEXT PRIMARY KEY,
+    first_slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS credentials (
+    address TEXT PRIMARY KEY,
+    identity TEXT NOT NULL,
+    issuer TEXT NOT NULL,
+    schema TEXT,
+    credential_ref TEXT NOT NULL,
+    issued_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    revoked INTEGER NOT NULL,
+    suspended INTEGER NOT NULL,
+    pending INTEGER NOT NULL,
+    superseded_by TEXT,
+    closed INTEGER NOT NULL DEFAULT 0,
+    slot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS credentials_identity ON credentials (identity);
+CREATE INDEX IF NOT EXISTS credentials_issuer ON credentials (issuer);
+CREATE INDEX IF NOT EXISTS credentials_schema ON credentials (schema);
+CREATE TABLE IF NOT EXISTS status_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    credential TEXT NOT NULL,
+    change TEXT NOT NULL,
+    expires_at INTEGER,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    signature TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS status_history_credential ON status_history (credential);
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    signature TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS subscriptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    target_kind TEXT NOT NULL,
+    target TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    identity TEXT,
+    issuer TEXT,
+    schema TEXT,
+    expiry_notice INTEGER NOT NULL,
+    since_change INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    subscription INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    delivered INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    UNIQUE (subscription, key)
+);
+";
+
+/// An identity as last read from its account.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct IdentityRecord {
+    pub address: String,
+    pub authority: String,
+    pub recovery: String,
+    pub active_credentials: u32,
+    pub credential_count: u64,
+    /// The account no longer exists
+    pub closed: bool,
+    pub slot: u64,
+}
+
+/// A credential as last read from its account. `schema` is known for credentials issued at
+/// schema-derived addresses (signed and batch issuance) that the indexer saw being issued or
+/// offered; backfilled credentials have none.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CredentialRecord {
+    pub address: String,
+    pub identity: String,
+    pub issuer: String,
+    pub schema: Option<String>,
+    pub credential_ref: String,
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub revoked: bool,
+    pub suspended: bool,
+    pub pending: bool,
+    pub superseded_by: Option<String>,
+    /// The account no longer exists
+    pub closed: bool,
+    pub slot: u64,
+}
+
+impl CredentialState for CredentialRecord {
+    fn is_revoked(&self) -> bool {
+        self.revoked
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    fn is_superseded(&self) -> bool {
+        self.superseded_by.is_some()
+    }
+
+    fn expires_at(&self) -> u64 {
+        self.expires_at
+    }
+}
+
+/// An issuer and how many indexed credentials it issued.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct IssuerRecord {
+    pub address: String,
+    pub first_slot: u64,
+    pub credentials: u64,
+}
+
+/// One event in a credential's history, such as `revoked` or `renewed` (with the new expiry).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StatusChange {
+    pub credential: String,
+    pub change: String,
+    pub expires_at: Option<u64>,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub signature: String,
+}
+
+/// The SQLite database the indexer maintains, and the queries over it.
+///
+/// `identities` and `credentials` hold the latest state read from the accounts; rows stay after
+/// the account is closed, marked `closed`. `status_history` holds every credential event in the
+/// order the program emitted it. `subscriptions` and `deliveries` back the notifications (see
+/// `notify`).
+pub struct Database {
+    pub(crate) conn: Connection,
+}
+
+pub(crate) fn key(value: &Pubkey) -> String {
+    value.to_string()
+}
+
+/// SQLite integers are signed; expiries of "never" (`u64::MAX`) are stored as `i64::MAX`.
+pub(crate) fn timestamp(value: u64) -> i64 {
+    value.min(i64::MAX as u64) as i64
+}
+
+pub(crate) fn credential_record(row: &Row) -> rusqlite::Result<CredentialRecord> {
+    Ok(CredentialRecord {
+        address: row.get("address")?,
+        identity: row.get("identity")?,
+        issuer: row.get("issuer")?,
+        schema: row.get("schema")?,
+        credential_ref: row.get("credential_ref")?,
+        issued_at: row.get("issued_at")?,
+        expires_at: row.get("expires_at")?,
+        revoked: row.get("revoked")?,
+        suspended: row.get("suspended")?,
+        pending: row.get("pending")?,
+        superseded_by: row.get("superseded_by")?,
+        closed: row.get("closed")?,
+        slot: row.get("slot")?,
+    })
+}
+
+pub(crate) fn status_change(row: &Row) -> rusqlite::Result<StatusChange> {
+    Ok(StatusChange {
+        credential: row.get("credential")?,
+        change: row.get("change")?,
+        expires_at: row.get("expires_at")?,
+        slot: row.get("slot")?,
+        block_time: row.get("block_time")?,
+        signature: row.get("signature")?,
+    })
+}
+
+impl Database {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Database { conn })
+    }
+
+    /// Runs `f` in a transaction, so a transaction's effects and the cursor move together.
+    pub fn transaction<T>(&mut self, f: impl FnOnce(&Database) -> rusqlite::Result<T>) -> rusqlite::Result<T> {
+        self.conn.execute_batch("BEGIN")?;
+        match f(self) {
+            Ok(value) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(value)
+            }
+            Err(err) => {
+                self.conn.execute_batch("ROLLBACK")?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Signature of the last transaction indexed.
+    pub fn cursor(&self) -> rusqlite::Result<Option<String>> {
+        self.conn.query_row("SELECT signature FROM cursor WHERE id = 0", [], |row| row.get(0)).optional()
+    }
+
+    pub fn set_cursor(&self, signature: &str) -> rusqlite::Result<()> {
+        self.conn.execute("INSERT OR REPLACE INTO cursor (id, signature) VALUES (0, ?1)", [signature])?;
+        Ok(())
+    }
+
+    /// Stores an identity read at `slot`, unless a later read is already stored.
+    pub fn upsert_identity(&self, address: &Pubkey, identity: &idenclave_core::Identity, slot: u64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO identities (address, authority, recovery, active_credentials, credential_count, closed, slot)
+             VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6)
+             ON CONFLICT (address) DO UPDATE SET authority = excluded.authority, recovery = excluded.recovery,
+                 active_credentials = excluded.active_credentials, credential_count = excluded.credential_count,
+                 closed = 0, slot = excluded.slot
+             WHERE excluded.slot >= identities.slot",
+            params![
+                key(address),
+                key(&Pubkey::new_from_array(identity.authority)),
+                key(&Pubkey::new_from_array(identity.recovery)),
+                identity.active_credentials,
+                identity.credential_count,
+                slot
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Stores a credential read at `slot`, unless a later read is already stored, and records
+    /// its issuer. A known schema is kept.
+    pub fn upsert_credential(&self, address: &Pubkey, credential: &idenclave_core::Credential, slot: u64) -> rusqlite::Result<()> {
+        let issuer = key(&Pubkey::new_from_array(credential.issuer));
+        let superseded_by = (credential.superseded_by != [0; 32]).then(|| key(&Pubkey::new_from_array(credential.superseded_by)));
+        self.conn.execute(
+            "INSERT INTO credentials (address, identity, issuer, credential_ref, issued_at, expires_at, revoked, suspended,
+                 pending, superseded_by, closed, slot)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 0, ?11)
+             ON CONFLICT (address) DO UPDATE SET identity = excluded.identity, issuer = excluded.issuer,
+                 credential_ref = excluded.credential_ref, issued_at = excluded.issued_at, expires_at = excluded.expires_at,
+                 revoked = excluded.revoked, suspended = excluded.suspended, pending = excluded.pending,
+                 superseded_by = excluded.superseded_by, closed = 0, slot = excluded.slot
+             WHERE excluded.slot >= credentials.slot",
+            params![
+                key(address),
+                key(&Pubkey::new_from_array(credential.identity)),
+                issuer,
+                hex::encode(credential.credential_ref),
+                timestamp(credential.issued_at),
+                timestamp(credential.expires_at),
+                credential.revoked,
+                credential.suspended,
+                credential.pending,
+                superseded_by,
+                slot
+            ],
+        )?;
+        self.conn.execute("INSERT OR IGNORE INTO issuers (address, first_slot) VALUES (?1, ?2)", params![issuer, slot])?;
+        Ok(())
+    }
+
+    pub fn set_schema(&self, credential: &Pubkey, schema: &[u8; 32]) -> rusqlite::Result<()> {
+        self.conn.execute("UPDATE credentials SET schema = ?2 WHERE address = ?1", params![key(credential), hex::encode(schema)])?;
+        Ok(())
+    }
+
+    /// Marks an indexed identity or credential whose account no longer exists as of `slot`.
+    pub fn mark_closed(&self, address: &Pubkey, slot: u64) -> rusqlite::Result<()> {
+        for table in ["identities", "credentials"] {
+            self.conn.execute(
+                &format!("UPDATE {table} SET closed = 1, slot = ?2 WHERE address = ?1 AND slot <= ?2"),
+                params![key(address), slot],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn is_indexed(&self, address: &Pubkey) -> rusqlite::Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM identities WHERE address = ?1) OR EXISTS (SELECT 1 FROM credentials WHERE address = ?1)",
+            [key(address)],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn record_change(&self, change: &StatusChange) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO status_history (credential, change, expires_at, slot, block_time, signature) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![change.credential, change.change, change.expires_at.map(timestamp), change.slot, change.block_time, change.signature],
+        )?;
+        Ok(())
+    }
+
+    pub fn identity(&self, address: &Pubkey) -> rusqlite::Result<Option<IdentityRecord>> {
+        self.conn
+            .query_row("SELECT * FROM identities WHERE address = ?1", [key(address)], |row| {
+                Ok(IdentityRecord {
+                    address: row.get("address")?,
+                    authority: row.get("authority")?,
+                    recovery: row.get("recovery")?,
+                    active_credentials: row.get("active_credentials")?,
+                    credential_count: row.get("credential_count")?,
+                    closed: row.get("closed")?,
+                    slot: row.get("slot")?,
+                })
+            })
+            .optional()
+    }
+
+    pub fn credential(&self, address: &Pubkey) -> rusqlite::Result<Option<CredentialRecord>> {
+        self.conn.query_row("SELECT * FROM credentials WHERE address = ?1", [key(address)], credential_record).optional()
+    }
+
+    fn credentials_where(&self, column: &str, value: &str) -> rusqlite::Result<Vec<CredentialRecord>> {
+        let mut statement = self.conn.prepare(&format!("SELECT * FROM credentials WHERE {column} = ?1 ORDER BY issued_at, address"))?;
+        let rows = statement.query_map([value], credential_record)?;
+        rows.collect()
+    }
+
+    /// Every credential `issuer` issued, closed ones included.
+    pub fn credentials_by_issuer(&self, issuer: &Pubkey) -> rusqlite::Result<Vec<CredentialRecord>> {
+        self.credentials_where("issuer", &key(issuer))
+    }
+
+    /// Every credential held by `identity`, closed ones included.
+    pub fn credentials_of(&self, identity: &Pubkey) -> rusqlite::Result<Vec<CredentialRecord>> {
+        self.credentials_where("identity", &key(identity))
+    }
+
+    /// Identities holding a credential of `schema` that is `Valid` at `now`. Only credentials with
+    /// a known schema (see `CredentialRecord`) are considered.
+    pub fn holders(&self, schema: &[u8; 32], now: u64) -> rusqlite::Result<Vec<String>> {
+        let mut holders: Vec<String> = self
+            .credentials_where("schema", &hex::encode(schema))?
+            .into_iter()
+            .filter(|credential| !credential.closed && credential.status(now) == CredentialStatus::Valid)
+            .map(|credential| credential.identity)
+            .collect();
+        holders.sort();
+        holders.dedup();
+        Ok(holders)
+    }
+
+    pub fn issuers(&self) -> rusqlite::Result<Vec<IssuerRecord>> {
+        let mut statement = self.conn.prepare(
+            "SELECT issuers.address, issuers.first_slot, COUNT(credentials.address) FROM issuers
+             LEFT JOIN credentials ON credentials.issuer = issuers.address
+             GROUP BY issuers.address ORDER BY issuers.first_slot, issuers.address",
+        )?;
+        let rows = statement.query_map([], |row| Ok(IssuerRecord { address: row.get(0)?, first_slot: row.get(1)?, credentials: row.get(2)? }))?;
+        rows.collect()
+    }
+
+    /// A credential's events, oldest first.
+    pub fn history(&self, credential: &Pubkey) -> rusqlite::Result<Vec<StatusChange>> {
+        self.changes_after(Some(credential), 0).map(|changes| changes.into_iter().map(|(_, change)| change).collect())
+    }
+
+    /// Events recorded after the one with id `after` (0 for all), oldest first, with their ids;
+    /// for one credential or all of them.
+    pub fn changes_after(&self, credential: Option<&Pubkey>, after: i64) -> rusqlite::Result<Vec<(i64, StatusChange)>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, credential, change, expires_at, slot, block_time, signature FROM status_history
+             WHERE id > ?1 AND (?2 IS NULL OR credential = ?2) ORDER BY id",
+        )?;
+        let rows = statement.query_map(params![after, credential.map(key)], |row| Ok((row.get("id")?, status_change(row)?)))?;
+        rows.collect()
+    }
+}
+
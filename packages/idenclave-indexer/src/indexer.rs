@@ -0,0 +1,224 @@
+use crate::db::{Database, StatusChange};
+use crate::logs::{program_events, ProgramEvent};
+use crate::notify::Dispatcher;
+use crate::rpc::{ConfirmedTransaction, RpcClient, RpcError};
+use idenclave::event::IdentityEvent;
+use idenclave_core::AccountLayout;
+use solana_program::pubkey::Pubkey;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Instructions that create credentials at schema-derived addresses and carry the schema right
+/// after the tag: `IssueCredential`, `SupersedeCredential`, `OfferCredential`,
+/// `IssueCredentialSigned` and `IssueCredentialBatch`.
+const SCHEMA_TAGS: [u8; 5] = [6, 12, 13, 19, 20];
+
+#[derive(Debug)]
+pub enum IndexerError {
+    Rpc(RpcError),
+    Db(rusqlite::Error),
+}
+
+impl fmt::Display for IndexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexerError::Rpc(err) => err.fmt(f),
+            IndexerError::Db(err) => write!(f, "database: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for IndexerError {}
+
+impl From<RpcError> for IndexerError {
+    fn from(err: RpcError) -> Self {
+        IndexerError::Rpc(err)
+    }
+}
+
+impl From<rusqlite::Error> for IndexerError {
+    fn from(err: rusqlite::Error) -> Self {
+        IndexerError::Db(err)
+    }
+}
+
+/// Keeps a `Database` in step with the program.
+///
+/// `backfill` stores every identity and credential account as it is now; `sync` then replays
+/// the program's transactions since the cursor, recording each credential event in the status
+/// history and re-reading the accounts the transaction touched. Only the native program's
+/// events (`IdentityEvent`) are decoded; account layouts are shared by both programs.
+///
+/// A credential's schema is not part of its account, only of the instruction that issued it, so
+/// credentials that were backfilled rather than seen being issued have no schema and are missing
+/// from `Database::holders`.
+pub struct Indexer {
+    pub rpc: RpcClient,
+    pub program_id: Pubkey,
+    pub db: Database,
+}
+
+impl Indexer {
+    pub fn new(rpc: RpcClient, program_id: Pubkey, db: Database) -> Self {
+        Indexer { rpc, program_id, db }
+    }
+
+    /// Stores every identity and credential the program owns. Returns how many were stored.
+    pub async fn backfill(&mut self) -> Result<usize, IndexerError> {
+        let (slot, accounts) = self.rpc.get_program_accounts(&self.program_id).await?;
+        self.db.transaction(|db| {
+            let mut stored = 0;
+            for (address, account) in &accounts {
+                stored += store_account(db, address, &account.data, slot)? as usize;
+            }
+            Ok(stored)
+        })
+        .map_err(Into::into)
+    }
+
+    /// Indexes the program's transactions since the last one indexed. Returns how many were
+    /// indexed.
+    pub async fn sync(&mut self) -> Result<usize, IndexerError> {
+        let cursor = self.db.cursor()?;
+        let signatures = self.rpc.get_signatures_since(&self.program_id, cursor.as_deref()).await?;
+        for signature in &signatures {
+            match self.rpc.get_transaction(signature).await? {
+                Some(transaction) => self.index_transaction(&transaction).await?,
+                None => return Err(RpcError(format!("transaction {} not found", signature)).into()),
+            }
+        }
+        Ok(signatures.len())
+    }
+
+    /// Backfills an empty database, then syncs every `interval` until an error, running a
+    /// `dispatcher` round after each sync if given.
+    pub async fn follow(&mut self, interval: Duration, dispatcher: Option<&Dispatcher>) -> Result<(), IndexerError> {
+        if self.db.cursor()?.is_none() {
+            self.backfill().await?;
+        }
+        loop {
+            self.sync().await?;
+            if let Some(dispatcher) = dispatcher {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                dispatcher.dispatch(&mut self.db, now).await?;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn index_transaction(&mut self, transaction: &ConfirmedTransaction) -> Result<(), IndexerError> {
+        if !transaction.succeeded {
+            return Ok(self.db.set_cursor(&transaction.signature)?);
+        }
+        let events = program_events(&transaction.logs, &self.program_id);
+        let mut touched: BTreeSet<Pubkey> = transaction
+            .instructions
+            .iter()
+            .filter(|instruction| instruction.program_id == self.program_id)
+            .flat_map(|instruction| instruction.accounts.iter().copied())
+            .collect();
+        for event in &events {
+            touched.extend(event_keys(&event.event));
+        }
+        let touched: Vec<Pubkey> = touched.into_iter().collect();
+        let (slot, accounts) = self.rpc.get_multiple_accounts(&touched).await?;
+        let program_id = self.program_id;
+        self.db.transaction(|db| {
+            for (address, account) in touched.iter().zip(&accounts) {
+                match account {
+                    Some(account) if account.owner == program_id => {
+                        store_account(db, address, &account.data, slot)?;
+                    }
+                    _ if db.is_indexed(address)? => db.mark_closed(address, slot)?,
+                    _ => {}
+                }
+            }
+            for event in &events {
+                if let Some((credential, schema)) = issued_schema(transaction, event) {
+                    db.set_schema(&credential, &schema)?;
+                }
+                for change in changes(&event.event) {
+                    db.record_change(&StatusChange {
+                        credential: change.0.to_string(),
+                        change: change.1.to_string(),
+                        expires_at: change.2,
+                        slot: transaction.slot,
+                        block_time: transaction.block_time,
+                        signature: transaction.signature.clone(),
+                    })?;
+                }
+            }
+            db.set_cursor(&transaction.signature)
+        })?;
+        Ok(())
+    }
+}
+
+/// Stores `data` if it is an identity or a credential; returns whether it was.
+fn store_account(db: &Database, address: &Pubkey, data: &[u8], slot: u64) -> rusqlite::Result<bool> {
+    if let Ok(identity) = idenclave_core::Identity::unpack_account(data) {
+        db.upsert_identity(address, &identity, slot)?;
+    } else if let Ok(credential) = idenclave_core::Credential::unpack_account(data) {
+        db.upsert_credential(address, &credential, slot)?;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Identities and credentials an event names, which may not be among the instruction's accounts
+/// when it came through CPI.
+fn event_keys(event: &IdentityEvent) -> Vec<Pubkey> {
+    match *event {
+        IdentityEvent::CredentialIssued { credential, identity, .. } | IdentityEvent::CredentialOffered { credential, identity, .. } => {
+            vec![credential, identity]
+        }
+        IdentityEvent::CredentialSuperseded { credential, new_credential, .. } => vec![credential, new_credential],
+        _ => changes(event).into_iter().map(|change| change.0).collect(),
+    }
+}
+
+/// The status history entries an event makes: credential, change and, for changes of expiry,
+/// the new expiry.
+fn changes(event: &IdentityEvent) -> Vec<(Pubkey, &'static str, Option<u64>)> {
+    match *event {
+        IdentityEvent::CredentialIssued { credential, .. } => vec![(credential, "issued", None)],
+        IdentityEvent::CredentialRevoked { credential, .. } => vec![(credential, "revoked", None)],
+        IdentityEvent::CredentialSuspended { credential, .. } => vec![(credential, "suspended", None)],
+        IdentityEvent::CredentialReinstated { credential, .. } => vec![(credential, "reinstated", None)],
+        IdentityEvent::CredentialRenewed { credential, expires_at, .. } => vec![(credential, "renewed", Some(expires_at))],
+        IdentityEvent::CredentialSuperseded { credential, new_credential, .. } => {
+            vec![(credential, "superseded", None), (new_credential, "issued", None)]
+        }
+        IdentityEvent::CredentialOffered { credential, .. } => vec![(credential, "offered", None)],
+        IdentityEvent::CredentialAccepted { credential, .. } => vec![(credential, "accepted", None)],
+        IdentityEvent::CredentialOfferRejected { credential, .. } => vec![(credential, "offer_rejected", None)],
+        IdentityEvent::CredentialOfferCancelled { credential, .. } => vec![(credential, "offer_cancelled", None)],
+        IdentityEvent::CredentialClosed { credential, .. } => vec![(credential, "closed", None)],
+        IdentityEvent::CredentialReissued { credential, expires_at, .. } => vec![(credential, "reissued", Some(expires_at))],
+        IdentityEvent::ClaimsCommitted { credential, .. } => vec![(credential, "claims_committed", None)],
+        _ => Vec::new(),
+    }
+}
+
+/// The schema of a credential issuance, read from the instruction that emitted it. Credentials
+/// the holder has yet to accept are issued by the same instructions, so offers count too, as do
+/// the new versions of superseded credentials; a batch offers all its credentials under one
+/// schema. Issuance through CPI is not attributed, as the inner instruction data is not in the log.
+fn issued_schema(transaction: &ConfirmedTransaction, event: &ProgramEvent) -> Option<(Pubkey, [u8; 32])> {
+    let (IdentityEvent::CredentialIssued { credential, .. }
+    | IdentityEvent::CredentialOffered { credential, .. }
+    | IdentityEvent::CredentialSuperseded { new_credential: credential, .. }) = event.event
+    else {
+        return None;
+    };
+    if !event.top_level {
+        return None;
+    }
+    let data = &transaction.instructions.get(event.instruction)?.data;
+    if !SCHEMA_TAGS.contains(data.first()?) {
+        return None;
+    }
+    Some((credential, data.get(1..33)?.try_into().ok()?))
+}
@@ -0,0 +1,21 @@
+//! Indexes the idenclave program into SQLite so questions like "which credentials did issuer X
+//! issue" or "which identities hold a valid credential of schema S" are queries rather than
+//! `getProgramAccounts` scans.
+//!
+//! The `Indexer` backfills from the program's accounts over RPC, then follows new transactions,
+//! decoding the events the program logs to build each credential's status history. The
+//! `Database` holds the result and answers the queries. Subscribers are notified of the
+//! revocations, suspensions and upcoming expiries of the credentials they follow by the
+//! `Dispatcher`, over signed webhooks or local commands.
+
+pub mod db;
+pub mod indexer;
+pub mod logs;
+pub mod notify;
+pub mod rpc;
+
+pub use db::{CredentialRecord, Database, IdentityRecord, IssuerRecord, StatusChange};
+pub use indexer::{Indexer, IndexerError};
+pub use logs::{program_events, ProgramEvent};
+pub use notify::{sign, verify_signature, Delivery, Dispatcher, Filter, Notification, Subscription, Target};
+pub use rpc::{Account, ConfirmedTransaction, RpcClient, RpcError, TransactionInstruction};
@@ -0,0 +1,65 @@
+use base64::Engine;
+use idenclave::event::IdentityEvent;
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// An event the program emitted, located in the transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgramEvent {
+    /// Index of the top-level instruction it was emitted under
+    pub instruction: usize,
+    /// Whether the program was that instruction's program rather than invoked through CPI
+    pub top_level: bool,
+    pub event: IdentityEvent,
+}
+
+/// Extracts the program's events from a transaction log.
+///
+/// `sol_log_data` lines ("Program data: ...") carry no program id, so the log is followed as a
+/// call stack of "Program <id> invoke [depth]" and "Program <id> success"/"failed" lines and each
+/// data line is attributed to the program on top. Lines that do not decode as an
+/// `IdentityEvent` are skipped, as are other programs' data lines.
+pub fn program_events(logs: &[String], program_id: &Pubkey) -> Vec<ProgramEvent> {
+    let mut events = Vec::new();
+    let mut stack: Vec<Pubkey> = Vec::new();
+    let mut instruction = None;
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if stack.last() != Some(program_id) {
+                continue;
+            }
+            for field in data.split(' ') {
+                let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(field) else {
+                    continue;
+                };
+                if let (Some(instruction), Ok(event)) = (instruction, IdentityEvent::unpack(&bytes)) {
+                    events.push(ProgramEvent { instruction, top_level: stack.len() == 1, event });
+                }
+            }
+            continue;
+        }
+        let mut words = rest.split(' ');
+        let (Some(id), Some(action)) = (words.next(), words.next()) else {
+            continue;
+        };
+        match action {
+            "invoke" => {
+                let Ok(id) = Pubkey::from_str(id) else {
+                    continue;
+                };
+                if stack.is_empty() {
+                    instruction = Some(instruction.map_or(0, |index| index + 1));
+                }
+                stack.push(id);
+            }
+            "success" | "failed:" => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    events
+}
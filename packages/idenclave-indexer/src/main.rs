@@ -0,0 +1,121 @@
+use idenclave_indexer::{Database, Dispatcher, Filter, Indexer, RpcClient, Target};
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "usage:
+  idenclave-indexer run --program-id <ADDRESS> [--rpc-url <URL>] [--db <PATH>] [--interval <SECONDS>] [--no-notify]
+  idenclave-indexer subscribe [--db <PATH>] (--webhook <URL> | --command <COMMAND>) [--identity <IDENTITY>]
+      [--issuer <ISSUER>] [--schema <SCHEMA_HEX>] [--expiry-notice <SECONDS>]
+  idenclave-indexer unsubscribe [--db <PATH>] <ID>
+  idenclave-indexer subscriptions [--db <PATH>]
+  idenclave-indexer query [--db <PATH>] issuers
+  idenclave-indexer query [--db <PATH>] issuer-credentials <ISSUER>
+  idenclave-indexer query [--db <PATH>] identity-credentials <IDENTITY>
+  idenclave-indexer query [--db <PATH>] holders <SCHEMA_HEX> [<UNIX_TIME>]
+  idenclave-indexer query [--db <PATH>] history <CREDENTIAL>";
+
+#[tokio::main]
+async fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let db_path = take_flag(&mut args, "--db").unwrap_or_else(|| "idenclave-index.sqlite".to_string());
+    let db = Database::open(&db_path).unwrap_or_else(|err| exit(&format!("cannot open {}: {}", db_path, err)));
+    match args.first().map(String::as_str) {
+        Some("run") => {
+            let program_id = take_flag(&mut args, "--program-id").map(|id| address(&id)).unwrap_or_else(|| exit(USAGE));
+            let rpc_url = take_flag(&mut args, "--rpc-url").unwrap_or_else(|| "http://127.0.0.1:8899".to_string());
+            let interval = take_flag(&mut args, "--interval").map_or(10, |secs| secs.parse().unwrap_or_else(|_| exit(USAGE)));
+            let dispatcher = (!args.iter().any(|arg| arg == "--no-notify")).then(Dispatcher::new);
+            println!("Indexing {} via {} into {}", program_id, rpc_url, db_path);
+            let mut indexer = Indexer::new(RpcClient::new(&rpc_url), program_id, db);
+            if let Err(err) = indexer.follow(Duration::from_secs(interval), dispatcher.as_ref()).await {
+                exit(&err.to_string());
+            }
+        }
+        Some("subscribe") => {
+            let target = match (take_flag(&mut args, "--webhook"), take_flag(&mut args, "--command")) {
+                (Some(url), None) => Target::Webhook(url),
+                (None, Some(command)) => Target::Command(command),
+                _ => exit(USAGE),
+            };
+            let filter = Filter {
+                identity: take_flag(&mut args, "--identity").map(|identity| address(&identity)),
+                issuer: take_flag(&mut args, "--issuer").map(|issuer| address(&issuer)),
+                schema: take_flag(&mut args, "--schema").map(|schema| schema_hex(&schema)),
+            };
+            let expiry_notice = take_flag(&mut args, "--expiry-notice").map_or(7 * 24 * 60 * 60, |secs| secs.parse().unwrap_or_else(|_| exit(USAGE)));
+            match db.subscribe(&target, &filter, expiry_notice) {
+                Ok(subscription) => println!("{}", serde_json::to_string(&subscription).unwrap()),
+                Err(err) => exit(&err.to_string()),
+            }
+        }
+        Some("unsubscribe") => {
+            let id = args.get(1).and_then(|id| id.parse().ok()).unwrap_or_else(|| exit(USAGE));
+            match db.unsubscribe(id) {
+                Ok(true) => {}
+                Ok(false) => exit(&format!("no subscription {}", id)),
+                Err(err) => exit(&err.to_string()),
+            }
+        }
+        Some("subscriptions") => {
+            if let Err(err) = print(db.subscriptions()) {
+                exit(&err.to_string());
+            }
+        }
+        Some("query") => query(&db, &args[1..]),
+        _ => exit(USAGE),
+    }
+}
+
+fn query(db: &Database, args: &[String]) {
+    let arg = |i: usize| args.get(i).map(String::as_str).unwrap_or_else(|| exit(USAGE));
+    let result = match arg(0) {
+        "issuers" => print(db.issuers()),
+        "issuer-credentials" => print(db.credentials_by_issuer(&address(arg(1)))),
+        "identity-credentials" => print(db.credentials_of(&address(arg(1)))),
+        "holders" => {
+            let schema = schema_hex(arg(1));
+            let now = match args.get(2) {
+                Some(time) => time.parse().unwrap_or_else(|_| exit(USAGE)),
+                None => SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            };
+            print(db.holders(&schema, now))
+        }
+        "history" => print(db.history(&address(arg(1)))),
+        _ => exit(USAGE),
+    };
+    if let Err(err) = result {
+        exit(&err.to_string());
+    }
+}
+
+fn print<T: Serialize>(rows: rusqlite::Result<Vec<T>>) -> rusqlite::Result<()> {
+    for row in rows? {
+        println!("{}", serde_json::to_string(&row).unwrap());
+    }
+    Ok(())
+}
+
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let position = args.iter().position(|arg| arg == flag)?;
+    if position + 1 >= args.len() {
+        exit(USAGE);
+    }
+    let value = args.remove(position + 1);
+    args.remove(position);
+    Some(value)
+}
+
+fn address(value: &str) -> Pubkey {
+    Pubkey::from_str(value).unwrap_or_else(|_| exit(&format!("{:?} is not an address", value)))
+}
+
+fn schema_hex(value: &str) -> [u8; 32] {
+    hex::decode(value).ok().and_then(|bytes| bytes.try_into().ok()).unwrap_or_else(|| exit("schema is not 32 bytes of hex"))
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2)
+}
@@ -0,0 +1,323 @@
+use crate::db::{credential_record, key, status_change, timestamp, CredentialRecord, Database, StatusChange};
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Row};
+use serde::Serialize;
+use sha2::Sha256;
+use solana_program::pubkey::Pubkey;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// Attempts after which a delivery is given up on.
+pub const MAX_ATTEMPTS: u32 = 5;
+/// Header carrying the Unix time the notification was signed at.
+pub const TIMESTAMP_HEADER: &str = "X-Idenclave-Timestamp";
+/// Header carrying `v1=<hex HMAC-SHA256 of "{timestamp}.{body}">`.
+pub const SIGNATURE_HEADER: &str = "X-Idenclave-Signature";
+
+/// Where a subscription's notifications go: a URL the JSON is POSTed to, or a command run with
+/// `sh -c` that reads it on stdin and gets the signature and timestamp in `IDENCLAVE_SIGNATURE`
+/// and `IDENCLAVE_TIMESTAMP`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "target", rename_all = "lowercase")]
+pub enum Target {
+    Webhook(String),
+    Command(String),
+}
+
+impl Target {
+    fn kind(&self) -> &'static str {
+        match self {
+            Target::Webhook(_) => "webhook",
+            Target::Command(_) => "command",
+        }
+    }
+
+    fn value(&self) -> &str {
+        match self {
+            Target::Webhook(target) | Target::Command(target) => target,
+        }
+    }
+}
+
+/// Which credentials a subscription is about; `None` matches any.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filter {
+    pub identity: Option<Pubkey>,
+    pub issuer: Option<Pubkey>,
+    pub schema: Option<[u8; 32]>,
+}
+
+/// A subscription to changes of the credentials its filter matches: revocation, suspension and,
+/// when `expiry_notice` is not zero, expiry within `expiry_notice` seconds.
+///
+/// Notifications are signed with `secret` (hex), which only the indexer and the subscriber know.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Subscription {
+    pub id: i64,
+    pub target: Target,
+    pub secret: String,
+    pub identity: Option<String>,
+    pub issuer: Option<String>,
+    pub schema: Option<String>,
+    pub expiry_notice: u64,
+}
+
+/// A notification queued for a subscription, with the outcome of the attempts so far.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Delivery {
+    pub id: i64,
+    pub subscription: i64,
+    pub key: String,
+    pub payload: String,
+    pub attempts: u32,
+    pub delivered: bool,
+    pub last_error: Option<String>,
+}
+
+/// The JSON body of a notification. `type` is `credential.revoked`, `credential.suspended` or
+/// `credential.expiring`; `change` is the status history entry behind the first two.
+#[derive(Clone, Debug, Serialize)]
+pub struct Notification {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub subscription: i64,
+    pub credential: CredentialRecord,
+    pub change: Option<StatusChange>,
+}
+
+fn subscription(row: &Row) -> rusqlite::Result<Subscription> {
+    let target: String = row.get("target")?;
+    let target = match row.get::<_, String>("target_kind")?.as_str() {
+        "command" => Target::Command(target),
+        _ => Target::Webhook(target),
+    };
+    Ok(Subscription {
+        id: row.get("id")?,
+        target,
+        secret: row.get("secret")?,
+        identity: row.get("identity")?,
+        issuer: row.get("issuer")?,
+        schema: row.get("schema")?,
+        expiry_notice: row.get("expiry_notice")?,
+    })
+}
+
+/// Conditions under which a subscription matches credential `c`.
+const MATCHES: &str = "(s.identity IS NULL OR s.identity = c.identity) AND (s.issuer IS NULL OR s.issuer = c.issuer)
+    AND (s.schema IS NULL OR s.schema = c.schema)";
+
+impl Database {
+    /// Adds a subscription with a fresh secret. Only changes recorded from now on are notified.
+    pub fn subscribe(&self, target: &Target, filter: &Filter, expiry_notice: u64) -> rusqlite::Result<Subscription> {
+        let mut secret = [0u8; 32];
+        getrandom::getrandom(&mut secret).expect("no system randomness");
+        self.conn.execute(
+            "INSERT INTO subscriptions (target_kind, target, secret, identity, issuer, schema, expiry_notice, since_change)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, (SELECT COALESCE(MAX(id), 0) FROM status_history))",
+            params![
+                target.kind(),
+                target.value(),
+                hex::encode(secret),
+                filter.identity.as_ref().map(key),
+                filter.issuer.as_ref().map(key),
+                filter.schema.map(hex::encode),
+                timestamp(expiry_notice)
+            ],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.conn.query_row("SELECT * FROM subscriptions WHERE id = ?1", [id], subscription)
+    }
+
+    /// Removes a subscription and its undelivered notifications. Returns whether it existed.
+    pub fn unsubscribe(&self, id: i64) -> rusqlite::Result<bool> {
+        self.conn.execute("DELETE FROM deliveries WHERE subscription = ?1 AND delivered = 0", [id])?;
+        Ok(self.conn.execute("DELETE FROM subscriptions WHERE id = ?1", [id])? > 0)
+    }
+
+    pub fn subscriptions(&self) -> rusqlite::Result<Vec<Subscription>> {
+        let mut statement = self.conn.prepare("SELECT * FROM subscriptions ORDER BY id")?;
+        let rows = statement.query_map([], subscription)?;
+        rows.collect()
+    }
+
+    /// A subscription's notifications, oldest first.
+    pub fn deliveries(&self, subscription: i64) -> rusqlite::Result<Vec<Delivery>> {
+        let mut statement = self.conn.prepare("SELECT * FROM deliveries WHERE subscription = ?1 ORDER BY id")?;
+        let rows = statement.query_map([subscription], |row| {
+            Ok(Delivery {
+                id: row.get("id")?,
+                subscription: row.get("subscription")?,
+                key: row.get("key")?,
+                payload: row.get("payload")?,
+                attempts: row.get("attempts")?,
+                delivered: row.get("delivered")?,
+                last_error: row.get("last_error")?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Queues a notification for every revocation and suspension recorded since each
+    /// subscription last looked, and for every credential expiring within a subscription's
+    /// notice. Each condition is queued once per subscription. Returns how many were queued.
+    pub fn enqueue_notifications(&self, now: u64) -> rusqlite::Result<usize> {
+        let mut queued = 0;
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT s.id AS subscription_id, h.id AS change_id, c.* FROM subscriptions s
+             JOIN status_history h ON h.id > s.since_change AND h.change IN ('revoked', 'suspended')
+             JOIN credentials c ON c.address = h.credential
+             WHERE {MATCHES} ORDER BY h.id, s.id"
+        ))?;
+        let changes = statement
+            .query_map([], |row| Ok((row.get::<_, i64>("subscription_id")?, row.get::<_, i64>("change_id")?, credential_record(row)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (subscription, change_id, credential) in changes {
+            let change = self.conn.query_row("SELECT * FROM status_history WHERE id = ?1", [change_id], status_change)?;
+            let kind = format!("credential.{}", change.change);
+            queued += self.enqueue(subscription, &format!("change:{}", change_id), &Notification { kind, subscription, credential, change: Some(change) })?;
+        }
+        self.conn.execute("UPDATE subscriptions SET since_change = (SELECT COALESCE(MAX(id), 0) FROM status_history)", [])?;
+
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT s.id AS subscription_id, c.* FROM subscriptions s
+             JOIN credentials c ON c.expires_at > ?1 AND c.expires_at <= ?1 + s.expiry_notice
+             WHERE s.expiry_notice > 0 AND c.closed = 0 AND c.revoked = 0 AND c.pending = 0 AND c.superseded_by IS NULL
+                 AND {MATCHES}
+             ORDER BY c.expires_at, s.id"
+        ))?;
+        let expiring = statement
+            .query_map([timestamp(now)], |row| Ok((row.get::<_, i64>("subscription_id")?, credential_record(row)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (subscription, credential) in expiring {
+            let key = format!("expiring:{}:{}", credential.address, credential.expires_at);
+            let notification = Notification { kind: "credential.expiring".to_string(), subscription, credential, change: None };
+            queued += self.enqueue(subscription, &key, &notification)?;
+        }
+        Ok(queued)
+    }
+
+    fn enqueue(&self, subscription: i64, key: &str, notification: &Notification) -> rusqlite::Result<usize> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO deliveries (subscription, key, payload) VALUES (?1, ?2, ?3)",
+            params![subscription, key, serde_json::to_string(notification).unwrap()],
+        )
+    }
+
+    /// Undelivered notifications with attempts left, with their target and secret.
+    fn pending_deliveries(&self) -> rusqlite::Result<Vec<(i64, Target, String, String)>> {
+        let mut statement = self.conn.prepare(
+            "SELECT d.id AS delivery_id, d.payload, s.* FROM deliveries d JOIN subscriptions s ON s.id = d.subscription
+             WHERE d.delivered = 0 AND d.attempts < ?1 ORDER BY d.id",
+        )?;
+        let rows = statement.query_map([MAX_ATTEMPTS], |row| {
+            let subscription = subscription(row)?;
+            Ok((row.get("delivery_id")?, subscription.target, subscription.secret, row.get("payload")?))
+        })?;
+        rows.collect()
+    }
+
+    fn record_attempt(&self, delivery: i64, result: &Result<(), String>) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE deliveries SET attempts = attempts + 1, delivered = ?2, last_error = ?3 WHERE id = ?1",
+            params![delivery, result.is_ok(), result.as_ref().err()],
+        )?;
+        Ok(())
+    }
+}
+
+/// `v1=` and the hex HMAC-SHA256 of `"{timestamp}.{body}"`, keyed with the subscription's secret
+/// as the hex string it is handed out as.
+pub fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("v1={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Checks a notification's signature as a subscriber would, in constant time.
+pub fn verify_signature(secret: &str, timestamp: u64, body: &str, signature: &str) -> bool {
+    let Some(tag) = signature.strip_prefix("v1=").and_then(|tag| hex::decode(tag).ok()) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    mac.verify_slice(&tag).is_ok()
+}
+
+/// Sends the notifications a `Database` queues.
+///
+/// Each `dispatch` round queues what changed and then attempts every undelivered notification
+/// once, so a failed delivery is retried on the next rounds until it has had `MAX_ATTEMPTS`.
+/// A webhook succeeds on a 2xx response and a command on exit status 0.
+#[derive(Clone)]
+pub struct Dispatcher {
+    http: reqwest::Client,
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        let http = reqwest::Client::builder().timeout(Duration::from_secs(10)).build().expect("HTTP client");
+        Dispatcher { http }
+    }
+
+    /// Runs one round at Unix time `now`. Returns how many notifications were delivered.
+    pub async fn dispatch(&self, db: &mut Database, now: u64) -> rusqlite::Result<usize> {
+        db.transaction(|db| db.enqueue_notifications(now))?;
+        let mut delivered = 0;
+        for (delivery, target, secret, payload) in db.pending_deliveries()? {
+            let result = self.deliver(&target, &secret, now, &payload).await;
+            delivered += result.is_ok() as usize;
+            db.record_attempt(delivery, &result)?;
+        }
+        Ok(delivered)
+    }
+
+    async fn deliver(&self, target: &Target, secret: &str, now: u64, payload: &str) -> Result<(), String> {
+        let signature = sign(secret, now, payload);
+        match target {
+            Target::Webhook(url) => {
+                let response = self
+                    .http
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .header(TIMESTAMP_HEADER, now.to_string())
+                    .header(SIGNATURE_HEADER, signature)
+                    .body(payload.to_string())
+                    .send()
+                    .await
+                    .map_err(|err| err.to_string())?;
+                match response.status() {
+                    status if status.is_success() => Ok(()),
+                    status => Err(format!("HTTP {}", status)),
+                }
+            }
+            Target::Command(command) => {
+                let mut child = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("IDENCLAVE_SIGNATURE", signature)
+                    .env("IDENCLAVE_TIMESTAMP", now.to_string())
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .map_err(|err| err.to_string())?;
+                let mut stdin = child.stdin.take().expect("piped stdin");
+                // A command that exits without reading its input is judged by its exit status alone.
+                let _ = stdin.write_all(payload.as_bytes()).await;
+                drop(stdin);
+                let output = child.wait_with_output().await.map_err(|err| err.to_string())?;
+                if !output.status.success() {
+                    return Err(format!("{}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()));
+                }
+                Ok(())
+            }
+        }
+    }
+}
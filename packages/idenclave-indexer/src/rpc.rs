@@ -0,0 +1,205 @@
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+use std::str::FromStr;
+
+/// Most accounts one `getMultipleAccounts` call may ask for.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+/// Most signatures one `getSignaturesForAddress` call returns.
+const SIGNATURE_PAGE: usize = 1000;
+
+/// An account as the RPC node returned it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+/// A top-level instruction of a transaction, with its accounts resolved to addresses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+/// A confirmed transaction: where it landed, whether it succeeded, its top-level instructions
+/// and its log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfirmedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub succeeded: bool,
+    pub instructions: Vec<TransactionInstruction>,
+    pub logs: Vec<String>,
+}
+
+/// A failed JSON-RPC call: unreachable node, error response or unexpected reply.
+#[derive(Debug)]
+pub struct RpcError(pub String);
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rpc: {}", self.0)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+fn malformed(method: &str) -> RpcError {
+    RpcError(format!("unexpected {} reply", method))
+}
+
+fn pubkey(value: &Value) -> Option<Pubkey> {
+    Pubkey::from_str(value.as_str()?).ok()
+}
+
+fn account(value: &Value) -> Option<Account> {
+    let data = base64::engine::general_purpose::STANDARD.decode(value["data"][0].as_str()?).ok()?;
+    Some(Account { owner: pubkey(&value["owner"])?, data })
+}
+
+/// Reads program accounts and history over Solana's JSON-RPC API, from a cluster or a local
+/// `solana-test-validator`. Everything is read at the `confirmed` commitment.
+#[derive(Clone)]
+pub struct RpcClient {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        RpcClient { url: url.to_string(), http: reqwest::Client::new() }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response: Value = self
+            .http
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|err| RpcError(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| RpcError(err.to_string()))?;
+        if let Some(error) = response.get("error") {
+            return Err(RpcError(format!("{} failed: {}", method, error)));
+        }
+        response.get("result").cloned().ok_or_else(|| malformed(method))
+    }
+
+    /// Every account the program owns, with the slot they were read at.
+    pub async fn get_program_accounts(&self, program_id: &Pubkey) -> Result<(u64, Vec<(Pubkey, Account)>), RpcError> {
+        let method = "getProgramAccounts";
+        let result = self
+            .call(method, json!([program_id.to_string(), { "encoding": "base64", "commitment": "confirmed", "withContext": true }]))
+            .await?;
+        let slot = result["context"]["slot"].as_u64().ok_or_else(|| malformed(method))?;
+        let accounts = result["value"].as_array().ok_or_else(|| malformed(method))?;
+        let accounts = accounts
+            .iter()
+            .map(|entry| Some((pubkey(&entry["pubkey"])?, account(&entry["account"])?)))
+            .collect::<Option<_>>()
+            .ok_or_else(|| malformed(method))?;
+        Ok((slot, accounts))
+    }
+
+    /// The accounts at `addresses`, `None` for those that do not exist, with the slot they were
+    /// read at (the oldest, when more than one call was needed).
+    pub async fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<(u64, Vec<Option<Account>>), RpcError> {
+        let method = "getMultipleAccounts";
+        let mut slot = u64::MAX;
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let keys: Vec<String> = chunk.iter().map(Pubkey::to_string).collect();
+            let result = self.call(method, json!([keys, { "encoding": "base64", "commitment": "confirmed" }])).await?;
+            slot = slot.min(result["context"]["slot"].as_u64().ok_or_else(|| malformed(method))?);
+            let values = result["value"].as_array().filter(|values| values.len() == chunk.len()).ok_or_else(|| malformed(method))?;
+            for value in values {
+                accounts.push(if value.is_null() { None } else { Some(account(value).ok_or_else(|| malformed(method))?) });
+            }
+        }
+        Ok((if addresses.is_empty() { 0 } else { slot }, accounts))
+    }
+
+    /// Signatures of the transactions that mentioned `address` after `until` (all of them for
+    /// `None`), oldest first.
+    pub async fn get_signatures_since(&self, address: &Pubkey, until: Option<&str>) -> Result<Vec<String>, RpcError> {
+        let method = "getSignaturesForAddress";
+        let mut signatures = Vec::new();
+        let mut before: Option<String> = None;
+        loop {
+            let mut config = json!({ "limit": SIGNATURE_PAGE, "commitment": "confirmed" });
+            if let Some(until) = until {
+                config["until"] = json!(until);
+            }
+            if let Some(before) = &before {
+                config["before"] = json!(before);
+            }
+            let page = self.call(method, json!([address.to_string(), config])).await?;
+            let page = page.as_array().ok_or_else(|| malformed(method))?;
+            for entry in page {
+                signatures.push(entry["signature"].as_str().ok_or_else(|| malformed(method))?.to_string());
+            }
+            if page.len() < SIGNATURE_PAGE {
+                break;
+            }
+            before = signatures.last().cloned();
+        }
+        // Pages come newest first
+        signatures.reverse();
+        Ok(signatures)
+    }
+
+    /// `None` when the node does not have the transaction.
+    pub async fn get_transaction(&self, signature: &str) -> Result<Option<ConfirmedTransaction>, RpcError> {
+        let method = "getTransaction";
+        let result = self
+            .call(method, json!([signature, { "encoding": "json", "commitment": "confirmed", "maxSupportedTransactionVersion": 0 }]))
+            .await?;
+        if result.is_null() {
+            return Ok(None);
+        }
+        parse_transaction(signature, &result).map(Some).ok_or_else(|| malformed(method))
+    }
+}
+
+fn parse_transaction(signature: &str, result: &Value) -> Option<ConfirmedTransaction> {
+    let meta = &result["meta"];
+    let message = &result["transaction"]["message"];
+    // Indices past the static keys refer to addresses loaded from lookup tables, writable first
+    let mut keys: Vec<Pubkey> = message["accountKeys"].as_array()?.iter().map(pubkey).collect::<Option<_>>()?;
+    for loaded in ["writable", "readonly"] {
+        if let Some(addresses) = meta["loadedAddresses"][loaded].as_array() {
+            keys.extend(addresses.iter().map(pubkey).collect::<Option<Vec<_>>>()?);
+        }
+    }
+    let key = |index: &Value| keys.get(index.as_u64()? as usize).copied();
+    let instructions = message["instructions"]
+        .as_array()?
+        .iter()
+        .map(|instruction| {
+            Some(TransactionInstruction {
+                program_id: key(&instruction["programIdIndex"])?,
+                accounts: instruction["accounts"].as_array()?.iter().map(key).collect::<Option<_>>()?,
+                data: bs58::decode(instruction["data"].as_str()?).into_vec().ok()?,
+            })
+        })
+        .collect::<Option<_>>()?;
+    let logs = match meta["logMessages"].as_array() {
+        Some(logs) => logs.iter().map(|line| line.as_str().map(str::to_string)).collect::<Option<_>>()?,
+        None => Vec::new(),
+    };
+    Some(ConfirmedTransaction {
+        signature: signature.to_string(),
+        slot: result["slot"].as_u64()?,
+        block_time: result["blockTime"].as_i64(),
+        succeeded: meta["err"].is_null(),
+        instructions,
+        logs,
+    })
+}
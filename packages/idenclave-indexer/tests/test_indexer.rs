@@ -0,0 +1,321 @@
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::Engine;
+use idenclave::event::IdentityEvent;
+use idenclave_core::{AccountLayout, CredentialStatus, CredentialState};
+use idenclave_indexer::{program_events, Database, Indexer, RpcClient};
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+const NOW: u64 = 1_700_000_000;
+
+#[derive(Default)]
+struct Chain {
+    slot: u64,
+    accounts: HashMap<Pubkey, (Pubkey, Vec<u8>)>,
+    /// Signature and `getTransaction` reply, oldest first
+    transactions: Vec<(String, Value)>,
+}
+
+type Shared = Arc<Mutex<Chain>>;
+
+fn account_json(owner: &Pubkey, data: &[u8]) -> Value {
+    json!({
+        "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+        "executable": false,
+        "lamports": 1_000_000,
+        "owner": owner.to_string(),
+        "rentEpoch": 0,
+    })
+}
+
+/// Answers the RPC methods the indexer uses from `chain`, standing in for a validator.
+async fn rpc_stand_in(State(chain): State<Shared>, Json(request): Json<Value>) -> Json<Value> {
+    let chain = chain.lock().unwrap();
+    let params = &request["params"];
+    let context = json!({ "slot": chain.slot });
+    let result = match request["method"].as_str().unwrap() {
+        "getProgramAccounts" => {
+            let program_id = Pubkey::from_str(params[0].as_str().unwrap()).unwrap();
+            let accounts: Vec<Value> = chain
+                .accounts
+                .iter()
+                .filter(|(_, (owner, _))| *owner == program_id)
+                .map(|(address, (owner, data))| json!({ "pubkey": address.to_string(), "account": account_json(owner, data) }))
+                .collect();
+            json!({ "context": context, "value": accounts })
+        }
+        "getMultipleAccounts" => {
+            let accounts: Vec<Value> = params[0]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|address| {
+                    let address = Pubkey::from_str(address.as_str().unwrap()).unwrap();
+                    chain.accounts.get(&address).map_or(Value::Null, |(owner, data)| account_json(owner, data))
+                })
+                .collect();
+            json!({ "context": context, "value": accounts })
+        }
+        "getSignaturesForAddress" => {
+            let until = params[1]["until"].as_str();
+            let start = until.map_or(0, |until| chain.transactions.iter().position(|(signature, _)| signature == until).unwrap() + 1);
+            let signatures: Vec<Value> =
+                chain.transactions[start..].iter().rev().map(|(signature, _)| json!({ "signature": signature, "err": null })).collect();
+            json!(signatures)
+        }
+        "getTransaction" => {
+            let signature = params[0].as_str().unwrap();
+            chain.transactions.iter().find(|(s, _)| s == signature).map_or(Value::Null, |(_, transaction)| transaction.clone())
+        }
+        method => panic!("unexpected {}", method),
+    };
+    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+fn packed<T: AccountLayout>(account: &T) -> Vec<u8> {
+    let mut data = vec![0u8; T::ACCOUNT_LEN];
+    account.pack_account(&mut data);
+    data
+}
+
+/// A `getTransaction` reply for one instruction of `program_id` that emitted `events`.
+fn transaction(signature: &str, slot: u64, program_id: &Pubkey, accounts: &[Pubkey], data: &[u8], logs: Vec<String>, failed: bool) -> Value {
+    let mut keys = vec![Pubkey::new_unique()]; // fee payer
+    keys.extend_from_slice(accounts);
+    keys.push(*program_id);
+    json!({
+        "slot": slot,
+        "blockTime": NOW as i64,
+        "meta": {
+            "err": if failed { json!({ "InstructionError": [0, "InvalidArgument"] }) } else { Value::Null },
+            "logMessages": logs,
+            "loadedAddresses": { "writable": [], "readonly": [] },
+        },
+        "transaction": {
+            "signatures": [signature],
+            "message": {
+                "accountKeys": keys.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+                "instructions": [{
+                    "programIdIndex": keys.len() - 1,
+                    "accounts": (1..=accounts.len()).collect::<Vec<_>>(),
+                    "data": bs58::encode(data).into_string(),
+                }],
+            },
+        },
+    })
+}
+
+fn event_logs(program_id: &Pubkey, events: &[IdentityEvent]) -> Vec<String> {
+    let mut logs = vec![format!("Program {} invoke [1]", program_id), "Program log: Instruction".to_string()];
+    for event in events {
+        logs.push(format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(event.pack())));
+    }
+    logs.push(format!("Program {} consumed 1000 of 200000 compute units", program_id));
+    logs.push(format!("Program {} success", program_id));
+    logs
+}
+
+#[test]
+fn test_program_events_follow_the_call_stack() {
+    let (program_id, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (credential, issuer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let data = |event: IdentityEvent| format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(event.pack()));
+    let logs = vec![
+        format!("Program {} invoke [1]", other),
+        // Another program's data, even when it decodes as an event
+        data(IdentityEvent::CredentialRevoked { credential, issuer }),
+        format!("Program {} invoke [2]", program_id),
+        data(IdentityEvent::CredentialSuspended { credential, issuer }),
+        format!("Program {} success", program_id),
+        format!("Program {} success", other),
+        format!("Program {} invoke [1]", program_id),
+        "Program data: bm90IGFuIGV2ZW50".to_string(),
+        data(IdentityEvent::CredentialReinstated { credential, issuer }),
+        format!("Program {} failed: custom program error: 0x1", program_id),
+    ];
+    let events = program_events(&logs, &program_id);
+    assert_eq!(events.len(), 2);
+    assert_eq!((events[0].instruction, events[0].top_level), (0, false));
+    assert_eq!(events[0].event, IdentityEvent::CredentialSuspended { credential, issuer });
+    assert_eq!((events[1].instruction, events[1].top_level), (1, true));
+    assert_eq!(events[1].event, IdentityEvent::CredentialReinstated { credential, issuer });
+}
+
+#[tokio::test]
+async fn test_backfill_and_follow_transactions() {
+    let program_id = Pubkey::new_unique();
+    let (issuer, identity, old_credential) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let schema = [4u8; 32];
+    let (credential, _) = idenclave::credential::Credential::find_address(&issuer, &identity, &schema, 0, &program_id);
+    let state = |revoked: bool| idenclave_core::Credential {
+        is_initialized: true,
+        revoked,
+        identity: identity.to_bytes(),
+        issuer: issuer.to_bytes(),
+        issued_at: NOW - 10,
+        expires_at: u64::MAX,
+        ..Default::default()
+    };
+
+    let chain = Arc::new(Mutex::new(Chain::default()));
+    {
+        let mut chain = chain.lock().unwrap();
+        chain.slot = 10;
+        let identity_state = idenclave_core::Identity { is_initialized: true, authority: identity.to_bytes(), ..Default::default() };
+        chain.accounts.insert(identity, (program_id, packed(&identity_state)));
+        chain.accounts.insert(old_credential, (program_id, packed(&state(false))));
+        // Not the program's, so never indexed
+        chain.accounts.insert(issuer, (Pubkey::new_unique(), vec![]));
+    }
+    let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+        .serve(Router::new().route("/", post(rpc_stand_in)).with_state(chain.clone()).into_make_service());
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    let mut indexer = Indexer::new(RpcClient::new(&url), program_id, Database::open_in_memory().unwrap());
+
+    // Backfill stores the accounts as they are
+    assert_eq!(indexer.backfill().await.unwrap(), 2);
+    assert!(indexer.db.identity(&identity).unwrap().is_some());
+    assert_eq!(indexer.db.credential(&old_credential).unwrap().unwrap().schema, None);
+
+    // Signed issuance: the schema comes from the instruction
+    {
+        let mut chain = chain.lock().unwrap();
+        chain.slot = 11;
+        chain.accounts.insert(credential, (program_id, packed(&state(false))));
+        let mut data = vec![19];
+        data.extend_from_slice(&schema);
+        let logs = event_logs(&program_id, &[IdentityEvent::CredentialIssued { credential, identity, issuer }]);
+        let reply = transaction("issue", 11, &program_id, &[identity, credential, issuer], &data, logs, false);
+        chain.transactions.push(("issue".to_string(), reply));
+    }
+    assert_eq!(indexer.sync().await.unwrap(), 1);
+    let record = indexer.db.credential(&credential).unwrap().unwrap();
+    assert_eq!((record.schema.as_deref(), record.status(NOW)), (Some(hex::encode(schema).as_str()), CredentialStatus::Valid));
+    assert_eq!(indexer.db.holders(&schema, NOW).unwrap(), vec![identity.to_string()]);
+    let issued = indexer.db.credentials_by_issuer(&issuer).unwrap();
+    assert_eq!(issued.len(), 2);
+    let issuers = indexer.db.issuers().unwrap();
+    assert_eq!((issuers.len(), issuers[0].address.clone(), issuers[0].credentials), (1, issuer.to_string(), 2));
+
+    // A failed transaction changes nothing; the revocation after it is recorded
+    {
+        let mut chain = chain.lock().unwrap();
+        chain.slot = 13;
+        let logs = event_logs(&program_id, &[IdentityEvent::CredentialRevoked { credential: old_credential, issuer }]);
+        let reply = transaction("failed", 12, &program_id, &[old_credential, issuer], &[7], logs, true);
+        chain.transactions.push(("failed".to_string(), reply));
+        chain.accounts.insert(credential, (program_id, packed(&state(true))));
+        let logs = event_logs(&program_id, &[IdentityEvent::CredentialRevoked { credential, issuer }]);
+        let reply = transaction("revoke", 13, &program_id, &[credential, issuer], &[7], logs, false);
+        chain.transactions.push(("revoke".to_string(), reply));
+    }
+    assert_eq!(indexer.sync().await.unwrap(), 2);
+    assert_eq!(indexer.sync().await.unwrap(), 0);
+    assert_eq!(indexer.db.credential(&credential).unwrap().unwrap().status(NOW), CredentialStatus::Revoked);
+    assert!(indexer.db.holders(&schema, NOW).unwrap().is_empty());
+    let history: Vec<String> = indexer.db.history(&credential).unwrap().into_iter().map(|change| change.change).collect();
+    assert_eq!(history, ["issued", "revoked"]);
+    assert!(indexer.db.history(&old_credential).unwrap().is_empty());
+
+    // Superseding issues the new version under the schema in the instruction
+    let (successor, _) = idenclave::credential::Credential::find_address(&issuer, &identity, &schema, 1, &program_id);
+    {
+        let mut chain = chain.lock().unwrap();
+        chain.slot = 13;
+        chain.accounts.insert(successor, (program_id, packed(&state(false))));
+        let mut data = vec![12];
+        data.extend_from_slice(&schema);
+        data.extend_from_slice(&1u64.to_le_bytes());
+        let logs = event_logs(&program_id, &[IdentityEvent::CredentialSuperseded { credential: old_credential, new_credential: successor, issuer }]);
+        let reply = transaction("supersede", 13, &program_id, &[old_credential, successor, issuer], &data, logs, false);
+        chain.transactions.push(("supersede".to_string(), reply));
+    }
+    assert_eq!(indexer.sync().await.unwrap(), 1);
+    assert_eq!(indexer.db.credential(&successor).unwrap().unwrap().schema, Some(hex::encode(schema)));
+    assert_eq!(indexer.db.holders(&schema, NOW).unwrap(), vec![identity.to_string()]);
+
+    // Closed accounts stay indexed, marked closed
+    {
+        let mut chain = chain.lock().unwrap();
+        chain.slot = 14;
+        chain.accounts.remove(&old_credential);
+        let logs = event_logs(&program_id, &[IdentityEvent::CredentialClosed { credential: old_credential, closed_by: issuer }]);
+        let reply = transaction("close", 14, &program_id, &[old_credential, issuer, issuer], &[17], logs, false);
+        chain.transactions.push(("close".to_string(), reply));
+    }
+    assert_eq!(indexer.sync().await.unwrap(), 1);
+    let record = indexer.db.credential(&old_credential).unwrap().unwrap();
+    assert_eq!((record.closed, record.slot), (true, 14));
+    let history = indexer.db.history(&old_credential).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!((history[1].change.as_str(), history[1].signature.as_str(), history[1].slot), ("closed", "close", 14));
+    assert_eq!(indexer.db.credentials_of(&identity).unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_batch_offers_carry_the_schema() {
+    let program_id = Pubkey::new_unique();
+    let (issuer, alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let schema = [9u8; 32];
+    let address = |identity: &Pubkey, nonce: u64| idenclave::credential::Credential::find_address(&issuer, identity, &schema, nonce, &program_id).0;
+    let (alice_credential, bob_credential) = (address(&alice, 0), address(&bob, 1));
+    let state = |identity: &Pubkey, pending: bool| idenclave_core::Credential {
+        is_initialized: true,
+        pending,
+        identity: identity.to_bytes(),
+        issuer: issuer.to_bytes(),
+        issued_at: NOW - 10,
+        expires_at: u64::MAX,
+        ..Default::default()
+    };
+
+    let chain = Arc::new(Mutex::new(Chain::default()));
+    {
+        let mut chain = chain.lock().unwrap();
+        chain.slot = 20;
+        chain.accounts.insert(alice_credential, (program_id, packed(&state(&alice, true))));
+        chain.accounts.insert(bob_credential, (program_id, packed(&state(&bob, true))));
+        // IssueCredentialBatch: the schema follows the tag, then the items
+        let mut data = vec![20];
+        data.extend_from_slice(&schema);
+        let logs = event_logs(
+            &program_id,
+            &[
+                IdentityEvent::CredentialOffered { credential: alice_credential, identity: alice, issuer },
+                IdentityEvent::CredentialOffered { credential: bob_credential, identity: bob, issuer },
+            ],
+        );
+        let accounts = [issuer, alice, alice_credential, bob, bob_credential];
+        chain.transactions.push(("batch".to_string(), transaction("batch", 20, &program_id, &accounts, &data, logs, false)));
+    }
+    let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+        .serve(Router::new().route("/", post(rpc_stand_in)).with_state(chain.clone()).into_make_service());
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    let mut indexer = Indexer::new(RpcClient::new(&url), program_id, Database::open_in_memory().unwrap());
+
+    assert_eq!(indexer.sync().await.unwrap(), 1);
+    for credential in [&alice_credential, &bob_credential] {
+        let record = indexer.db.credential(credential).unwrap().unwrap();
+        assert_eq!((record.schema.as_deref(), record.status(NOW)), (Some(hex::encode(schema).as_str()), CredentialStatus::Pending));
+    }
+    // Offers are not held until accepted
+    assert!(indexer.db.holders(&schema, NOW).unwrap().is_empty());
+    {
+        let mut chain = chain.lock().unwrap();
+        chain.slot = 21;
+        chain.accounts.insert(alice_credential, (program_id, packed(&state(&alice, false))));
+        let logs = event_logs(&program_id, &[IdentityEvent::CredentialAccepted { credential: alice_credential, identity: alice }]);
+        let reply = transaction("accept", 21, &program_id, &[alice, alice_credential, alice], &[14], logs, false);
+        chain.transactions.push(("accept".to_string(), reply));
+    }
+    assert_eq!(indexer.sync().await.unwrap(), 1);
+    assert_eq!(indexer.db.holders(&schema, NOW).unwrap(), vec![alice.to_string()]);
+}
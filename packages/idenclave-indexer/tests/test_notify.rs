@@ -0,0 +1,167 @@
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use idenclave_indexer::notify::{MAX_ATTEMPTS, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use idenclave_indexer::{sign, verify_signature, Database, Dispatcher, Filter, StatusChange, Target};
+use serde_json::Value;
+use solana_program::pubkey::Pubkey;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+const NOW: u64 = 1_700_000_000;
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Default)]
+struct Inbox {
+    /// Requests to refuse before accepting
+    failures: usize,
+    /// Timestamp, signature and body of each accepted notification
+    received: Vec<(u64, String, String)>,
+}
+
+type Shared = Arc<Mutex<Inbox>>;
+
+/// Stands in for a subscriber's webhook endpoint.
+async fn webhook_stand_in(State(inbox): State<Shared>, headers: HeaderMap, body: String) -> StatusCode {
+    let mut inbox = inbox.lock().unwrap();
+    if inbox.failures > 0 {
+        inbox.failures -= 1;
+        return StatusCode::SERVICE_UNAVAILABLE;
+    }
+    let header = |name: &str| headers.get(name).unwrap().to_str().unwrap().to_string();
+    inbox.received.push((header(TIMESTAMP_HEADER).parse().unwrap(), header(SIGNATURE_HEADER), body));
+    StatusCode::NO_CONTENT
+}
+
+fn store_credential(db: &Database, address: &Pubkey, identity: &Pubkey, issuer: &Pubkey, expires_at: u64) {
+    let credential = idenclave_core::Credential {
+        is_initialized: true,
+        identity: identity.to_bytes(),
+        issuer: issuer.to_bytes(),
+        issued_at: NOW - DAY,
+        expires_at,
+        ..Default::default()
+    };
+    db.upsert_credential(address, &credential, 1).unwrap();
+}
+
+fn record(db: &Database, credential: &Pubkey, change: &str) {
+    db.record_change(&StatusChange {
+        credential: credential.to_string(),
+        change: change.to_string(),
+        expires_at: None,
+        slot: 2,
+        block_time: Some(NOW as i64),
+        signature: format!("{}-{}", change, credential),
+    })
+    .unwrap();
+}
+
+#[test]
+fn test_signature() {
+    let signature = sign("secret", NOW, "{}");
+    assert!(signature.starts_with("v1=") && signature.len() == 3 + 64);
+    assert!(verify_signature("secret", NOW, "{}", &signature));
+    assert!(!verify_signature("other", NOW, "{}", &signature));
+    assert!(!verify_signature("secret", NOW + 1, "{}", &signature));
+    assert!(!verify_signature("secret", NOW, "{ }", &signature));
+    assert!(!verify_signature("secret", NOW, "{}", &signature[3..]));
+}
+
+#[tokio::test]
+async fn test_webhook_notifications() {
+    let (issuer, other_issuer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (identity, revoked, expiring, lasting, foreign) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut db = Database::open_in_memory().unwrap();
+    store_credential(&db, &revoked, &identity, &issuer, u64::MAX);
+    store_credential(&db, &expiring, &identity, &issuer, NOW + DAY);
+    store_credential(&db, &lasting, &identity, &issuer, NOW + 30 * DAY);
+    store_credential(&db, &foreign, &identity, &other_issuer, NOW + DAY);
+    // Recorded before the subscription, so never notified
+    record(&db, &lasting, "suspended");
+
+    let inbox = Arc::new(Mutex::new(Inbox { failures: 2, ..Default::default() }));
+    let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+        .serve(Router::new().route("/hook", post(webhook_stand_in)).with_state(inbox.clone()).into_make_service());
+    let url = format!("http://{}/hook", server.local_addr());
+    tokio::spawn(server);
+
+    let filter = Filter { issuer: Some(issuer), ..Default::default() };
+    let subscription = db.subscribe(&Target::Webhook(url.clone()), &filter, 7 * DAY).unwrap();
+    assert_eq!(subscription.secret.len(), 64);
+    assert_eq!(db.subscriptions().unwrap(), vec![subscription.clone()]);
+    record(&db, &revoked, "revoked");
+    record(&db, &foreign, "revoked");
+    record(&db, &revoked, "reinstated");
+
+    // The endpoint is down for the first round; everything is retried on the next
+    let dispatcher = Dispatcher::new();
+    assert_eq!(dispatcher.dispatch(&mut db, NOW).await.unwrap(), 0);
+    let deliveries = db.deliveries(subscription.id).unwrap();
+    assert_eq!(deliveries.len(), 2);
+    assert!(deliveries.iter().all(|delivery| delivery.attempts == 1 && !delivery.delivered));
+    assert_eq!(deliveries[0].last_error.as_deref(), Some("HTTP 503 Service Unavailable"));
+    assert_eq!(dispatcher.dispatch(&mut db, NOW + 60).await.unwrap(), 2);
+    assert!(db.deliveries(subscription.id).unwrap().iter().all(|delivery| delivery.delivered && delivery.last_error.is_none()));
+
+    let received = inbox.lock().unwrap().received.clone();
+    let bodies: Vec<Value> = received.iter().map(|(_, _, body)| serde_json::from_str(body).unwrap()).collect();
+    assert_eq!(bodies[0]["type"], "credential.revoked");
+    assert_eq!(bodies[0]["credential"]["address"], revoked.to_string());
+    assert_eq!(bodies[0]["change"]["signature"], format!("revoked-{}", revoked));
+    assert_eq!(bodies[1]["type"], "credential.expiring");
+    assert_eq!((bodies[1]["credential"]["address"].as_str(), bodies[1]["change"].is_null()), (Some(expiring.to_string().as_str()), true));
+    for (timestamp, signature, body) in &received {
+        assert_eq!(*timestamp, NOW + 60);
+        assert!(verify_signature(&subscription.secret, *timestamp, body, signature));
+    }
+
+    // Each condition is notified once; a renewal into the notice window is a new one
+    assert_eq!(dispatcher.dispatch(&mut db, NOW + 120).await.unwrap(), 0);
+    store_credential(&db, &lasting, &identity, &issuer, NOW + 2 * DAY);
+    assert_eq!(dispatcher.dispatch(&mut db, NOW + 180).await.unwrap(), 1);
+    assert_eq!(inbox.lock().unwrap().received.len(), 3);
+
+    // Undeliverable notifications are given up on after `MAX_ATTEMPTS`, and dropped with their
+    // subscription
+    let dead = db.subscribe(&Target::Webhook(format!("{}-missing", url)), &Filter::default(), 0).unwrap();
+    record(&db, &foreign, "suspended");
+    for round in 0..MAX_ATTEMPTS as u64 + 2 {
+        assert_eq!(dispatcher.dispatch(&mut db, NOW + 240 + round).await.unwrap(), 0);
+    }
+    let deliveries = db.deliveries(dead.id).unwrap();
+    assert_eq!((deliveries.len(), deliveries[0].attempts, deliveries[0].delivered), (1, MAX_ATTEMPTS, false));
+    assert!(db.unsubscribe(dead.id).unwrap());
+    assert!(!db.unsubscribe(dead.id).unwrap());
+    assert!(db.deliveries(dead.id).unwrap().is_empty());
+    assert_eq!(db.subscriptions().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_command_notifications() {
+    let (identity, issuer, credential) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut db = Database::open_in_memory().unwrap();
+    store_credential(&db, &credential, &identity, &issuer, u64::MAX);
+    let out = std::env::temp_dir().join(format!("idenclave-notify-{}", credential));
+    let command = format!("cat > {path} && echo \"$IDENCLAVE_TIMESTAMP $IDENCLAVE_SIGNATURE\" > {path}.sig", path = out.display());
+    let filter = Filter { identity: Some(identity), ..Default::default() };
+    let subscription = db.subscribe(&Target::Command(command), &filter, DAY).unwrap();
+    let failing = db.subscribe(&Target::Command("echo refused >&2; exit 3".to_string()), &filter, DAY).unwrap();
+    record(&db, &credential, "suspended");
+
+    assert_eq!(Dispatcher::new().dispatch(&mut db, NOW).await.unwrap(), 1);
+    let body = std::fs::read_to_string(&out).unwrap();
+    let sig = std::fs::read_to_string(format!("{}.sig", out.display())).unwrap();
+    let (timestamp, signature) = sig.trim().split_once(' ').unwrap();
+    assert_eq!(timestamp, NOW.to_string());
+    assert!(verify_signature(&subscription.secret, NOW, &body, signature));
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!((body["type"].as_str(), body["subscription"].as_i64()), (Some("credential.suspended"), Some(subscription.id)));
+    let refused = &db.deliveries(failing.id).unwrap()[0];
+    assert!(!refused.delivered);
+    assert!(refused.last_error.as_deref().unwrap().ends_with("refused"));
+    std::fs::remove_file(&out).unwrap();
+    std::fs::remove_file(format!("{}.sig", out.display())).unwrap();
+}
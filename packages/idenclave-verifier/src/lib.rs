@@ -0,0 +1,19 @@
+//! HTTP service verifying idenclave presentations for a backend.
+//!
+//! A verifier that asked for a presentation with `CreatePresentationRequest` gets the holder's
+//! answer off-chain: the credential accounts, the signed credential documents and a snarkjs
+//! proof. `POST /presentations/verify` checks all of it at once: that each document is the one
+//! its credential refers to and carries the issuer's signature, the proof against the registered
+//! circuit, and the credentials' on-chain status read over RPC and decoded with the program's own
+//! account layouts. The verdict carries the result
+//! `RespondToRequest` would record and a reason for every problem found.
+
+pub mod presentation;
+pub mod rpc;
+pub mod server;
+pub mod verdict;
+
+pub use presentation::{verify_presentation, Presentation, VerifyError};
+pub use rpc::{Account, RpcClient, RpcError};
+pub use server::{router, Service};
+pub use verdict::{Check, Reason, Verdict};
@@ -0,0 +1,34 @@
+use idenclave_verifier::{router, RpcClient, Service};
+use solana_program::pubkey::Pubkey;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const USAGE: &str = "usage: idenclave-verifier --program-id <ADDRESS> [--rpc-url <URL>] [--listen <ADDR:PORT>]";
+
+#[tokio::main]
+async fn main() {
+    let mut program_id = None;
+    let mut rpc_url = "http://127.0.0.1:8899".to_string();
+    let mut listen: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args.next().unwrap_or_else(|| exit(USAGE));
+        match flag.as_str() {
+            "--program-id" => program_id = Some(Pubkey::from_str(&value).unwrap_or_else(|_| exit("--program-id is not an address"))),
+            "--rpc-url" => rpc_url = value,
+            "--listen" => listen = value.parse().unwrap_or_else(|_| exit("--listen is not an address:port")),
+            _ => exit(USAGE),
+        }
+    }
+    let program_id = program_id.unwrap_or_else(|| exit(USAGE));
+
+    let service = Arc::new(Service { rpc: RpcClient::new(&rpc_url), program_id });
+    println!("Verifying presentations for {} via {} on {}", program_id, rpc_url, listen);
+    axum::Server::bind(&listen).serve(router(service).into_make_service()).await.unwrap();
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2)
+}
@@ -0,0 +1,235 @@
+use crate::rpc::{Account, RpcClient, RpcError};
+use crate::verdict::{Check, Reason, Verdict};
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use idenclave::credential::Credential;
+use idenclave::presentation::AltBn128;
+use idenclave::{Circuit, Identity, Organization, PresentationRequest};
+use idenclave_core::{PresentedCredential, Scalar};
+use serde::Deserialize;
+use solana_program::hash::hash;
+use solana_program::program_pack::{IsInitialized, Pack};
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// A holder's answer to an on-chain presentation request, as `POST /presentations/verify` takes
+/// it. Accounts are base58 addresses.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Presentation {
+    pub request: String,
+    pub identity: String,
+    /// One credential per schema of the request, in the request's order
+    pub credentials: Vec<PresentedCredentialJson>,
+    /// Required when the request names a circuit
+    pub proof: Option<ProofJson>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PresentedCredentialJson {
+    pub address: String,
+    /// Nonce the credential address was derived with, which shows it is of the schema
+    pub nonce: u64,
+    pub document: Option<SignedDocument>,
+}
+
+/// The off-chain credential document, such as the JSON a VC was issued as, with the issuer's
+/// Ed25519 signature over its exact bytes. The SHA-256 digest of those bytes, which is the digest
+/// of the document's IPFS CID, must be the credential's `credential_ref`. The signing key must be
+/// the credential's issuer or, for an organization, one of its current keys.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SignedDocument {
+    pub content: String,
+    /// Base58, 64 bytes
+    pub signature: String,
+}
+
+/// snarkjs output as the frontend gets it from `groth16.fullProve`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProofJson {
+    pub proof: serde_json::Value,
+    pub public_signals: serde_json::Value,
+}
+
+/// Why a presentation could not be verified at all, as opposed to not passing.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The presentation is malformed, such as an address that is not base58
+    BadRequest(String),
+    Rpc(RpcError),
+}
+
+impl From<RpcError> for VerifyError {
+    fn from(err: RpcError) -> Self {
+        VerifyError::Rpc(err)
+    }
+}
+
+pub(crate) fn parse_address(address: &str) -> Result<Pubkey, VerifyError> {
+    Pubkey::from_str(address).map_err(|_| VerifyError::BadRequest(format!("{:?} is not an address", address)))
+}
+
+/// Decodes an account the way the program loads it: owned by the program, the right size and
+/// discriminator, and initialized.
+pub(crate) fn decode<T: Pack + IsInitialized>(account: Option<&Account>, program_id: &Pubkey) -> Result<T, String> {
+    let account = account.ok_or("account does not exist")?;
+    if account.owner != *program_id {
+        return Err(format!("account is owned by {}, not the program", account.owner));
+    }
+    T::unpack(&account.data).map_err(|_| "account does not hold the expected type".to_string())
+}
+
+fn reason(check: Check, account: Option<&str>, message: impl Into<String>) -> Reason {
+    Reason { check, account: account.map(str::to_string), message: message.into() }
+}
+
+/// Checks `document` is the one `credential_ref` refers to and was signed by one of `signers`.
+fn check_signature(document: &SignedDocument, credential_ref: &[u8; 32], signers: &[Pubkey]) -> Result<(), String> {
+    if hash(document.content.as_bytes()).to_bytes() != *credential_ref {
+        return Err("document is not the one the credential refers to".to_string());
+    }
+    let signature = bs58::decode(&document.signature).into_vec().map_err(|_| "signature is not base58")?;
+    let signature = Signature::from_bytes(&signature).map_err(|_| "signature is not 64 bytes")?;
+    let signed = signers
+        .iter()
+        .filter_map(|signer| PublicKey::from_bytes(signer.as_ref()).ok())
+        .any(|key| key.verify(document.content.as_bytes(), &signature).is_ok());
+    if !signed {
+        return Err("document is not signed by the credential's issuer".to_string());
+    }
+    Ok(())
+}
+
+/// The keys that sign documents for `issuer`: the issuer itself, or the current keys of the
+/// organization when the issuer is an organization's address rather than a key.
+async fn issuer_keys(rpc: &RpcClient, program_id: &Pubkey, issuer: &Pubkey) -> Result<Result<Vec<Pubkey>, String>, RpcError> {
+    if issuer.is_on_curve() {
+        return Ok(Ok(vec![*issuer]));
+    }
+    let organization = decode::<Organization>(rpc.get_account(issuer).await?.as_ref(), program_id);
+    Ok(organization.map(|organization| organization.keys).map_err(|message| format!("issuer organization: {}", message)))
+}
+
+/// Verifies a presentation the way `RespondToRequest` evaluates it, against the accounts `rpc`
+/// currently returns and the cluster's clock, and also checks the document signatures. Every
+/// problem found becomes a reason; nothing is written on-chain.
+pub async fn verify_presentation(rpc: &RpcClient, program_id: &Pubkey, presentation: &Presentation) -> Result<Verdict, VerifyError> {
+    let request_key = parse_address(&presentation.request)?;
+    let identity_key = parse_address(&presentation.identity)?;
+    let credential_keys = presentation.credentials.iter().map(|credential| parse_address(&credential.address)).collect::<Result<Vec<_>, _>>()?;
+    let now = rpc.unix_timestamp().await?;
+    let mut reasons = Vec::new();
+
+    let request = match decode::<PresentationRequest>(rpc.get_account(&request_key).await?.as_ref(), program_id) {
+        Ok(request) => request.to_core(),
+        Err(message) => {
+            reasons.push(reason(Check::Request, Some(&presentation.request), message));
+            return Ok(Verdict::new(None, reasons, now));
+        }
+    };
+    if request.is_expired(now) {
+        reasons.push(reason(Check::Request, Some(&presentation.request), format!("request expired at {}", request.expires_at)));
+        return Ok(Verdict::new(None, reasons, now));
+    }
+    if let Err(message) = decode::<Identity>(rpc.get_account(&identity_key).await?.as_ref(), program_id) {
+        reasons.push(reason(Check::Identity, Some(&presentation.identity), message));
+        return Ok(Verdict::new(None, reasons, now));
+    }
+
+    if presentation.credentials.len() != request.schemas.len() {
+        reasons.push(reason(
+            Check::Credential,
+            None,
+            format!("request needs {} credentials, got {}", request.schemas.len(), presentation.credentials.len()),
+        ));
+    }
+    let mut presented = Vec::with_capacity(request.schemas.len());
+    for ((schema, shown), key) in request.schemas.iter().zip(&presentation.credentials).zip(&credential_keys) {
+        let address = Some(shown.address.as_str());
+        let credential: Credential = match decode(rpc.get_account(key).await?.as_ref(), program_id) {
+            Ok(credential) => credential,
+            Err(message) => {
+                reasons.push(reason(Check::Credential, address, message));
+                presented.push(None);
+                continue;
+            }
+        };
+        // As on-chain, a credential is of a schema when it sits at the address derived from it
+        let (expected, _) = Credential::find_address(&credential.issuer, &credential.identity, schema, shown.nonce, program_id);
+        if expected != *key {
+            reasons.push(reason(Check::Credential, address, "credential is not of the requested schema with that nonce"));
+            presented.push(None);
+            continue;
+        }
+        if credential.identity != identity_key {
+            reasons.push(reason(Check::Credential, address, format!("credential belongs to {}", credential.identity)));
+        }
+        if !request.trusts(&credential.issuer.to_bytes()) {
+            reasons.push(reason(Check::Credential, address, format!("issuer {} is not trusted by the request", credential.issuer)));
+        }
+        let status = credential.status(now);
+        if status != idenclave_core::CredentialStatus::Valid {
+            reasons.push(reason(Check::Credential, address, format!("credential is {:?}", status)));
+        }
+        let signed = match &shown.document {
+            None => Err("no signed document presented".to_string()),
+            Some(document) => match issuer_keys(rpc, program_id, &credential.issuer).await? {
+                Ok(signers) => check_signature(document, &credential.credential_ref, &signers),
+                Err(message) => Err(message),
+            },
+        };
+        if let Err(message) = signed {
+            reasons.push(reason(Check::Signature, address, message));
+        }
+        presented.push(Some(PresentedCredential {
+            identity: credential.identity.to_bytes(),
+            issuer: credential.issuer.to_bytes(),
+            status,
+            commitment: credential.commitment,
+        }));
+    }
+
+    let mut public_inputs: Vec<Scalar> = Vec::new();
+    let mut verified = false;
+    if request.has_circuit() {
+        let circuit_key = Pubkey::new_from_array(request.circuit);
+        let circuit = decode::<Circuit>(rpc.get_account(&circuit_key).await?.as_ref(), program_id);
+        match (circuit, &presentation.proof) {
+            (Err(message), _) => reasons.push(reason(Check::Proof, Some(&circuit_key.to_string()), message)),
+            (Ok(_), None) => reasons.push(reason(Check::Proof, None, "request needs a proof")),
+            (Ok(circuit), Some(proof)) => match check_proof(&circuit, proof, &request) {
+                Ok(inputs) => {
+                    public_inputs = inputs;
+                    verified = true;
+                }
+                Err(message) => reasons.push(reason(Check::Proof, None, message)),
+            },
+        }
+        if verified {
+            let commitment = presented.first().copied().flatten().map(|credential| credential.commitment);
+            match commitment {
+                Some(commitment) if commitment == Scalar::default() => {
+                    reasons.push(reason(Check::Proof, Some(&presentation.credentials[0].address), "credential has no claims commitment"))
+                }
+                Some(commitment) if public_inputs.get(request.commitment_input()) != Some(&commitment) => {
+                    reasons.push(reason(Check::Proof, Some(&presentation.credentials[0].address), "proof is not about the credential's committed claims"))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let result = request.evaluate(&identity_key.to_bytes(), &presented, verified.then_some(public_inputs.as_slice()));
+    Ok(Verdict::new(Some(result), reasons, now))
+}
+
+/// Verifies the snarkjs proof with the registered circuit's key, through the same `alt_bn128`
+/// calls the program makes, and returns its public inputs.
+fn check_proof(circuit: &Circuit, proof: &ProofJson, request: &idenclave_core::PresentationRequest) -> Result<Vec<Scalar>, String> {
+    let signals = idenclave_groth16::PublicSignals::from_json(&proof.public_signals.to_string()).map_err(|err| err.to_string())?;
+    let proof = idenclave_groth16::Proof::from_json(&proof.proof.to_string()).map_err(|err| err.to_string())?;
+    let inputs = signals.to_program();
+    if !request.accepts_inputs(&inputs) {
+        return Err("proof is not for the request's predicate inputs".to_string());
+    }
+    idenclave_core::groth16::verify::<AltBn128>(&circuit.verifying_key, &proof.to_program(), &inputs).map_err(|err| err.to_string())?;
+    Ok(inputs)
+}
@@ -0,0 +1,81 @@
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar;
+use std::fmt;
+use std::str::FromStr;
+
+/// An account as the RPC node returned it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+/// A failed JSON-RPC call: unreachable node, error response or unexpected reply.
+#[derive(Debug)]
+pub struct RpcError(pub String);
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rpc: {}", self.0)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// Reads accounts over Solana's JSON-RPC API, from a cluster or a local `solana-test-validator`.
+/// Reads use the `confirmed` commitment.
+#[derive(Clone)]
+pub struct RpcClient {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Self {
+        RpcClient { url: url.to_string(), http: reqwest::Client::new() }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response: Value = self
+            .http
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|err| RpcError(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| RpcError(err.to_string()))?;
+        if let Some(error) = response.get("error") {
+            return Err(RpcError(format!("{} failed: {}", method, error)));
+        }
+        response.get("result").cloned().ok_or_else(|| RpcError(format!("{} returned no result", method)))
+    }
+
+    /// `None` when the account does not exist.
+    pub async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, RpcError> {
+        let result = self
+            .call("getAccountInfo", json!([address.to_string(), { "encoding": "base64", "commitment": "confirmed" }]))
+            .await?;
+        let value = &result["value"];
+        if value.is_null() {
+            return Ok(None);
+        }
+        let malformed = || RpcError(format!("unexpected getAccountInfo reply for {}", address));
+        let owner = value["owner"].as_str().and_then(|owner| Pubkey::from_str(owner).ok()).ok_or_else(malformed)?;
+        let data = value["data"][0].as_str().ok_or_else(malformed)?;
+        let data = base64::engine::general_purpose::STANDARD.decode(data).map_err(|_| malformed())?;
+        Ok(Some(Account { owner, data }))
+    }
+
+    /// The cluster's `Clock::unix_timestamp`, which the program evaluates credential status against.
+    pub async fn unix_timestamp(&self) -> Result<u64, RpcError> {
+        let clock = self.get_account(&sysvar::clock::ID).await?.ok_or_else(|| RpcError("clock sysvar not found".to_string()))?;
+        // Clock: slot, epoch_start_timestamp, epoch, leader_schedule_epoch, unix_timestamp (8 bytes each)
+        let timestamp = clock.data.get(32..40).ok_or_else(|| RpcError("clock sysvar is too short".to_string()))?;
+        Ok(idenclave_core::unix_now(i64::from_le_bytes(timestamp.try_into().unwrap())))
+    }
+}
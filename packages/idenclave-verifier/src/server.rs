@@ -0,0 +1,63 @@
+use crate::presentation::{decode, parse_address, verify_presentation, Presentation, VerifyError};
+use crate::rpc::RpcClient;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use idenclave::credential::Credential;
+use serde_json::json;
+use solana_program::pubkey::Pubkey;
+use std::sync::Arc;
+
+/// What the handlers share: where accounts come from and which program owns them.
+pub struct Service {
+    pub rpc: RpcClient,
+    pub program_id: Pubkey,
+}
+
+/// Routes:
+/// - `GET /health`
+/// - `GET /credentials/:address`: the credential's fields and status now
+/// - `POST /presentations/verify`: a `Presentation` in, a `Verdict` out
+pub fn router(service: Arc<Service>) -> Router {
+    Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .route("/credentials/:address", get(credential_status))
+        .route("/presentations/verify", post(verify))
+        .with_state(service)
+}
+
+impl IntoResponse for VerifyError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            VerifyError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            VerifyError::Rpc(err) => (StatusCode::BAD_GATEWAY, err.to_string()),
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+async fn verify(State(service): State<Arc<Service>>, Json(presentation): Json<Presentation>) -> Result<Response, VerifyError> {
+    let verdict = verify_presentation(&service.rpc, &service.program_id, &presentation).await?;
+    Ok(Json(verdict).into_response())
+}
+
+async fn credential_status(State(service): State<Arc<Service>>, Path(address): Path<String>) -> Result<Response, VerifyError> {
+    let key = parse_address(&address)?;
+    let now = service.rpc.unix_timestamp().await?;
+    let credential: Credential = match decode(service.rpc.get_account(&key).await?.as_ref(), &service.program_id) {
+        Ok(credential) => credential,
+        Err(message) => return Ok((StatusCode::NOT_FOUND, Json(json!({ "error": message }))).into_response()),
+    };
+    Ok(Json(json!({
+        "address": address,
+        "identity": credential.identity.to_string(),
+        "issuer": credential.issuer.to_string(),
+        "issued_at": credential.issued_at,
+        "expires_at": credential.expires_at,
+        "status": format!("{:?}", credential.status(now)),
+        "checked_at": now,
+    }))
+    .into_response())
+}
@@ -0,0 +1,52 @@
+use idenclave_core::PresentationResult;
+use serde::Serialize;
+
+/// Which part of a presentation a reason is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// The presentation request account and its expiry
+    Request,
+    /// The holder's identity account
+    Identity,
+    /// A presented credential account: address, holder, issuer and status
+    Credential,
+    /// The credential document: that it is the one the credential refers to and the issuer signed it
+    Signature,
+    /// The Groth16 proof and its commitment signal
+    Proof,
+}
+
+/// One reason a presentation does not pass.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Reason {
+    pub check: Check,
+    /// The account the reason is about, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    pub message: String,
+}
+
+/// Outcome of verifying a presentation.
+///
+/// `result` is what `RespondToRequest` would record for the presentation at `checked_at`
+/// (`None` when the request could not be evaluated at all). Document signatures are checked on
+/// top of that, so `passed` needs both a `Passed` result and no reasons.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Verdict {
+    pub passed: bool,
+    pub result: Option<String>,
+    pub reasons: Vec<Reason>,
+    pub checked_at: u64,
+}
+
+impl Verdict {
+    pub fn new(result: Option<PresentationResult>, reasons: Vec<Reason>, checked_at: u64) -> Self {
+        Verdict {
+            passed: result == Some(PresentationResult::Passed) && reasons.is_empty(),
+            result: result.map(|result| format!("{:?}", result)),
+            reasons,
+            checked_at,
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::Engine;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+use idenclave_core::{AccountLayout, Scalar};
+use idenclave_verifier::presentation::SignedDocument;
+use idenclave_verifier::{router, verify_presentation, Check, Presentation, RpcClient, Service};
+use serde_json::{json, Value};
+use solana_program::hash::hash;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+const PROOF: &str = include_str!("../../idenclave-groth16/tests/fixtures/multiplier/proof.json");
+const VERIFYING_KEY: &str = include_str!("../../idenclave-groth16/tests/fixtures/multiplier/verification_key.json");
+const NOW: u64 = 1_700_000_000;
+
+type Accounts = Arc<Mutex<HashMap<Pubkey, (Pubkey, Vec<u8>)>>>;
+
+/// Answers `getAccountInfo` from `accounts`, standing in for a validator's RPC.
+async fn rpc_stand_in(State(accounts): State<Accounts>, Json(request): Json<Value>) -> Json<Value> {
+    assert_eq!(request["method"], "getAccountInfo");
+    let address = Pubkey::from_str(request["params"][0].as_str().unwrap()).unwrap();
+    let value = accounts.lock().unwrap().get(&address).map(|(owner, data)| {
+        json!({
+            "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+            "executable": false,
+            "lamports": 1_000_000,
+            "owner": owner.to_string(),
+            "rentEpoch": 0,
+        })
+    });
+    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "context": { "slot": 1 }, "value": value } }))
+}
+
+async fn serve(app: Router) -> String {
+    let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(app.into_make_service());
+    let url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    url
+}
+
+fn packed<T: AccountLayout>(account: &T) -> Vec<u8> {
+    let mut data = vec![0u8; T::ACCOUNT_LEN];
+    account.pack_account(&mut data);
+    data
+}
+
+fn scalar(value: u8) -> Scalar {
+    let mut bytes = [0u8; 32];
+    bytes[31] = value;
+    bytes
+}
+
+fn keypair(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+    Keypair { public: PublicKey::from(&secret), secret }
+}
+
+struct Fixture {
+    accounts: Accounts,
+    rpc: RpcClient,
+    program_id: Pubkey,
+    issuer: Keypair,
+    request: Pubkey,
+    request_state: idenclave_core::PresentationRequest,
+    identity: Pubkey,
+    credential: Pubkey,
+    credential_state: idenclave_core::Credential,
+}
+
+impl Fixture {
+    /// A request for one credential of schema [4; 32] from `issuer` with a proof for the
+    /// multiplier circuit, whose only public signal is the commitment, and a holder with a
+    /// matching credential committed to 33 that refers to the document `{"age":25}`.
+    async fn new() -> Self {
+        let program_id = Pubkey::new_unique();
+        let issuer = keypair(7);
+        let issuer_key = Pubkey::new_from_array(issuer.public.to_bytes());
+        let (identity, request, circuit) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let schema = [4u8; 32];
+        let (credential, _) = idenclave::credential::Credential::find_address(&issuer_key, &identity, &schema, 2, &program_id);
+
+        let mut clock = vec![0u8; 40];
+        clock[32..].copy_from_slice(&(NOW as i64).to_le_bytes());
+        let credential_state = idenclave_core::Credential {
+            is_initialized: true,
+            identity: identity.to_bytes(),
+            issuer: issuer_key.to_bytes(),
+            credential_ref: hash(b"{\"age\":25}").to_bytes(),
+            issued_at: NOW - 100,
+            expires_at: NOW + 100,
+            commitment: scalar(33),
+            ..Default::default()
+        };
+        let request_state = idenclave_core::PresentationRequest {
+            is_initialized: true,
+            verifier: Pubkey::new_unique().to_bytes(),
+            nonce: 0,
+            expires_at: NOW + 100,
+            schemas: vec![schema],
+            trusted_issuers: vec![issuer_key.to_bytes()],
+            circuit: circuit.to_bytes(),
+            predicate_inputs: vec![],
+        };
+        let accounts: HashMap<Pubkey, (Pubkey, Vec<u8>)> = [
+            (sysvar::clock::ID, (sysvar::ID, clock)),
+            (identity, (program_id, packed(&idenclave_core::Identity { is_initialized: true, ..Default::default() }))),
+            (credential, (program_id, packed(&credential_state))),
+            (request, (program_id, packed(&request_state))),
+            (
+                circuit,
+                (
+                    program_id,
+                    packed(&idenclave_core::Circuit {
+                        is_initialized: true,
+                        authority: Pubkey::new_unique().to_bytes(),
+                        verifying_key: idenclave_groth16::VerifyingKey::from_json(VERIFYING_KEY).unwrap().to_program(),
+                    }),
+                ),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let accounts = Arc::new(Mutex::new(accounts));
+        let url = serve(Router::new().route("/", post(rpc_stand_in)).with_state(accounts.clone())).await;
+        Fixture { accounts, rpc: RpcClient::new(&url), program_id, issuer, request, request_state, identity, credential, credential_state }
+    }
+
+    fn presentation(&self, document: &str, public_signals: Value) -> Presentation {
+        let signature = bs58::encode(self.issuer.sign(b"{\"age\":25}").to_bytes()).into_string();
+        serde_json::from_value(json!({
+            "request": self.request.to_string(),
+            "identity": self.identity.to_string(),
+            "credentials": [{
+                "address": self.credential.to_string(),
+                "nonce": 2,
+                "document": { "content": document, "signature": signature },
+            }],
+            "proof": { "proof": serde_json::from_str::<Value>(PROOF).unwrap(), "public_signals": public_signals },
+        }))
+        .unwrap()
+    }
+
+    fn document(signer: &Keypair, content: &str) -> SignedDocument {
+        SignedDocument { content: content.to_string(), signature: bs58::encode(signer.sign(content.as_bytes()).to_bytes()).into_string() }
+    }
+
+    fn set_credential(&self, credential: &idenclave_core::Credential) {
+        self.set_account(self.credential, packed(credential));
+    }
+
+    fn set_account(&self, address: Pubkey, data: Vec<u8>) {
+        self.accounts.lock().unwrap().insert(address, (self.program_id, data));
+    }
+}
+
+#[tokio::test]
+async fn test_verify_presentation() {
+    let fixture = Fixture::new().await;
+    let verify = |presentation: Presentation| {
+        let (rpc, program_id) = (fixture.rpc.clone(), fixture.program_id);
+        async move { verify_presentation(&rpc, &program_id, &presentation).await.unwrap() }
+    };
+
+    let verdict = verify(fixture.presentation("{\"age\":25}", json!(["33"]))).await;
+    assert!(verdict.passed, "{:?}", verdict.reasons);
+    assert_eq!((verdict.result.as_deref(), verdict.checked_at), (Some("Passed"), NOW));
+
+    // A document other than the one the credential refers to fails on its own, whatever the
+    // program would record
+    let verdict = verify(fixture.presentation("{\"age\":52}", json!(["33"]))).await;
+    assert!(!verdict.passed);
+    assert_eq!(verdict.result.as_deref(), Some("Passed"));
+    assert_eq!(verdict.reasons.len(), 1);
+    assert_eq!((verdict.reasons[0].check, verdict.reasons[0].account.clone()), (Check::Signature, Some(fixture.credential.to_string())));
+    assert_eq!(verdict.reasons[0].message, "document is not the one the credential refers to");
+
+    // So does the right document signed by someone else, or no document at all
+    let stranger = keypair(8);
+    let mut presentation = fixture.presentation("{\"age\":25}", json!(["33"]));
+    presentation.credentials[0].document = Some(Fixture::document(&stranger, "{\"age\":25}"));
+    let verdict = verify(presentation).await;
+    assert_eq!((verdict.reasons.len(), verdict.reasons[0].check), (1, Check::Signature));
+    assert_eq!(verdict.reasons[0].message, "document is not signed by the credential's issuer");
+    let mut presentation = fixture.presentation("{\"age\":25}", json!(["33"]));
+    presentation.credentials[0].document = None;
+    let verdict = verify(presentation).await;
+    assert!(!verdict.passed);
+    assert_eq!((verdict.reasons.len(), verdict.reasons[0].message.as_str()), (1, "no signed document presented"));
+
+    let verdict = verify(fixture.presentation("{\"age\":25}", json!(["34"]))).await;
+    assert_eq!(verdict.result.as_deref(), Some("InvalidProof"));
+    assert_eq!(verdict.reasons[0].check, Check::Proof);
+
+    // A valid proof about other claims does not bind to the credential
+    fixture.set_credential(&idenclave_core::Credential { commitment: scalar(34), ..fixture.credential_state.clone() });
+    let verdict = verify(fixture.presentation("{\"age\":25}", json!(["33"]))).await;
+    assert_eq!(verdict.result.as_deref(), Some("CommitmentMismatch"));
+    assert_eq!(verdict.reasons[0].message, "proof is not about the credential's committed claims");
+
+    // Status comes from the account as it is now
+    fixture.set_credential(&idenclave_core::Credential { revoked: true, ..fixture.credential_state.clone() });
+    let verdict = verify(fixture.presentation("{\"age\":25}", json!(["33"]))).await;
+    assert_eq!(verdict.result.as_deref(), Some("InvalidCredential"));
+    assert_eq!((verdict.reasons[0].check, verdict.reasons[0].message.as_str()), (Check::Credential, "credential is Revoked"));
+    fixture.set_credential(&fixture.credential_state);
+
+    // The nonce must derive the credential's address for the requested schema
+    let mut presentation = fixture.presentation("{\"age\":25}", json!(["33"]));
+    presentation.credentials[0].nonce = 3;
+    let verdict = verify(presentation).await;
+    assert_eq!(verdict.result.as_deref(), Some("MissingCredential"));
+
+    // Requests that cannot be evaluated get no result
+    let mut presentation = fixture.presentation("{\"age\":25}", json!(["33"]));
+    presentation.request = fixture.identity.to_string();
+    let verdict = verify(presentation).await;
+    assert_eq!((verdict.passed, verdict.result, verdict.reasons[0].check), (false, None, Check::Request));
+}
+
+#[tokio::test]
+async fn test_organization_documents() {
+    let fixture = Fixture::new().await;
+    let verify = |presentation: Presentation| {
+        let (rpc, program_id) = (fixture.rpc.clone(), fixture.program_id);
+        async move { verify_presentation(&rpc, &program_id, &presentation).await.unwrap() }
+    };
+
+    // The same credential, issued by an organization whose keys include the fixture's issuer
+    let organization = idenclave::Organization::find_address(&Pubkey::new_unique(), &fixture.program_id).0;
+    let (credential, _) = idenclave::credential::Credential::find_address(&organization, &fixture.identity, &[4; 32], 2, &fixture.program_id);
+    fixture.set_account(credential, packed(&idenclave_core::Credential { issuer: organization.to_bytes(), ..fixture.credential_state.clone() }));
+    fixture.set_account(
+        fixture.request,
+        packed(&idenclave_core::PresentationRequest { trusted_issuers: vec![organization.to_bytes()], ..fixture.request_state.clone() }),
+    );
+    let mut presentation = fixture.presentation("{\"age\":25}", json!(["33"]));
+    presentation.credentials[0].address = credential.to_string();
+
+    // The organization's address is not a key; without its account nothing can be checked
+    let verdict = verify(presentation.clone()).await;
+    assert_eq!((verdict.reasons.len(), verdict.reasons[0].check), (1, Check::Signature));
+    assert_eq!(verdict.reasons[0].message, "issuer organization: account does not exist");
+
+    let mut state = idenclave_core::Organization { is_initialized: true, ..Default::default() };
+    state.add_key(&keypair(9).public.to_bytes()).unwrap();
+    state.add_key(&fixture.issuer.public.to_bytes()).unwrap();
+    fixture.set_account(organization, packed(&state));
+    let verdict = verify(presentation.clone()).await;
+    assert!(verdict.passed, "{:?}", verdict.reasons);
+
+    // A key the organization has removed no longer signs for it
+    state.remove_key(&fixture.issuer.public.to_bytes()).unwrap();
+    fixture.set_account(organization, packed(&state));
+    let verdict = verify(presentation).await;
+    assert_eq!(verdict.reasons[0].message, "document is not signed by the credential's issuer");
+}
+
+#[tokio::test]
+async fn test_http_endpoints() {
+    let fixture = Fixture::new().await;
+    let url = serve(router(Arc::new(Service { rpc: fixture.rpc.clone(), program_id: fixture.program_id }))).await;
+    let http = reqwest::Client::new();
+
+    let body = json!({
+        "request": fixture.request.to_string(),
+        "identity": fixture.identity.to_string(),
+        "credentials": [{
+            "address": fixture.credential.to_string(),
+            "nonce": 2,
+            "document": { "content": "{\"age\":25}", "signature": Fixture::document(&fixture.issuer, "{\"age\":25}").signature },
+        }],
+        "proof": { "proof": serde_json::from_str::<Value>(PROOF).unwrap(), "public_signals": ["33"] },
+    });
+    let response = http.post(format!("{}/presentations/verify", url)).json(&body).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let verdict: Value = response.json().await.unwrap();
+    assert_eq!((verdict["passed"].clone(), verdict["result"].clone()), (json!(true), json!("Passed")));
+
+    let mut bad = body.clone();
+    bad["identity"] = json!("not-an-address");
+    let response = http.post(format!("{}/presentations/verify", url)).json(&bad).send().await.unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = http.get(format!("{}/credentials/{}", url, fixture.credential)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let status: Value = response.json().await.unwrap();
+    assert_eq!((status["status"].clone(), status["identity"].clone()), (json!("Valid"), json!(fixture.identity.to_string())));
+    let response = http.get(format!("{}/credentials/{}", url, Pubkey::new_unique())).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+}
@@ -0,0 +1,69 @@
+use idenclave_core::AccountLayout;
+use solana_program::{
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    program_error::ProgramError,
+};
+
+/// An accreditor's statement that `issuer` may issue credentials of `schema`, stored in the PDA
+/// `[b"accreditation", accreditor, issuer, schema]`. Chains of these lead from a credential's
+/// issuer to a root of trust the verifier picks; see `idenclave_core::evaluate_chain`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Accreditation {
+    pub is_initialized: bool,
+    pub revoked: bool,
+    pub accreditor: Pubkey,
+    pub issuer: Pubkey,
+    pub schema: [u8; 32],
+    pub expires_at: u64,
+    pub delegation_depth: u8, // Levels below the issuer that may still be accredited for the schema
+}
+
+impl Accreditation {
+    pub const SEED: &'static [u8] = idenclave_core::Accreditation::SEED;
+
+    pub fn find_address(accreditor: &Pubkey, issuer: &Pubkey, schema: &[u8; 32], program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, accreditor.as_ref(), issuer.as_ref(), schema], program_id)
+    }
+
+    pub fn to_core(&self) -> idenclave_core::Accreditation {
+        idenclave_core::Accreditation {
+            is_initialized: self.is_initialized,
+            revoked: self.revoked,
+            accreditor: self.accreditor.to_bytes(),
+            issuer: self.issuer.to_bytes(),
+            schema: self.schema,
+            expires_at: self.expires_at,
+            delegation_depth: self.delegation_depth,
+        }
+    }
+}
+
+impl IsInitialized for Accreditation {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Accreditation {}
+
+impl Pack for Accreditation {
+    const LEN: usize = idenclave_core::Accreditation::ACCOUNT_LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        self.to_core().pack_account(dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let accreditation = idenclave_core::Accreditation::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Accreditation {
+            is_initialized: accreditation.is_initialized,
+            revoked: accreditation.revoked,
+            accreditor: Pubkey::new_from_array(accreditation.accreditor),
+            issuer: Pubkey::new_from_array(accreditation.issuer),
+            schema: accreditation.schema,
+            expires_at: accreditation.expires_at,
+            delegation_depth: accreditation.delegation_depth,
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use idenclave_core::merkle::{MerkleHasher, Node};
+use idenclave_core::{CompressedCredential, CredentialTreeMirror, TreeError};
+use solana_program::{hash::hashv, msg, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::IdentityEvent;
+
+/// SHA-256 through the `sol_sha256` syscall, the hash of every credential tree.
+pub struct Sha256Hasher;
+
+impl MerkleHasher for Sha256Hasher {
+    fn hashv(parts: &[&[u8]]) -> Node {
+        hashv(parts).to_bytes()
+    }
+}
+
+/// Logs a tree error and maps it onto the error this program reports for it.
+pub(crate) fn tree_error(error: TreeError) -> ProgramError {
+    msg!("Credential tree: {}", error);
+    match error {
+        TreeError::NotInitialized => ProgramError::UninitializedAccount,
+        TreeError::AlreadyInitialized => ProgramError::AccountAlreadyInitialized,
+        TreeError::InvalidParameters => ProgramError::InvalidAccountData,
+        TreeError::TreeFull
+        | TreeError::IndexOutOfRange
+        | TreeError::RootNotFound
+        | TreeError::InvalidProof
+        | TreeError::LeafChanged
+        | TreeError::OutOfSync => ProgramError::InvalidArgument,
+    }
+}
+
+/// A compressed credential as presented to `RevokeCompressedCredential` and
+/// `VerifyCompressedCredential`: the full credential, its leaf index and a proof against `root`.
+///
+/// Encoded as root (32) + index (u32 LE) + credential (`CompressedCredential::LEN`) + proof
+/// (one 32-byte node per tree level, leaf level first).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressedCredentialProof {
+    pub root: Node,
+    pub index: u32,
+    pub credential: CompressedCredential,
+    pub proof: Vec<Node>,
+}
+
+impl CompressedCredentialProof {
+    const FIXED_LEN: usize = 32 + 4 + CompressedCredential::LEN;
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::FIXED_LEN + self.proof.len() * 32);
+        buf.extend_from_slice(&self.root);
+        buf.extend_from_slice(&self.index.to_le_bytes());
+        buf.extend_from_slice(&self.credential.pack());
+        for node in &self.proof {
+            buf.extend_from_slice(node);
+        }
+        buf
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() < Self::FIXED_LEN || !(input.len() - Self::FIXED_LEN).is_multiple_of(32) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let root = <[u8; 32]>::try_from(&input[..32]).unwrap();
+        let index = u32::from_le_bytes(input[32..36].try_into().unwrap());
+        let credential = CompressedCredential::unpack(&input[36..]).map_err(|_| ProgramError::InvalidInstructionData)?;
+        let proof = input[Self::FIXED_LEN..].chunks_exact(32).map(|node| <[u8; 32]>::try_from(node).unwrap()).collect();
+        Ok(CompressedCredentialProof { root, index, credential, proof })
+    }
+}
+
+/// Feeds one of the program's events to an off-chain mirror of a credential tree. Events of
+/// other trees, and events that are not about compressed credentials, are ignored.
+pub fn apply_event(mirror: &mut CredentialTreeMirror<Sha256Hasher>, tree: &Pubkey, event: &IdentityEvent) -> Result<(), TreeError> {
+    match event {
+        IdentityEvent::CompressedCredentialIssued { tree: t, index, root, credential } if t == tree => {
+            mirror.issued(*index, credential.clone(), root)
+        }
+        IdentityEvent::CompressedCredentialRevoked { tree: t, index, root } if t == tree => mirror.revoked(*index, root),
+        _ => Ok(()),
+    }
+}
@@ -1,3 +1,4 @@
+use idenclave_core::AccountLayout;
 use solana_program::{
     program_pack::{Pack, Sealed},
     pubkey::Pubkey,
@@ -7,7 +8,7 @@ use solana_program::{
 /// A verifiable credential issued to an identity.
 use solana_program::program_pack::IsInitialized;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Credential {
     pub expires_at: u64,
     pub is_initialized: bool,
@@ -16,20 +17,58 @@ pub struct Credential {
     pub issuer: Pubkey,        // The issuer's public key
     pub credential_ref: [u8; 32], // Off-chain reference (e.g. IPFS CID hash or similar, for MVP use 32 bytes)
     pub issued_at: u64,        // Unix timestamp
+    pub suspended: bool,       // Temporarily suspended by the issuer (reversible, unlike `revoked`)
+    pub previous: Pubkey,      // Credential this one supersedes (default if it is the first version)
+    pub superseded_by: Pubkey, // Newer version of this credential (default if this is the latest)
+    pub pending: bool,         // Offered by the issuer but not yet accepted by the holder
+    pub claims_root: [u8; 32], // Merkle root of the salted claims (see `idenclave_core::claims`), all zeros for none
+    pub commitment: [u8; 32],  // Poseidon commitment proofs bind to (see `idenclave_core::commitment`), all zeros for none
     // Add more fields as needed (e.g., type, expiration, status)
 }
 
-impl Default for Credential {
-    fn default() -> Self {
-        Self {
-            expires_at: 0,
-            is_initialized: false,
-            revoked: false,
-            identity: Pubkey::default(),
-            issuer: Pubkey::default(),
-            credential_ref: [0u8; 32],
-            issued_at: 0,
-        }
+// Status values and the rules for moving between them live in idenclave-core,
+// shared with the Anchor program.
+pub use idenclave_core::{CredentialState, CredentialStatus};
+
+impl Credential {
+    pub const SEED: &'static [u8] = idenclave_core::Credential::SEED;
+
+    /// Address of the credential `issuer` issues to `identity` under `schema` and `nonce`. Every
+    /// issuance instruction creates the credential there, and presentations rely on it to tell a
+    /// credential's schema. For a credential issued on behalf of an organization, `issuer` is the
+    /// organization.
+    pub fn find_address(issuer: &Pubkey, identity: &Pubkey, schema: &[u8; 32], nonce: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEED, issuer.as_ref(), identity.as_ref(), schema, &nonce.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Evaluates the credential status at the given unix timestamp.
+    pub fn status(&self, now: u64) -> CredentialStatus {
+        CredentialState::status(self, now)
+    }
+}
+
+impl CredentialState for Credential {
+    fn is_revoked(&self) -> bool {
+        self.revoked
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    fn is_superseded(&self) -> bool {
+        self.superseded_by != Pubkey::default()
+    }
+
+    fn expires_at(&self) -> u64 {
+        self.expires_at
     }
 }
 
@@ -42,28 +81,43 @@ impl IsInitialized for Credential {
 impl Sealed for Credential {}
 
 impl Pack for Credential {
-    // LEN: is_initialized (1) + revoked (1) + identity (32) + issuer (32) + ref (32) + issued_at (8)
-    const LEN: usize = 1 + 1 + 32 + 32 + 32 + 8;
+    const LEN: usize = idenclave_core::Credential::ACCOUNT_LEN;
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        dst[0] = self.is_initialized as u8;
-        dst[1] = self.revoked as u8;
-        dst[2..34].copy_from_slice(self.identity.as_ref());
-        dst[34..66].copy_from_slice(self.issuer.as_ref());
-        dst[66..98].copy_from_slice(&self.credential_ref);
-        dst[98..106].copy_from_slice(&self.issued_at.to_le_bytes());
+        idenclave_core::Credential {
+            is_initialized: self.is_initialized,
+            revoked: self.revoked,
+            identity: self.identity.to_bytes(),
+            issuer: self.issuer.to_bytes(),
+            credential_ref: self.credential_ref,
+            issued_at: self.issued_at,
+            expires_at: self.expires_at,
+            suspended: self.suspended,
+            previous: self.previous.to_bytes(),
+            superseded_by: self.superseded_by.to_bytes(),
+            pending: self.pending,
+            claims_root: self.claims_root,
+            commitment: self.commitment,
+        }
+        .pack_account(dst);
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        if src.len() < Self::LEN {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let is_initialized = src[0] != 0;
-        let revoked = src[1] != 0;
-        let identity = Pubkey::new_from_array(<[u8; 32]>::try_from(&src[2..34]).map_err(|_| ProgramError::InvalidAccountData)?);
-        let issuer = Pubkey::new_from_array(<[u8; 32]>::try_from(&src[34..66]).map_err(|_| ProgramError::InvalidAccountData)?);
-        let credential_ref = <[u8; 32]>::try_from(&src[66..98]).map_err(|_| ProgramError::InvalidAccountData)?;
-        let issued_at = u64::from_le_bytes(<[u8; 8]>::try_from(&src[98..106]).map_err(|_| ProgramError::InvalidAccountData)?);
-        Ok(Credential { expires_at: 0, is_initialized, revoked, identity, issuer, credential_ref, issued_at })
+        let cred = idenclave_core::Credential::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Credential {
+            expires_at: cred.expires_at,
+            is_initialized: cred.is_initialized,
+            revoked: cred.revoked,
+            identity: Pubkey::new_from_array(cred.identity),
+            issuer: Pubkey::new_from_array(cred.issuer),
+            credential_ref: cred.credential_ref,
+            issued_at: cred.issued_at,
+            suspended: cred.suspended,
+            previous: Pubkey::new_from_array(cred.previous),
+            superseded_by: Pubkey::new_from_array(cred.superseded_by),
+            pending: cred.pending,
+            claims_root: cred.claims_root,
+            commitment: cred.commitment,
+        })
     }
 }
@@ -0,0 +1,261 @@
+use idenclave_core::CompressedCredential;
+use solana_program::{log::sol_log_data, program_error::ProgramError, pubkey::Pubkey};
+
+/// Events emitted by the program through `sol_log_data` ("Program data: ..." log lines),
+/// so off-chain consumers can follow state transitions without polling accounts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IdentityEvent {
+    CredentialIssued { credential: Pubkey, identity: Pubkey, issuer: Pubkey }, // 0
+    CredentialRevoked { credential: Pubkey, issuer: Pubkey }, // 1
+    CredentialSuspended { credential: Pubkey, issuer: Pubkey }, // 2
+    CredentialReinstated { credential: Pubkey, issuer: Pubkey }, // 3
+    CredentialRenewed { credential: Pubkey, issuer: Pubkey, expires_at: u64 }, // 4
+    CredentialSuperseded { credential: Pubkey, new_credential: Pubkey, issuer: Pubkey }, // 5
+    CredentialOffered { credential: Pubkey, identity: Pubkey, issuer: Pubkey }, // 6
+    CredentialAccepted { credential: Pubkey, identity: Pubkey }, // 7
+    CredentialOfferRejected { credential: Pubkey, identity: Pubkey }, // 8
+    CredentialOfferCancelled { credential: Pubkey, issuer: Pubkey }, // 9
+    CredentialClosed { credential: Pubkey, closed_by: Pubkey }, // 10
+    CredentialReissued { credential: Pubkey, issuer: Pubkey, previous_ref: [u8; 32], credential_ref: [u8; 32], expires_at: u64 }, // 11
+    CompressedCredentialIssued { tree: Pubkey, index: u32, root: [u8; 32], credential: CompressedCredential }, // 12
+    CompressedCredentialRevoked { tree: Pubkey, index: u32, root: [u8; 32] }, // 13
+    OrganizationKeyAdded { organization: Pubkey, key: Pubkey }, // 14
+    OrganizationKeyRemoved { organization: Pubkey, key: Pubkey }, // 15
+    OrganizationKeyRotated { organization: Pubkey, old_key: Pubkey, new_key: Pubkey }, // 16
+    AccreditationGranted { accreditation: Pubkey, accreditor: Pubkey, issuer: Pubkey, schema: [u8; 32], expires_at: u64, delegation_depth: u8 }, // 17
+    AccreditationRevoked { accreditation: Pubkey, accreditor: Pubkey, issuer: Pubkey }, // 18
+    PresentationRequested { request: Pubkey, verifier: Pubkey }, // 19
+    PresentationResponded { request: Pubkey, identity: Pubkey, result: u8 }, // 20
+    VerificationRecorded { receipt: Pubkey, verifier: Pubkey, credential: Pubkey, result: u8 }, // 21
+    VerificationReceiptClosed { receipt: Pubkey, verifier: Pubkey }, // 22
+    ClaimsCommitted { credential: Pubkey, issuer: Pubkey, commitment: [u8; 32] }, // 23
+}
+
+impl IdentityEvent {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(1 + 32 * 3);
+        match self {
+            Self::CredentialIssued { credential, identity, issuer } => {
+                buf.push(0);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(identity.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+            }
+            Self::CredentialRevoked { credential, issuer } => {
+                buf.push(1);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+            }
+            Self::CredentialSuspended { credential, issuer } => {
+                buf.push(2);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+            }
+            Self::CredentialReinstated { credential, issuer } => {
+                buf.push(3);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+            }
+            Self::CredentialRenewed { credential, issuer, expires_at } => {
+                buf.push(4);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+                buf.extend_from_slice(&expires_at.to_le_bytes());
+            }
+            Self::CredentialSuperseded { credential, new_credential, issuer } => {
+                buf.push(5);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(new_credential.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+            }
+            Self::CredentialOffered { credential, identity, issuer } => {
+                buf.push(6);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(identity.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+            }
+            Self::CredentialAccepted { credential, identity } => {
+                buf.push(7);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(identity.as_ref());
+            }
+            Self::CredentialOfferRejected { credential, identity } => {
+                buf.push(8);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(identity.as_ref());
+            }
+            Self::CredentialOfferCancelled { credential, issuer } => {
+                buf.push(9);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+            }
+            Self::CredentialClosed { credential, closed_by } => {
+                buf.push(10);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(closed_by.as_ref());
+            }
+            Self::CredentialReissued { credential, issuer, previous_ref, credential_ref, expires_at } => {
+                buf.push(11);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+                buf.extend_from_slice(previous_ref);
+                buf.extend_from_slice(credential_ref);
+                buf.extend_from_slice(&expires_at.to_le_bytes());
+            }
+            Self::CompressedCredentialIssued { tree, index, root, credential } => {
+                buf.push(12);
+                buf.extend_from_slice(tree.as_ref());
+                buf.extend_from_slice(root);
+                buf.extend_from_slice(&index.to_le_bytes());
+                buf.extend_from_slice(&credential.pack());
+            }
+            Self::CompressedCredentialRevoked { tree, index, root } => {
+                buf.push(13);
+                buf.extend_from_slice(tree.as_ref());
+                buf.extend_from_slice(root);
+                buf.extend_from_slice(&index.to_le_bytes());
+            }
+            Self::OrganizationKeyAdded { organization, key } => {
+                buf.push(14);
+                buf.extend_from_slice(organization.as_ref());
+                buf.extend_from_slice(key.as_ref());
+            }
+            Self::OrganizationKeyRemoved { organization, key } => {
+                buf.push(15);
+                buf.extend_from_slice(organization.as_ref());
+                buf.extend_from_slice(key.as_ref());
+            }
+            Self::OrganizationKeyRotated { organization, old_key, new_key } => {
+                buf.push(16);
+                buf.extend_from_slice(organization.as_ref());
+                buf.extend_from_slice(old_key.as_ref());
+                buf.extend_from_slice(new_key.as_ref());
+            }
+            Self::AccreditationGranted { accreditation, accreditor, issuer, schema, expires_at, delegation_depth } => {
+                buf.push(17);
+                buf.extend_from_slice(accreditation.as_ref());
+                buf.extend_from_slice(accreditor.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+                buf.extend_from_slice(schema);
+                buf.extend_from_slice(&expires_at.to_le_bytes());
+                buf.push(*delegation_depth);
+            }
+            Self::AccreditationRevoked { accreditation, accreditor, issuer } => {
+                buf.push(18);
+                buf.extend_from_slice(accreditation.as_ref());
+                buf.extend_from_slice(accreditor.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+            }
+            Self::PresentationRequested { request, verifier } => {
+                buf.push(19);
+                buf.extend_from_slice(request.as_ref());
+                buf.extend_from_slice(verifier.as_ref());
+            }
+            Self::PresentationResponded { request, identity, result } => {
+                buf.push(20);
+                buf.extend_from_slice(request.as_ref());
+                buf.extend_from_slice(identity.as_ref());
+                buf.push(*result);
+            }
+            Self::VerificationRecorded { receipt, verifier, credential, result } => {
+                buf.push(21);
+                buf.extend_from_slice(receipt.as_ref());
+                buf.extend_from_slice(verifier.as_ref());
+                buf.extend_from_slice(credential.as_ref());
+                buf.push(*result);
+            }
+            Self::VerificationReceiptClosed { receipt, verifier } => {
+                buf.push(22);
+                buf.extend_from_slice(receipt.as_ref());
+                buf.extend_from_slice(verifier.as_ref());
+            }
+            Self::ClaimsCommitted { credential, issuer, commitment } => {
+                buf.push(23);
+                buf.extend_from_slice(credential.as_ref());
+                buf.extend_from_slice(issuer.as_ref());
+                buf.extend_from_slice(commitment);
+            }
+        }
+        buf
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+        let key = |i: usize| -> Result<Pubkey, ProgramError> {
+            rest.get(i * 32..(i + 1) * 32)
+                .and_then(|b| <[u8; 32]>::try_from(b).ok())
+                .map(Pubkey::new_from_array)
+                .ok_or(ProgramError::InvalidInstructionData)
+        };
+        let u64_at = |offset: usize| -> Result<u64, ProgramError> {
+            rest.get(offset..offset + 8)
+                .and_then(|b| <[u8; 8]>::try_from(b).ok())
+                .map(u64::from_le_bytes)
+                .ok_or(ProgramError::InvalidInstructionData)
+        };
+        let u32_at = |offset: usize| -> Result<u32, ProgramError> {
+            rest.get(offset..offset + 4)
+                .and_then(|b| <[u8; 4]>::try_from(b).ok())
+                .map(u32::from_le_bytes)
+                .ok_or(ProgramError::InvalidInstructionData)
+        };
+        Ok(match tag {
+            0 => Self::CredentialIssued { credential: key(0)?, identity: key(1)?, issuer: key(2)? },
+            1 => Self::CredentialRevoked { credential: key(0)?, issuer: key(1)? },
+            2 => Self::CredentialSuspended { credential: key(0)?, issuer: key(1)? },
+            3 => Self::CredentialReinstated { credential: key(0)?, issuer: key(1)? },
+            4 => Self::CredentialRenewed { credential: key(0)?, issuer: key(1)?, expires_at: u64_at(64)? },
+            5 => Self::CredentialSuperseded { credential: key(0)?, new_credential: key(1)?, issuer: key(2)? },
+            6 => Self::CredentialOffered { credential: key(0)?, identity: key(1)?, issuer: key(2)? },
+            7 => Self::CredentialAccepted { credential: key(0)?, identity: key(1)? },
+            8 => Self::CredentialOfferRejected { credential: key(0)?, identity: key(1)? },
+            9 => Self::CredentialOfferCancelled { credential: key(0)?, issuer: key(1)? },
+            10 => Self::CredentialClosed { credential: key(0)?, closed_by: key(1)? },
+            11 => Self::CredentialReissued {
+                credential: key(0)?,
+                issuer: key(1)?,
+                previous_ref: key(2)?.to_bytes(),
+                credential_ref: key(3)?.to_bytes(),
+                expires_at: u64_at(128)?,
+            },
+            12 => Self::CompressedCredentialIssued {
+                tree: key(0)?,
+                root: key(1)?.to_bytes(),
+                index: u32_at(64)?,
+                credential: rest.get(68..).and_then(|b| CompressedCredential::unpack(b).ok()).ok_or(ProgramError::InvalidInstructionData)?,
+            },
+            13 => Self::CompressedCredentialRevoked { tree: key(0)?, root: key(1)?.to_bytes(), index: u32_at(64)? },
+            14 => Self::OrganizationKeyAdded { organization: key(0)?, key: key(1)? },
+            15 => Self::OrganizationKeyRemoved { organization: key(0)?, key: key(1)? },
+            16 => Self::OrganizationKeyRotated { organization: key(0)?, old_key: key(1)?, new_key: key(2)? },
+            17 => Self::AccreditationGranted {
+                accreditation: key(0)?,
+                accreditor: key(1)?,
+                issuer: key(2)?,
+                schema: key(3)?.to_bytes(),
+                expires_at: u64_at(128)?,
+                delegation_depth: *rest.get(136).ok_or(ProgramError::InvalidInstructionData)?,
+            },
+            18 => Self::AccreditationRevoked { accreditation: key(0)?, accreditor: key(1)?, issuer: key(2)? },
+            19 => Self::PresentationRequested { request: key(0)?, verifier: key(1)? },
+            20 => Self::PresentationResponded {
+                request: key(0)?,
+                identity: key(1)?,
+                result: *rest.get(64).ok_or(ProgramError::InvalidInstructionData)?,
+            },
+            21 => Self::VerificationRecorded {
+                receipt: key(0)?,
+                verifier: key(1)?,
+                credential: key(2)?,
+                result: *rest.get(96).ok_or(ProgramError::InvalidInstructionData)?,
+            },
+            22 => Self::VerificationReceiptClosed { receipt: key(0)?, verifier: key(1)? },
+            23 => Self::ClaimsCommitted { credential: key(0)?, issuer: key(1)?, commitment: key(2)?.to_bytes() },
+            _ => return Err(ProgramError::InvalidInstructionData),
+        })
+    }
+
+    pub fn emit(&self) {
+        sol_log_data(&[&self.pack()]);
+    }
+}
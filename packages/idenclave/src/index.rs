@@ -0,0 +1,75 @@
+use idenclave_core::AccountLayout;
+use solana_program::{
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    program_error::ProgramError,
+};
+
+/// Entry `n` of an identity's credential index, stored in the PDA
+/// `[b"cred-index", identity, n (u64 LE)]`. Entries are created when a credential is
+/// issued to the identity and closed together with the credential, so walking
+/// `0..identity.credential_count` lists every live credential of a holder.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CredentialIndex {
+    pub is_initialized: bool,
+    pub identity: Pubkey,
+    pub index: u64,
+    pub credential: Pubkey,
+}
+
+impl CredentialIndex {
+    pub const SEED: &'static [u8] = idenclave_core::CredentialIndex::SEED;
+
+    pub fn find_address(identity: &Pubkey, index: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, identity.as_ref(), &index.to_le_bytes()], program_id)
+    }
+}
+
+impl IsInitialized for CredentialIndex {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for CredentialIndex {}
+
+impl Pack for CredentialIndex {
+    const LEN: usize = idenclave_core::CredentialIndex::ACCOUNT_LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        idenclave_core::CredentialIndex {
+            is_initialized: self.is_initialized,
+            identity: self.identity.to_bytes(),
+            index: self.index,
+            credential: self.credential.to_bytes(),
+        }
+        .pack_account(dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let entry = idenclave_core::CredentialIndex::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(CredentialIndex {
+            is_initialized: entry.is_initialized,
+            identity: Pubkey::new_from_array(entry.identity),
+            index: entry.index,
+            credential: Pubkey::new_from_array(entry.credential),
+        })
+    }
+}
+
+/// Client helper: walks an identity's credential index and returns the live credential addresses
+/// in issuance order. `fetch` returns the data of an account, or `None` if it does not exist
+/// (e.g. `|key| rpc.get_account_data(key).ok()`). Closed entries are skipped.
+pub fn walk_credential_index<F>(identity: &Pubkey, credential_count: u64, program_id: &Pubkey, mut fetch: F) -> Vec<Pubkey>
+where
+    F: FnMut(&Pubkey) -> Option<Vec<u8>>,
+{
+    (0..credential_count)
+        .filter_map(|n| {
+            let (address, _) = CredentialIndex::find_address(identity, n, program_id);
+            let data = fetch(&address)?;
+            let entry = CredentialIndex::unpack(&data).ok()?;
+            (entry.identity == *identity && entry.index == n).then_some(entry.credential)
+        })
+        .collect()
+}
@@ -1,15 +1,37 @@
+pub mod accreditation;
+pub mod compressed;
 pub mod credential;
-pub use credential::Credential;
+pub mod event;
+pub mod index;
+pub mod organization;
+pub mod presentation;
+pub mod tombstone;
+pub mod validation;
+pub mod verification;
+pub use accreditation::Accreditation;
+pub use compressed::CompressedCredentialProof;
+pub use credential::{Credential, CredentialState, CredentialStatus};
+pub use idenclave_core::RuleViolation;
+pub use event::IdentityEvent;
+pub use index::{walk_credential_index, CredentialIndex};
+pub use organization::Organization;
+pub use presentation::{Circuit, PresentationRequest, PresentationResponse};
+pub use tombstone::Tombstone;
+pub use verification::VerificationReceipt;
+pub use idenclave_core::Service;
+use idenclave_core::{AccountLayout, BatchIssueItem, BatchItemResult, ClaimError, ClaimProof, CompressedCredential, CredentialTree, Groth16Proof, Scalar, ServiceError, VerifyingKey};
+use compressed::{tree_error, Sha256Hasher};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint,
     entrypoint::ProgramResult,
     msg,
+    program::{invoke, invoke_signed},
     pubkey::Pubkey,
     program_error::ProgramError,
     program_pack::{Pack, Sealed},
+    system_instruction,
     sysvar::{Sysvar},
-    rent,
 
 };
 
@@ -23,41 +45,61 @@ pub struct Identity {
     pub authority: Pubkey,
     pub recovery: Pubkey,
     pub attributes: Vec<u8>,
+    pub active_credentials: u32, // Credentials referencing this identity that have not been closed yet
+    pub credential_count: u64,   // Number of credential index entries ever created (next index to use)
+    pub services: Vec<Service>,  // DID service entries, at most idenclave_core::MAX_SERVICES
+}
+
+impl Identity {
+    /// Applies a change to the service entries with idenclave-core's rules.
+    pub fn update_services(&mut self, change: impl FnOnce(&mut idenclave_core::Identity) -> Result<(), ServiceError>) -> ProgramResult {
+        let mut identity = self.to_core();
+        if let Err(error) = change(&mut identity) {
+            msg!("Identity services: {}", error);
+            return Err(match error {
+                ServiceError::AlreadyExists => ProgramError::AccountAlreadyInitialized,
+                _ => ProgramError::InvalidArgument,
+            });
+        }
+        self.services = identity.services;
+        Ok(())
+    }
+
+    fn to_core(&self) -> idenclave_core::Identity {
+        let mut identity = idenclave_core::Identity {
+            is_initialized: self.is_initialized,
+            authority: self.authority.to_bytes(),
+            recovery: self.recovery.to_bytes(),
+            active_credentials: self.active_credentials,
+            credential_count: self.credential_count,
+            services: self.services.clone(),
+            ..Default::default()
+        };
+        identity.set_attributes(&self.attributes);
+        identity
+    }
 }
 
 impl Sealed for Identity {}
 
 impl Pack for Identity {
-    // LEN: is_initialized (1) + authority (32) + recovery (32) + attributes (up to 128 for demo, can be increased)
-    const LEN: usize = 1 + 32 + 32 + 128; // attributes fixed to 128 bytes for simplicity
+    const LEN: usize = idenclave_core::Identity::ACCOUNT_LEN;
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        dst[0] = self.is_initialized as u8;
-        dst[1..33].copy_from_slice(self.authority.as_ref());
-        dst[33..65].copy_from_slice(self.recovery.as_ref());
-        // Attributes: fixed 128 bytes
-        let attr_len = self.attributes.len().min(128);
-        dst[65..65+attr_len].copy_from_slice(&self.attributes[..attr_len]);
-        // Zero out remaining bytes if attributes is less than 128
-        for b in &mut dst[65+attr_len..193] {
-            *b = 0;
-        }
+        self.to_core().pack_account(dst);
     }
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        if src.len() < Self::LEN {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        let is_initialized = src[0] != 0;
-        let authority = Pubkey::new_from_array(<[u8; 32]>::try_from(&src[1..33]).map_err(|_| ProgramError::InvalidAccountData)?);
-        let recovery = Pubkey::new_from_array(<[u8; 32]>::try_from(&src[33..65]).map_err(|_| ProgramError::InvalidAccountData)?);
-        // Attributes: fixed 128 bytes, trim trailing zeros
-        let mut attributes = src[65..193].to_vec();
-        if let Some(pos) = attributes.iter().rposition(|&x| x != 0) {
-            attributes.truncate(pos + 1);
-        } else {
-            attributes.clear();
-        }
-        Ok(Identity { is_initialized, authority, recovery, attributes })
+        let identity = idenclave_core::Identity::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Identity {
+            is_initialized: identity.is_initialized,
+            authority: Pubkey::new_from_array(identity.authority),
+            recovery: Pubkey::new_from_array(identity.recovery),
+            // Attributes are stored zero padded; the trailing zeros are trimmed here
+            attributes: identity.attributes().to_vec(),
+            active_credentials: identity.active_credentials,
+            credential_count: identity.credential_count,
+            services: identity.services,
+        })
     }
 }
 
@@ -78,14 +120,62 @@ pub enum IdentityInstruction {
     SetAttributes { data: Vec<u8> }, // 3
     SetRecovery { new_recovery: Pubkey }, // 4
     RecoverAuthority { new_authority: Pubkey }, // 5
-    IssueCredential { credential_ref: [u8; 32], issued_at: u64, expires_at: u64 }, // 6
+    IssueCredential { schema: [u8; 32], nonce: u64, credential_ref: [u8; 32], issued_at: u64, expires_at: u64, claims_root: [u8; 32] }, // 6
     RevokeCredential, // 7
     QueryCredentialStatus, // 8
+    SuspendCredential, // 9
+    ReinstateCredential, // 10
+    RenewCredential { expires_at: u64 }, // 11
+    SupersedeCredential { schema: [u8; 32], nonce: u64, credential_ref: [u8; 32], issued_at: u64, expires_at: u64, claims_root: [u8; 32] }, // 12
+    OfferCredential { schema: [u8; 32], nonce: u64, credential_ref: [u8; 32], issued_at: u64, expires_at: u64, claims_root: [u8; 32] }, // 13
+    AcceptCredential, // 14
+    RejectCredential, // 15
+    CancelCredentialOffer, // 16
+    CloseCredential, // 17
+    ReissueCredential { credential_ref: [u8; 32], issued_at: u64, expires_at: u64, claims_root: [u8; 32] }, // 18
+    IssueCredentialSigned {
+        schema: [u8; 32],
+        nonce: u64,
+        credential_ref: [u8; 32],
+        issued_at: u64,
+        expires_at: u64,
+        deadline: u64,
+        claims_root: [u8; 32],
+    }, // 19
+    IssueCredentialBatch { schema: [u8; 32], items: Vec<BatchIssueItem> }, // 20
+    RevokeCredentialBatch, // 21
+    CreateCredentialTree { max_depth: u8, max_buffer_size: u8 }, // 22
+    IssueCompressedCredential { schema: [u8; 32], credential_ref: [u8; 32], issued_at: u64, expires_at: u64 }, // 23
+    RevokeCompressedCredential { proof: CompressedCredentialProof }, // 24
+    VerifyCompressedCredential { proof: CompressedCredentialProof }, // 25
+    CreateOrganization { keys: Vec<Pubkey> }, // 26
+    AddOrganizationKey { key: Pubkey }, // 27
+    RemoveOrganizationKey { key: Pubkey }, // 28
+    RotateOrganizationKey { old_key: Pubkey, new_key: Pubkey }, // 29
+    Accredit { schema: [u8; 32], expires_at: u64, delegation_depth: u8 }, // 30
+    RevokeAccreditation, // 31
+    QueryAccreditation { root: Pubkey, schema: [u8; 32] }, // 32
+    RegisterCircuit { verifying_key: VerifyingKey }, // 33
+    CreatePresentationRequest {
+        nonce: u64,
+        expires_at: u64,
+        schemas: Vec<[u8; 32]>,
+        trusted_issuers: Vec<Pubkey>,
+        predicate_inputs: Vec<Scalar>,
+    }, // 34
+    RespondToRequest { nonces: Vec<u64>, proof: Option<(Groth16Proof, Vec<Scalar>)> }, // 35
+    RecordVerification { id: u64, proof_hash: Option<[u8; 32]> }, // 36
+    CloseVerificationReceipt, // 37
+    VerifyClaim { proof: ClaimProof }, // 38
+    CommitClaims { claim_hashes: Vec<Scalar> }, // 39
+    AddService { service: Service }, // 40
+    UpdateService { service: Service }, // 41
+    RemoveService { id: String }, // 42
 }
 
 impl IdentityInstruction {
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        solana_program::msg!("[DEBUG] IdentityInstruction::unpack tag: {:?}, rest.len(): {}", input.get(0), input.len().saturating_sub(1));
+        solana_program::msg!("[DEBUG] IdentityInstruction::unpack tag: {:?}, rest.len(): {}", input.first(), input.len().saturating_sub(1));
         let (&tag, rest) = input.split_first().ok_or(ProgramError::InvalidInstructionData)?;
         Ok(match tag {
             0 => Self::RegisterIdentity,
@@ -112,24 +202,483 @@ impl IdentityInstruction {
                 let new_authority = Pubkey::new_from_array(rest.try_into().unwrap());
                 Self::RecoverAuthority { new_authority }
             }
-            6 => {
-                if rest.len() != 48 {
+            6 | 12 | 13 => {
+                // The schema and nonce the credential's address is derived from, then its contents
+                if rest.len() != 88 && rest.len() != 120 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let schema = <[u8; 32]>::try_from(&rest[..32]).unwrap();
+                let nonce = u64::from_le_bytes(rest[32..40].try_into().unwrap());
+                let credential_ref = <[u8; 32]>::try_from(&rest[40..72]).unwrap();
+                let issued_at = u64::from_le_bytes(rest[72..80].try_into().unwrap());
+                let expires_at = u64::from_le_bytes(rest[80..88].try_into().unwrap());
+                let claims_root = rest.get(88..120).map_or([0u8; 32], |root| root.try_into().unwrap());
+                match tag {
+                    6 => Self::IssueCredential { schema, nonce, credential_ref, issued_at, expires_at, claims_root },
+                    12 => Self::SupersedeCredential { schema, nonce, credential_ref, issued_at, expires_at, claims_root },
+                    _ => Self::OfferCredential { schema, nonce, credential_ref, issued_at, expires_at, claims_root },
+                }
+            }
+            7 => Self::RevokeCredential,
+            8 => Self::QueryCredentialStatus,
+            9 => Self::SuspendCredential,
+            10 => Self::ReinstateCredential,
+            11 => {
+                if rest.len() != 8 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let expires_at = u64::from_le_bytes(rest.try_into().unwrap());
+                Self::RenewCredential { expires_at }
+            }
+            14 => Self::AcceptCredential,
+            15 => Self::RejectCredential,
+            16 => Self::CancelCredentialOffer,
+            17 => Self::CloseCredential,
+            18 => {
+                if rest.len() != 48 && rest.len() != 80 {
                     return Err(ProgramError::InvalidInstructionData);
                 }
                 let credential_ref = <[u8; 32]>::try_from(&rest[..32]).unwrap();
                 let issued_at = u64::from_le_bytes(rest[32..40].try_into().unwrap());
                 let expires_at = u64::from_le_bytes(rest[40..48].try_into().unwrap());
-                Self::IssueCredential { credential_ref, issued_at, expires_at }
+                let claims_root = rest.get(48..80).map_or([0u8; 32], |root| root.try_into().unwrap());
+                Self::ReissueCredential { credential_ref, issued_at, expires_at, claims_root }
+            }
+            19 => {
+                if rest.len() != 96 && rest.len() != 128 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let schema = <[u8; 32]>::try_from(&rest[..32]).unwrap();
+                let nonce = u64::from_le_bytes(rest[32..40].try_into().unwrap());
+                let credential_ref = <[u8; 32]>::try_from(&rest[40..72]).unwrap();
+                let issued_at = u64::from_le_bytes(rest[72..80].try_into().unwrap());
+                let expires_at = u64::from_le_bytes(rest[80..88].try_into().unwrap());
+                let deadline = u64::from_le_bytes(rest[88..96].try_into().unwrap());
+                let claims_root = rest.get(96..128).map_or([0u8; 32], |root| root.try_into().unwrap());
+                Self::IssueCredentialSigned { schema, nonce, credential_ref, issued_at, expires_at, deadline, claims_root }
+            }
+            20 => {
+                if rest.len() < 32 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let schema = <[u8; 32]>::try_from(&rest[..32]).unwrap();
+                let items = BatchIssueItem::unpack_all(&rest[32..]).ok_or(ProgramError::InvalidInstructionData)?;
+                Self::IssueCredentialBatch { schema, items }
+            }
+            21 => Self::RevokeCredentialBatch,
+            22 => {
+                if rest.len() != 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Self::CreateCredentialTree { max_depth: rest[0], max_buffer_size: rest[1] }
+            }
+            23 => {
+                if rest.len() != 80 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let schema = <[u8; 32]>::try_from(&rest[..32]).unwrap();
+                let credential_ref = <[u8; 32]>::try_from(&rest[32..64]).unwrap();
+                let issued_at = u64::from_le_bytes(rest[64..72].try_into().unwrap());
+                let expires_at = u64::from_le_bytes(rest[72..80].try_into().unwrap());
+                Self::IssueCompressedCredential { schema, credential_ref, issued_at, expires_at }
+            }
+            24 => Self::RevokeCompressedCredential { proof: CompressedCredentialProof::unpack(rest)? },
+            25 => Self::VerifyCompressedCredential { proof: CompressedCredentialProof::unpack(rest)? },
+            26 => {
+                if rest.len() % 32 != 0 || rest.len() / 32 > idenclave_core::MAX_ORGANIZATION_KEYS {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let keys = rest.chunks_exact(32).map(|key| Pubkey::new_from_array(key.try_into().unwrap())).collect();
+                Self::CreateOrganization { keys }
+            }
+            27 | 28 => {
+                if rest.len() != 32 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let key = Pubkey::new_from_array(rest.try_into().unwrap());
+                if tag == 27 { Self::AddOrganizationKey { key } } else { Self::RemoveOrganizationKey { key } }
+            }
+            29 => {
+                if rest.len() != 64 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let old_key = Pubkey::new_from_array(rest[..32].try_into().unwrap());
+                let new_key = Pubkey::new_from_array(rest[32..].try_into().unwrap());
+                Self::RotateOrganizationKey { old_key, new_key }
+            }
+            30 => {
+                if rest.len() != 41 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let schema = <[u8; 32]>::try_from(&rest[..32]).unwrap();
+                let expires_at = u64::from_le_bytes(rest[32..40].try_into().unwrap());
+                Self::Accredit { schema, expires_at, delegation_depth: rest[40] }
+            }
+            31 => Self::RevokeAccreditation,
+            32 => {
+                if rest.len() != 64 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let root = Pubkey::new_from_array(rest[..32].try_into().unwrap());
+                let schema = <[u8; 32]>::try_from(&rest[32..]).unwrap();
+                Self::QueryAccreditation { root, schema }
+            }
+            33 => Self::RegisterCircuit { verifying_key: VerifyingKey::unpack(rest).ok_or(ProgramError::InvalidInstructionData)? },
+            34 => {
+                if rest.len() < 16 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let nonce = u64::from_le_bytes(rest[..8].try_into().unwrap());
+                let expires_at = u64::from_le_bytes(rest[8..16].try_into().unwrap());
+                let (schemas, rest) = presentation::split_list(&rest[16..], idenclave_core::MAX_REQUEST_SCHEMAS)?;
+                let (trusted_issuers, rest) = presentation::split_list(rest, idenclave_core::MAX_TRUSTED_ISSUERS)?;
+                let (predicate_inputs, rest) = presentation::split_list(rest, idenclave_core::MAX_PREDICATE_INPUTS)?;
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let trusted_issuers = trusted_issuers.into_iter().map(Pubkey::new_from_array).collect();
+                Self::CreatePresentationRequest { nonce, expires_at, schemas, trusted_issuers, predicate_inputs }
+            }
+            35 => {
+                // Nonces of the presented credentials, one per schema of the request, then optionally
+                // a proof followed by its public inputs
+                let (&count, rest) = rest.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+                let count = count as usize;
+                if count > idenclave_core::MAX_REQUEST_SCHEMAS || rest.len() < count * 8 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let nonces = rest[..count * 8].chunks_exact(8).map(|nonce| u64::from_le_bytes(nonce.try_into().unwrap())).collect();
+                let rest = &rest[count * 8..];
+                let proof = if rest.is_empty() {
+                    None
+                } else {
+                    if rest.len() < Groth16Proof::LEN {
+                        return Err(ProgramError::InvalidInstructionData);
+                    }
+                    let proof = Groth16Proof::unpack(&rest[..Groth16Proof::LEN]).unwrap();
+                    let (public_inputs, rest) = presentation::split_list(&rest[Groth16Proof::LEN..], idenclave_core::MAX_PUBLIC_INPUTS)?;
+                    if !rest.is_empty() {
+                        return Err(ProgramError::InvalidInstructionData);
+                    }
+                    Some((proof, public_inputs))
+                };
+                Self::RespondToRequest { nonces, proof }
+            }
+            36 => {
+                let proof_hash = match rest.len() {
+                    8 => None,
+                    40 => Some(<[u8; 32]>::try_from(&rest[8..]).unwrap()),
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
+                Self::RecordVerification { id: u64::from_le_bytes(rest[..8].try_into().unwrap()), proof_hash }
+            }
+            37 => Self::CloseVerificationReceipt,
+            38 => Self::VerifyClaim { proof: ClaimProof::unpack(rest).map_err(|_| ProgramError::InvalidInstructionData)? },
+            39 => {
+                let (claim_hashes, rest) = presentation::split_list(rest, idenclave_core::MAX_COMMITTED_CLAIMS)?;
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Self::CommitClaims { claim_hashes }
+            }
+            40 | 41 => {
+                let service = Service::unpack(rest).map_err(|_| ProgramError::InvalidInstructionData)?;
+                if tag == 40 { Self::AddService { service } } else { Self::UpdateService { service } }
+            }
+            42 => {
+                if rest.is_empty() || rest.len() > idenclave_core::service::MAX_SERVICE_ID_LEN {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let id = std::str::from_utf8(rest).map_err(|_| ProgramError::InvalidInstructionData)?.to_string();
+                Self::RemoveService { id }
             }
-            7 => Self::RevokeCredential,
-            8 => Self::QueryCredentialStatus,
             _ => return Err(ProgramError::InvalidInstructionData),
         })
     }
 }
 
+/// Maps a credential rule from idenclave-core onto the error this program reports for it.
+fn rule_error(violation: RuleViolation) -> ProgramError {
+    match violation {
+        RuleViolation::ExpiryNotExtended => ProgramError::InvalidArgument,
+        RuleViolation::AlreadyRevoked
+        | RuleViolation::InvalidState
+        | RuleViolation::NotPending
+        | RuleViolation::StillValid => ProgramError::InvalidAccountData,
+    }
+}
+
+/// Logs why a disclosed claim was not accepted and maps it onto the error this program reports for it.
+fn claim_error(error: ClaimError) -> ProgramError {
+    msg!("Claim: {}", error);
+    match error {
+        ClaimError::NoClaimsRoot => ProgramError::InvalidAccountData,
+        ClaimError::TooManyClaims | ClaimError::ClaimTooLarge | ClaimError::Malformed | ClaimError::InvalidProof => {
+            ProgramError::InvalidArgument
+        }
+    }
+}
+
+/// Moves all lamports from a program-owned account to `recipient`, zeroes its data
+/// and hands it back to the system program so it cannot be revived with stale state.
+fn close_program_account(account: &AccountInfo, recipient: &AccountInfo) -> ProgramResult {
+    let lamports = account.lamports();
+    **recipient.lamports.borrow_mut() = recipient
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **account.lamports.borrow_mut() = 0;
+    account.try_borrow_mut_data()?.fill(0);
+    account.assign(&solana_program::system_program::ID);
+    Ok(())
+}
+
+/// Creates a program-owned PDA funded by `payer`. Works even if the address was pre-funded
+/// by a third party, which would make a plain `create_account` fail.
+fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    pda: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    program_id: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let required = solana_program::rent::Rent::get()?.minimum_balance(space);
+    if pda.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, pda.key, required, space as u64, program_id),
+            &[payer.clone(), pda.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+    let shortfall = required.saturating_sub(pda.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, pda.key, shortfall),
+            &[payer.clone(), pda.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(&system_instruction::allocate(pda.key, space as u64), &[pda.clone(), system_program.clone()], &[seeds])?;
+    invoke_signed(&system_instruction::assign(pda.key, program_id), &[pda.clone(), system_program.clone()], &[seeds])
+}
+
+/// Creates the account of the credential `issuer` issues to `identity` under `schema` and `nonce`
+/// at its PDA, funded by `payer`. Issuing never writes over an existing credential; changing one
+/// goes through ReissueCredential.
+#[allow(clippy::too_many_arguments)]
+fn create_credential_account<'a>(
+    program_id: &Pubkey,
+    credential_account: &AccountInfo<'a>,
+    issuer: &Pubkey,
+    identity: &Pubkey,
+    schema: &[u8; 32],
+    nonce: u64,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (credential_key, bump) = Credential::find_address(issuer, identity, schema, nonce, program_id);
+    validation::address(credential_account, &credential_key)?;
+    validation::writable(credential_account)?;
+    validation::system_program(system_program)?;
+    if credential_account.owner == program_id {
+        msg!("Credential {} already exists", credential_key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_pda_account(
+        payer,
+        credential_account,
+        system_program,
+        Credential::LEN,
+        program_id,
+        &[Credential::SEED, issuer.as_ref(), identity.as_ref(), schema, &nonce.to_le_bytes(), &[bump]],
+    )
+}
+
+/// Records a new credential on its identity: creates the next credential index PDA
+/// (funded by `payer`) and bumps the identity's counters. The identity must already be validated.
+fn index_credential<'a>(
+    program_id: &Pubkey,
+    identity_account: &AccountInfo<'a>,
+    credential_key: &Pubkey,
+    index_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let mut identity = Identity::unpack(&identity_account.try_borrow_data()?)?;
+    let n = identity.credential_count;
+    let (index_key, bump) = CredentialIndex::find_address(identity_account.key, n, program_id);
+    validation::address(index_account, &index_key)?;
+    validation::writable(index_account)?;
+    validation::system_program(system_program)?;
+    create_pda_account(
+        payer,
+        index_account,
+        system_program,
+        CredentialIndex::LEN,
+        program_id,
+        &[CredentialIndex::SEED, identity_account.key.as_ref(), &n.to_le_bytes(), &[bump]],
+    )?;
+    let entry = CredentialIndex { is_initialized: true, identity: *identity_account.key, index: n, credential: *credential_key };
+    CredentialIndex::pack(entry, &mut index_account.try_borrow_mut_data()?)?;
+    identity.credential_count = n.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+    identity.active_credentials = identity.active_credentials.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+    Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)
+}
+
+/// Removes a closed credential from its identity: closes its index entry (rent to `recipient`)
+/// and decrements the identity's live credential count. The identity must already be validated.
+fn unindex_credential(
+    program_id: &Pubkey,
+    identity_account: &AccountInfo,
+    credential_key: &Pubkey,
+    index_account: &AccountInfo,
+    recipient: &AccountInfo,
+) -> ProgramResult {
+    let entry: CredentialIndex = validation::load_mut(index_account, program_id)?;
+    let (index_key, _) = CredentialIndex::find_address(identity_account.key, entry.index, program_id);
+    validation::address(index_account, &index_key)?;
+    if entry.identity != *identity_account.key || entry.credential != *credential_key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    close_program_account(index_account, recipient)?;
+    let mut identity = Identity::unpack(&identity_account.try_borrow_data()?)?;
+    identity.active_credentials = identity.active_credentials.saturating_sub(1);
+    Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)
+}
+
+/// Offers one credential of an `IssueCredentialBatch` from the acting `issuer` at its PDA. Problems with the item's own
+/// accounts skip it and are reported in its result; failing to fund the accounts aborts the batch.
+#[allow(clippy::too_many_arguments)]
+fn offer_batch_item<'a>(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    payer_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    schema: &[u8; 32],
+    item: &BatchIssueItem,
+    identity_account: &AccountInfo<'a>,
+    credential_account: &AccountInfo<'a>,
+    index_account: &AccountInfo<'a>,
+) -> Result<BatchItemResult, ProgramError> {
+    let identity: Identity = match validation::load_mut(identity_account, program_id) {
+        Ok(identity) => identity,
+        Err(_) => return Ok(BatchItemResult::InvalidAccount),
+    };
+    let (credential_key, bump) = Credential::find_address(issuer, identity_account.key, schema, item.nonce, program_id);
+    if *credential_account.key != credential_key || !credential_account.is_writable {
+        return Ok(BatchItemResult::InvalidAccount);
+    }
+    if credential_account.owner == program_id {
+        return Ok(BatchItemResult::AlreadyExists);
+    }
+    let (index_key, _) = CredentialIndex::find_address(identity_account.key, identity.credential_count, program_id);
+    if *index_account.key != index_key || !index_account.is_writable {
+        return Ok(BatchItemResult::InvalidAccount);
+    }
+    create_pda_account(
+        payer_account,
+        credential_account,
+        system_program,
+        Credential::LEN,
+        program_id,
+        &[Credential::SEED, issuer.as_ref(), identity_account.key.as_ref(), schema, &item.nonce.to_le_bytes(), &[bump]],
+    )?;
+    index_credential(program_id, identity_account, credential_account.key, index_account, payer_account, system_program)?;
+    let cred = Credential {
+        is_initialized: true,
+        pending: true,
+        identity: *identity_account.key,
+        issuer: *issuer,
+        credential_ref: item.credential_ref,
+        issued_at: item.issued_at,
+        expires_at: item.expires_at,
+        ..Default::default()
+    };
+    Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+    IdentityEvent::CredentialOffered {
+        credential: *credential_account.key,
+        identity: *identity_account.key,
+        issuer: *issuer,
+    }.emit();
+    Ok(BatchItemResult::Ok)
+}
+
+/// Revokes one credential of a `RevokeCredentialBatch` for the acting `issuer`, skipping it if it
+/// cannot be revoked.
+fn revoke_batch_item(
+    program_id: &Pubkey,
+    issuer: &Pubkey,
+    credential_account: &AccountInfo,
+) -> Result<BatchItemResult, ProgramError> {
+    let mut cred: Credential = match validation::load_mut(credential_account, program_id) {
+        Ok(cred) => cred,
+        Err(_) => return Ok(BatchItemResult::InvalidAccount),
+    };
+    if cred.issuer != *issuer {
+        return Ok(BatchItemResult::NotIssuer);
+    }
+    if let Err(violation) = cred.check_revoke() {
+        return Ok(violation.into());
+    }
+    cred.revoked = true;
+    cred.suspended = false;
+    Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+    IdentityEvent::CredentialRevoked { credential: *credential_account.key, issuer: *issuer }.emit();
+    Ok(BatchItemResult::Ok)
+}
+
+/// Splits off the organization account a batch's item accounts may start with. Items follow it,
+/// so it is told apart by being an organization account of this program.
+fn batch_organization<'b, 'a>(accounts: &'b [AccountInfo<'a>], program_id: &Pubkey) -> (Option<&'b AccountInfo<'a>>, &'b [AccountInfo<'a>]) {
+    match accounts.split_first() {
+        Some((first, items)) if validation::load::<Organization>(first, program_id).is_ok() => (Some(first), items),
+        _ => (None, accounts),
+    }
+}
+
+/// Logs the skipped items of a batch and returns every item's result as return data.
+fn report_batch(instruction: &str, results: &[BatchItemResult]) {
+    for (i, result) in results.iter().enumerate() {
+        if *result != BatchItemResult::Ok {
+            msg!("[{}] Item {} skipped: {:?}", instruction, i, result);
+        }
+    }
+    let done = results.iter().filter(|result| **result == BatchItemResult::Ok).count();
+    msg!("[{}] {} of {} items processed", instruction, done, results.len());
+    solana_program::program::set_return_data(&idenclave_core::batch::encode_results(results));
+}
+
+/// Loads an organization for a change to its keys, which only its authority may make.
+fn load_organization(
+    program_id: &Pubkey,
+    organization_account: &AccountInfo,
+    authority_account: &AccountInfo,
+) -> Result<Organization, ProgramError> {
+    validation::signer(authority_account)?;
+    let organization: Organization = validation::load_mut(organization_account, program_id)?;
+    if organization.authority != *authority_account.key {
+        msg!("Only the organization's authority can change its keys");
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(organization)
+}
+
+/// Loads an identity for a change to its service entries, which only its authority may make.
+fn load_identity(
+    program_id: &Pubkey,
+    identity_account: &AccountInfo,
+    authority_account: &AccountInfo,
+) -> Result<Identity, ProgramError> {
+    validation::signer(authority_account)?;
+    let identity: Identity = validation::load_mut(identity_account, program_id)?;
+    if identity.authority != *authority_account.key {
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(identity)
+}
+
 pub fn process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
@@ -142,197 +691,1161 @@ pub fn process_instruction(
         IdentityInstruction::RevokeCredential => {
             // [credential_account, issuer_account]
             let credential_account = next_account_info(account_info_iter)?;
-let issuer_account = next_account_info(account_info_iter)?;
-
-
-            if !issuer_account.is_signer {
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            let mut cred_data = credential_account.try_borrow_mut_data()?;
-            let mut cred = Credential::unpack(&cred_data)?;
-            if cred.issuer != *issuer_account.key {
+            let issuer_account = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            let mut cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.issuer != issuer {
                 return Err(ProgramError::IllegalOwner);
             }
-            if cred.revoked {
-                return Err(ProgramError::InvalidAccountData);
-            }
+            cred.check_revoke().map_err(rule_error)?;
             cred.revoked = true;
-            Credential::pack(cred, &mut cred_data)?;
+            cred.suspended = false;
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            IdentityEvent::CredentialRevoked { credential: *credential_account.key, issuer }.emit();
             msg!("[RevokeCredential] Credential revoked");
-            return Ok(());
+            Ok(())
+        }
+        IdentityInstruction::SuspendCredential => {
+            // [credential_account, issuer_account]
+            let credential_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            let mut cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.issuer != issuer {
+                return Err(ProgramError::IllegalOwner);
+            }
+            cred.check_suspend().map_err(rule_error)?;
+            cred.suspended = true;
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            IdentityEvent::CredentialSuspended { credential: *credential_account.key, issuer }.emit();
+            msg!("[SuspendCredential] Credential suspended");
+            Ok(())
+        }
+        IdentityInstruction::ReinstateCredential => {
+            // [credential_account, issuer_account]
+            let credential_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            let mut cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.issuer != issuer {
+                return Err(ProgramError::IllegalOwner);
+            }
+            cred.check_reinstate().map_err(rule_error)?;
+            cred.suspended = false;
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            IdentityEvent::CredentialReinstated { credential: *credential_account.key, issuer }.emit();
+            msg!("[ReinstateCredential] Credential reinstated");
+            Ok(())
+        }
+        IdentityInstruction::RenewCredential { expires_at } => {
+            // [credential_account, issuer_account]
+            let credential_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            let mut cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.issuer != issuer {
+                return Err(ProgramError::IllegalOwner);
+            }
+            cred.check_renew(expires_at).map_err(rule_error)?;
+            cred.expires_at = expires_at;
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            IdentityEvent::CredentialRenewed { credential: *credential_account.key, issuer, expires_at }.emit();
+            msg!("[RenewCredential] Credential renewed until {}", expires_at);
+            Ok(())
+        }
+        IdentityInstruction::ReissueCredential { credential_ref, issued_at, expires_at, claims_root } => {
+            // [credential_account, issuer_account]
+            let credential_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            let mut cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.issuer != issuer {
+                return Err(ProgramError::IllegalOwner);
+            }
+            cred.check_reissue().map_err(rule_error)?;
+            // The replaced reference is logged so the credential's history can be rebuilt from events
+            let previous_ref = cred.credential_ref;
+            cred.credential_ref = credential_ref;
+            cred.issued_at = issued_at;
+            cred.expires_at = expires_at;
+            // The claims belong to the document being replaced, so they are replaced along with it
+            cred.claims_root = claims_root;
+            cred.commitment = [0u8; 32];
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            IdentityEvent::CredentialReissued {
+                credential: *credential_account.key,
+                issuer,
+                previous_ref,
+                credential_ref,
+                expires_at,
+            }.emit();
+            msg!("[ReissueCredential] Credential reissued");
+            Ok(())
+        }
+        IdentityInstruction::SupersedeCredential { schema, nonce, credential_ref, issued_at, expires_at, claims_root } => {
+            // [old_credential_account, new_credential_account, issuer_account, payer_account, identity_account, index_account, system_program]
+            let old_credential_account = next_account_info(account_info_iter)?;
+            let new_credential_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let identity_account = next_account_info(account_info_iter)?;
+            let index_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            validation::payer(payer_account)?;
+            if old_credential_account.key == new_credential_account.key {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let mut old: Credential = validation::load_mut(old_credential_account, program_id)?;
+            if old.issuer != issuer {
+                return Err(ProgramError::IllegalOwner);
+            }
+            old.check_supersede().map_err(rule_error)?;
+            if old.identity != *identity_account.key {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let _: Identity = validation::load_mut(identity_account, program_id)?;
+            create_credential_account(
+                program_id,
+                new_credential_account,
+                &issuer,
+                identity_account.key,
+                &schema,
+                nonce,
+                payer_account,
+                system_program,
+            )?;
+            let cred = Credential {
+                is_initialized: true,
+                identity: old.identity,
+                issuer: old.issuer,
+                credential_ref,
+                issued_at,
+                expires_at,
+                claims_root,
+                previous: *old_credential_account.key,
+                ..Default::default()
+            };
+            Credential::pack(cred, &mut new_credential_account.try_borrow_mut_data()?)?;
+            old.superseded_by = *new_credential_account.key;
+            Credential::pack(old, &mut old_credential_account.try_borrow_mut_data()?)?;
+            index_credential(program_id, identity_account, new_credential_account.key, index_account, payer_account, system_program)?;
+            IdentityEvent::CredentialSuperseded {
+                credential: *old_credential_account.key,
+                new_credential: *new_credential_account.key,
+                issuer,
+            }.emit();
+            msg!("[SupersedeCredential] Credential superseded by {}", new_credential_account.key);
+            Ok(())
+        }
+        IdentityInstruction::OfferCredential { schema, nonce, credential_ref, issued_at, expires_at, claims_root } => {
+            // [identity_account, credential_account, issuer_account, payer_account, index_account, system_program]
+            let identity_account = next_account_info(account_info_iter)?;
+            let credential_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let index_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            validation::payer(payer_account)?;
+            let _: Identity = validation::load_mut(identity_account, program_id)?;
+            create_credential_account(program_id, credential_account, &issuer, identity_account.key, &schema, nonce, payer_account, system_program)?;
+            index_credential(program_id, identity_account, credential_account.key, index_account, payer_account, system_program)?;
+            let cred = Credential {
+                is_initialized: true,
+                pending: true,
+                identity: *identity_account.key,
+                issuer,
+                credential_ref,
+                issued_at,
+                expires_at,
+                claims_root,
+                ..Default::default()
+            };
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            IdentityEvent::CredentialOffered {
+                credential: *credential_account.key,
+                identity: *identity_account.key,
+                issuer,
+            }.emit();
+            msg!("[OfferCredential] Credential offered to {}", identity_account.key);
+            Ok(())
+        }
+        IdentityInstruction::AcceptCredential => {
+            // [identity_account, credential_account, authority_account]
+            let identity_account = next_account_info(account_info_iter)?;
+            let credential_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            validation::signer(authority_account)?;
+            let identity: Identity = validation::load(identity_account, program_id)?;
+            if identity.authority != *authority_account.key {
+                return Err(ProgramError::IllegalOwner);
+            }
+            let mut cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.identity != *identity_account.key {
+                return Err(ProgramError::InvalidArgument);
+            }
+            cred.check_accept().map_err(rule_error)?;
+            cred.pending = false;
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            IdentityEvent::CredentialAccepted { credential: *credential_account.key, identity: *identity_account.key }.emit();
+            msg!("[AcceptCredential] Credential accepted");
+            Ok(())
+        }
+        IdentityInstruction::RejectCredential => {
+            // [identity_account, credential_account, authority_account, issuer_account, recipient_account, index_account]
+            let identity_account = next_account_info(account_info_iter)?;
+            let credential_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            let recipient_account = next_account_info(account_info_iter)?;
+            let index_account = next_account_info(account_info_iter)?;
+            validation::signer(authority_account)?;
+            validation::writable(recipient_account)?;
+            let identity: Identity = validation::load_mut(identity_account, program_id)?;
+            if identity.authority != *authority_account.key {
+                return Err(ProgramError::IllegalOwner);
+            }
+            let cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.identity != *identity_account.key || cred.issuer != *issuer_account.key {
+                return Err(ProgramError::InvalidArgument);
+            }
+            cred.check_withdraw_offer().map_err(rule_error)?;
+            // The issuer decides where the rent goes; unless it signs the rejection too, it is refunded to the issuer
+            if !issuer_account.is_signer && recipient_account.key != issuer_account.key {
+                msg!("Only the issuer can send the refunded rent elsewhere");
+                return Err(ProgramError::InvalidArgument);
+            }
+            close_program_account(credential_account, recipient_account)?;
+            unindex_credential(program_id, identity_account, credential_account.key, index_account, recipient_account)?;
+            IdentityEvent::CredentialOfferRejected { credential: *credential_account.key, identity: *identity_account.key }.emit();
+            msg!("[RejectCredential] Credential offer rejected");
+            Ok(())
+        }
+        IdentityInstruction::CancelCredentialOffer => {
+            // [credential_account, issuer_account, recipient_account, identity_account, index_account]
+            let credential_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            let recipient_account = next_account_info(account_info_iter)?;
+            let identity_account = next_account_info(account_info_iter)?;
+            let index_account = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            validation::writable(recipient_account)?;
+            let cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.issuer != issuer {
+                return Err(ProgramError::IllegalOwner);
+            }
+            if cred.identity != *identity_account.key {
+                return Err(ProgramError::InvalidArgument);
+            }
+            cred.check_withdraw_offer().map_err(rule_error)?;
+            let _: Identity = validation::load_mut(identity_account, program_id)?;
+            close_program_account(credential_account, recipient_account)?;
+            unindex_credential(program_id, identity_account, credential_account.key, index_account, recipient_account)?;
+            IdentityEvent::CredentialOfferCancelled { credential: *credential_account.key, issuer }.emit();
+            msg!("[CancelCredentialOffer] Credential offer cancelled");
+            Ok(())
+        }
+        IdentityInstruction::CloseCredential => {
+            use solana_program::sysvar::clock::Clock;
+            // [credential_account, closer_account, issuer_account, recipient_account, identity_account, index_account, organization_account (optional)]
+            let credential_account = next_account_info(account_info_iter)?;
+            let closer_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            let recipient_account = next_account_info(account_info_iter)?;
+            let identity_account = next_account_info(account_info_iter)?;
+            let index_account = next_account_info(account_info_iter)?;
+            let closer = validation::issuer(closer_account, next_account_info(account_info_iter).ok(), program_id)?;
+            validation::writable(recipient_account)?;
+            let cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.issuer != *issuer_account.key || cred.identity != *identity_account.key {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let identity: Identity = validation::load_mut(identity_account, program_id)?;
+            // The holder may also close, proven through the identity's current authority
+            if cred.issuer != closer && identity.authority != *closer_account.key {
+                return Err(ProgramError::IllegalOwner);
+            }
+            if let Err(violation) = cred.check_close(idenclave_core::unix_now(Clock::get()?.unix_timestamp)) {
+                msg!("Only revoked or expired credentials can be closed");
+                return Err(rule_error(violation));
+            }
+            // The issuer decides where the rent goes; when the holder closes, it is refunded to the issuer
+            if closer != *issuer_account.key && recipient_account.key != issuer_account.key {
+                msg!("Only the issuer can send the refunded rent elsewhere");
+                return Err(ProgramError::InvalidArgument);
+            }
+            close_program_account(credential_account, recipient_account)?;
+            unindex_credential(program_id, identity_account, credential_account.key, index_account, recipient_account)?;
+            IdentityEvent::CredentialClosed { credential: *credential_account.key, closed_by: closer }.emit();
+            msg!("[CloseCredential] Credential closed");
+            Ok(())
         }
         IdentityInstruction::RegisterIdentity => {
+            // [identity_account, authority_account, tombstone_account]
             let identity_account = next_account_info(account_info_iter)?;
             let authority_account = next_account_info(account_info_iter)?;
-            if !authority_account.is_signer {
-                return Err(ProgramError::MissingRequiredSignature);
+            let tombstone_account = next_account_info(account_info_iter)?;
+            validation::signer(authority_account)?;
+            let (tombstone_key, _) = Tombstone::find_address(identity_account.key, program_id);
+            validation::address(tombstone_account, &tombstone_key)?;
+            // A closed identity can only be brought back by the authority that closed it
+            if tombstone_account.owner == program_id {
+                let tombstone = Tombstone::unpack_unchecked(&tombstone_account.try_borrow_data()?)?;
+                if tombstone.is_initialized && tombstone.authority != *authority_account.key {
+                    msg!("Identity was closed by a different authority");
+                    return Err(ProgramError::IllegalOwner);
+                }
             }
-            let mut identity_data = identity_account.try_borrow_mut_data()?;
-            let mut identity = Identity::default();
-            if identity_data[0] != 0 {
+            let existing: Identity = validation::new_account(identity_account, program_id)?;
+            if existing.is_initialized {
                 return Err(ProgramError::AccountAlreadyInitialized);
             }
-            identity.is_initialized = true;
-            identity.authority = *authority_account.key;
-            Identity::pack(identity, &mut identity_data)?;
-            return Ok(());
+            let identity = Identity { is_initialized: true, authority: *authority_account.key, ..Default::default() };
+            Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)?;
+            Ok(())
         }
         IdentityInstruction::UpdateAuthority { new_authority } => {
             let identity_account = next_account_info(account_info_iter)?;
             let authority_account = next_account_info(account_info_iter)?;
-            if !authority_account.is_signer {
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            let mut identity_data = identity_account.try_borrow_mut_data()?;
-            let mut identity = Identity::unpack(&identity_data)?;
-            if !identity.is_initialized || identity.authority != *authority_account.key {
+            validation::signer(authority_account)?;
+            let mut identity: Identity = validation::load_mut(identity_account, program_id)?;
+            if identity.authority != *authority_account.key {
                 return Err(ProgramError::IllegalOwner);
             }
             identity.authority = new_authority;
-            Identity::pack(identity, &mut identity_data)?;
-            return Ok(());
+            Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)?;
+            Ok(())
         }
         IdentityInstruction::SetAttributes { data } => {
             let identity_account = next_account_info(account_info_iter)?;
             let authority_account = next_account_info(account_info_iter)?;
-            if !authority_account.is_signer {
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            let mut identity_data = identity_account.try_borrow_mut_data()?;
-            let mut identity = Identity::unpack(&identity_data)?;
-            if !identity.is_initialized || identity.authority != *authority_account.key {
+            validation::signer(authority_account)?;
+            let mut identity: Identity = validation::load_mut(identity_account, program_id)?;
+            if identity.authority != *authority_account.key {
                 return Err(ProgramError::IllegalOwner);
             }
-            identity.attributes = data[..data.len().min(128)].to_vec();
-            Identity::pack(identity, &mut identity_data)?;
-            return Ok(());
+            identity.attributes = idenclave_core::truncate_attributes(&data).to_vec();
+            Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)?;
+            Ok(())
+        }
+        IdentityInstruction::AddService { service } => {
+            // [identity_account, authority_account]
+            let identity_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let mut identity = load_identity(program_id, identity_account, authority_account)?;
+            let id = service.id.clone();
+            identity.update_services(|identity| identity.add_service(service))?;
+            Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)?;
+            msg!("[AddService] Service {} added", id);
+            Ok(())
+        }
+        IdentityInstruction::UpdateService { service } => {
+            // [identity_account, authority_account]
+            let identity_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let mut identity = load_identity(program_id, identity_account, authority_account)?;
+            let id = service.id.clone();
+            identity.update_services(|identity| identity.update_service(service))?;
+            Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)?;
+            msg!("[UpdateService] Service {} updated", id);
+            Ok(())
+        }
+        IdentityInstruction::RemoveService { id } => {
+            // [identity_account, authority_account]
+            let identity_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let mut identity = load_identity(program_id, identity_account, authority_account)?;
+            identity.update_services(|identity| identity.remove_service(&id))?;
+            Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)?;
+            msg!("[RemoveService] Service {} removed", id);
+            Ok(())
         }
         IdentityInstruction::SetRecovery { new_recovery } => {
             let identity_account = next_account_info(account_info_iter)?;
             let authority_account = next_account_info(account_info_iter)?;
-            if !authority_account.is_signer {
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            let mut identity_data = identity_account.try_borrow_mut_data()?;
-            let mut identity = Identity::unpack(&identity_data)?;
-            if !identity.is_initialized || identity.authority != *authority_account.key {
+            validation::signer(authority_account)?;
+            let mut identity: Identity = validation::load_mut(identity_account, program_id)?;
+            if identity.authority != *authority_account.key {
                 return Err(ProgramError::IllegalOwner);
             }
             identity.recovery = new_recovery;
-            Identity::pack(identity, &mut identity_data)?;
-            return Ok(());
+            Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)?;
+            Ok(())
         }
-        IdentityInstruction::IssueCredential { credential_ref, issued_at, expires_at } => {
+        IdentityInstruction::IssueCredential { schema, nonce, credential_ref, issued_at, expires_at, claims_root } => {
+            // [identity_account, authority_account, credential_account, issuer_account, payer_account, index_account, system_program]
             let identity_account = next_account_info(account_info_iter)?;
             let authority_account = next_account_info(account_info_iter)?;
             let credential_account = next_account_info(account_info_iter)?;
-let issuer_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let index_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            validation::signer(authority_account)?;
+            validation::payer(payer_account)?;
+            let identity: Identity = validation::load_mut(identity_account, program_id)?;
+            if identity.authority != *authority_account.key {
+                return Err(ProgramError::IllegalOwner);
+            }
+            create_credential_account(program_id, credential_account, &issuer, identity_account.key, &schema, nonce, payer_account, system_program)?;
+            index_credential(program_id, identity_account, credential_account.key, index_account, payer_account, system_program)?;
+            let cred = Credential {
+                is_initialized: true,
+                identity: *identity_account.key,
+                issuer,
+                credential_ref,
+                issued_at,
+                expires_at,
+                claims_root,
+                ..Default::default()
+            };
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            IdentityEvent::CredentialIssued {
+                credential: *credential_account.key,
+                identity: *identity_account.key,
+                issuer,
+            }.emit();
+            Ok(())
+        }
+        IdentityInstruction::IssueCredentialSigned { schema, nonce, credential_ref, issued_at, expires_at, deadline, claims_root } => {
+            use solana_program::sysvar::clock::Clock;
+            // [identity_account, authority_account, credential_account, issuer_account, payer_account,
+            //  index_account, instructions_sysvar, system_program]
+            let identity_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let credential_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let index_account = next_account_info(account_info_iter)?;
+            let instructions_sysvar = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signing key then issues for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::acting_issuer(issuer_account.key, organization_account, program_id)?;
+            validation::payer(payer_account)?;
+            validation::system_program(system_program)?;
+            let identity: Identity = validation::load_mut(identity_account, program_id)?;
+            if identity.authority != *authority_account.key {
+                return Err(ProgramError::IllegalOwner);
+            }
+            if idenclave_core::unix_now(Clock::get()?.unix_timestamp) > deadline {
+                msg!("Issuance signature expired at {}", deadline);
+                return Err(ProgramError::InvalidArgument);
+            }
+            let message = idenclave_core::IssuanceMessage {
+                program_id: program_id.to_bytes(),
+                issuer: issuer.to_bytes(),
+                identity: identity_account.key.to_bytes(),
+                schema,
+                nonce,
+                credential_ref,
+                issued_at,
+                expires_at,
+                claims_root,
+                deadline,
+            };
+            validation::ed25519_signature(instructions_sysvar, issuer_account.key, &message.to_bytes())?;
 
+            // The address is bound to the signed issuer, identity, schema and nonce, so the same
+            // message can never create a second credential
+            create_credential_account(
+                program_id,
+                credential_account,
+                &issuer,
+                identity_account.key,
+                &schema,
+                nonce,
+                payer_account,
+                system_program,
+            )?;
+            index_credential(program_id, identity_account, credential_account.key, index_account, payer_account, system_program)?;
 
-            if !issuer_account.is_signer {
-                return Err(ProgramError::MissingRequiredSignature);
+            // Without the holder's signature the credential waits for acceptance like an offer
+            let pending = !authority_account.is_signer;
+            let cred = Credential {
+                is_initialized: true,
+                pending,
+                identity: *identity_account.key,
+                issuer,
+                credential_ref,
+                issued_at,
+                expires_at,
+                claims_root,
+                ..Default::default()
+            };
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            if pending {
+                IdentityEvent::CredentialOffered {
+                    credential: *credential_account.key,
+                    identity: *identity_account.key,
+                    issuer,
+                }.emit();
+            } else {
+                IdentityEvent::CredentialIssued {
+                    credential: *credential_account.key,
+                    identity: *identity_account.key,
+                    issuer,
+                }.emit();
             }
-            if !authority_account.is_signer {
-                return Err(ProgramError::MissingRequiredSignature);
+            msg!("[IssueCredentialSigned] Credential issued at {}", credential_account.key);
+            Ok(())
+        }
+        IdentityInstruction::IssueCredentialBatch { schema, items } => {
+            // [issuer_account, payer_account, system_program], then per item
+            // [identity_account, credential_account, index_account]
+            let issuer_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            // An organization account may precede the items: the signer then acts for the organization
+            let (organization_account, item_accounts) = batch_organization(account_info_iter.as_slice(), program_id);
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            validation::payer(payer_account)?;
+            validation::system_program(system_program)?;
+            if item_accounts.len() != items.len() * 3 {
+                return Err(ProgramError::NotEnoughAccountKeys);
             }
-            let identity_data = identity_account.try_borrow_data()?;
-            let identity = Identity::unpack(&identity_data)?;
-            if !identity.is_initialized {
-                return Err(ProgramError::UninitializedAccount);
+            // A cohort's holders cannot all sign one transaction, so batch credentials arrive as offers
+            let mut results = Vec::with_capacity(items.len());
+            for (item, accounts) in items.iter().zip(item_accounts.chunks_exact(3)) {
+                let [identity_account, credential_account, index_account] = accounts else { unreachable!() };
+                results.push(offer_batch_item(
+                    program_id,
+                    &issuer,
+                    payer_account,
+                    system_program,
+                    &schema,
+                    item,
+                    identity_account,
+                    credential_account,
+                    index_account,
+                )?);
             }
-            let mut cred_data = credential_account.try_borrow_mut_data()?;
-            let mut cred = Credential::default();
-            cred.is_initialized = true;
-            cred.identity = *identity_account.key;
-            cred.issuer = *issuer_account.key;
-            cred.credential_ref = credential_ref;
-            cred.issued_at = issued_at;
-            cred.expires_at = expires_at;
-            Credential::pack(cred, &mut cred_data)?;
-            return Ok(());
+            report_batch("IssueCredentialBatch", &results);
+            Ok(())
+        }
+        IdentityInstruction::RevokeCredentialBatch => {
+            // [issuer_account], then one credential_account per item
+            let issuer_account = next_account_info(account_info_iter)?;
+            // An organization account may precede the credentials: the signer then acts for the organization
+            let (organization_account, credential_accounts) = batch_organization(account_info_iter.as_slice(), program_id);
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            if credential_accounts.is_empty() || credential_accounts.len() > idenclave_core::MAX_BATCH_ITEMS {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let mut results = Vec::with_capacity(credential_accounts.len());
+            for credential_account in credential_accounts {
+                results.push(revoke_batch_item(program_id, &issuer, credential_account)?);
+            }
+            report_batch("RevokeCredentialBatch", &results);
+            Ok(())
+        }
+        IdentityInstruction::CreateCredentialTree { max_depth, max_buffer_size } => {
+            // [tree_account, authority_account]; the tree account is allocated by the client,
+            // sized with CredentialTree::account_len and owned by this program
+            let tree_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            validation::signer(authority_account)?;
+            validation::owned_by_program(tree_account, program_id)?;
+            validation::writable(tree_account)?;
+            validation::rent_exempt(tree_account)?;
+            let mut data = tree_account.try_borrow_mut_data()?;
+            CredentialTree::initialize::<Sha256Hasher>(&mut data[..], max_depth, max_buffer_size, &authority_account.key.to_bytes())
+                .map_err(tree_error)?;
+            msg!("[CreateCredentialTree] Tree of depth {} created", max_depth);
+            Ok(())
+        }
+        IdentityInstruction::IssueCompressedCredential { schema, credential_ref, issued_at, expires_at } => {
+            // [tree_account, issuer_account, identity_account, authority_account]
+            let tree_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            let identity_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            validation::signer(issuer_account)?;
+            validation::signer(authority_account)?;
+            validation::owned_by_program(tree_account, program_id)?;
+            validation::writable(tree_account)?;
+            // Holders consent to a compressed credential just like to one in its own account
+            let identity: Identity = validation::load(identity_account, program_id)?;
+            if !identity.is_initialized || identity.authority != *authority_account.key {
+                return Err(ProgramError::IllegalOwner);
+            }
+            let mut data = tree_account.try_borrow_mut_data()?;
+            let mut tree = CredentialTree::load(&mut data[..]).map_err(tree_error)?;
+            if tree.authority() != issuer_account.key.to_bytes() {
+                msg!("Only the tree's authority can issue into it");
+                return Err(ProgramError::IllegalOwner);
+            }
+            let credential = CompressedCredential {
+                identity: identity_account.key.to_bytes(),
+                issuer: issuer_account.key.to_bytes(),
+                schema,
+                credential_ref,
+                issued_at,
+                expires_at,
+                revoked: false,
+            };
+            let (index, root) = tree.append::<Sha256Hasher>(&credential.leaf_hash::<Sha256Hasher>()).map_err(tree_error)?;
+            IdentityEvent::CompressedCredentialIssued { tree: *tree_account.key, index, root, credential }.emit();
+            msg!("[IssueCompressedCredential] Credential issued at leaf {}", index);
+            Ok(())
+        }
+        IdentityInstruction::RevokeCompressedCredential { proof } => {
+            // [tree_account, issuer_account]
+            let tree_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            validation::signer(issuer_account)?;
+            validation::owned_by_program(tree_account, program_id)?;
+            validation::writable(tree_account)?;
+            if proof.credential.issuer != issuer_account.key.to_bytes() {
+                return Err(ProgramError::IllegalOwner);
+            }
+            proof.credential.check_revoke().map_err(rule_error)?;
+            let mut data = tree_account.try_borrow_mut_data()?;
+            let mut tree = CredentialTree::load(&mut data[..]).map_err(tree_error)?;
+            let revoked = CompressedCredential { revoked: true, ..proof.credential.clone() };
+            let root = tree
+                .replace::<Sha256Hasher>(
+                    &proof.root,
+                    proof.index,
+                    &proof.credential.leaf_hash::<Sha256Hasher>(),
+                    &revoked.leaf_hash::<Sha256Hasher>(),
+                    &proof.proof,
+                )
+                .map_err(tree_error)?;
+            IdentityEvent::CompressedCredentialRevoked { tree: *tree_account.key, index: proof.index, root }.emit();
+            msg!("[RevokeCompressedCredential] Credential at leaf {} revoked", proof.index);
+            Ok(())
+        }
+        IdentityInstruction::VerifyCompressedCredential { proof } => {
+            use solana_program::sysvar::{clock::Clock, Sysvar};
+            // [tree_account]
+            let tree_account = next_account_info(account_info_iter)?;
+            validation::owned_by_program(tree_account, program_id)?;
+            let data = tree_account.try_borrow_data()?;
+            let tree = CredentialTree::load(&data[..]).map_err(tree_error)?;
+            tree.verify::<Sha256Hasher>(&proof.root, proof.index, &proof.credential.leaf_hash::<Sha256Hasher>(), &proof.proof)
+                .map_err(tree_error)?;
+            // The proof shows the presented credential is the current leaf, so its status is the credential's
+            let status = proof.credential.status(idenclave_core::unix_now(Clock::get()?.unix_timestamp));
+            msg!("[VerifyCompressedCredential] Credential at leaf {} is {:?}", proof.index, status);
+            solana_program::program::set_return_data(&[status as u8]);
+            Ok(())
+        }
+        IdentityInstruction::CreateOrganization { keys } => {
+            // [organization_account, authority_account, payer_account, system_program]
+            let organization_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            validation::signer(authority_account)?;
+            validation::payer(payer_account)?;
+            validation::system_program(system_program)?;
+            let (organization_key, bump) = Organization::find_address(authority_account.key, program_id);
+            validation::address(organization_account, &organization_key)?;
+            validation::writable(organization_account)?;
+            if organization_account.owner == program_id {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            let mut organization = Organization { is_initialized: true, authority: *authority_account.key, keys: Vec::new() };
+            for key in &keys {
+                organization.add_key(key)?;
+            }
+            create_pda_account(
+                payer_account,
+                organization_account,
+                system_program,
+                Organization::LEN,
+                program_id,
+                &[Organization::SEED, authority_account.key.as_ref(), &[bump]],
+            )?;
+            Organization::pack(organization, &mut organization_account.try_borrow_mut_data()?)?;
+            for key in keys {
+                IdentityEvent::OrganizationKeyAdded { organization: organization_key, key }.emit();
+            }
+            msg!("[CreateOrganization] Organization created");
+            Ok(())
+        }
+        IdentityInstruction::AddOrganizationKey { key } => {
+            // [organization_account, authority_account]
+            let organization_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let mut organization = load_organization(program_id, organization_account, authority_account)?;
+            organization.add_key(&key)?;
+            Organization::pack(organization, &mut organization_account.try_borrow_mut_data()?)?;
+            IdentityEvent::OrganizationKeyAdded { organization: *organization_account.key, key }.emit();
+            msg!("[AddOrganizationKey] Key {} added", key);
+            Ok(())
+        }
+        IdentityInstruction::RemoveOrganizationKey { key } => {
+            // [organization_account, authority_account]
+            let organization_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let mut organization = load_organization(program_id, organization_account, authority_account)?;
+            organization.remove_key(&key)?;
+            Organization::pack(organization, &mut organization_account.try_borrow_mut_data()?)?;
+            IdentityEvent::OrganizationKeyRemoved { organization: *organization_account.key, key }.emit();
+            msg!("[RemoveOrganizationKey] Key {} removed", key);
+            Ok(())
+        }
+        IdentityInstruction::RotateOrganizationKey { old_key, new_key } => {
+            // [organization_account, authority_account]
+            let organization_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let mut organization = load_organization(program_id, organization_account, authority_account)?;
+            organization.rotate_key(&old_key, &new_key)?;
+            Organization::pack(organization, &mut organization_account.try_borrow_mut_data()?)?;
+            IdentityEvent::OrganizationKeyRotated { organization: *organization_account.key, old_key, new_key }.emit();
+            msg!("[RotateOrganizationKey] Key {} replaced by {}", old_key, new_key);
+            Ok(())
+        }
+        IdentityInstruction::Accredit { schema, expires_at, delegation_depth } => {
+            // [accreditation_account, accreditor_account, issuer_account, payer_account, system_program, organization_account (optional)]
+            let accreditation_account = next_account_info(account_info_iter)?;
+            let accreditor_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            let accreditor = validation::issuer(accreditor_account, next_account_info(account_info_iter).ok(), program_id)?;
+            validation::payer(payer_account)?;
+            validation::system_program(system_program)?;
+            let (accreditation_key, bump) = Accreditation::find_address(&accreditor, issuer_account.key, &schema, program_id);
+            validation::address(accreditation_account, &accreditation_key)?;
+            // Accrediting again updates the terms and lifts a revocation
+            if accreditation_account.owner != program_id {
+                create_pda_account(
+                    payer_account,
+                    accreditation_account,
+                    system_program,
+                    Accreditation::LEN,
+                    program_id,
+                    &[Accreditation::SEED, accreditor.as_ref(), issuer_account.key.as_ref(), &schema, &[bump]],
+                )?;
+            } else {
+                validation::load_mut::<Accreditation>(accreditation_account, program_id)?;
+            }
+            let accreditation = Accreditation {
+                is_initialized: true,
+                revoked: false,
+                accreditor,
+                issuer: *issuer_account.key,
+                schema,
+                expires_at,
+                delegation_depth,
+            };
+            Accreditation::pack(accreditation, &mut accreditation_account.try_borrow_mut_data()?)?;
+            IdentityEvent::AccreditationGranted {
+                accreditation: accreditation_key,
+                accreditor,
+                issuer: *issuer_account.key,
+                schema,
+                expires_at,
+                delegation_depth,
+            }
+            .emit();
+            msg!("[Accredit] {} accredited by {}", issuer_account.key, accreditor);
+            Ok(())
+        }
+        IdentityInstruction::RevokeAccreditation => {
+            // [accreditation_account, accreditor_account, organization_account (optional)]
+            let accreditation_account = next_account_info(account_info_iter)?;
+            let accreditor_account = next_account_info(account_info_iter)?;
+            let accreditor = validation::issuer(accreditor_account, next_account_info(account_info_iter).ok(), program_id)?;
+            let mut accreditation: Accreditation = validation::load_mut(accreditation_account, program_id)?;
+            if accreditation.accreditor != accreditor {
+                msg!("Only the accreditor can revoke the accreditation");
+                return Err(ProgramError::IllegalOwner);
+            }
+            if accreditation.revoked {
+                msg!("Accreditation is already revoked");
+                return Err(ProgramError::InvalidAccountData);
+            }
+            accreditation.revoked = true;
+            let issuer = accreditation.issuer;
+            Accreditation::pack(accreditation, &mut accreditation_account.try_borrow_mut_data()?)?;
+            IdentityEvent::AccreditationRevoked { accreditation: *accreditation_account.key, accreditor, issuer }.emit();
+            msg!("[RevokeAccreditation] Accreditation of {} revoked", issuer);
+            Ok(())
+        }
+        IdentityInstruction::QueryAccreditation { root, schema } => {
+            use solana_program::sysvar::clock::Clock;
+            // [credential_account, accreditation accounts from the issuer's up to the root's...]
+            let credential_account = next_account_info(account_info_iter)?;
+            let cred: Credential = validation::load(credential_account, program_id)?;
+            let chain = account_info_iter
+                .map(|account| validation::load::<Accreditation>(account, program_id).map(|accreditation| accreditation.to_core()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let now = idenclave_core::unix_now(Clock::get()?.unix_timestamp);
+            let report = idenclave_core::evaluate_chain(&cred.issuer.to_bytes(), &schema, &root.to_bytes(), &chain, now);
+            msg!("[QueryAccreditation] Issuer {} is {:?} after {} links", cred.issuer, report.status, report.path.len() - 1);
+            // The status and the path walked are returned like `QueryCredentialStatus`'s status byte
+            solana_program::program::set_return_data(&report.pack());
+            Ok(())
+        }
+        IdentityInstruction::RegisterCircuit { verifying_key } => {
+            // [circuit_account, authority_account]; the circuit account is allocated by the client,
+            // sized with Circuit::LEN and owned by this program
+            let circuit_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            validation::signer(authority_account)?;
+            let circuit: Circuit = validation::new_account(circuit_account, program_id)?;
+            // Requests refer to circuits by address, so a registered circuit is never replaced
+            if circuit.is_initialized {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            let public_inputs = verifying_key.public_inputs();
+            let circuit = Circuit { is_initialized: true, authority: *authority_account.key, verifying_key };
+            Circuit::pack(circuit, &mut circuit_account.try_borrow_mut_data()?)?;
+            msg!("[RegisterCircuit] Circuit with {} public inputs registered", public_inputs);
+            Ok(())
+        }
+        IdentityInstruction::CreatePresentationRequest { nonce, expires_at, schemas, trusted_issuers, predicate_inputs } => {
+            // [request_account, verifier_account, payer_account, system_program, circuit_account (optional)]
+            let request_account = next_account_info(account_info_iter)?;
+            let verifier_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            let circuit_account = next_account_info(account_info_iter).ok();
+            validation::signer(verifier_account)?;
+            validation::payer(payer_account)?;
+            validation::system_program(system_program)?;
+            let mut circuit_key = Pubkey::default();
+            if let Some(circuit_account) = circuit_account {
+                let circuit: Circuit = validation::load(circuit_account, program_id)?;
+                // The commitment signal of the first schema's credential follows the predicate inputs
+                if schemas.is_empty() {
+                    msg!("A circuit needs a schema whose credential the proof is about");
+                    return Err(ProgramError::InvalidArgument);
+                }
+                if predicate_inputs.len() >= circuit.verifying_key.public_inputs() {
+                    msg!("The circuit takes only {} public inputs", circuit.verifying_key.public_inputs());
+                    return Err(ProgramError::InvalidArgument);
+                }
+                circuit_key = *circuit_account.key;
+            } else if !predicate_inputs.is_empty() {
+                msg!("Predicate inputs need a circuit");
+                return Err(ProgramError::InvalidArgument);
+            }
+            let (request_key, bump) = PresentationRequest::find_address(verifier_account.key, nonce, program_id);
+            validation::address(request_account, &request_key)?;
+            validation::writable(request_account)?;
+            if request_account.owner == program_id {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            create_pda_account(
+                payer_account,
+                request_account,
+                system_program,
+                PresentationRequest::LEN,
+                program_id,
+                &[PresentationRequest::SEED, verifier_account.key.as_ref(), &nonce.to_le_bytes(), &[bump]],
+            )?;
+            let request = PresentationRequest {
+                is_initialized: true,
+                verifier: *verifier_account.key,
+                nonce,
+                expires_at,
+                schemas,
+                trusted_issuers,
+                circuit: circuit_key,
+                predicate_inputs,
+            };
+            PresentationRequest::pack(request, &mut request_account.try_borrow_mut_data()?)?;
+            IdentityEvent::PresentationRequested { request: request_key, verifier: *verifier_account.key }.emit();
+            msg!("[CreatePresentationRequest] Request {} created", request_key);
+            Ok(())
+        }
+        IdentityInstruction::RespondToRequest { nonces, proof } => {
+            use solana_program::sysvar::clock::Clock;
+            // [request_account, response_account, identity_account, authority_account, payer_account, system_program,
+            //  circuit_account (if the request names one), one credential account per schema of the request...]
+            let request_account = next_account_info(account_info_iter)?;
+            let response_account = next_account_info(account_info_iter)?;
+            let identity_account = next_account_info(account_info_iter)?;
+            let authority_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            validation::signer(authority_account)?;
+            validation::payer(payer_account)?;
+            validation::system_program(system_program)?;
+            let request: PresentationRequest = validation::load(request_account, program_id)?;
+            let identity: Identity = validation::load(identity_account, program_id)?;
+            if identity.authority != *authority_account.key {
+                msg!("Only the identity's authority can respond");
+                return Err(ProgramError::IllegalOwner);
+            }
+            let now = idenclave_core::unix_now(Clock::get()?.unix_timestamp);
+            let request = request.to_core();
+            if request.is_expired(now) {
+                msg!("Presentation request expired at {}", request.expires_at);
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let mut verified_inputs = None;
+            if request.has_circuit() {
+                let circuit_account = next_account_info(account_info_iter)?;
+                validation::address(circuit_account, &Pubkey::new_from_array(request.circuit))?;
+                let circuit: Circuit = validation::load(circuit_account, program_id)?;
+                verified_inputs = match &proof {
+                    Some((_, public_inputs)) if !request.accepts_inputs(public_inputs) => {
+                        msg!("Proof is not for the request's predicate inputs");
+                        None
+                    }
+                    Some((proof, public_inputs)) => {
+                        let verified = idenclave_core::groth16::verify::<presentation::AltBn128>(&circuit.verifying_key, proof, public_inputs);
+                        if let Err(err) = &verified {
+                            msg!("Proof does not verify: {}", err);
+                        }
+                        verified.ok().map(|_| public_inputs.as_slice())
+                    }
+                    None => None,
+                };
+            }
+
+            // A credential is shown to be of a schema by sitting at the address derived from it
+            let mut presented = Vec::with_capacity(nonces.len());
+            for (schema, nonce) in request.schemas.iter().zip(&nonces) {
+                let credential_account = next_account_info(account_info_iter)?;
+                let cred: Credential = validation::load(credential_account, program_id)?;
+                let (expected, _) = Credential::find_address(&cred.issuer, &cred.identity, schema, *nonce, program_id);
+                presented.push((expected == *credential_account.key).then(|| idenclave_core::PresentedCredential {
+                    identity: cred.identity.to_bytes(),
+                    issuer: cred.issuer.to_bytes(),
+                    status: cred.status(now),
+                    commitment: cred.commitment,
+                }));
+            }
+            let result = request.evaluate(&identity_account.key.to_bytes(), &presented, verified_inputs);
+
+            // A later response replaces the recorded one
+            let (response_key, bump) = PresentationResponse::find_address(request_account.key, identity_account.key, program_id);
+            validation::address(response_account, &response_key)?;
+            if response_account.owner != program_id {
+                create_pda_account(
+                    payer_account,
+                    response_account,
+                    system_program,
+                    PresentationResponse::LEN,
+                    program_id,
+                    &[PresentationResponse::SEED, request_account.key.as_ref(), identity_account.key.as_ref(), &[bump]],
+                )?;
+            } else {
+                validation::load_mut::<PresentationResponse>(response_account, program_id)?;
+            }
+            let response = PresentationResponse {
+                is_initialized: true,
+                request: *request_account.key,
+                identity: *identity_account.key,
+                result: result as u8,
+                responded_at: now,
+            };
+            PresentationResponse::pack(response, &mut response_account.try_borrow_mut_data()?)?;
+            IdentityEvent::PresentationResponded { request: *request_account.key, identity: *identity_account.key, result: result as u8 }.emit();
+            msg!("[RespondToRequest] {} responded to {}: {:?}", identity_account.key, request_account.key, result);
+            solana_program::program::set_return_data(&[result as u8]);
+            Ok(())
+        }
+        IdentityInstruction::RecordVerification { id, proof_hash } => {
+            use solana_program::sysvar::clock::Clock;
+            // [receipt_account, verifier_account, credential_account, payer_account, system_program]
+            let receipt_account = next_account_info(account_info_iter)?;
+            let verifier_account = next_account_info(account_info_iter)?;
+            let credential_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            validation::signer(verifier_account)?;
+            validation::payer(payer_account)?;
+            validation::system_program(system_program)?;
+            let cred: Credential = validation::load(credential_account, program_id)?;
+            let (receipt_key, bump) = VerificationReceipt::find_address(verifier_account.key, credential_account.key, id, program_id);
+            validation::address(receipt_account, &receipt_key)?;
+            validation::writable(receipt_account)?;
+            // Receipts are an audit trail, so an existing one is never overwritten
+            if receipt_account.owner == program_id {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            let clock = Clock::get()?;
+            let timestamp = idenclave_core::unix_now(clock.unix_timestamp);
+            let status = cred.status(timestamp);
+            create_pda_account(
+                payer_account,
+                receipt_account,
+                system_program,
+                VerificationReceipt::LEN,
+                program_id,
+                &[VerificationReceipt::SEED, verifier_account.key.as_ref(), credential_account.key.as_ref(), &id.to_le_bytes(), &[bump]],
+            )?;
+            let receipt = VerificationReceipt {
+                is_initialized: true,
+                verifier: *verifier_account.key,
+                credential: *credential_account.key,
+                id,
+                result: status as u8,
+                slot: clock.slot,
+                timestamp,
+                proof_hash: proof_hash.unwrap_or_default(),
+            };
+            VerificationReceipt::pack(receipt, &mut receipt_account.try_borrow_mut_data()?)?;
+            IdentityEvent::VerificationRecorded {
+                receipt: receipt_key,
+                verifier: *verifier_account.key,
+                credential: *credential_account.key,
+                result: status as u8,
+            }
+            .emit();
+            msg!("[RecordVerification] {} found credential {} {:?}", verifier_account.key, credential_account.key, status);
+            solana_program::program::set_return_data(&[status as u8]);
+            Ok(())
+        }
+        IdentityInstruction::CloseVerificationReceipt => {
+            // [receipt_account, verifier_account, recipient_account]
+            let receipt_account = next_account_info(account_info_iter)?;
+            let verifier_account = next_account_info(account_info_iter)?;
+            let recipient_account = next_account_info(account_info_iter)?;
+            validation::signer(verifier_account)?;
+            validation::writable(recipient_account)?;
+            let receipt: VerificationReceipt = validation::load_mut(receipt_account, program_id)?;
+            if receipt.verifier != *verifier_account.key {
+                msg!("Only the verifier can close the receipt");
+                return Err(ProgramError::IllegalOwner);
+            }
+            close_program_account(receipt_account, recipient_account)?;
+            IdentityEvent::VerificationReceiptClosed { receipt: *receipt_account.key, verifier: receipt.verifier }.emit();
+            msg!("[CloseVerificationReceipt] Receipt closed");
+            Ok(())
+        }
+        IdentityInstruction::VerifyClaim { proof } => {
+            use solana_program::sysvar::clock::Clock;
+            // [credential_account]
+            let credential_account = next_account_info(account_info_iter)?;
+            let cred: Credential = validation::load(credential_account, program_id)?;
+            proof.verify::<Sha256Hasher>(&cred.claims_root).map_err(claim_error)?;
+            // The claim is part of the credential either way; whether it still vouches for it is up to the status
+            let status = cred.status(idenclave_core::unix_now(Clock::get()?.unix_timestamp));
+            msg!("[VerifyClaim] Claim {} verified, credential is {:?}", proof.claim.name, status);
+            solana_program::program::set_return_data(&[status as u8]);
+            Ok(())
+        }
+        IdentityInstruction::CommitClaims { claim_hashes } => {
+            // [credential_account, issuer_account]
+            let credential_account = next_account_info(account_info_iter)?;
+            let issuer_account = next_account_info(account_info_iter)?;
+            // An organization account may follow: the signer then acts for the organization
+            let organization_account = next_account_info(account_info_iter).ok();
+            let issuer = validation::issuer(issuer_account, organization_account, program_id)?;
+            let mut cred: Credential = validation::load_mut(credential_account, program_id)?;
+            if cred.issuer != issuer {
+                return Err(ProgramError::IllegalOwner);
+            }
+            cred.check_commit().map_err(rule_error)?;
+            // Proofs made against a commitment must keep meaning the same claims; re-issuing clears it
+            if cred.commitment != [0u8; 32] {
+                msg!("Credential claims are already committed");
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            let commitment = idenclave_core::claims_commitment::<presentation::PoseidonSyscall>(&claim_hashes).ok_or_else(|| {
+                msg!("Claim hashes must be one to {} field elements", idenclave_core::MAX_COMMITTED_CLAIMS);
+                ProgramError::InvalidArgument
+            })?;
+            cred.commitment = commitment;
+            Credential::pack(cred, &mut credential_account.try_borrow_mut_data()?)?;
+            IdentityEvent::ClaimsCommitted { credential: *credential_account.key, issuer, commitment }.emit();
+            msg!("[CommitClaims] Committed to {} claims", claim_hashes.len());
+            Ok(())
         }
         IdentityInstruction::QueryCredentialStatus => {
             use solana_program::sysvar::{clock::Clock, Sysvar};
             // [credential_account]
             let credential_account = next_account_info(account_info_iter)?;
-            let cred_data = credential_account.try_borrow_data()?;
-            let cred = Credential::unpack(&cred_data)?;
+            let cred: Credential = validation::load(credential_account, program_id)?;
             let clock = Clock::get()?;
-            if cred.revoked {
-                msg!("[QueryCredentialStatus] Credential is revoked");
-            } else if clock.unix_timestamp as u64 > cred.expires_at {
-                msg!("[QueryCredentialStatus] Credential is expired");
-            } else {
-                msg!("[QueryCredentialStatus] Credential is valid");
+            let status = cred.status(idenclave_core::unix_now(clock.unix_timestamp));
+            match status {
+                CredentialStatus::Revoked => msg!("[QueryCredentialStatus] Credential is revoked"),
+                CredentialStatus::Pending => msg!("[QueryCredentialStatus] Credential offer is pending acceptance"),
+                CredentialStatus::Superseded => msg!("[QueryCredentialStatus] Credential is superseded by {}", cred.superseded_by),
+                CredentialStatus::Suspended => msg!("[QueryCredentialStatus] Credential is suspended"),
+                CredentialStatus::Expired => msg!("[QueryCredentialStatus] Credential is expired"),
+                CredentialStatus::Valid => msg!("[QueryCredentialStatus] Credential is valid"),
             }
-            return Ok(());
+            // Also expose the status as return data so clients can read it from a simulation
+            solana_program::program::set_return_data(&[status as u8]);
+            Ok(())
         }
         IdentityInstruction::RecoverAuthority { new_authority } => {
             let identity_account = next_account_info(account_info_iter)?;
             let authority_account = next_account_info(account_info_iter)?;
-            if !authority_account.is_signer {
-                msg!("Authority signature missing");
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            let mut identity_data = identity_account.try_borrow_mut_data()?;
-            let mut identity = Identity::unpack(&identity_data)?;
+            validation::signer(authority_account)?;
+            let mut identity: Identity = validation::load_mut(identity_account, program_id)?;
             // The signer must be the recovery address
-            if !identity.is_initialized || identity.recovery != *authority_account.key {
+            if identity.recovery != *authority_account.key {
                 msg!("Only recovery address can recover authority");
                 return Err(ProgramError::IllegalOwner);
             }
             identity.authority = new_authority;
-            Identity::pack(identity, &mut identity_data)?;
+            Identity::pack(identity, &mut identity_account.try_borrow_mut_data()?)?;
             msg!("[RecoverAuthority] Authority recovered");
-            return Ok(());
+            Ok(())
         }
         IdentityInstruction::CloseAccount => {
-            // [identity_account, authority_account, recipient_account]
+            // [identity_account, authority_account, recipient_account, tombstone_account, payer_account, system_program]
             let identity_account = next_account_info(account_info_iter)?;
             let authority_account = next_account_info(account_info_iter)?;
             let recipient_account = next_account_info(account_info_iter)?;
-            if !authority_account.is_signer {
-                msg!("Authority signature missing");
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            let identity_data = identity_account.try_borrow_mut_data()?;
-            let identity = Identity::unpack(&identity_data)?;
-            if !identity.is_initialized || identity.authority != *authority_account.key {
+            let tombstone_account = next_account_info(account_info_iter)?;
+            let payer_account = next_account_info(account_info_iter)?;
+            let system_program = next_account_info(account_info_iter)?;
+            validation::signer(authority_account)?;
+            validation::writable(recipient_account)?;
+            validation::writable(tombstone_account)?;
+            validation::system_program(system_program)?;
+            let identity: Identity = validation::load_mut(identity_account, program_id)?;
+            if identity.authority != *authority_account.key {
                 msg!("Only current authority can close");
                 return Err(ProgramError::IllegalOwner);
             }
-            msg!("[CloseAccount] Start");
-            // Unpack identity, drop data borrow before mutably borrowing lamports
-            {
-                let identity_data = identity_account.try_borrow_data()?;
-                let identity = Identity::unpack(&identity_data)?;
-                msg!("[CloseAccount] Unpacked identity, is_initialized={}, authority={}", identity.is_initialized as u8, identity.authority);
-                if !identity.is_initialized || identity.authority != *authority_account.key {
-                    msg!("Only current authority can close");
-                    return Err(ProgramError::IllegalOwner);
-                }
+            if identity.active_credentials > 0 {
+                msg!("Identity still has {} credentials that have not been closed", identity.active_credentials);
+                return Err(ProgramError::InvalidAccountData);
             }
-            msg!("[CloseAccount] Passed authority check");
-            // Transfer lamports to recipient (must do before mutably borrowing data again)
-            let rent = solana_program::rent::Rent::get()?;
-            let min_balance = rent.minimum_balance(identity_account.data_len());
-            let lamports = **identity_account.lamports.borrow();
-            let to_transfer = lamports.saturating_sub(min_balance);
-            msg!("[CloseAccount] lamports={}, min_balance={}, to_transfer={}", lamports, min_balance, to_transfer);
-            if to_transfer > 0 {
-                **recipient_account.lamports.borrow_mut() += to_transfer;
-                **identity_account.lamports.borrow_mut() -= to_transfer;
-                msg!("Transferred {} lamports to recipient, left {} for rent-exemption", to_transfer, **identity_account.lamports.borrow());
-            } else {
-                msg!("No lamports available for transfer, account at rent-exempt minimum");
-            }
-            // Mark account as closed
-            let mut identity_data = identity_account.try_borrow_mut_data()?;
-            let mut identity = Identity::unpack(&identity_data)?;
-            identity.is_initialized = false;
-            Identity::pack(identity, &mut identity_data)?;
-            msg!("[CloseAccount] Marked account as closed (is_initialized = false)");
-            return Ok(());
+            let (tombstone_key, bump) = Tombstone::find_address(identity_account.key, program_id);
+            validation::address(tombstone_account, &tombstone_key)?;
+            if tombstone_account.owner != program_id {
+                // The payer funds the tombstone, the identity's own lamports all go to the recipient
+                validation::payer(payer_account)?;
+                create_pda_account(
+                    payer_account,
+                    tombstone_account,
+                    system_program,
+                    Tombstone::LEN,
+                    program_id,
+                    &[Tombstone::SEED, identity_account.key.as_ref(), &[bump]],
+                )?;
+            }
+            let tombstone = Tombstone { is_initialized: true, authority: *authority_account.key };
+            Tombstone::pack(tombstone, &mut tombstone_account.try_borrow_mut_data()?)?;
+            close_program_account(identity_account, recipient_account)?;
+            msg!("[CloseAccount] Identity closed");
+            Ok(())
         }
     }
 }
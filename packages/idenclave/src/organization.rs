@@ -0,0 +1,97 @@
+use idenclave_core::{AccountLayout, OrganizationError, MAX_ORGANIZATION_KEYS};
+use solana_program::{
+    msg,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    program_error::ProgramError,
+};
+
+/// An issuer organization: a set of signing keys credentials can be issued and managed with,
+/// which records the organization as their issuer. Stored in the PDA `[b"organization", authority]`;
+/// the authority manages the keys.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Organization {
+    pub is_initialized: bool,
+    pub authority: Pubkey,
+    pub keys: Vec<Pubkey>, // Currently authorized signing keys, at most MAX_ORGANIZATION_KEYS
+}
+
+impl Organization {
+    pub const SEED: &'static [u8] = idenclave_core::Organization::SEED;
+
+    pub fn find_address(authority: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, authority.as_ref()], program_id)
+    }
+
+    pub fn is_authorized(&self, key: &Pubkey) -> bool {
+        self.keys.contains(key)
+    }
+
+    pub fn add_key(&mut self, key: &Pubkey) -> Result<(), ProgramError> {
+        self.update_keys(|organization| organization.add_key(&key.to_bytes()))
+    }
+
+    pub fn remove_key(&mut self, key: &Pubkey) -> Result<(), ProgramError> {
+        self.update_keys(|organization| organization.remove_key(&key.to_bytes()))
+    }
+
+    pub fn rotate_key(&mut self, old: &Pubkey, new: &Pubkey) -> Result<(), ProgramError> {
+        self.update_keys(|organization| organization.rotate_key(&old.to_bytes(), &new.to_bytes()))
+    }
+
+    /// Applies a key change with idenclave-core's rules.
+    fn update_keys(
+        &mut self,
+        change: impl FnOnce(&mut idenclave_core::Organization) -> Result<(), OrganizationError>,
+    ) -> Result<(), ProgramError> {
+        let mut organization = self.to_core();
+        if let Err(error) = change(&mut organization) {
+            msg!("Organization keys: {}", error);
+            return Err(ProgramError::InvalidArgument);
+        }
+        *self = Self::from_core(&organization);
+        Ok(())
+    }
+
+    fn to_core(&self) -> idenclave_core::Organization {
+        let mut keys = [[0u8; 32]; MAX_ORGANIZATION_KEYS];
+        for (slot, key) in keys.iter_mut().zip(&self.keys) {
+            *slot = key.to_bytes();
+        }
+        idenclave_core::Organization {
+            is_initialized: self.is_initialized,
+            authority: self.authority.to_bytes(),
+            key_count: self.keys.len().min(MAX_ORGANIZATION_KEYS) as u8,
+            keys,
+        }
+    }
+
+    fn from_core(organization: &idenclave_core::Organization) -> Self {
+        Organization {
+            is_initialized: organization.is_initialized,
+            authority: Pubkey::new_from_array(organization.authority),
+            keys: organization.keys().iter().map(|key| Pubkey::new_from_array(*key)).collect(),
+        }
+    }
+}
+
+impl IsInitialized for Organization {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Organization {}
+
+impl Pack for Organization {
+    const LEN: usize = idenclave_core::Organization::ACCOUNT_LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        self.to_core().pack_account(dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let organization = idenclave_core::Organization::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Self::from_core(&organization))
+    }
+}
@@ -0,0 +1,206 @@
+use idenclave_core::groth16::{G1, Scalar};
+use idenclave_core::{AccountLayout, Bn254, PoseidonHasher, VerifyingKey};
+use solana_program::{
+    alt_bn128::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing},
+    poseidon::{hashv, Endianness, Parameters},
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    program_error::ProgramError,
+};
+
+/// BN254 through the `alt_bn128` syscalls, for verifying presentation proofs.
+pub struct AltBn128;
+
+impl Bn254 for AltBn128 {
+    fn add(a: &G1, b: &G1) -> Option<G1> {
+        alt_bn128_addition(&[&a[..], &b[..]].concat()).ok()?.try_into().ok()
+    }
+
+    fn mul(point: &G1, scalar: &Scalar) -> Option<G1> {
+        alt_bn128_multiplication(&[&point[..], &scalar[..]].concat()).ok()?.try_into().ok()
+    }
+
+    fn pairing_is_one(pairs: &[u8]) -> Option<bool> {
+        let result = alt_bn128_pairing(pairs).ok()?;
+        Some(result.last() == Some(&1) && result[..result.len() - 1].iter().all(|&b| b == 0))
+    }
+}
+
+/// Poseidon through the `sol_poseidon` syscall, for the claim commitments proofs bind to.
+pub struct PoseidonSyscall;
+
+impl PoseidonHasher for PoseidonSyscall {
+    fn hashv(inputs: &[Scalar]) -> Option<Scalar> {
+        let inputs: Vec<&[u8]> = inputs.iter().map(|input| &input[..]).collect();
+        Some(hashv(Parameters::Bn254X5, Endianness::BigEndian, &inputs).ok()?.to_bytes())
+    }
+}
+
+/// A circuit presentation requests can require proofs for. Registered into an account the client
+/// created (`Circuit::LEN` bytes, owned by this program) and never changed afterwards.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Circuit {
+    pub is_initialized: bool,
+    pub authority: Pubkey,
+    pub verifying_key: VerifyingKey,
+}
+
+impl IsInitialized for Circuit {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Circuit {}
+
+impl Pack for Circuit {
+    const LEN: usize = idenclave_core::Circuit::ACCOUNT_LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        idenclave_core::Circuit {
+            is_initialized: self.is_initialized,
+            authority: self.authority.to_bytes(),
+            verifying_key: self.verifying_key.clone(),
+        }
+        .pack_account(dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let circuit = idenclave_core::Circuit::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Circuit {
+            is_initialized: circuit.is_initialized,
+            authority: Pubkey::new_from_array(circuit.authority),
+            verifying_key: circuit.verifying_key,
+        })
+    }
+}
+
+/// A verifier's presentation request, stored in the PDA `[b"presentation-request", verifier, nonce]`.
+/// See `idenclave_core::PresentationRequest` for what a response has to present.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PresentationRequest {
+    pub is_initialized: bool,
+    pub verifier: Pubkey,
+    pub nonce: u64,
+    pub expires_at: u64,
+    pub schemas: Vec<[u8; 32]>,
+    pub trusted_issuers: Vec<Pubkey>,
+    pub circuit: Pubkey, // Circuit account a proof is required for; the default key for none
+    pub predicate_inputs: Vec<Scalar>,
+}
+
+impl PresentationRequest {
+    pub const SEED: &'static [u8] = idenclave_core::PresentationRequest::SEED;
+
+    pub fn find_address(verifier: &Pubkey, nonce: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, verifier.as_ref(), &nonce.to_le_bytes()], program_id)
+    }
+
+    pub fn to_core(&self) -> idenclave_core::PresentationRequest {
+        idenclave_core::PresentationRequest {
+            is_initialized: self.is_initialized,
+            verifier: self.verifier.to_bytes(),
+            nonce: self.nonce,
+            expires_at: self.expires_at,
+            schemas: self.schemas.clone(),
+            trusted_issuers: self.trusted_issuers.iter().map(|issuer| issuer.to_bytes()).collect(),
+            circuit: self.circuit.to_bytes(),
+            predicate_inputs: self.predicate_inputs.clone(),
+        }
+    }
+}
+
+impl IsInitialized for PresentationRequest {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for PresentationRequest {}
+
+impl Pack for PresentationRequest {
+    const LEN: usize = idenclave_core::PresentationRequest::ACCOUNT_LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        self.to_core().pack_account(dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let request = idenclave_core::PresentationRequest::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(PresentationRequest {
+            is_initialized: request.is_initialized,
+            verifier: Pubkey::new_from_array(request.verifier),
+            nonce: request.nonce,
+            expires_at: request.expires_at,
+            schemas: request.schemas,
+            trusted_issuers: request.trusted_issuers.into_iter().map(Pubkey::new_from_array).collect(),
+            circuit: Pubkey::new_from_array(request.circuit),
+            predicate_inputs: request.predicate_inputs,
+        })
+    }
+}
+
+/// The recorded response of an identity to a presentation request, stored in the PDA
+/// `[b"presentation-response", request, identity]`. `result` is an
+/// `idenclave_core::PresentationResult`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PresentationResponse {
+    pub is_initialized: bool,
+    pub request: Pubkey,
+    pub identity: Pubkey,
+    pub result: u8,
+    pub responded_at: u64,
+}
+
+impl PresentationResponse {
+    pub const SEED: &'static [u8] = idenclave_core::PresentationResponse::SEED;
+
+    pub fn find_address(request: &Pubkey, identity: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, request.as_ref(), identity.as_ref()], program_id)
+    }
+}
+
+impl IsInitialized for PresentationResponse {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for PresentationResponse {}
+
+impl Pack for PresentationResponse {
+    const LEN: usize = idenclave_core::PresentationResponse::ACCOUNT_LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        idenclave_core::PresentationResponse {
+            is_initialized: self.is_initialized,
+            request: self.request.to_bytes(),
+            identity: self.identity.to_bytes(),
+            result: self.result,
+            responded_at: self.responded_at,
+        }
+        .pack_account(dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let response = idenclave_core::PresentationResponse::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(PresentationResponse {
+            is_initialized: response.is_initialized,
+            request: Pubkey::new_from_array(response.request),
+            identity: Pubkey::new_from_array(response.identity),
+            result: response.result,
+            responded_at: response.responded_at,
+        })
+    }
+}
+
+/// Reads a u8 count followed by that many 32-byte values, at most `max`, from the front of `src`.
+pub(crate) fn split_list(src: &[u8], max: usize) -> Result<(Vec<[u8; 32]>, &[u8]), ProgramError> {
+    let (&count, rest) = src.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+    let count = count as usize;
+    if count > max || rest.len() < count * 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let values = rest[..count * 32].chunks_exact(32).map(|value| value.try_into().unwrap()).collect();
+    Ok((values, &rest[count * 32..]))
+}
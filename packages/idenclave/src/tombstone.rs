@@ -0,0 +1,45 @@
+use idenclave_core::AccountLayout;
+use solana_program::{
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    program_error::ProgramError,
+};
+
+/// Record left behind when an identity account is closed, so the address cannot be
+/// re-registered by anyone other than the authority that closed it.
+/// Stored in the PDA `[b"tombstone", identity]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tombstone {
+    pub is_initialized: bool,
+    pub authority: Pubkey, // Authority of the identity at the time it was closed
+}
+
+impl Tombstone {
+    pub const SEED: &'static [u8] = idenclave_core::Tombstone::SEED;
+
+    pub fn find_address(identity: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, identity.as_ref()], program_id)
+    }
+}
+
+impl IsInitialized for Tombstone {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Tombstone {}
+
+impl Pack for Tombstone {
+    const LEN: usize = idenclave_core::Tombstone::ACCOUNT_LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        idenclave_core::Tombstone { is_initialized: self.is_initialized, authority: self.authority.to_bytes() }
+            .pack_account(dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let tombstone = idenclave_core::Tombstone::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Tombstone { is_initialized: tombstone.is_initialized, authority: Pubkey::new_from_array(tombstone.authority) })
+    }
+}
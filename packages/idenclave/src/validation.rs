@@ -0,0 +1,138 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    rent::Rent,
+    ed25519_program, system_program,
+    sysvar::{instructions, Sysvar},
+};
+
+use crate::Organization;
+
+// Checks applied to the accounts an instruction receives before any of their data is trusted.
+// Every handler goes through these so a wrong-type, foreign-owned or read-only account
+// is rejected the same way everywhere.
+
+pub fn signer(account: &AccountInfo) -> ProgramResult {
+    if !account.is_signer {
+        msg!("Missing signature for {}", account.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+pub fn writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        msg!("Account {} must be writable", account.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Funds account creation: signs and has its lamports debited. May be any account, e.g. a sponsor
+/// onboarding users, independent of the authority or issuer authorizing the instruction.
+pub fn payer(account: &AccountInfo) -> ProgramResult {
+    signer(account)?;
+    writable(account)
+}
+
+pub fn owned_by_program(account: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+    if account.owner != program_id {
+        msg!("Account {} is not owned by this program", account.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+pub fn rent_exempt(account: &AccountInfo) -> ProgramResult {
+    if !Rent::get()?.is_exempt(account.lamports(), account.data_len()) {
+        msg!("Account {} is not rent exempt", account.key);
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    Ok(())
+}
+
+/// The account must sit at `expected`, typically a PDA derived by the caller.
+pub fn address(account: &AccountInfo, expected: &Pubkey) -> ProgramResult {
+    if account.key != expected {
+        msg!("Expected account {} but got {}", expected, account.key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+pub fn system_program(account: &AccountInfo) -> ProgramResult {
+    if *account.key != system_program::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Resolves the issuer `signer` acts as. Without an organization that is the signer itself; with
+/// one, the signer must be among the organization's current keys and acts for the organization,
+/// which is what credentials record as their issuer.
+pub fn issuer(signer: &AccountInfo, organization: Option<&AccountInfo>, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+    self::signer(signer)?;
+    acting_issuer(signer.key, organization, program_id)
+}
+
+/// Like `issuer`, for a key that authorizes with an Ed25519 signature over the instruction's
+/// terms rather than by signing the transaction.
+pub fn acting_issuer(key: &Pubkey, organization: Option<&AccountInfo>, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+    let Some(organization_account) = organization else {
+        return Ok(*key);
+    };
+    let organization: Organization = load(organization_account, program_id)?;
+    if !organization.is_authorized(key) {
+        msg!("{} is not a signing key of organization {}", key, organization_account.key);
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(*organization_account.key)
+}
+
+/// Loads an initialized program account of type `T`. The account must be owned by this program
+/// and carry `T`'s discriminator, so e.g. a credential can never be read as an identity.
+pub fn load<T: Pack + IsInitialized>(account: &AccountInfo, program_id: &Pubkey) -> Result<T, ProgramError> {
+    owned_by_program(account, program_id)?;
+    T::unpack(&account.try_borrow_data()?)
+}
+
+/// Like `load`, for an account the instruction is going to write.
+pub fn load_mut<T: Pack + IsInitialized>(account: &AccountInfo, program_id: &Pubkey) -> Result<T, ProgramError> {
+    writable(account)?;
+    load(account, program_id)
+}
+
+/// Checks an account the client allocated for the instruction to fill in: owned by this program,
+/// writable, rent exempt and sized for `T`, with either no data yet or `T`'s discriminator.
+/// Returns its current contents so the caller can decide what to do with an initialized one.
+pub fn new_account<T: Pack + IsInitialized>(account: &AccountInfo, program_id: &Pubkey) -> Result<T, ProgramError> {
+    owned_by_program(account, program_id)?;
+    writable(account)?;
+    rent_exempt(account)?;
+    T::unpack_unchecked(&account.try_borrow_data()?)
+}
+
+/// The instruction right before the current one must be an Ed25519 program instruction verifying
+/// `signer`'s signature over exactly `message`. The Ed25519 program fails the whole transaction on a
+/// bad signature, so finding it there is proof that `signer` signed.
+pub fn ed25519_signature(instructions_sysvar: &AccountInfo, signer: &Pubkey, message: &[u8]) -> ProgramResult {
+    address(instructions_sysvar, &instructions::ID)?;
+    let current = instructions::load_current_index_checked(instructions_sysvar)?;
+    let previous = current.checked_sub(1).ok_or(ProgramError::MissingRequiredSignature)?;
+    let ix = instructions::load_instruction_at_checked(previous as usize, instructions_sysvar)?;
+    if ix.program_id != ed25519_program::ID {
+        msg!("Expected an Ed25519 signature verification before this instruction");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    match idenclave_core::issuance::parse_ed25519_instruction(&ix.data) {
+        Some((pubkey, signed)) if pubkey == signer.to_bytes() && signed == message => Ok(()),
+        _ => {
+            msg!("Ed25519 instruction does not carry {}'s signature over the expected message", signer);
+            Err(ProgramError::MissingRequiredSignature)
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use idenclave_core::AccountLayout;
+use solana_program::{
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+    program_error::ProgramError,
+};
+
+/// A verifier's record of having checked a credential, stored in the PDA
+/// `[b"verification-receipt", verifier, credential, id]`. See `idenclave_core::VerificationReceipt`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VerificationReceipt {
+    pub is_initialized: bool,
+    pub verifier: Pubkey,
+    pub credential: Pubkey,
+    pub id: u64,
+    pub result: u8,           // Credential status at the time of the check
+    pub slot: u64,
+    pub timestamp: u64,
+    pub proof_hash: [u8; 32], // Hash of off-chain evidence, all zeros for none
+}
+
+impl VerificationReceipt {
+    pub const SEED: &'static [u8] = idenclave_core::VerificationReceipt::SEED;
+
+    pub fn find_address(verifier: &Pubkey, credential: &Pubkey, id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, verifier.as_ref(), credential.as_ref(), &id.to_le_bytes()], program_id)
+    }
+}
+
+impl IsInitialized for VerificationReceipt {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for VerificationReceipt {}
+
+impl Pack for VerificationReceipt {
+    const LEN: usize = idenclave_core::VerificationReceipt::ACCOUNT_LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        idenclave_core::VerificationReceipt {
+            is_initialized: self.is_initialized,
+            verifier: self.verifier.to_bytes(),
+            credential: self.credential.to_bytes(),
+            id: self.id,
+            result: self.result,
+            slot: self.slot,
+            timestamp: self.timestamp,
+            proof_hash: self.proof_hash,
+        }
+        .pack_account(dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let receipt = idenclave_core::VerificationReceipt::unpack_account(src).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(VerificationReceipt {
+            is_initialized: receipt.is_initialized,
+            verifier: Pubkey::new_from_array(receipt.verifier),
+            credential: Pubkey::new_from_array(receipt.credential),
+            id: receipt.id,
+            result: receipt.result,
+            slot: receipt.slot,
+            timestamp: receipt.timestamp,
+            proof_hash: receipt.proof_hash,
+        })
+    }
+}
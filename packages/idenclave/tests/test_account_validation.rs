@@ -0,0 +1,157 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{Credential, CredentialIndex, Identity, Tombstone};
+use std::str::FromStr;
+
+fn packed<T: Pack>(value: T, owner: Pubkey) -> Account {
+    let mut data = vec![0u8; T::LEN];
+    T::pack(value, &mut data).unwrap();
+    Account { lamports: 1_000_000_000, data, owner, ..Account::default() }
+}
+
+#[tokio::test]
+async fn test_account_validation() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let issuer = Keypair::new();
+    for kp in [&authority, &issuer] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+
+    // A valid identity and credential, plus accounts that carry valid bytes of the wrong kind
+    let identity = Pubkey::new_unique();
+    let identity_state = Identity { is_initialized: true, authority: authority.pubkey(), ..Identity::default() };
+    program_test.add_account(identity, packed(identity_state.clone(), program_id));
+    let credential = Pubkey::new_unique();
+    let credential_state = Credential { is_initialized: true, identity, issuer: issuer.pubkey(), ..Credential::default() };
+    program_test.add_account(credential, packed(credential_state.clone(), program_id));
+    let foreign_identity = Pubkey::new_unique();
+    program_test.add_account(foreign_identity, packed(identity_state, Pubkey::new_unique()));
+    let index_entry = Pubkey::new_unique();
+    let entry = CredentialIndex { is_initialized: true, identity, index: 0, credential };
+    program_test.add_account(index_entry, packed(entry, program_id));
+
+    // Program owned, still empty accounts for the instructions that create state
+    let fresh = |lamports: u64, len: usize| Account { lamports, data: vec![0; len], owner: program_id, ..Account::default() };
+    let new_identity = Pubkey::new_unique();
+    program_test.add_account(new_identity, fresh(1_000_000_000, Identity::LEN));
+    // Credentials live at the address derived from their issuer, identity, schema and nonce; one
+    // already sits at nonce 1
+    let schema = [3u8; 32];
+    let credential_at = |nonce: u64| Credential::find_address(&issuer.pubkey(), &identity, &schema, nonce, &program_id).0;
+    program_test.add_account(credential_at(1), packed(credential_state, program_id));
+
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+
+    let set_attributes = |identity: Pubkey, writable: bool| Instruction {
+        program_id,
+        accounts: vec![
+            if writable { AccountMeta::new(identity, false) } else { AccountMeta::new_readonly(identity, false) },
+            AccountMeta::new_readonly(authority.pubkey(), true),
+        ],
+        data: vec![3, 1, 2, 3],
+    };
+    let revoke = |credential: Pubkey| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(credential, false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+        ],
+        data: vec![7],
+    };
+    let issue = |nonce: u64, credential: Pubkey, system_program: Pubkey| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity, false),
+            AccountMeta::new_readonly(authority.pubkey(), true),
+            AccountMeta::new(credential, false),
+            AccountMeta::new(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(CredentialIndex::find_address(&identity, 0, &program_id).0, false),
+            AccountMeta::new_readonly(system_program, false),
+        ],
+        data: {
+            let mut data = vec![6];
+            data.extend_from_slice(&schema);
+            data.extend_from_slice(&nonce.to_le_bytes());
+            data.extend_from_slice(&[3u8; 32]);
+            data.extend_from_slice(&1_000u64.to_le_bytes());
+            data.extend_from_slice(&u64::MAX.to_le_bytes());
+            data
+        },
+    };
+
+    let expect_error = |ix: Instruction, expected: InstructionError| {
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&authority.pubkey()));
+        let signers: Vec<&Keypair> = if tx.message.account_keys.contains(&issuer.pubkey()) { vec![&authority, &issuer] } else { vec![&authority] };
+        tx.sign(&signers, recent_blockhash);
+        let mut banks_client = context.banks_client.clone();
+        async move {
+            let err = banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+            assert_eq!(err, TransactionError::InstructionError(0, expected));
+        }
+    };
+
+    // A credential cannot stand in for an identity, nor can an identity owned by another program
+    expect_error(set_attributes(credential, true), InstructionError::InvalidAccountData).await;
+    expect_error(set_attributes(foreign_identity, true), InstructionError::IncorrectProgramId).await;
+    // An identity the instruction is about to modify must be passed writable
+    expect_error(set_attributes(identity, false), InstructionError::InvalidArgument).await;
+
+    // Neither an identity nor a credential index entry can be revoked or queried as a credential
+    expect_error(revoke(identity), InstructionError::InvalidAccountData).await;
+    expect_error(revoke(index_entry), InstructionError::InvalidAccountData).await;
+    let query = Instruction { program_id, accounts: vec![AccountMeta::new_readonly(index_entry, false)], data: vec![8] };
+    expect_error(query, InstructionError::InvalidAccountData).await;
+
+    // A new credential must be created at its derived address, never over an existing one, and only
+    // the real system program may fund it
+    let system_program = solana_sdk::system_program::ID;
+    expect_error(issue(0, Pubkey::new_unique(), system_program), InstructionError::InvalidSeeds).await;
+    expect_error(issue(1, credential_at(1), system_program), InstructionError::AccountAlreadyInitialized).await;
+    expect_error(issue(0, credential_at(0), Pubkey::new_unique()), InstructionError::IncorrectProgramId).await;
+
+    // Registration must be given the identity's own tombstone PDA
+    let register = |tombstone: Pubkey| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(new_identity, false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(tombstone, false),
+        ],
+        data: vec![0],
+    };
+    expect_error(register(Pubkey::new_unique()), InstructionError::InvalidSeeds).await;
+
+    // The same accounts pass once they are used for what they are
+    let mut tx = Transaction::new_with_payer(
+        &[
+            set_attributes(identity, true),
+            issue(0, credential_at(0), system_program),
+            register(Tombstone::find_address(&new_identity, &program_id).0),
+        ],
+        Some(&authority.pubkey()),
+    );
+    tx.sign(&[&authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let identity_account = context.banks_client.get_account(identity).await.unwrap().unwrap();
+    let identity_state = Identity::unpack(&identity_account.data).unwrap();
+    assert_eq!(identity_state.attributes, vec![1, 2, 3]);
+    assert_eq!(identity_state.active_credentials, 1);
+}
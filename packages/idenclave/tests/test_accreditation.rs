@@ -0,0 +1,187 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{Accreditation, CredentialIndex, Identity};
+use idenclave::credential::Credential;
+use idenclave_core::{AccreditationReport, AccreditationStatus};
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_accreditation_chain() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let government = Keypair::new();
+    let university = Keypair::new();
+    let faculty = Keypair::new();
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    for kp in [&government, &university, &faculty, &authority] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let degree = [9u8; 32];
+
+    // Register identity
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(Identity::LEN),
+        Identity::LEN as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The faculty issues a degree
+    let cred = Credential::find_address(&faculty.pubkey(), &identity.pubkey(), &degree, 0, &program_id).0;
+    let mut data = vec![6]; // 6 = IssueCredential
+    data.extend_from_slice(&degree);
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&[3u8; 32]);
+    data.extend_from_slice(&1_000u64.to_le_bytes());
+    data.extend_from_slice(&u64::MAX.to_le_bytes());
+    let issue_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(faculty.pubkey(), true),
+            AccountMeta::new(faculty.pubkey(), true), // payer
+            AccountMeta::new(CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    };
+    let mut tx = Transaction::new_with_payer(&[issue_ix], Some(&faculty.pubkey()));
+    tx.sign(&[&faculty, &authority], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The government accredits the university, which may accredit one level further,
+    // and the university accredits the faculty
+    let accreditation = |accreditor: &Keypair, issuer: &Keypair| {
+        Accreditation::find_address(&accreditor.pubkey(), &issuer.pubkey(), &degree, &program_id).0
+    };
+    let accredit_ix = |accreditor: &Keypair, issuer: &Keypair, expires_at: u64, delegation_depth: u8| {
+        let mut data = vec![30]; // 30 = Accredit
+        data.extend_from_slice(&degree);
+        data.extend_from_slice(&expires_at.to_le_bytes());
+        data.push(delegation_depth);
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(accreditation(accreditor, issuer), false),
+                AccountMeta::new_readonly(accreditor.pubkey(), true),
+                AccountMeta::new_readonly(issuer.pubkey(), false),
+                AccountMeta::new(accreditor.pubkey(), true), // payer
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            data,
+        }
+    };
+    let mut tx = Transaction::new_with_payer(
+        &[accredit_ix(&government, &university, u64::MAX, 1), accredit_ix(&university, &faculty, u64::MAX, 0)],
+        Some(&government.pubkey()),
+    );
+    tx.sign(&[&government, &university], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(accreditation(&government, &university)).await.unwrap().unwrap();
+    let record = Accreditation::unpack(&account.data).unwrap();
+    assert_eq!((record.accreditor, record.issuer, record.delegation_depth), (government.pubkey(), university.pubkey(), 1));
+
+    // Verifiers trusting the government see the path from the faculty up to it
+    let chain = [accreditation(&university, &faculty), accreditation(&government, &university)];
+    let query = |root: &Pubkey, schema: [u8; 32], chain: &[Pubkey], blockhash: Hash| {
+        let mut accounts = vec![AccountMeta::new_readonly(cred, false)];
+        accounts.extend(chain.iter().map(|link| AccountMeta::new_readonly(*link, false)));
+        let data = [vec![32], root.to_bytes().to_vec(), schema.to_vec()].concat(); // 32 = QueryAccreditation
+        let mut tx = Transaction::new_with_payer(&[Instruction { program_id, accounts, data }], Some(&authority.pubkey()));
+        tx.sign(&[&authority], blockhash);
+        tx
+    };
+    let sim = context.banks_client.simulate_transaction(query(&government.pubkey(), degree, &chain, recent_blockhash)).await.unwrap();
+    let report = AccreditationReport::unpack(&sim.simulation_details.unwrap().return_data.unwrap().data).unwrap();
+    assert_eq!(report.status, AccreditationStatus::Accredited);
+    assert_eq!(report.path, vec![faculty.pubkey().to_bytes(), university.pubkey().to_bytes(), government.pubkey().to_bytes()]);
+
+    // Other roots and other schemas are not trusted
+    let sim = context.banks_client.simulate_transaction(query(&Pubkey::new_unique(), degree, &chain, recent_blockhash)).await.unwrap();
+    let report = AccreditationReport::unpack(&sim.simulation_details.unwrap().return_data.unwrap().data).unwrap();
+    assert_eq!(report.status, AccreditationStatus::Untrusted);
+    let sim = context.banks_client.simulate_transaction(query(&government.pubkey(), [8; 32], &chain, recent_blockhash)).await.unwrap();
+    let report = AccreditationReport::unpack(&sim.simulation_details.unwrap().return_data.unwrap().data).unwrap();
+    assert_eq!(report.status, AccreditationStatus::Untrusted);
+
+    // Only the accreditor revokes; revoking the university's accreditation breaks the chain
+    let revoke_ix = |accreditor: &Keypair, link: Pubkey| Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(link, false), AccountMeta::new_readonly(accreditor.pubkey(), true)],
+        data: vec![31], // 31 = RevokeAccreditation
+    };
+    let mut tx = Transaction::new_with_payer(&[revoke_ix(&university, chain[1])], Some(&university.pubkey()));
+    tx.sign(&[&university], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    let mut tx = Transaction::new_with_payer(&[revoke_ix(&government, chain[1])], Some(&government.pubkey()));
+    tx.sign(&[&government], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let sim = context.banks_client.simulate_transaction(query(&government.pubkey(), degree, &chain, recent_blockhash)).await.unwrap();
+    let report = AccreditationReport::unpack(&sim.simulation_details.unwrap().return_data.unwrap().data).unwrap();
+    assert_eq!(report, AccreditationReport {
+        status: AccreditationStatus::Revoked,
+        path: vec![faculty.pubkey().to_bytes(), university.pubkey().to_bytes()],
+    });
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx = Transaction::new_with_payer(&[revoke_ix(&government, chain[1])], Some(&government.pubkey()));
+    tx.sign(&[&government], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+
+    // Accrediting again lifts the revocation; without delegation the university can no longer vouch for the faculty
+    let mut tx = Transaction::new_with_payer(&[accredit_ix(&government, &university, u64::MAX, 0)], Some(&government.pubkey()));
+    tx.sign(&[&government], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let sim = context.banks_client.simulate_transaction(query(&government.pubkey(), degree, &chain, recent_blockhash)).await.unwrap();
+    let report = AccreditationReport::unpack(&sim.simulation_details.unwrap().return_data.unwrap().data).unwrap();
+    assert_eq!(report.status, AccreditationStatus::DelegationExceeded);
+
+    // An expired accreditation no longer counts
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx = Transaction::new_with_payer(&[accredit_ix(&government, &university, 1, 1)], Some(&government.pubkey()));
+    tx.sign(&[&government], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let sim = context.banks_client.simulate_transaction(query(&government.pubkey(), degree, &chain, recent_blockhash)).await.unwrap();
+    let report = AccreditationReport::unpack(&sim.simulation_details.unwrap().return_data.unwrap().data).unwrap();
+    assert_eq!(report.status, AccreditationStatus::Expired);
+
+    // Chain links must be accreditation accounts of this program
+    let sim = context.banks_client.simulate_transaction(query(&government.pubkey(), degree, &[cred], recent_blockhash)).await.unwrap();
+    assert_eq!(sim.result.unwrap(), Err(TransactionError::InstructionError(0, InstructionError::InvalidAccountData)));
+}
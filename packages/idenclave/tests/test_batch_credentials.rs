@@ -0,0 +1,168 @@
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{CredentialIndex, CredentialStatus, Identity};
+use idenclave::credential::Credential;
+use idenclave_core::batch::decode_results;
+use idenclave_core::{BatchIssueItem, BatchItemResult};
+use std::str::FromStr;
+
+async fn process(banks_client: &mut BanksClient, tx: Transaction) -> Vec<BatchItemResult> {
+    let outcome = banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    outcome.result.unwrap();
+    let return_data = outcome.metadata.unwrap().return_data.unwrap();
+    decode_results(&return_data.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_batch_issue_and_revoke() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let issuer = Keypair::new();
+    let other_issuer = Keypair::new();
+    for kp in [&authority, &issuer, &other_issuer] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register two holders
+    let holders = [Keypair::new(), Keypair::new()];
+    for identity in &holders {
+        let create_ix = solana_sdk::system_instruction::create_account(
+            &authority.pubkey(),
+            &identity.pubkey(),
+            rent.minimum_balance(Identity::LEN),
+            Identity::LEN as u64,
+            &program_id,
+        );
+        let register_ix = Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(identity.pubkey(), false),
+                AccountMeta::new(authority.pubkey(), true),
+                AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+            ],
+            data: vec![0],
+        };
+        let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+        tx.sign(&[&authority, identity], recent_blockhash);
+        context.banks_client.process_transaction(tx).await.unwrap();
+    }
+    let (a, b) = (holders[0].pubkey(), holders[1].pubkey());
+
+    let schema = [6u8; 32];
+    let credential = |issuer: &Keypair, identity: &Pubkey, nonce: u64| {
+        Credential::find_address(&issuer.pubkey(), identity, &schema, nonce, &program_id).0
+    };
+    let index = |identity: &Pubkey, n: u64| CredentialIndex::find_address(identity, n, &program_id).0;
+    // Each item: holder, nonce and the index entry the client expects it to take
+    let issue_batch = |issuer: &Keypair, items: &[(Pubkey, u64, u64)], blockhash: Hash| {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+        let mut data = vec![20]; // 20 = IssueCredentialBatch
+        data.extend_from_slice(&schema);
+        for (identity, nonce, n) in items {
+            accounts.push(AccountMeta::new(*identity, false));
+            accounts.push(AccountMeta::new(credential(issuer, identity, *nonce), false));
+            accounts.push(AccountMeta::new(index(identity, *n), false));
+            BatchIssueItem { nonce: *nonce, credential_ref: [*nonce as u8; 32], issued_at: 1_000, expires_at: u64::MAX }.pack(&mut data);
+        }
+        let ix = Instruction { program_id, accounts, data };
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&issuer.pubkey()));
+        tx.sign(&[issuer], blockhash);
+        tx
+    };
+    let revoke_batch = |issuer: &Keypair, credentials: &[Pubkey], blockhash: Hash| {
+        let mut accounts = vec![AccountMeta::new_readonly(issuer.pubkey(), true)];
+        accounts.extend(credentials.iter().map(|credential| AccountMeta::new(*credential, false)));
+        let ix = Instruction { program_id, accounts, data: vec![21] }; // 21 = RevokeCredentialBatch
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&issuer.pubkey()));
+        tx.sign(&[issuer], blockhash);
+        tx
+    };
+
+    // One instruction offers credentials to several holders, two of them to the same holder
+    let results = process(&mut context.banks_client, issue_batch(&issuer, &[(a, 0, 0), (b, 0, 0), (a, 1, 1)], recent_blockhash)).await;
+    assert_eq!(results, vec![BatchItemResult::Ok; 3]);
+    for (identity, nonce) in [(a, 0), (b, 0), (a, 1)] {
+        let account = context.banks_client.get_account(credential(&issuer, &identity, nonce)).await.unwrap().unwrap();
+        let cred = Credential::unpack(&account.data).unwrap();
+        assert_eq!(cred.identity, identity);
+        assert_eq!(cred.credential_ref, [nonce as u8; 32]);
+        assert_eq!(cred.status(0), CredentialStatus::Pending);
+    }
+    let identity_a = context.banks_client.get_account(a).await.unwrap().unwrap();
+    assert_eq!(Identity::unpack(&identity_a.data).unwrap().credential_count, 2);
+
+    // Items that cannot be issued are skipped and reported, the rest of the batch still goes through
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let results = process(
+        &mut context.banks_client,
+        issue_batch(&issuer, &[(a, 0, 2), (b, 1, 0), (b, 2, 1)], recent_blockhash),
+    ).await;
+    assert_eq!(results, vec![BatchItemResult::AlreadyExists, BatchItemResult::InvalidAccount, BatchItemResult::Ok]);
+    assert!(context.banks_client.get_account(credential(&issuer, &b, 1)).await.unwrap().is_none());
+    let identity_b = context.banks_client.get_account(b).await.unwrap().unwrap();
+    assert_eq!(Identity::unpack(&identity_b.data).unwrap().active_credentials, 2);
+    let results = process(&mut context.banks_client, issue_batch(&other_issuer, &[(a, 0, 2)], recent_blockhash)).await;
+    assert_eq!(results, vec![BatchItemResult::Ok]);
+
+    // Revocation reports credentials of other issuers, repeats and non-credentials
+    let revoked = [credential(&issuer, &a, 0), credential(&issuer, &b, 0)];
+    let results = process(
+        &mut context.banks_client,
+        revoke_batch(&issuer, &[revoked[0], revoked[1], revoked[0], credential(&other_issuer, &a, 0), a], recent_blockhash),
+    ).await;
+    assert_eq!(
+        results,
+        vec![
+            BatchItemResult::Ok,
+            BatchItemResult::Ok,
+            BatchItemResult::AlreadyRevoked,
+            BatchItemResult::NotIssuer,
+            BatchItemResult::InvalidAccount,
+        ]
+    );
+    for credential in revoked {
+        let account = context.banks_client.get_account(credential).await.unwrap().unwrap();
+        assert_eq!(Credential::unpack(&account.data).unwrap().status(0), CredentialStatus::Revoked);
+    }
+    let account = context.banks_client.get_account(credential(&other_issuer, &a, 0)).await.unwrap().unwrap();
+    assert_eq!(Credential::unpack(&account.data).unwrap().status(0), CredentialStatus::Pending);
+
+    // The issuer has to sign, and every item needs its three accounts
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx = issue_batch(&issuer, &[(a, 5, 3)], recent_blockhash);
+    tx.message.instructions[0].accounts.pop();
+    tx.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    let ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(issuer.pubkey(), false), AccountMeta::new(revoked[0], false)],
+        data: vec![21],
+    };
+    let mut tx = Transaction::new_with_payer(&[ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+}
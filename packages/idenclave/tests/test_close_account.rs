@@ -0,0 +1,158 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{Identity, Tombstone};
+use idenclave::credential::Credential;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_close_account_drains_and_tombstones() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    let squatter = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    for kp in [&authority, &issuer, &squatter] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let (tombstone, _) = Tombstone::find_address(&identity.pubkey(), &program_id);
+
+    let space = Identity::LEN;
+    let register_ixs = |signer: &Keypair| {
+        vec![
+            solana_sdk::system_instruction::create_account(
+                &signer.pubkey(),
+                &identity.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &program_id,
+            ),
+            Instruction {
+                program_id,
+                accounts: vec![
+                    AccountMeta::new(identity.pubkey(), false),
+                    AccountMeta::new(signer.pubkey(), true),
+                    AccountMeta::new_readonly(tombstone, false),
+                ],
+                data: vec![0],
+            },
+        ]
+    };
+    let close_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(recipient, false),
+            AccountMeta::new(tombstone, false),
+            AccountMeta::new(authority.pubkey(), true), // payer
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: vec![2], // 2 = CloseAccount
+    };
+
+    let mut tx = Transaction::new_with_payer(&register_ixs(&authority), Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Issue a credential so the identity is still referenced
+    let cred = Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &[9u8; 32], 0, &program_id).0;
+    let issue_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(cred, false),
+            AccountMeta::new(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(idenclave::CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: {
+            let mut data = vec![6];
+            data.extend_from_slice(&[9u8; 32]); // schema
+            data.extend_from_slice(&0u64.to_le_bytes()); // nonce
+            data.extend_from_slice(&[5u8; 32]);
+            data.extend_from_slice(&1_000u64.to_le_bytes());
+            data.extend_from_slice(&u64::MAX.to_le_bytes());
+            data
+        },
+    };
+    let mut tx_issue = Transaction::new_with_payer(&[issue_ix], Some(&issuer.pubkey()));
+    tx_issue.sign(&[&authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_issue).await.unwrap();
+
+    // Closing is refused while the credential is outstanding
+    let mut tx_bad = Transaction::new_with_payer(std::slice::from_ref(&close_ix), Some(&authority.pubkey()));
+    tx_bad.sign(&[&authority], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // Revoke and close the credential, then the identity can be closed
+    let revoke_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new(issuer.pubkey(), true),
+        ],
+        data: vec![7],
+    };
+    let close_cred_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new_readonly(issuer.pubkey(), false),
+            AccountMeta::new(issuer.pubkey(), false), // recipient
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(idenclave::CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+        ],
+        data: vec![17],
+    };
+    let mut tx_cred = Transaction::new_with_payer(&[revoke_ix, close_cred_ix], Some(&issuer.pubkey()));
+    tx_cred.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_cred).await.unwrap();
+
+    let identity_lamports = context.banks_client.get_balance(identity.pubkey()).await.unwrap();
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx_close = Transaction::new_with_payer(&[close_ix], Some(&authority.pubkey()));
+    tx_close.sign(&[&authority], recent_blockhash);
+    context.banks_client.process_transaction(tx_close).await.unwrap();
+
+    // All lamports went to the recipient and the account is gone
+    assert!(context.banks_client.get_account(identity.pubkey()).await.unwrap().is_none());
+    assert_eq!(context.banks_client.get_balance(recipient).await.unwrap(), identity_lamports);
+    let tombstone_account = context.banks_client.get_account(tombstone).await.unwrap().unwrap();
+    let tombstone_data = Tombstone::unpack(&tombstone_account.data).unwrap();
+    assert_eq!(tombstone_data.authority, authority.pubkey());
+
+    // A different authority cannot revive the identity
+    let mut tx_bad = Transaction::new_with_payer(&register_ixs(&squatter), Some(&squatter.pubkey()));
+    tx_bad.sign(&[&squatter, &identity], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // The authority that closed it can
+    let mut tx = Transaction::new_with_payer(&register_ixs(&authority), Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let identity_account = context.banks_client.get_account(identity.pubkey()).await.unwrap().unwrap();
+    assert_eq!(Identity::unpack(&identity_account.data).unwrap().authority, authority.pubkey());
+}
@@ -0,0 +1,148 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    pubkey::Pubkey,
+};
+use solana_program::program_pack::Pack;
+use idenclave::Identity;
+use idenclave::credential::Credential;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_close_credential() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    let stranger = Keypair::new();
+    for kp in [&authority, &issuer, &stranger] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let space = Identity::LEN;
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let cred_space = Credential::LEN;
+    let index = |n: u64| idenclave::CredentialIndex::find_address(&identity.pubkey(), n, &program_id).0;
+    let schema = [3u8; 32];
+    let cred_key = |n: u64| Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, n, &program_id).0;
+    // Issues the credential with nonce `n`, which is also its index entry
+    let issue_ix = |n: u64, expires_at: u64| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(cred_key(n), false),
+            AccountMeta::new(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(index(n), false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+        data: {
+            let mut data = vec![6];
+            data.extend_from_slice(&schema);
+            data.extend_from_slice(&n.to_le_bytes());
+            data.extend_from_slice(&[3u8; 32]);
+            data.extend_from_slice(&1_000u64.to_le_bytes());
+            data.extend_from_slice(&expires_at.to_le_bytes());
+            data
+        },
+    };
+    let close_ix = |cred: Pubkey, n: u64, closer: &Keypair| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(closer.pubkey(), true),
+            AccountMeta::new_readonly(issuer.pubkey(), false),
+            AccountMeta::new(issuer.pubkey(), false), // recipient
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(index(n), false),
+        ],
+        data: vec![17], // 17 = CloseCredential
+    };
+
+    // A valid credential cannot be closed
+    let live = cred_key(0);
+    let mut tx_issue = Transaction::new_with_payer(&[issue_ix(0, u64::MAX)], Some(&issuer.pubkey()));
+    tx_issue.sign(&[&authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_issue).await.unwrap();
+    let mut tx_bad = Transaction::new_with_payer(&[close_ix(live, 0, &issuer)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // Once revoked, the issuer closes it and gets the rent back
+    let revoke_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(live, false),
+            AccountMeta::new(issuer.pubkey(), true),
+        ],
+        data: vec![7],
+    };
+    let mut tx_revoke = Transaction::new_with_payer(&[revoke_ix], Some(&authority.pubkey()));
+    tx_revoke.sign(&[&authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_revoke).await.unwrap();
+    let issuer_before = context.banks_client.get_balance(issuer.pubkey()).await.unwrap();
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx_close = Transaction::new_with_payer(&[close_ix(live, 0, &issuer)], Some(&authority.pubkey()));
+    tx_close.sign(&[&authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_close).await.unwrap();
+    assert!(context.banks_client.get_account(live).await.unwrap().is_none());
+    assert!(context.banks_client.get_account(index(0)).await.unwrap().is_none());
+    let issuer_after = context.banks_client.get_balance(issuer.pubkey()).await.unwrap();
+    assert_eq!(issuer_after, issuer_before + rent.minimum_balance(cred_space) + rent.minimum_balance(idenclave::CredentialIndex::LEN));
+
+    // An expired credential may also be closed by the holder, with rent still refunded to the issuer
+    let expired = cred_key(1);
+    let mut tx_issue = Transaction::new_with_payer(&[issue_ix(1, 2_000)], Some(&issuer.pubkey()));
+    tx_issue.sign(&[&authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_issue).await.unwrap();
+
+    // A stranger cannot close it
+    let mut tx_bad = Transaction::new_with_payer(&[close_ix(expired, 1, &stranger)], Some(&stranger.pubkey()));
+    tx_bad.sign(&[&stranger], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    let issuer_before = context.banks_client.get_balance(issuer.pubkey()).await.unwrap();
+    let mut tx_close = Transaction::new_with_payer(&[close_ix(expired, 1, &authority)], Some(&authority.pubkey()));
+    tx_close.sign(&[&authority], recent_blockhash);
+    context.banks_client.process_transaction(tx_close).await.unwrap();
+    assert!(context.banks_client.get_account(expired).await.unwrap().is_none());
+    let issuer_after = context.banks_client.get_balance(issuer.pubkey()).await.unwrap();
+    assert_eq!(issuer_after, issuer_before + rent.minimum_balance(cred_space) + rent.minimum_balance(idenclave::CredentialIndex::LEN));
+    let identity_account = context.banks_client.get_account(identity.pubkey()).await.unwrap().unwrap();
+    assert_eq!(Identity::unpack(&identity_account.data).unwrap().active_credentials, 0);
+}
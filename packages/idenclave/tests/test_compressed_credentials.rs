@@ -0,0 +1,202 @@
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+};
+use solana_program::program_pack::Pack;
+use idenclave::compressed::{apply_event, Sha256Hasher};
+use idenclave::{CompressedCredentialProof, CredentialStatus, Identity, IdentityEvent};
+use idenclave_core::{CompressedCredential, CredentialTree, CredentialTreeMirror, TreeError};
+use std::str::FromStr;
+
+async fn tree_root(banks_client: &mut BanksClient, tree: Pubkey) -> [u8; 32] {
+    let account = banks_client.get_account(tree).await.unwrap().unwrap();
+    CredentialTree::load(&account.data[..]).unwrap().root()
+}
+
+#[tokio::test]
+async fn test_compressed_credentials() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    let stranger = Keypair::new();
+    for kp in [&authority, &issuer, &stranger] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(Identity::LEN),
+        Identity::LEN as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Create a tree with room for 32 credentials, owned by the issuer
+    let tree = Keypair::new();
+    let space = CredentialTree::account_len(5, 8);
+    let create_tree_ix = solana_sdk::system_instruction::create_account(
+        &issuer.pubkey(),
+        &tree.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let init_tree_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(tree.pubkey(), false), AccountMeta::new_readonly(issuer.pubkey(), true)],
+        data: vec![22, 5, 8], // 22 = CreateCredentialTree
+    };
+    let mut tx = Transaction::new_with_payer(&[create_tree_ix, init_tree_ix], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer, &tree], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let issue_ix = |issuer: &Keypair, n: u8| {
+        let mut data = vec![23]; // 23 = IssueCompressedCredential
+        data.extend_from_slice(&[7u8; 32]);
+        data.extend_from_slice(&[n; 32]);
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(tree.pubkey(), false),
+                AccountMeta::new_readonly(issuer.pubkey(), true),
+                AccountMeta::new_readonly(identity.pubkey(), false),
+                AccountMeta::new_readonly(authority.pubkey(), true),
+            ],
+            data,
+        }
+    };
+    let proof_ix = |tag: u8, proof: &CompressedCredentialProof, signer: Option<&Keypair>| {
+        let mut accounts = vec![AccountMeta::new(tree.pubkey(), false)];
+        accounts.extend(signer.map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)));
+        Instruction { program_id, accounts, data: [vec![tag], proof.pack()].concat() }
+    };
+
+    // Issue three credentials and follow them off-chain with the events the program emits.
+    // The test runtime does not capture `sol_log_data`, so the events are rebuilt from the
+    // instructions and the tree's root, and sent through their encoding like a log line would be.
+    let mut mirror = CredentialTreeMirror::<Sha256Hasher>::new(5);
+    let issued = |issuer: &Keypair, n: u8| CompressedCredential {
+        identity: identity.pubkey().to_bytes(),
+        issuer: issuer.pubkey().to_bytes(),
+        schema: [7; 32],
+        credential_ref: [n; 32],
+        issued_at: 1_000,
+        expires_at: u64::MAX,
+        revoked: false,
+    };
+    for n in 0..3u8 {
+        let mut tx = Transaction::new_with_payer(&[issue_ix(&issuer, n)], Some(&issuer.pubkey()));
+        tx.sign(&[&issuer, &authority], recent_blockhash);
+        context.banks_client.process_transaction(tx).await.unwrap();
+        let root = tree_root(&mut context.banks_client, tree.pubkey()).await;
+        let event = IdentityEvent::CompressedCredentialIssued { tree: tree.pubkey(), index: n as u32, root, credential: issued(&issuer, n) };
+        apply_event(&mut mirror, &tree.pubkey(), &IdentityEvent::unpack(&event.pack()).unwrap()).unwrap();
+    }
+    assert_eq!(mirror.len(), 3);
+    let first = mirror.credential(0).unwrap().clone();
+    assert_eq!(first.identity, identity.pubkey().to_bytes());
+    assert_eq!(first.issuer, issuer.pubkey().to_bytes());
+    assert_eq!(first.credential_ref, [0; 32]);
+    let presented = |mirror: &CredentialTreeMirror<Sha256Hasher>, index: u32| CompressedCredentialProof {
+        root: mirror.root(),
+        index,
+        credential: mirror.credential(index).unwrap().clone(),
+        proof: mirror.proof(index),
+    };
+
+    // Anyone can check a credential against the tree
+    let verify = |proof: &CompressedCredentialProof, blockhash: Hash| {
+        let mut tx = Transaction::new_with_payer(&[proof_ix(25, proof, None)], Some(&stranger.pubkey()));
+        tx.sign(&[&stranger], blockhash);
+        tx
+    };
+    let sim = context.banks_client.simulate_transaction(verify(&presented(&mirror, 1), recent_blockhash)).await.unwrap();
+    assert_eq!(sim.simulation_details.unwrap().return_data.unwrap().data, vec![CredentialStatus::Valid as u8]);
+
+    // A proof made before another issuance still revokes: the tree fast-forwards it
+    let stale = presented(&mirror, 0);
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx = Transaction::new_with_payer(&[issue_ix(&issuer, 3)], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer, &authority], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let root = tree_root(&mut context.banks_client, tree.pubkey()).await;
+    let event = IdentityEvent::CompressedCredentialIssued { tree: tree.pubkey(), index: 3, root, credential: issued(&issuer, 3) };
+    apply_event(&mut mirror, &tree.pubkey(), &event).unwrap();
+    let mut tx = Transaction::new_with_payer(&[proof_ix(24, &stale, Some(&issuer))], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let root = tree_root(&mut context.banks_client, tree.pubkey()).await;
+    let event = IdentityEvent::CompressedCredentialRevoked { tree: tree.pubkey(), index: 0, root };
+    apply_event(&mut mirror, &tree.pubkey(), &IdentityEvent::unpack(&event.pack()).unwrap()).unwrap();
+    assert!(mirror.credential(0).unwrap().revoked);
+
+    // Events of other trees are ignored, and a missed event is noticed
+    let other = IdentityEvent::CompressedCredentialRevoked { tree: Pubkey::new_unique(), index: 1, root };
+    apply_event(&mut mirror, &tree.pubkey(), &other).unwrap();
+    let mut lagging = CredentialTreeMirror::<Sha256Hasher>::new(5);
+    let event = IdentityEvent::CompressedCredentialIssued { tree: tree.pubkey(), index: 1, root, credential: issued(&issuer, 1) };
+    assert_eq!(apply_event(&mut lagging, &tree.pubkey(), &event), Err(TreeError::OutOfSync));
+
+    let sim = context.banks_client.simulate_transaction(verify(&presented(&mirror, 0), recent_blockhash)).await.unwrap();
+    assert_eq!(sim.simulation_details.unwrap().return_data.unwrap().data, vec![CredentialStatus::Revoked as u8]);
+    let sim = context.banks_client.simulate_transaction(verify(&presented(&mirror, 3), recent_blockhash)).await.unwrap();
+    assert_eq!(sim.simulation_details.unwrap().return_data.unwrap().data, vec![CredentialStatus::Valid as u8]);
+
+    // The credential as it was before revocation no longer verifies, nor can it be revoked again
+    let unrevoked = CompressedCredentialProof { credential: CompressedCredential { revoked: false, ..first }, ..presented(&mirror, 0) };
+    let err = context.banks_client.process_transaction(verify(&unrevoked, recent_blockhash)).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx = Transaction::new_with_payer(&[proof_ix(24, &stale, Some(&issuer))], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+
+    // Only the issuer revokes, and only the tree's authority issues into it
+    let mut tx = Transaction::new_with_payer(&[proof_ix(24, &presented(&mirror, 1), Some(&stranger))], Some(&stranger.pubkey()));
+    tx.sign(&[&stranger], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    let mut tx = Transaction::new_with_payer(&[issue_ix(&stranger, 9)], Some(&stranger.pubkey()));
+    tx.sign(&[&stranger, &authority], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+
+    // The mirror still matches the tree
+    let account = context.banks_client.get_account(tree.pubkey()).await.unwrap().unwrap();
+    let onchain = CredentialTree::load(&account.data[..]).unwrap();
+    assert_eq!(onchain.root(), mirror.root());
+    assert_eq!(onchain.next_index(), 4);
+}
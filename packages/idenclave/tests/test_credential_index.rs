@@ -0,0 +1,137 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{walk_credential_index, CredentialIndex, Identity, Tombstone};
+use idenclave::credential::Credential;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_credential_index_enumeration() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    for kp in [&authority, &issuer] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let space = Identity::LEN;
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Issue three credentials; each one takes the next index entry
+    let index = |n: u64| CredentialIndex::find_address(&identity.pubkey(), n, &program_id).0;
+    let schema = [4u8; 32];
+    let cred_key = |nonce: u64| Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, nonce, &program_id).0;
+    let issue_ix = |nonce: u64, entry: u64| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(cred_key(nonce), false),
+            AccountMeta::new(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(index(entry), false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: {
+            let mut data = vec![6];
+            data.extend_from_slice(&schema);
+            data.extend_from_slice(&nonce.to_le_bytes());
+            data.extend_from_slice(&[nonce as u8; 32]);
+            data.extend_from_slice(&1_000u64.to_le_bytes());
+            data.extend_from_slice(&u64::MAX.to_le_bytes());
+            data
+        },
+    };
+    let creds: Vec<Pubkey> = (0..3).map(cred_key).collect();
+    for n in 0..3 {
+        let mut tx_issue = Transaction::new_with_payer(&[issue_ix(n, n)], Some(&issuer.pubkey()));
+        tx_issue.sign(&[&authority, &issuer], recent_blockhash);
+        context.banks_client.process_transaction(tx_issue).await.unwrap();
+    }
+
+    // Using the wrong index entry is rejected
+    let mut tx_bad = Transaction::new_with_payer(&[issue_ix(3, 7)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&authority, &issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // Revoke and close the middle credential
+    let revoke_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(creds[1], false),
+            AccountMeta::new(issuer.pubkey(), true),
+        ],
+        data: vec![7],
+    };
+    let close_cred_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(creds[1], false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new_readonly(issuer.pubkey(), false),
+            AccountMeta::new(issuer.pubkey(), false), // recipient
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(index(1), false),
+        ],
+        data: vec![17],
+    };
+    let mut tx_close = Transaction::new_with_payer(&[revoke_ix, close_cred_ix], Some(&issuer.pubkey()));
+    tx_close.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_close).await.unwrap();
+
+    let identity_account = context.banks_client.get_account(identity.pubkey()).await.unwrap().unwrap();
+    let identity_data = Identity::unpack(&identity_account.data).unwrap();
+    assert_eq!(identity_data.credential_count, 3);
+    assert_eq!(identity_data.active_credentials, 2);
+
+    // Walk the index the way a wallet would
+    let mut fetched = HashMap::new();
+    for n in 0..identity_data.credential_count {
+        if let Some(account) = context.banks_client.get_account(index(n)).await.unwrap() {
+            fetched.insert(index(n), account.data);
+        }
+    }
+    let listed = walk_credential_index(&identity.pubkey(), identity_data.credential_count, &program_id, |key| {
+        fetched.get(key).cloned()
+    });
+    assert_eq!(listed, vec![creds[0], creds[2]]);
+}
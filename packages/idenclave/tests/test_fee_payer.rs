@@ -0,0 +1,176 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{CredentialIndex, Identity, Tombstone};
+use idenclave::credential::Credential;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_sponsor_pays_and_issuer_picks_refund_recipient() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    // Neither the holder nor the issuer own any lamports; a sponsor funds every account
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    let sponsor = Keypair::new();
+    let treasury = Pubkey::new_unique();
+    program_test.add_account(
+        sponsor.pubkey(),
+        Account { lamports: 10_000_000_000, ..Account::default() },
+    );
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let space = Identity::LEN;
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &sponsor.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&sponsor.pubkey()));
+    tx.sign(&[&sponsor, &authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Issue one credential and offer another, index entries paid by the sponsor
+    let cred_space = Credential::LEN;
+    let index = |n: u64| CredentialIndex::find_address(&identity.pubkey(), n, &program_id).0;
+    let schema = [6u8; 32];
+    let cred_key = |nonce: u64| Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, nonce, &program_id).0;
+    let terms = |nonce: u64, expires_at: u64| {
+        let mut data = vec![];
+        data.extend_from_slice(&schema);
+        data.extend_from_slice(&nonce.to_le_bytes());
+        data.extend_from_slice(&[5u8; 32]);
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&expires_at.to_le_bytes());
+        data
+    };
+    let issued = cred_key(0);
+    let issue_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(issued, false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new(sponsor.pubkey(), true), // payer
+            AccountMeta::new(index(0), false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: [vec![6], terms(0, 2_000)].concat(),
+    };
+    let offered = cred_key(1);
+    let offer_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(offered, false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new(sponsor.pubkey(), true), // payer
+            AccountMeta::new(index(1), false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: [vec![13], terms(1, u64::MAX)].concat(),
+    };
+    let mut tx = Transaction::new_with_payer(
+        &[issue_ix, offer_ix],
+        Some(&sponsor.pubkey()),
+    );
+    tx.sign(&[&sponsor, &authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert!(context.banks_client.get_account(issuer.pubkey()).await.unwrap().is_none());
+    assert!(context.banks_client.get_account(authority.pubkey()).await.unwrap().is_none());
+    assert!(context.banks_client.get_account(index(1)).await.unwrap().is_some());
+
+    // The holder may close the expired credential, but cannot redirect the issuer's rent
+    let close_ix = |closer: &Keypair, recipient: Pubkey| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(issued, false),
+            AccountMeta::new_readonly(closer.pubkey(), true),
+            AccountMeta::new_readonly(issuer.pubkey(), false),
+            AccountMeta::new(recipient, false),
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(index(0), false),
+        ],
+        data: vec![17], // 17 = CloseCredential
+    };
+    let mut tx = Transaction::new_with_payer(&[close_ix(&authority, authority.pubkey())], Some(&sponsor.pubkey()));
+    tx.sign(&[&sponsor, &authority], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+
+    // The issuer sends it back to wherever the funds came from
+    let mut tx = Transaction::new_with_payer(&[close_ix(&issuer, treasury)], Some(&sponsor.pubkey()));
+    tx.sign(&[&sponsor, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let refund = rent.minimum_balance(cred_space) + rent.minimum_balance(CredentialIndex::LEN);
+    assert_eq!(context.banks_client.get_balance(treasury).await.unwrap(), refund);
+
+    // Cancelling the offer refunds to the recipient as well
+    let cancel_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(offered, false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new(treasury, false), // recipient
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(index(1), false),
+        ],
+        data: vec![16], // 16 = CancelCredentialOffer
+    };
+    let mut tx = Transaction::new_with_payer(&[cancel_ix], Some(&sponsor.pubkey()));
+    tx.sign(&[&sponsor, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(context.banks_client.get_balance(treasury).await.unwrap(), 2 * refund);
+    assert!(context.banks_client.get_account(issuer.pubkey()).await.unwrap().is_none());
+
+    // Closing the identity: the sponsor funds the tombstone, the authority still decides
+    let tombstone = Tombstone::find_address(&identity.pubkey(), &program_id).0;
+    let identity_lamports = context.banks_client.get_balance(identity.pubkey()).await.unwrap();
+    let close_account_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new_readonly(authority.pubkey(), true),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(tombstone, false),
+            AccountMeta::new(sponsor.pubkey(), true), // payer
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: vec![2], // 2 = CloseAccount
+    };
+    let mut tx = Transaction::new_with_payer(&[close_account_ix], Some(&sponsor.pubkey()));
+    tx.sign(&[&sponsor, &authority], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert!(context.banks_client.get_account(identity.pubkey()).await.unwrap().is_none());
+    assert_eq!(context.banks_client.get_balance(treasury).await.unwrap(), 2 * refund + identity_lamports);
+    let tombstone_account = context.banks_client.get_account(tombstone).await.unwrap().unwrap();
+    assert_eq!(Tombstone::unpack(&tombstone_account.data).unwrap().authority, authority.pubkey());
+    assert!(context.banks_client.get_account(authority.pubkey()).await.unwrap().is_none());
+}
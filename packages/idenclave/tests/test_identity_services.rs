@@ -0,0 +1,119 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{Identity, Service};
+use idenclave_core::MAX_SERVICES;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_identity_services() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let stranger = Keypair::new();
+    for kp in [&authority, &stranger] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let identity = Keypair::new();
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(Identity::LEN),
+        Identity::LEN as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let service_ix = |tag: u8, payload: &[u8], signer: &Keypair| {
+        let mut data = vec![tag]; // 40 = AddService, 41 = UpdateService, 42 = RemoveService
+        data.extend_from_slice(payload);
+        Instruction {
+            program_id,
+            accounts: vec![AccountMeta::new(identity.pubkey(), false), AccountMeta::new_readonly(signer.pubkey(), true)],
+            data,
+        }
+    };
+    let messaging = Service::new("messaging", "DIDCommMessaging", "https://example.com/didcomm");
+    let domain = Service::new("domain", "LinkedDomains", "https://example.com");
+    let mut tx = Transaction::new_with_payer(
+        &[service_ix(40, &messaging.pack(), &authority), service_ix(40, &domain.pack(), &authority)],
+        Some(&authority.pubkey()),
+    );
+    tx.sign(&[&authority], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(identity.pubkey()).await.unwrap().unwrap();
+    assert_eq!(Identity::unpack(&account.data).unwrap().services, vec![messaging.clone(), domain.clone()]);
+
+    // Only the authority changes the services
+    let mut tx = Transaction::new_with_payer(&[service_ix(42, b"messaging", &stranger)], Some(&stranger.pubkey()));
+    tx.sign(&[&stranger], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+
+    // Ids are unique, and entries over the size limits never decode
+    let mut tx = Transaction::new_with_payer(&[service_ix(40, &messaging.pack(), &authority)], Some(&authority.pubkey()));
+    tx.sign(&[&authority], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
+    let oversized = Service::new("profile", "Profile", &format!("https://{}", "a".repeat(128)));
+    let mut tx = Transaction::new_with_payer(&[service_ix(40, &oversized.pack(), &authority)], Some(&authority.pubkey()));
+    tx.sign(&[&authority], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidInstructionData));
+
+    // Update in place, then remove
+    let moved = Service::new("messaging", "DIDCommMessaging", "https://example.org/didcomm");
+    let mut tx = Transaction::new_with_payer(
+        &[service_ix(41, &moved.pack(), &authority), service_ix(42, b"domain", &authority)],
+        Some(&authority.pubkey()),
+    );
+    tx.sign(&[&authority], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(identity.pubkey()).await.unwrap().unwrap();
+    assert_eq!(Identity::unpack(&account.data).unwrap().services, vec![moved]);
+    let mut tx = Transaction::new_with_payer(&[service_ix(42, b"domain", &authority)], Some(&authority.pubkey()));
+    tx.sign(&[&authority], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+
+    // The identity holds at most MAX_SERVICES entries
+    let extra: Vec<Instruction> = (1..MAX_SERVICES)
+        .map(|n| service_ix(40, &Service::new(&format!("extra-{}", n), "Profile", "https://example.com").pack(), &authority))
+        .collect();
+    let mut tx = Transaction::new_with_payer(&extra, Some(&authority.pubkey()));
+    tx.sign(&[&authority], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let mut tx = Transaction::new_with_payer(&[service_ix(40, &domain.pack(), &authority)], Some(&authority.pubkey()));
+    tx.sign(&[&authority], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+}
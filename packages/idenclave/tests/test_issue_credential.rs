@@ -1,9 +1,9 @@
 use solana_program_test::{processor, ProgramTest};
 use solana_sdk::{
     account::Account,
-    instruction::{AccountMeta, Instruction},
+    instruction::{AccountMeta, Instruction, InstructionError},
     signature::{Keypair, Signer},
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
     pubkey::Pubkey,
 };
 use solana_program::program_pack::Pack;
@@ -46,63 +46,58 @@ async fn test_issue_credential() {
         accounts: vec![
             AccountMeta::new(identity.pubkey(), false),
             AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
         ],
         data: vec![0],
     };
     let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
     tx.sign(&[&authority, &identity], recent_blockhash);
     banks_client.process_transaction(tx).await.unwrap();
-    // Create credential account
-    let cred = Keypair::new();
-    let cred_space = Credential::LEN;
-    let cred_lamports = rent.minimum_balance(cred_space);
-    let create_cred_ix = solana_sdk::system_instruction::create_account(
-        &issuer.pubkey(),
-        &cred.pubkey(),
-        cred_lamports,
-        cred_space as u64,
-        &program_id,
-    );
-    // First, create the credential account in its own transaction
-    let mut tx_create_cred = Transaction::new_with_payer(&[create_cred_ix], Some(&issuer.pubkey()));
-    tx_create_cred.sign(&[&issuer, &cred], recent_blockhash);
-    banks_client.process_transaction(tx_create_cred).await.unwrap();
-
-    // Prepare IssueCredential instruction
+    // The credential lives at the address derived from its issuer, identity, schema and nonce
+    let schema = [7u8; 32];
+    let nonce = 0u64;
+    let (cred, _) = Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, nonce, &program_id);
     let credential_ref = [42u8; 32];
     let issued_at = 1_700_000_000u64;
+    let expires_at = u64::MAX;
     let mut issue_data = vec![6]; // 6 = IssueCredential
+    issue_data.extend_from_slice(&schema);
+    issue_data.extend_from_slice(&nonce.to_le_bytes());
     issue_data.extend_from_slice(&credential_ref);
     issue_data.extend_from_slice(&issued_at.to_le_bytes());
+    issue_data.extend_from_slice(&expires_at.to_le_bytes());
 
-    // The order of accounts must match the program's expectation:
-    // [identity_account, authority_account (dummy), credential_account, issuer_account]
+    // [identity_account, authority_account, credential_account, issuer_account, payer_account, index_account, system_program]
     let issue_ix = Instruction {
         program_id,
         accounts: vec![
-            AccountMeta::new(identity.pubkey(), false),   // writable, not signer
-            AccountMeta::new(authority.pubkey(), false),  // writable, not signer
-            AccountMeta::new(cred.pubkey(), false),       // writable, not signer
-            AccountMeta::new(issuer.pubkey(), true),      // writable, signer
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new_readonly(authority.pubkey(), true), // the holder consents
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(idenclave::CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
         ],
         data: issue_data,
     };
-
-
-
-
-
-
-    let mut tx_issue = Transaction::new_with_payer(&[issue_ix], Some(&issuer.pubkey()));
-    tx_issue.sign(&[&issuer], recent_blockhash);
+    let mut tx_issue = Transaction::new_with_payer(std::slice::from_ref(&issue_ix), Some(&issuer.pubkey()));
+    tx_issue.sign(&[&issuer, &authority], recent_blockhash);
     banks_client.process_transaction(tx_issue).await.unwrap();
 
     // Fetch and verify credential
-    let cred_account = banks_client.get_account(cred.pubkey()).await.unwrap().unwrap();
+    let cred_account = banks_client.get_account(cred).await.unwrap().unwrap();
     let cred_data = Credential::unpack(&cred_account.data).unwrap();
     assert!(cred_data.is_initialized);
     assert_eq!(cred_data.identity, identity.pubkey());
     assert_eq!(cred_data.issuer, issuer.pubkey());
     assert_eq!(cred_data.credential_ref, credential_ref);
     assert_eq!(cred_data.issued_at, issued_at);
+    assert_eq!(cred_data.expires_at, expires_at);
+
+    // The same schema and nonce cannot issue a second credential
+    let mut tx_again = Transaction::new_with_payer(&[issue_ix], Some(&authority.pubkey()));
+    tx_again.sign(&[&issuer, &authority], recent_blockhash);
+    let err = banks_client.process_transaction(tx_again).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
 }
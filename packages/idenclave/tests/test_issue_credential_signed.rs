@@ -0,0 +1,160 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    pubkey::Pubkey,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{CredentialStatus, Identity};
+use idenclave::credential::Credential;
+use idenclave_core::issuance::ed25519_instruction_data;
+use idenclave_core::IssuanceMessage;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_issue_credential_signed() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    let relayer = Keypair::new();
+    for kp in [&authority, &relayer] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let space = Identity::LEN;
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The issuer signs offline and never holds lamports or submits anything
+    let schema = [4u8; 32];
+    let message = |nonce: u64, deadline: u64| IssuanceMessage {
+        program_id: program_id.to_bytes(),
+        issuer: issuer.pubkey().to_bytes(),
+        identity: identity.pubkey().to_bytes(),
+        schema,
+        nonce,
+        credential_ref: [1u8; 32],
+        issued_at: 1_000,
+        expires_at: u64::MAX,
+        claims_root: [9u8; 32],
+        deadline,
+    };
+    let verify_ix = |signer: &Keypair, message: &IssuanceMessage| {
+        let bytes = message.to_bytes();
+        let signature: [u8; 64] = signer.sign_message(&bytes).into();
+        Instruction {
+            program_id: solana_sdk::ed25519_program::ID,
+            accounts: vec![],
+            data: ed25519_instruction_data(&signer.pubkey().to_bytes(), &signature, &bytes),
+        }
+    };
+    let credential = |nonce: u64| Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, nonce, &program_id).0;
+    let issue_ix = |message: &IssuanceMessage, payer: &Keypair, holder_signs: bool, n: u64| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new_readonly(authority.pubkey(), holder_signs),
+            AccountMeta::new(credential(message.nonce), false),
+            AccountMeta::new_readonly(issuer.pubkey(), false),
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new(idenclave::CredentialIndex::find_address(&identity.pubkey(), n, &program_id).0, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::instructions::ID, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+        data: {
+            let mut data = vec![19]; // 19 = IssueCredentialSigned
+            data.extend_from_slice(&message.schema);
+            data.extend_from_slice(&message.nonce.to_le_bytes());
+            data.extend_from_slice(&message.credential_ref);
+            data.extend_from_slice(&message.issued_at.to_le_bytes());
+            data.extend_from_slice(&message.expires_at.to_le_bytes());
+            data.extend_from_slice(&message.deadline.to_le_bytes());
+            data.extend_from_slice(&message.claims_root);
+            data
+        },
+    };
+
+    // A relayer submits it; without the holder's signature the credential arrives as a pending offer
+    let signed = message(0, u64::MAX);
+    let mut tx = Transaction::new_with_payer(&[verify_ix(&issuer, &signed), issue_ix(&signed, &relayer, false, 0)], Some(&relayer.pubkey()));
+    tx.sign(&[&relayer], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let cred_account = context.banks_client.get_account(credential(0)).await.unwrap().unwrap();
+    let cred = Credential::unpack(&cred_account.data).unwrap();
+    assert_eq!(cred.issuer, issuer.pubkey());
+    assert_eq!(cred.identity, identity.pubkey());
+    assert_eq!(cred.credential_ref, [1u8; 32]);
+    assert_eq!(cred.claims_root, [9u8; 32]);
+    assert_eq!(cred.status(0), CredentialStatus::Pending);
+
+    // The same signed message cannot be used again
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx = Transaction::new_with_payer(&[verify_ix(&issuer, &signed), issue_ix(&signed, &relayer, false, 1)], Some(&relayer.pubkey()));
+    tx.sign(&[&relayer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+
+    // Signatures by anyone but the issuer, over different terms, or past their deadline are refused
+    let other = message(1, u64::MAX);
+    let mut tx = Transaction::new_with_payer(&[verify_ix(&relayer, &other), issue_ix(&other, &relayer, false, 1)], Some(&relayer.pubkey()));
+    tx.sign(&[&relayer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    for tampered in [IssuanceMessage { expires_at: 2_000, ..other.clone() }, IssuanceMessage { claims_root: [0u8; 32], ..other.clone() }] {
+        let mut tx = Transaction::new_with_payer(&[verify_ix(&issuer, &other), issue_ix(&tampered, &relayer, false, 1)], Some(&relayer.pubkey()));
+        tx.sign(&[&relayer], recent_blockhash);
+        assert!(context.banks_client.process_transaction(tx).await.is_err());
+    }
+    let stale = message(1, 1);
+    let mut tx = Transaction::new_with_payer(&[verify_ix(&issuer, &stale), issue_ix(&stale, &relayer, false, 1)], Some(&relayer.pubkey()));
+    tx.sign(&[&relayer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    let mut forged = verify_ix(&issuer, &other);
+    forged.data[16 + 32] ^= 1; // first signature byte
+    let mut tx = Transaction::new_with_payer(&[forged, issue_ix(&other, &relayer, false, 1)], Some(&relayer.pubkey()));
+    tx.sign(&[&relayer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+    let mut tx = Transaction::new_with_payer(&[issue_ix(&other, &relayer, false, 1)], Some(&relayer.pubkey()));
+    tx.sign(&[&relayer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+
+    // Submitted by the holder, the credential is valid right away
+    let mut tx = Transaction::new_with_payer(&[verify_ix(&issuer, &other), issue_ix(&other, &authority, true, 1)], Some(&authority.pubkey()));
+    tx.sign(&[&authority], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let cred_account = context.banks_client.get_account(credential(1)).await.unwrap().unwrap();
+    let cred = Credential::unpack(&cred_account.data).unwrap();
+    assert_eq!(cred.status(0), CredentialStatus::Valid);
+    let identity_account = context.banks_client.get_account(identity.pubkey()).await.unwrap().unwrap();
+    assert_eq!(Identity::unpack(&identity_account.data).unwrap().active_credentials, 2);
+}
@@ -0,0 +1,366 @@
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{CredentialIndex, CredentialStatus, Identity, Organization};
+use idenclave::credential::Credential;
+use idenclave_core::batch::decode_results;
+use idenclave_core::issuance::ed25519_instruction_data;
+use idenclave_core::{BatchIssueItem, BatchItemResult, IssuanceMessage};
+use std::str::FromStr;
+
+async fn process(banks_client: &mut BanksClient, tx: Transaction) -> Vec<BatchItemResult> {
+    let outcome = banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    outcome.result.unwrap();
+    let return_data = outcome.metadata.unwrap().return_data.unwrap();
+    decode_results(&return_data.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_issuer_organization_keys() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let admin = Keypair::new();
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let old_key = Keypair::new();
+    let new_key = Keypair::new();
+    for kp in [&admin, &authority] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Create the organization with one signing key
+    let organization = Organization::find_address(&admin.pubkey(), &program_id).0;
+    let create_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(organization, false),
+            AccountMeta::new_readonly(admin.pubkey(), true),
+            AccountMeta::new(admin.pubkey(), true), // payer
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: [vec![26], old_key.pubkey().to_bytes().to_vec()].concat(), // 26 = CreateOrganization
+    };
+    let mut tx = Transaction::new_with_payer(std::slice::from_ref(&create_ix), Some(&admin.pubkey()));
+    tx.sign(&[&admin], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(organization).await.unwrap().unwrap();
+    let state = Organization::unpack(&account.data).unwrap();
+    assert_eq!(state.authority, admin.pubkey());
+    assert_eq!(state.keys, vec![old_key.pubkey()]);
+
+    // Register identity
+    let create_identity_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(Identity::LEN),
+        Identity::LEN as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_identity_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Issue with the organization's key: the credential records the organization, and its
+    // address is derived from the organization rather than from the key that signed
+    let schema = [8u8; 32];
+    let cred = Credential::find_address(&organization, &identity.pubkey(), &schema, 0, &program_id).0;
+    let mut data = vec![6]; // 6 = IssueCredential
+    data.extend_from_slice(&schema);
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&[3u8; 32]);
+    data.extend_from_slice(&1_000u64.to_le_bytes());
+    data.extend_from_slice(&u64::MAX.to_le_bytes());
+    let issue_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(old_key.pubkey(), true),
+            AccountMeta::new(admin.pubkey(), true), // payer
+            AccountMeta::new(CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(organization, false),
+        ],
+        data,
+    };
+    let mut tx = Transaction::new_with_payer(&[issue_ix], Some(&admin.pubkey()));
+    tx.sign(&[&admin, &authority, &old_key], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(cred).await.unwrap().unwrap();
+    assert_eq!(Credential::unpack(&account.data).unwrap().issuer, organization);
+
+    // The admin rotates the key; only the admin manages keys
+    let key_ix = |signer: &Keypair, data: Vec<u8>| Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(organization, false), AccountMeta::new_readonly(signer.pubkey(), true)],
+        data,
+    };
+    let rotate_data = [vec![29], old_key.pubkey().to_bytes().to_vec(), new_key.pubkey().to_bytes().to_vec()].concat(); // 29 = RotateOrganizationKey
+    let mut tx = Transaction::new_with_payer(&[key_ix(&authority, rotate_data.clone())], Some(&authority.pubkey()));
+    tx.sign(&[&authority], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    let mut tx = Transaction::new_with_payer(&[key_ix(&admin, rotate_data)], Some(&admin.pubkey()));
+    tx.sign(&[&admin], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let update_ix = |tag: u8, key: &Keypair, organization: Option<Pubkey>| {
+        let mut accounts = vec![AccountMeta::new(cred, false), AccountMeta::new_readonly(key.pubkey(), true)];
+        accounts.extend(organization.map(|organization| AccountMeta::new_readonly(organization, false)));
+        Instruction { program_id, accounts, data: vec![tag] }
+    };
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    // The rotated-out key no longer acts for the organization
+    let mut tx = Transaction::new_with_payer(&[update_ix(7, &old_key, Some(organization))], Some(&admin.pubkey()));
+    tx.sign(&[&admin, &old_key], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    // Without the organization account, the new key is just a stranger to the credential
+    let mut tx = Transaction::new_with_payer(&[update_ix(7, &new_key, None)], Some(&admin.pubkey()));
+    tx.sign(&[&admin, &new_key], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    // The new key manages the credential issued with the old one
+    let mut tx = Transaction::new_with_payer(
+        &[update_ix(9, &new_key, Some(organization)), update_ix(10, &new_key, Some(organization)), update_ix(7, &new_key, Some(organization))],
+        Some(&admin.pubkey()),
+    );
+    tx.sign(&[&admin, &new_key], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(cred).await.unwrap().unwrap();
+    assert_eq!(Credential::unpack(&account.data).unwrap().status(0), CredentialStatus::Revoked);
+
+    // The organization closes its revoked credential and picks where the rent goes
+    let treasury = Pubkey::new_unique();
+    let index = CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0;
+    let close_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(new_key.pubkey(), true),
+            AccountMeta::new_readonly(organization, false), // issuer
+            AccountMeta::new(treasury, false), // recipient
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(index, false),
+            AccountMeta::new_readonly(organization, false),
+        ],
+        data: vec![17], // 17 = CloseCredential
+    };
+    let mut tx = Transaction::new_with_payer(&[close_ix], Some(&admin.pubkey()));
+    tx.sign(&[&admin, &new_key], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert!(context.banks_client.get_account(cred).await.unwrap().is_none());
+    let refund = rent.minimum_balance(Credential::LEN) + rent.minimum_balance(CredentialIndex::LEN);
+    assert_eq!(context.banks_client.get_balance(treasury).await.unwrap(), refund);
+
+    // Adding a key twice or removing an unknown one is refused; removal revokes the key's rights
+    let mut tx = Transaction::new_with_payer(&[key_ix(&admin, [vec![27], new_key.pubkey().to_bytes().to_vec()].concat())], Some(&admin.pubkey()));
+    tx.sign(&[&admin], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    let mut tx = Transaction::new_with_payer(&[key_ix(&admin, [vec![28], old_key.pubkey().to_bytes().to_vec()].concat())], Some(&admin.pubkey()));
+    tx.sign(&[&admin], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    let mut tx = Transaction::new_with_payer(&[key_ix(&admin, [vec![28], new_key.pubkey().to_bytes().to_vec()].concat())], Some(&admin.pubkey()));
+    tx.sign(&[&admin], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(organization).await.unwrap().unwrap();
+    assert!(Organization::unpack(&account.data).unwrap().keys.is_empty());
+
+    // An organization is created once per authority
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx = Transaction::new_with_payer(&[create_ix], Some(&admin.pubkey()));
+    tx.sign(&[&admin], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
+}
+
+#[tokio::test]
+async fn test_organization_batch_and_signed_issuance() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let admin = Keypair::new();
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let key = Keypair::new();
+    let stranger = Keypair::new();
+    for kp in [&admin, &authority] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // An organization with one signing key, and a holder
+    let organization = Organization::find_address(&admin.pubkey(), &program_id).0;
+    let create_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(organization, false),
+            AccountMeta::new_readonly(admin.pubkey(), true),
+            AccountMeta::new(admin.pubkey(), true), // payer
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: [vec![26], key.pubkey().to_bytes().to_vec()].concat(), // 26 = CreateOrganization
+    };
+    let create_identity_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(Identity::LEN),
+        Identity::LEN as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, create_identity_ix, register_ix], Some(&admin.pubkey()));
+    tx.sign(&[&admin, &authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The organization offers a batch through its key; the organization account goes ahead of the
+    // items, and the credential lands at the address derived from the organization
+    let schema = [8u8; 32];
+    let credential = |nonce: u64| Credential::find_address(&organization, &identity.pubkey(), &schema, nonce, &program_id).0;
+    let index = |n: u64| CredentialIndex::find_address(&identity.pubkey(), n, &program_id).0;
+    let mut data = vec![20]; // 20 = IssueCredentialBatch
+    data.extend_from_slice(&schema);
+    BatchIssueItem { nonce: 0, credential_ref: [3u8; 32], issued_at: 1_000, expires_at: u64::MAX }.pack(&mut data);
+    let batch_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(key.pubkey(), true),
+            AccountMeta::new(admin.pubkey(), true), // payer
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(organization, false),
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(credential(0), false),
+            AccountMeta::new(index(0), false),
+        ],
+        data,
+    };
+    let mut tx = Transaction::new_with_payer(&[batch_ix], Some(&admin.pubkey()));
+    tx.sign(&[&admin, &key], recent_blockhash);
+    assert_eq!(process(&mut context.banks_client, tx).await, vec![BatchItemResult::Ok]);
+    let account = context.banks_client.get_account(credential(0)).await.unwrap().unwrap();
+    assert_eq!(Credential::unpack(&account.data).unwrap().issuer, organization);
+
+    // A key signs an issuance naming the organization as issuer; only the organization's keys may
+    let message = IssuanceMessage {
+        program_id: program_id.to_bytes(),
+        issuer: organization.to_bytes(),
+        identity: identity.pubkey().to_bytes(),
+        schema,
+        nonce: 1,
+        credential_ref: [4u8; 32],
+        issued_at: 1_000,
+        expires_at: u64::MAX,
+        claims_root: [0u8; 32],
+        deadline: u64::MAX,
+    };
+    let signed_ix = |signer: &Keypair| {
+        let bytes = message.to_bytes();
+        let signature: [u8; 64] = signer.sign_message(&bytes).into();
+        let verify_ix = Instruction {
+            program_id: solana_sdk::ed25519_program::ID,
+            accounts: vec![],
+            data: ed25519_instruction_data(&signer.pubkey().to_bytes(), &signature, &bytes),
+        };
+        let mut data = vec![19]; // 19 = IssueCredentialSigned
+        data.extend_from_slice(&message.schema);
+        data.extend_from_slice(&message.nonce.to_le_bytes());
+        data.extend_from_slice(&message.credential_ref);
+        data.extend_from_slice(&message.issued_at.to_le_bytes());
+        data.extend_from_slice(&message.expires_at.to_le_bytes());
+        data.extend_from_slice(&message.deadline.to_le_bytes());
+        let issue_ix = Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(identity.pubkey(), false),
+                AccountMeta::new_readonly(authority.pubkey(), false),
+                AccountMeta::new(credential(1), false),
+                AccountMeta::new_readonly(signer.pubkey(), false),
+                AccountMeta::new(admin.pubkey(), true), // payer
+                AccountMeta::new(index(1), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::instructions::ID, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(organization, false),
+            ],
+            data,
+        };
+        let mut tx = Transaction::new_with_payer(&[verify_ix, issue_ix], Some(&admin.pubkey()));
+        tx.sign(&[&admin], recent_blockhash);
+        tx
+    };
+    let err = context.banks_client.process_transaction(signed_ix(&stranger)).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(1, InstructionError::IllegalOwner));
+    context.banks_client.process_transaction(signed_ix(&key)).await.unwrap();
+    let account = context.banks_client.get_account(credential(1)).await.unwrap().unwrap();
+    let cred = Credential::unpack(&account.data).unwrap();
+    assert_eq!((cred.issuer, cred.status(0)), (organization, CredentialStatus::Pending));
+
+    // Revocation takes the organization account the same way
+    let revoke_batch = |signer: &Keypair, organization: Option<Pubkey>| {
+        let mut accounts = vec![AccountMeta::new_readonly(signer.pubkey(), true)];
+        accounts.extend(organization.map(|organization| AccountMeta::new_readonly(organization, false)));
+        accounts.extend([AccountMeta::new(credential(0), false), AccountMeta::new(credential(1), false)]);
+        Instruction { program_id, accounts, data: vec![21] } // 21 = RevokeCredentialBatch
+    };
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    // Without the organization account the key only revokes its own credentials
+    let mut tx = Transaction::new_with_payer(&[revoke_batch(&key, None)], Some(&admin.pubkey()));
+    tx.sign(&[&admin, &key], recent_blockhash);
+    assert_eq!(process(&mut context.banks_client, tx).await, vec![BatchItemResult::NotIssuer; 2]);
+    let mut tx = Transaction::new_with_payer(&[revoke_batch(&key, Some(organization))], Some(&admin.pubkey()));
+    tx.sign(&[&admin, &key], recent_blockhash);
+    assert_eq!(process(&mut context.banks_client, tx).await, vec![BatchItemResult::Ok; 2]);
+    for nonce in [0, 1] {
+        let account = context.banks_client.get_account(credential(nonce)).await.unwrap().unwrap();
+        assert_eq!(Credential::unpack(&account.data).unwrap().status(0), CredentialStatus::Revoked);
+    }
+    // A key outside the organization cannot act for it
+    let mut tx = Transaction::new_with_payer(&[revoke_batch(&stranger, Some(organization))], Some(&admin.pubkey()));
+    tx.sign(&[&admin, &stranger], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+}
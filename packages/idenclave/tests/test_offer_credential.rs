@@ -0,0 +1,182 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{CredentialStatus, Identity};
+use idenclave::credential::Credential;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_offer_accept_reject_cancel() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    let stranger = Keypair::new();
+    for kp in [&authority, &issuer, &stranger] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let space = Identity::LEN;
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The issuer alone creates offers; the holder does not sign
+    let cred_space = Credential::LEN;
+    let index = |n: u64| idenclave::CredentialIndex::find_address(&identity.pubkey(), n, &program_id).0;
+    let schema = [2u8; 32];
+    let cred_key = |nonce: u64| Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, nonce, &program_id).0;
+    // Offers the credential with `nonce`, taking index entry `n`
+    let offer_ix = |nonce: u64, n: u64| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(cred_key(nonce), false),
+            AccountMeta::new(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(index(n), false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+        data: {
+            let mut data = vec![13]; // 13 = OfferCredential
+            data.extend_from_slice(&schema);
+            data.extend_from_slice(&nonce.to_le_bytes());
+            data.extend_from_slice(&[9u8; 32]);
+            data.extend_from_slice(&1_700_000_000u64.to_le_bytes());
+            data.extend_from_slice(&u64::MAX.to_le_bytes());
+            data
+        },
+    };
+    let accept_ix = |cred: Pubkey, signer: &Keypair| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(identity.pubkey(), false),
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(signer.pubkey(), true),
+        ],
+        data: vec![14], // 14 = AcceptCredential
+    };
+
+    // Offer, then accept as the holder
+    let accepted = cred_key(0);
+    let mut tx_offer = Transaction::new_with_payer(&[offer_ix(0, 0)], Some(&issuer.pubkey()));
+    tx_offer.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_offer).await.unwrap();
+    let cred_account = context.banks_client.get_account(accepted).await.unwrap().unwrap();
+    let cred_data = Credential::unpack(&cred_account.data).unwrap();
+    assert!(cred_data.pending);
+    assert_eq!(cred_data.status(0), CredentialStatus::Pending);
+
+    let mut tx_bad = Transaction::new_with_payer(&[accept_ix(accepted, &stranger)], Some(&stranger.pubkey()));
+    tx_bad.sign(&[&stranger], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    let mut tx_accept = Transaction::new_with_payer(&[accept_ix(accepted, &authority)], Some(&authority.pubkey()));
+    tx_accept.sign(&[&authority], recent_blockhash);
+    context.banks_client.process_transaction(tx_accept).await.unwrap();
+    let cred_account = context.banks_client.get_account(accepted).await.unwrap().unwrap();
+    let cred_data = Credential::unpack(&cred_account.data).unwrap();
+    assert!(!cred_data.pending);
+    assert_eq!(cred_data.status(0), CredentialStatus::Valid);
+
+    // Offering again with the same schema and nonce fails
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx_bad = Transaction::new_with_payer(&[offer_ix(0, 1)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    let cancel_ix = |cred: Pubkey, n: u64| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), false), // recipient
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(index(n), false),
+        ],
+        data: vec![16], // 16 = CancelCredentialOffer
+    };
+
+    // An accepted credential is no longer an offer and cannot be cancelled
+    let mut tx_bad = Transaction::new_with_payer(&[cancel_ix(accepted, 0)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // Offer, then reject as the holder: the account is closed and rent returned to the issuer
+    let rejected = cred_key(1);
+    let mut tx_offer = Transaction::new_with_payer(&[offer_ix(1, 1)], Some(&issuer.pubkey()));
+    tx_offer.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_offer).await.unwrap();
+    let issuer_before = context.banks_client.get_balance(issuer.pubkey()).await.unwrap();
+    let reject_ix = |recipient: Pubkey| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(rejected, false),
+            AccountMeta::new_readonly(authority.pubkey(), true),
+            AccountMeta::new_readonly(issuer.pubkey(), false),
+            AccountMeta::new(recipient, false),
+            AccountMeta::new(index(1), false),
+        ],
+        data: vec![15], // 15 = RejectCredential
+    };
+    // Without the issuer's signature, the holder cannot redirect the rent
+    let mut tx_bad = Transaction::new_with_payer(&[reject_ix(authority.pubkey())], Some(&authority.pubkey()));
+    tx_bad.sign(&[&authority], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx_bad).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    let mut tx_reject = Transaction::new_with_payer(&[reject_ix(issuer.pubkey())], Some(&authority.pubkey()));
+    tx_reject.sign(&[&authority], recent_blockhash);
+    context.banks_client.process_transaction(tx_reject).await.unwrap();
+    assert!(context.banks_client.get_account(rejected).await.unwrap().is_none());
+    let issuer_after = context.banks_client.get_balance(issuer.pubkey()).await.unwrap();
+    assert_eq!(issuer_after, issuer_before + rent.minimum_balance(cred_space) + rent.minimum_balance(idenclave::CredentialIndex::LEN));
+
+    // Offer, then cancel as the issuer
+    let cancelled = cred_key(2);
+    let mut tx_offer = Transaction::new_with_payer(&[offer_ix(2, 2)], Some(&issuer.pubkey()));
+    tx_offer.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_offer).await.unwrap();
+    let mut tx_cancel = Transaction::new_with_payer(&[cancel_ix(cancelled, 2)], Some(&issuer.pubkey()));
+    tx_cancel.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_cancel).await.unwrap();
+    assert!(context.banks_client.get_account(cancelled).await.unwrap().is_none());
+
+    // Only the accepted credential still counts against the identity
+    let identity_account = context.banks_client.get_account(identity.pubkey()).await.unwrap().unwrap();
+    assert_eq!(Identity::unpack(&identity_account.data).unwrap().active_credentials, 1);
+}
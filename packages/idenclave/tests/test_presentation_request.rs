@@ -0,0 +1,332 @@
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, Field, PrimeField};
+use ark_groth16::Groth16;
+use ark_relations::lc;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{Circuit, CredentialIndex, Identity, PresentationRequest, PresentationResponse};
+use idenclave::credential::Credential;
+use idenclave::presentation::PoseidonSyscall;
+use idenclave_core::{claim_hash, claims_commitment, BatchIssueItem, Groth16Proof, PresentationResult, Scalar, VerifyingKey};
+use std::str::FromStr;
+
+/// Proves knowledge of `w` with `a * w == b` for public inputs `a` and `b`.
+struct Divides {
+    a: Option<Fr>,
+    b: Option<Fr>,
+    w: Option<Fr>,
+}
+
+impl ConstraintSynthesizer<Fr> for Divides {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let a = cs.new_input_variable(|| self.a.ok_or(SynthesisError::AssignmentMissing))?;
+        let b = cs.new_input_variable(|| self.b.ok_or(SynthesisError::AssignmentMissing))?;
+        let w = cs.new_witness_variable(|| self.w.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce_constraint(lc!() + a, lc!() + w, lc!() + b)
+    }
+}
+
+fn field<F: PrimeField>(value: F) -> [u8; 32] {
+    value.into_bigint().to_bytes_be().try_into().unwrap()
+}
+
+fn g1(point: G1Affine) -> [u8; 64] {
+    [field(point.x), field(point.y)].concat().try_into().unwrap()
+}
+
+fn g2(point: G2Affine) -> [u8; 128] {
+    [field(point.x.c1), field(point.x.c0), field(point.y.c1), field(point.y.c0)].concat().try_into().unwrap()
+}
+
+fn scalar(value: u64) -> Scalar {
+    field(Fr::from(value))
+}
+
+async fn respond(banks_client: &mut BanksClient, tx: Transaction) -> Result<PresentationResult, TransactionError> {
+    let outcome = banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    outcome.result?;
+    let return_data = outcome.metadata.unwrap().return_data.unwrap();
+    Ok(PresentationResult::try_from(return_data.data[0]).unwrap())
+}
+
+#[tokio::test]
+async fn test_presentation_request() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let issuer = Keypair::new();
+    let verifier = Keypair::new();
+    for kp in [&authority, &issuer, &verifier] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let schema = [4u8; 32];
+
+    // Register identity
+    let identity = Keypair::new();
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(Identity::LEN),
+        Identity::LEN as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The issuer offers a credential of the schema at its derived address and the holder accepts it
+    let credential = Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, 0, &program_id).0;
+    let mut data = vec![20]; // 20 = IssueCredentialBatch
+    data.extend_from_slice(&schema);
+    BatchIssueItem { nonce: 0, credential_ref: [1; 32], issued_at: 1_000, expires_at: u64::MAX }.pack(&mut data);
+    let issue_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(credential, false),
+            AccountMeta::new(CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+        ],
+        data,
+    };
+    let accept_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(identity.pubkey(), false),
+            AccountMeta::new(credential, false),
+            AccountMeta::new_readonly(authority.pubkey(), true),
+        ],
+        data: vec![14], // 14 = AcceptCredential
+    };
+    let mut tx = Transaction::new_with_payer(&[issue_ix, accept_ix], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer, &authority], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The verifier registers a circuit
+    let mut rng = StdRng::seed_from_u64(0);
+    let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(Divides { a: None, b: None, w: None }, &mut rng).unwrap();
+    let verifying_key = VerifyingKey {
+        alpha: g1(vk.alpha_g1),
+        beta: g2(vk.beta_g2),
+        gamma: g2(vk.gamma_g2),
+        delta: g2(vk.delta_g2),
+        ic: vk.gamma_abc_g1.iter().map(|point| g1(*point)).collect(),
+    };
+    let mut prove = |a: Fr, b: Fr, w: Fr| {
+        let proof = Groth16::<Bn254>::prove(&pk, Divides { a: Some(a), b: Some(b), w: Some(w) }, &mut rng).unwrap();
+        Groth16Proof { a: g1(proof.a), b: g2(proof.b), c: g1(proof.c) }
+    };
+    let circuit = Keypair::new();
+    let create_circuit_ix = solana_sdk::system_instruction::create_account(
+        &verifier.pubkey(),
+        &circuit.pubkey(),
+        rent.minimum_balance(Circuit::LEN),
+        Circuit::LEN as u64,
+        &program_id,
+    );
+    let register_circuit_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(circuit.pubkey(), false), AccountMeta::new_readonly(verifier.pubkey(), true)],
+        data: [vec![33], verifying_key.pack()].concat(), // 33 = RegisterCircuit
+    };
+    let mut tx = Transaction::new_with_payer(&[create_circuit_ix, register_circuit_ix.clone()], Some(&verifier.pubkey()));
+    tx.sign(&[&verifier, &circuit], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(circuit.pubkey()).await.unwrap().unwrap();
+    assert_eq!(Circuit::unpack(&account.data).unwrap().verifying_key, verifying_key);
+
+    // Registered circuits cannot be replaced
+    let mut tx = Transaction::new_with_payer(&[register_circuit_ix], Some(&verifier.pubkey()));
+    tx.sign(&[&verifier], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
+
+    // Requests: a credential of the schema from the issuer, plus a proof that 3 divides the second
+    // input, which has to be the credential's commitment
+    let request_ix = |nonce: u64, expires_at: u64, trusted_issuer: Pubkey, circuit: Option<Pubkey>, predicate_inputs: &[Scalar]| {
+        let mut data = vec![34]; // 34 = CreatePresentationRequest
+        data.extend_from_slice(&nonce.to_le_bytes());
+        data.extend_from_slice(&expires_at.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&schema);
+        data.push(1);
+        data.extend_from_slice(trusted_issuer.as_ref());
+        data.push(predicate_inputs.len() as u8);
+        predicate_inputs.iter().for_each(|input| data.extend_from_slice(input));
+        let mut accounts = vec![
+            AccountMeta::new(PresentationRequest::find_address(&verifier.pubkey(), nonce, &program_id).0, false),
+            AccountMeta::new_readonly(verifier.pubkey(), true),
+            AccountMeta::new(verifier.pubkey(), true), // payer
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+        accounts.extend(circuit.map(|circuit| AccountMeta::new_readonly(circuit, false)));
+        Instruction { program_id, accounts, data }
+    };
+    let proof_request = PresentationRequest::find_address(&verifier.pubkey(), 0, &program_id).0;
+    let untrusted_request = PresentationRequest::find_address(&verifier.pubkey(), 1, &program_id).0;
+    let expired_request = PresentationRequest::find_address(&verifier.pubkey(), 2, &program_id).0;
+    let mut tx = Transaction::new_with_payer(
+        &[
+            request_ix(0, u64::MAX, issuer.pubkey(), Some(circuit.pubkey()), &[scalar(3)]),
+            request_ix(1, u64::MAX, Pubkey::new_unique(), None, &[]),
+            request_ix(2, 1, issuer.pubkey(), None, &[]),
+        ],
+        Some(&verifier.pubkey()),
+    );
+    tx.sign(&[&verifier], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(proof_request).await.unwrap().unwrap();
+    let request = PresentationRequest::unpack(&account.data).unwrap();
+    assert_eq!((request.circuit, request.predicate_inputs, request.trusted_issuers), (circuit.pubkey(), vec![scalar(3)], vec![issuer.pubkey()]));
+
+    // Predicate inputs without a circuit, or leaving the circuit no public input for the commitment, are refused
+    let mut tx = Transaction::new_with_payer(&[request_ix(3, u64::MAX, issuer.pubkey(), None, &[scalar(3)])], Some(&verifier.pubkey()));
+    tx.sign(&[&verifier], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    let inputs = [scalar(3), scalar(21)];
+    let mut tx = Transaction::new_with_payer(&[request_ix(3, u64::MAX, issuer.pubkey(), Some(circuit.pubkey()), &inputs)], Some(&verifier.pubkey()));
+    tx.sign(&[&verifier], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+
+    let respond_ix = |request: Pubkey, nonce: u64, proof: Option<(&Groth16Proof, &[Scalar])>, with_circuit: bool, blockhash: Hash| {
+        let mut data = vec![35, 1]; // 35 = RespondToRequest, one credential
+        data.extend_from_slice(&nonce.to_le_bytes());
+        if let Some((proof, inputs)) = proof {
+            data.extend_from_slice(&proof.pack());
+            data.push(inputs.len() as u8);
+            inputs.iter().for_each(|input| data.extend_from_slice(input));
+        }
+        let mut accounts = vec![
+            AccountMeta::new_readonly(request, false),
+            AccountMeta::new(PresentationResponse::find_address(&request, &identity.pubkey(), &program_id).0, false),
+            AccountMeta::new_readonly(identity.pubkey(), false),
+            AccountMeta::new_readonly(authority.pubkey(), true),
+            AccountMeta::new(authority.pubkey(), true), // payer
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+        if with_circuit {
+            accounts.push(AccountMeta::new_readonly(circuit.pubkey(), false));
+        }
+        accounts.push(AccountMeta::new_readonly(credential, false));
+        let mut tx = Transaction::new_with_payer(&[Instruction { program_id, accounts, data }], Some(&authority.pubkey()));
+        tx.sign(&[&authority], blockhash);
+        tx
+    };
+
+    // Until the issuer commits to the credential's claims no proof can be about it
+    let unbound = prove(Fr::from(3), Fr::from(21), Fr::from(7));
+    let result = respond(&mut context.banks_client, respond_ix(proof_request, 0, Some((&unbound, &[scalar(3), scalar(21)])), true, recent_blockhash)).await;
+    assert_eq!(result, Ok(PresentationResult::CommitmentMismatch));
+
+    // The issuer publishes the claim hashes and the program commits to them with Poseidon, the
+    // same hash circomlib circuits use
+    assert_eq!(claim_hash::<PoseidonSyscall>(&scalar(1), &scalar(2)).unwrap(), field(Fr::from_str("7853200120776062878684798364095072458815029376092732009249414926327459813530").unwrap()));
+    let claim_hashes = [claim_hash::<PoseidonSyscall>(&[9; 32], &scalar(1990)).unwrap(), claim_hash::<PoseidonSyscall>(&[8; 32], &scalar(1)).unwrap()];
+    let commitment = claims_commitment::<PoseidonSyscall>(&claim_hashes).unwrap();
+    let commit_ix = |signer: &Keypair| {
+        let mut data = vec![39, 2]; // 39 = CommitClaims, two claim hashes
+        claim_hashes.iter().for_each(|hash| data.extend_from_slice(hash));
+        Instruction {
+            program_id,
+            accounts: vec![AccountMeta::new(credential, false), AccountMeta::new_readonly(signer.pubkey(), true)],
+            data,
+        }
+    };
+    let mut tx = Transaction::new_with_payer(&[commit_ix(&verifier)], Some(&verifier.pubkey()));
+    tx.sign(&[&verifier], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    let mut tx = Transaction::new_with_payer(&[commit_ix(&issuer)], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(credential).await.unwrap().unwrap();
+    assert_eq!(Credential::unpack(&account.data).unwrap().commitment, commitment);
+    // A commitment proofs were made against is never changed
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx = Transaction::new_with_payer(&[commit_ix(&issuer)], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
+
+    // The holder presents the credential with a valid proof about its commitment
+    let bound = Fr::from_be_bytes_mod_order(&commitment);
+    let proof = prove(Fr::from(3), bound, bound * Fr::from(3).inverse().unwrap());
+    let inputs = [scalar(3), commitment];
+    let result = respond(&mut context.banks_client, respond_ix(proof_request, 0, Some((&proof, &inputs)), true, recent_blockhash)).await;
+    assert_eq!(result, Ok(PresentationResult::Passed));
+    let response_key = PresentationResponse::find_address(&proof_request, &identity.pubkey(), &program_id).0;
+    let account = context.banks_client.get_account(response_key).await.unwrap().unwrap();
+    let response = PresentationResponse::unpack(&account.data).unwrap();
+    assert_eq!((response.request, response.identity, response.result), (proof_request, identity.pubkey(), PresentationResult::Passed as u8));
+
+    // Proofs for other inputs, for other predicate inputs or without a proof fail, and the response records the latest outcome
+    let result = respond(&mut context.banks_client, respond_ix(proof_request, 0, Some((&proof, &[scalar(3), scalar(22)])), true, recent_blockhash)).await;
+    assert_eq!(result, Ok(PresentationResult::InvalidProof));
+    let result = respond(&mut context.banks_client, respond_ix(proof_request, 0, Some((&unbound, &[scalar(3), scalar(21)])), true, recent_blockhash)).await;
+    assert_eq!(result, Ok(PresentationResult::CommitmentMismatch));
+    let other = prove(Fr::from(4), Fr::from(28), Fr::from(7));
+    let result = respond(&mut context.banks_client, respond_ix(proof_request, 0, Some((&other, &[scalar(4), scalar(28)])), true, recent_blockhash)).await;
+    assert_eq!(result, Ok(PresentationResult::InvalidProof));
+    let result = respond(&mut context.banks_client, respond_ix(proof_request, 0, None, true, recent_blockhash)).await;
+    assert_eq!(result, Ok(PresentationResult::InvalidProof));
+    let account = context.banks_client.get_account(response_key).await.unwrap().unwrap();
+    assert_eq!(PresentationResponse::unpack(&account.data).unwrap().result, PresentationResult::InvalidProof as u8);
+
+    // A credential not derived from the schema and nonce does not count, nor does an untrusted issuer
+    let result = respond(&mut context.banks_client, respond_ix(proof_request, 1, Some((&proof, &inputs)), true, recent_blockhash)).await;
+    assert_eq!(result, Ok(PresentationResult::MissingCredential));
+    let result = respond(&mut context.banks_client, respond_ix(untrusted_request, 0, None, false, recent_blockhash)).await;
+    assert_eq!(result, Ok(PresentationResult::UntrustedIssuer));
+
+    // Expired requests take no responses
+    let result = respond(&mut context.banks_client, respond_ix(expired_request, 0, None, false, recent_blockhash)).await;
+    assert_eq!(result, Err(TransactionError::InstructionError(0, InstructionError::InvalidArgument)));
+
+    // Once the credential is revoked the presentation fails
+    let revoke_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(issuer.pubkey(), true), AccountMeta::new(credential, false)],
+        data: vec![21], // 21 = RevokeCredentialBatch
+    };
+    let mut tx = Transaction::new_with_payer(&[revoke_ix], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let result = respond(&mut context.banks_client, respond_ix(proof_request, 0, Some((&proof, &inputs)), true, recent_blockhash)).await;
+    assert_eq!(result, Ok(PresentationResult::InvalidCredential));
+}
@@ -34,6 +34,7 @@ async fn test_recover_authority() {
         accounts: vec![
             AccountMeta::new(identity.pubkey(), false),
             AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
         ],
         data: vec![0],
     };
@@ -67,24 +68,12 @@ async fn test_recover_authority() {
         ],
         data: recover_ix_data.clone(),
     };
-    let mut tx_recover = Transaction::new_with_payer(&[recover_ix], Some(&recovery.pubkey()));
-    tx_recover.sign(&[&recovery], recent_blockhash);
+    // The recovery key need not hold lamports; the old authority's wallet pays the fee here
+    let mut tx_recover = Transaction::new_with_payer(&[recover_ix], Some(&authority.pubkey()));
+    tx_recover.sign(&[&authority, &recovery], recent_blockhash);
     banks_client.process_transaction(tx_recover).await.unwrap();
     // Verify authority is now new_authority
-    let identity_account_result = banks_client.get_account(identity.pubkey()).await;
-    match identity_account_result {
-        Ok(Some(identity_account)) => {
-            let identity_data = Identity::unpack(&identity_account.data).unwrap();
-            assert_eq!(identity_data.authority, new_authority.pubkey());
-        },
-        Err(e) => {
-            if format!("{:?}", e).contains("DeadlineExceeded") {
-                println!("[Test] Fetch after recover failed with DeadlineExceeded (known solana-program-test limitation), skipping assertion");
-            } else {
-                panic!("[Test] Unexpected error fetching account after recover: {:?}", e);
-            }
-        },
-        Ok(None) => panic!("[Test] Account not found after recover"),
-    }
-    // NOTE: DeadlineExceeded is a known solana-program-test limitation after heavy mutations.
+    let identity_account = banks_client.get_account(identity.pubkey()).await.unwrap().unwrap();
+    let identity_data = Identity::unpack(&identity_account.data).unwrap();
+    assert_eq!(identity_data.authority, new_authority.pubkey());
 }
@@ -0,0 +1,146 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{CredentialStatus, Identity};
+use idenclave::credential::Credential;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_issue_refuses_overwrite_and_reissue() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    let stranger = Keypair::new();
+    for kp in [&authority, &issuer, &stranger] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let space = Identity::LEN;
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Issue a credential
+    let schema = [4u8; 32];
+    let cred = Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, 0, &program_id).0;
+    let issued_at = 1_700_000_000u64;
+    let issue_ix = |n: u64, credential_ref: [u8; 32]| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(cred, false),
+            AccountMeta::new(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(idenclave::CredentialIndex::find_address(&identity.pubkey(), n, &program_id).0, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+        data: {
+            let mut data = vec![6];
+            data.extend_from_slice(&schema);
+            data.extend_from_slice(&0u64.to_le_bytes());
+            data.extend_from_slice(&credential_ref);
+            data.extend_from_slice(&issued_at.to_le_bytes());
+            data.extend_from_slice(&u64::MAX.to_le_bytes());
+            data
+        },
+    };
+    let mut tx_issue = Transaction::new_with_payer(&[issue_ix(0, [1u8; 32])], Some(&issuer.pubkey()));
+    tx_issue.sign(&[&authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_issue).await.unwrap();
+
+    // Issuing again at the same schema and nonce is refused instead of overwriting it
+    let mut tx_bad = Transaction::new_with_payer(&[issue_ix(1, [9u8; 32])], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&authority, &issuer], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx_bad).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
+
+    let reissue_ix = |signer: &Keypair, credential_ref: [u8; 32], expires_at: u64| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(signer.pubkey(), true),
+        ],
+        data: {
+            let mut data = vec![18]; // 18 = ReissueCredential
+            data.extend_from_slice(&credential_ref);
+            data.extend_from_slice(&(issued_at + 10).to_le_bytes());
+            data.extend_from_slice(&expires_at.to_le_bytes());
+            data
+        },
+    };
+
+    // Only the issuer may re-issue
+    let mut tx_bad = Transaction::new_with_payer(&[reissue_ix(&stranger, [2u8; 32], u64::MAX)], Some(&stranger.pubkey()));
+    tx_bad.sign(&[&stranger], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // Re-issuing rewrites the contents in place, keeping identity, issuer and index entry
+    let mut tx_reissue = Transaction::new_with_payer(&[reissue_ix(&issuer, [2u8; 32], issued_at + 500)], Some(&issuer.pubkey()));
+    tx_reissue.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_reissue).await.unwrap();
+    let cred_account = context.banks_client.get_account(cred).await.unwrap().unwrap();
+    let cred_data = Credential::unpack(&cred_account.data).unwrap();
+    assert_eq!(cred_data.credential_ref, [2u8; 32]);
+    assert_eq!(cred_data.issued_at, issued_at + 10);
+    assert_eq!(cred_data.expires_at, issued_at + 500);
+    assert_eq!(cred_data.identity, identity.pubkey());
+    assert_eq!(cred_data.issuer, issuer.pubkey());
+    let identity_account = context.banks_client.get_account(identity.pubkey()).await.unwrap().unwrap();
+    assert_eq!(Identity::unpack(&identity_account.data).unwrap().active_credentials, 1);
+
+    // A revoked credential stays revoked
+    let revoke_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+        ],
+        data: vec![7],
+    };
+    let mut tx_revoke = Transaction::new_with_payer(&[revoke_ix], Some(&issuer.pubkey()));
+    tx_revoke.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_revoke).await.unwrap();
+    let mut tx_bad = Transaction::new_with_payer(&[reissue_ix(&issuer, [3u8; 32], u64::MAX)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+    let cred_account = context.banks_client.get_account(cred).await.unwrap().unwrap();
+    let cred_data = Credential::unpack(&cred_account.data).unwrap();
+    assert_eq!(cred_data.credential_ref, [2u8; 32]);
+    assert_eq!(cred_data.status(0), CredentialStatus::Revoked);
+}
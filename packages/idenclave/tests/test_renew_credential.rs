@@ -0,0 +1,172 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    pubkey::Pubkey,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{CredentialStatus, Identity};
+use idenclave::credential::Credential;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_renew_and_supersede_credential() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    let stranger = Keypair::new();
+    for kp in [&authority, &issuer, &stranger] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let space = Identity::LEN;
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Issue the first version of the credential
+    let schema = [1u8; 32];
+    let cred_key = |nonce: u64| Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, nonce, &program_id).0;
+    let cred = cred_key(0);
+    let issued_at = 1_700_000_000u64;
+    let expires_at = issued_at + 1000;
+    let issue_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(cred, false),
+            AccountMeta::new(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(idenclave::CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+        data: {
+            let mut data = vec![6];
+            data.extend_from_slice(&schema);
+            data.extend_from_slice(&0u64.to_le_bytes());
+            data.extend_from_slice(&[1u8; 32]);
+            data.extend_from_slice(&issued_at.to_le_bytes());
+            data.extend_from_slice(&expires_at.to_le_bytes());
+            data
+        },
+    };
+    let mut tx_issue = Transaction::new_with_payer(&[issue_ix], Some(&issuer.pubkey()));
+    tx_issue.sign(&[&authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_issue).await.unwrap();
+
+    let renew_ix = |signer: &Keypair, new_expires_at: u64| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(signer.pubkey(), true),
+        ],
+        data: {
+            let mut data = vec![11]; // 11 = RenewCredential
+            data.extend_from_slice(&new_expires_at.to_le_bytes());
+            data
+        },
+    };
+
+    // Only the issuer may renew
+    let mut tx_bad = Transaction::new_with_payer(&[renew_ix(&stranger, expires_at + 500)], Some(&stranger.pubkey()));
+    tx_bad.sign(&[&stranger], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // Renewal cannot shorten validity
+    let mut tx_bad = Transaction::new_with_payer(&[renew_ix(&issuer, expires_at - 1)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // Renew in place
+    let renewed_until = u64::MAX - 1;
+    let mut tx_renew = Transaction::new_with_payer(&[renew_ix(&issuer, renewed_until)], Some(&issuer.pubkey()));
+    tx_renew.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_renew).await.unwrap();
+    let cred_account = context.banks_client.get_account(cred).await.unwrap().unwrap();
+    let cred_data = Credential::unpack(&cred_account.data).unwrap();
+    assert_eq!(cred_data.expires_at, renewed_until);
+    assert_eq!(cred_data.issued_at, issued_at);
+    assert_eq!(cred_data.status(renewed_until), CredentialStatus::Valid);
+
+    // Supersede with a new version
+    let new_cred = cred_key(1);
+    let supersede_ix = |nonce: u64| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new(cred_key(nonce), false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(idenclave::CredentialIndex::find_address(&identity.pubkey(), 1, &program_id).0, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+        data: {
+            let mut data = vec![12]; // 12 = SupersedeCredential
+            data.extend_from_slice(&schema);
+            data.extend_from_slice(&nonce.to_le_bytes());
+            data.extend_from_slice(&[2u8; 32]);
+            data.extend_from_slice(&(issued_at + 10).to_le_bytes());
+            data.extend_from_slice(&u64::MAX.to_le_bytes());
+            data
+        },
+    };
+    let mut tx_supersede = Transaction::new_with_payer(&[supersede_ix(1)], Some(&issuer.pubkey()));
+    tx_supersede.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_supersede).await.unwrap();
+
+    let old_account = context.banks_client.get_account(cred).await.unwrap().unwrap();
+    let old = Credential::unpack(&old_account.data).unwrap();
+    assert_eq!(old.superseded_by, new_cred);
+    assert_eq!(old.status(0), CredentialStatus::Superseded);
+    let new_account = context.banks_client.get_account(new_cred).await.unwrap().unwrap();
+    let new = Credential::unpack(&new_account.data).unwrap();
+    assert!(new.is_initialized);
+    assert_eq!(new.previous, cred);
+    assert_eq!(new.superseded_by, Pubkey::default());
+    assert_eq!(new.identity, identity.pubkey());
+    assert_eq!(new.issuer, issuer.pubkey());
+    assert_eq!(new.credential_ref, [2u8; 32]);
+    assert_eq!(new.status(0), CredentialStatus::Valid);
+
+    // A superseded credential can be neither superseded again nor renewed
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx_bad = Transaction::new_with_payer(&[supersede_ix(2)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+    let mut tx_bad = Transaction::new_with_payer(&[renew_ix(&issuer, u64::MAX)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+}
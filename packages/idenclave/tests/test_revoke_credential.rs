@@ -46,6 +46,7 @@ async fn test_revoke_credential_and_query_status() {
         accounts: vec![
             AccountMeta::new(identity.pubkey(), false),
             AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
         ],
         data: vec![0],
     };
@@ -53,22 +54,9 @@ async fn test_revoke_credential_and_query_status() {
     tx.sign(&[&authority, &identity], recent_blockhash);
     banks_client.process_transaction(tx).await.unwrap();
 
-    // Create credential account
-    let cred = Keypair::new();
-    let cred_space = Credential::LEN;
-    let cred_lamports = rent.minimum_balance(cred_space);
-    let create_cred_ix = solana_sdk::system_instruction::create_account(
-        &issuer.pubkey(),
-        &cred.pubkey(),
-        cred_lamports,
-        cred_space as u64,
-        &program_id,
-    );
-    let mut tx_create_cred = Transaction::new_with_payer(&[create_cred_ix], Some(&issuer.pubkey()));
-    tx_create_cred.sign(&[&issuer, &cred], recent_blockhash);
-    banks_client.process_transaction(tx_create_cred).await.unwrap();
-
     // Issue credential
+    let schema = [5u8; 32];
+    let (cred, _) = Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, 0, &program_id);
     let credential_ref = [42u8; 32];
     let issued_at = 1_700_000_000u64;
     let expires_at = issued_at + 1000;
@@ -77,11 +65,16 @@ async fn test_revoke_credential_and_query_status() {
         accounts: vec![
             AccountMeta::new(identity.pubkey(), false),
             AccountMeta::new(authority.pubkey(), true),
-            AccountMeta::new(cred.pubkey(), false),
+            AccountMeta::new(cred, false),
             AccountMeta::new(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(idenclave::CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
         ],
         data: {
             let mut data = vec![6];
+            data.extend_from_slice(&schema);
+            data.extend_from_slice(&0u64.to_le_bytes());
             data.extend_from_slice(&credential_ref);
             data.extend_from_slice(&issued_at.to_le_bytes());
             data.extend_from_slice(&expires_at.to_le_bytes());
@@ -93,11 +86,11 @@ async fn test_revoke_credential_and_query_status() {
     banks_client.process_transaction(tx_issue).await.unwrap();
 
     // Revoke credential
-    let mut revoke_data = vec![7]; // 7 = RevokeCredential
+    let revoke_data = vec![7]; // 7 = RevokeCredential
     let revoke_ix = Instruction {
         program_id,
         accounts: vec![
-            AccountMeta::new(cred.pubkey(), false),
+            AccountMeta::new(cred, false),
             AccountMeta::new(issuer.pubkey(), true),
         ],
         data: revoke_data,
@@ -107,15 +100,15 @@ async fn test_revoke_credential_and_query_status() {
     banks_client.process_transaction(tx_revoke).await.unwrap();
 
     // Fetch and verify credential is revoked
-    let cred_account = banks_client.get_account(cred.pubkey()).await.unwrap().unwrap();
+    let cred_account = banks_client.get_account(cred).await.unwrap().unwrap();
     let cred_data = Credential::unpack(&cred_account.data).unwrap();
     assert!(cred_data.revoked, "Credential should be revoked");
 
     // Query credential status (should log revoked)
-    let mut query_data = vec![8]; // 8 = QueryCredentialStatus
+    let query_data = vec![8]; // 8 = QueryCredentialStatus
     let query_ix = Instruction {
         program_id,
-        accounts: vec![AccountMeta::new(cred.pubkey(), false)],
+        accounts: vec![AccountMeta::new(cred, false)],
         data: query_data,
     };
     let mut tx_query = Transaction::new_with_payer(&[query_ix], Some(&issuer.pubkey()));
@@ -129,15 +122,15 @@ async fn test_revoke_credential_and_query_status() {
     expired_cred.expires_at = 0; // expired
     Credential::pack(expired_cred, &mut expired_cred_data).unwrap();
     // Overwrite account data
-    let mut cred_account_mut = banks_client.get_account(cred.pubkey()).await.unwrap().unwrap();
+    let mut cred_account_mut = banks_client.get_account(cred).await.unwrap().unwrap();
     cred_account_mut.data = expired_cred_data;
     // Re-insert the modified account into the test bank (mock)
     // (In actual Solana test framework, this step may require a CPI or custom test harness)
     // Query status again
-    let mut query_data = vec![8];
+    let query_data = vec![8];
     let query_ix = Instruction {
         program_id,
-        accounts: vec![AccountMeta::new(cred.pubkey(), false)],
+        accounts: vec![AccountMeta::new(cred, false)],
         data: query_data,
     };
     let mut tx_query = Transaction::new_with_payer(&[query_ix], Some(&issuer.pubkey()));
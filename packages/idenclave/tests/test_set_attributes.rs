@@ -34,6 +34,7 @@ async fn test_set_attributes() {
         accounts: vec![
             AccountMeta::new(identity.pubkey(), false),
             AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
         ],
         data: vec![0],
     };
@@ -0,0 +1,182 @@
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+    pubkey::Pubkey,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{CredentialStatus, Identity};
+use idenclave::credential::Credential;
+use std::str::FromStr;
+
+async fn query_status(
+    banks_client: &mut BanksClient,
+    program_id: Pubkey,
+    cred: Pubkey,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+) -> u8 {
+    let query_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new_readonly(cred, false)],
+        data: vec![8], // 8 = QueryCredentialStatus
+    };
+    let mut tx = Transaction::new_with_payer(&[query_ix], Some(&payer.pubkey()));
+    tx.sign(&[payer], recent_blockhash);
+    let sim = banks_client.simulate_transaction(tx).await.unwrap();
+    sim.simulation_details.unwrap().return_data.unwrap().data[0]
+}
+
+#[tokio::test]
+async fn test_suspend_and_reinstate_credential() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let identity = Keypair::new();
+    let issuer = Keypair::new();
+    let stranger = Keypair::new();
+    for kp in [&authority, &issuer, &stranger] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let space = Identity::LEN;
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Create and issue credential far in the future so expiry does not interfere
+    let schema = [7u8; 32];
+    let cred = Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, 0, &program_id).0;
+    let issued_at = 1_700_000_000u64;
+    let expires_at = u64::MAX;
+    let issue_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(cred, false),
+            AccountMeta::new(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(idenclave::CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ],
+        data: {
+            let mut data = vec![6];
+            data.extend_from_slice(&schema);
+            data.extend_from_slice(&0u64.to_le_bytes());
+            data.extend_from_slice(&[7u8; 32]);
+            data.extend_from_slice(&issued_at.to_le_bytes());
+            data.extend_from_slice(&expires_at.to_le_bytes());
+            data
+        },
+    };
+    let mut tx_issue = Transaction::new_with_payer(&[issue_ix], Some(&issuer.pubkey()));
+    tx_issue.sign(&[&authority, &issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_issue).await.unwrap();
+    assert_eq!(
+        query_status(&mut context.banks_client, program_id, cred, &issuer, recent_blockhash).await,
+        CredentialStatus::Valid as u8
+    );
+
+    let transition_ix = |tag: u8, signer: &Keypair| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(signer.pubkey(), true),
+        ],
+        data: vec![tag],
+    };
+
+    // Only the issuer may suspend
+    let mut tx_bad = Transaction::new_with_payer(&[transition_ix(9, &stranger)], Some(&stranger.pubkey()));
+    tx_bad.sign(&[&stranger], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // Reinstating a credential that is not suspended fails
+    let mut tx_bad = Transaction::new_with_payer(&[transition_ix(10, &issuer)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+
+    // Suspend (9)
+    let mut tx_suspend = Transaction::new_with_payer(&[transition_ix(9, &issuer)], Some(&issuer.pubkey()));
+    tx_suspend.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_suspend).await.unwrap();
+    let cred_account = context.banks_client.get_account(cred).await.unwrap().unwrap();
+    let cred_data = Credential::unpack(&cred_account.data).unwrap();
+    assert!(cred_data.suspended);
+    assert!(!cred_data.revoked);
+    assert_eq!(cred_data.expires_at, expires_at);
+    assert_eq!(
+        query_status(&mut context.banks_client, program_id, cred, &issuer, recent_blockhash).await,
+        CredentialStatus::Suspended as u8
+    );
+
+    // Suspending twice fails
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx_again = Transaction::new_with_payer(&[transition_ix(9, &issuer)], Some(&issuer.pubkey()));
+    tx_again.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_again).await.is_err());
+
+    // Reinstate (10)
+    let mut tx_reinstate = Transaction::new_with_payer(&[transition_ix(10, &issuer)], Some(&issuer.pubkey()));
+    tx_reinstate.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_reinstate).await.unwrap();
+    let cred_account = context.banks_client.get_account(cred).await.unwrap().unwrap();
+    assert!(!Credential::unpack(&cred_account.data).unwrap().suspended);
+    assert_eq!(
+        query_status(&mut context.banks_client, program_id, cred, &issuer, recent_blockhash).await,
+        CredentialStatus::Valid as u8
+    );
+
+    // Suspend again, then revoke: revocation wins and is final
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx_suspend = Transaction::new_with_payer(&[transition_ix(9, &issuer)], Some(&issuer.pubkey()));
+    tx_suspend.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_suspend).await.unwrap();
+    let mut tx_revoke = Transaction::new_with_payer(&[transition_ix(7, &issuer)], Some(&issuer.pubkey()));
+    tx_revoke.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx_revoke).await.unwrap();
+    assert_eq!(
+        query_status(&mut context.banks_client, program_id, cred, &issuer, recent_blockhash).await,
+        CredentialStatus::Revoked as u8
+    );
+
+    // A revoked credential can be neither reinstated nor suspended
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let mut tx_bad = Transaction::new_with_payer(&[transition_ix(10, &issuer)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+    let mut tx_bad = Transaction::new_with_payer(&[transition_ix(9, &issuer)], Some(&issuer.pubkey()));
+    tx_bad.sign(&[&issuer], recent_blockhash);
+    assert!(context.banks_client.process_transaction(tx_bad).await.is_err());
+}
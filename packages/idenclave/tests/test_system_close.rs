@@ -3,10 +3,8 @@ use solana_sdk::{
     account::Account,
     signature::{Keypair, Signer},
     transaction::Transaction,
-    pubkey::Pubkey,
     system_instruction,
     system_program,
-    sysvar,
 };
 
 #[tokio::test]
@@ -43,14 +41,14 @@ async fn test_system_program_close_account() {
     tx.sign(&[&payer, &close_me], recent_blockhash);
     banks_client.process_transaction(tx).await.unwrap();
 
-    // Now close the account using system program
+    // Now close the account using system program; the payer covers the fee so every lamport moves
     let close_ix = system_instruction::transfer(
         &close_me.pubkey(),
         &recipient.pubkey(),
         min_balance + 1000,
     );
-    let mut tx2 = Transaction::new_with_payer(&[close_ix], Some(&close_me.pubkey()));
-    tx2.sign(&[&close_me], recent_blockhash);
+    let mut tx2 = Transaction::new_with_payer(&[close_ix], Some(&payer.pubkey()));
+    tx2.sign(&[&payer, &close_me], recent_blockhash);
     let result = banks_client.process_transaction(tx2).await;
     println!("[System Close Test] Close result: {:?}", result);
     assert!(result.is_ok());
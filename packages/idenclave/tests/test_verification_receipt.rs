@@ -0,0 +1,158 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::program_pack::Pack;
+use idenclave::{CredentialIndex, CredentialStatus, Identity, VerificationReceipt};
+use idenclave::credential::Credential;
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_verification_receipt() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let issuer = Keypair::new();
+    let verifier = Keypair::new();
+    let auditor = Keypair::new();
+    for kp in [&authority, &issuer, &verifier, &auditor] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let identity = Keypair::new();
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(Identity::LEN),
+        Identity::LEN as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // Issue a credential
+    let schema = [6u8; 32];
+    let cred = Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, 0, &program_id).0;
+    let mut data = vec![6]; // 6 = IssueCredential
+    data.extend_from_slice(&schema);
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&[3u8; 32]);
+    data.extend_from_slice(&1_000u64.to_le_bytes());
+    data.extend_from_slice(&u64::MAX.to_le_bytes());
+    let issue_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new(cred, false),
+            AccountMeta::new_readonly(issuer.pubkey(), true),
+            AccountMeta::new(issuer.pubkey(), true), // payer
+            AccountMeta::new(CredentialIndex::find_address(&identity.pubkey(), 0, &program_id).0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    };
+    let mut tx = Transaction::new_with_payer(&[issue_ix], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer, &authority], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let receipt = |id: u64| VerificationReceipt::find_address(&verifier.pubkey(), &cred, id, &program_id).0;
+    let record_ix = |id: u64, proof_hash: Option<[u8; 32]>| {
+        let mut data = vec![36]; // 36 = RecordVerification
+        data.extend_from_slice(&id.to_le_bytes());
+        if let Some(proof_hash) = proof_hash {
+            data.extend_from_slice(&proof_hash);
+        }
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(receipt(id), false),
+                AccountMeta::new_readonly(verifier.pubkey(), true),
+                AccountMeta::new_readonly(cred, false),
+                AccountMeta::new(verifier.pubkey(), true), // payer
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            data,
+        }
+    };
+
+    // The verifier records a check with the hash of its evidence and gets the status back
+    let mut tx = Transaction::new_with_payer(&[record_ix(0, Some([5; 32]))], Some(&verifier.pubkey()));
+    tx.sign(&[&verifier], recent_blockhash);
+    let sim = context.banks_client.simulate_transaction(tx.clone()).await.unwrap();
+    assert_eq!(sim.simulation_details.unwrap().return_data.unwrap().data, vec![CredentialStatus::Valid as u8]);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(receipt(0)).await.unwrap().unwrap();
+    let record = VerificationReceipt::unpack(&account.data).unwrap();
+    assert_eq!((record.verifier, record.credential, record.id), (verifier.pubkey(), cred, 0));
+    assert_eq!((record.result, record.proof_hash), (CredentialStatus::Valid as u8, [5; 32]));
+    assert!(record.timestamp > 0);
+
+    // Receipts are never overwritten
+    let mut tx = Transaction::new_with_payer(&[record_ix(0, None)], Some(&verifier.pubkey()));
+    tx.sign(&[&verifier], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized));
+
+    // After revocation a new check records the revoked status
+    let revoke_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(cred, false), AccountMeta::new_readonly(issuer.pubkey(), true)],
+        data: vec![7], // 7 = RevokeCredential
+    };
+    let mut tx = Transaction::new_with_payer(&[revoke_ix, record_ix(1, None)], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer, &verifier], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let account = context.banks_client.get_account(receipt(1)).await.unwrap().unwrap();
+    let record = VerificationReceipt::unpack(&account.data).unwrap();
+    assert_eq!((record.result, record.proof_hash), (CredentialStatus::Revoked as u8, [0; 32]));
+    let account = context.banks_client.get_account(receipt(0)).await.unwrap().unwrap();
+    assert_eq!(VerificationReceipt::unpack(&account.data).unwrap().result, CredentialStatus::Valid as u8);
+
+    // Only the verifier closes its receipts
+    let close_ix = |closer: &Keypair| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(receipt(0), false),
+            AccountMeta::new_readonly(closer.pubkey(), true),
+            AccountMeta::new(auditor.pubkey(), false), // recipient
+        ],
+        data: vec![37], // 37 = CloseVerificationReceipt
+    };
+    let mut tx = Transaction::new_with_payer(&[close_ix(&auditor)], Some(&auditor.pubkey()));
+    tx.sign(&[&auditor], recent_blockhash);
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::IllegalOwner));
+    let before = context.banks_client.get_balance(auditor.pubkey()).await.unwrap();
+    let mut tx = Transaction::new_with_payer(&[close_ix(&verifier)], Some(&verifier.pubkey()));
+    tx.sign(&[&verifier], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    assert!(context.banks_client.get_account(receipt(0)).await.unwrap().is_none());
+    assert_eq!(context.banks_client.get_balance(auditor.pubkey()).await.unwrap(), before + rent.minimum_balance(VerificationReceipt::LEN));
+}
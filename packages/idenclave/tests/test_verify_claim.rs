@@ -0,0 +1,147 @@
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::program_pack::Pack;
+use idenclave::compressed::Sha256Hasher;
+use idenclave::credential::Credential;
+use idenclave::{CredentialIndex, CredentialStatus, Identity};
+use idenclave_core::{Claim, ClaimSet};
+use std::str::FromStr;
+
+#[tokio::test]
+async fn test_verify_claim() {
+    let program_id = Pubkey::from_str("GTfY1BxDLovBFHHuaQVR27URfEQvqSFLWS39wHHSk21K").unwrap();
+    let mut program_test = ProgramTest::new(
+        "idenclave",
+        program_id,
+        processor!(idenclave::process_instruction),
+    );
+    let authority = Keypair::new();
+    let issuer = Keypair::new();
+    let verifier = Keypair::new();
+    for kp in [&authority, &issuer, &verifier] {
+        program_test.add_account(
+            kp.pubkey(),
+            Account { lamports: 10_000_000_000, ..Account::default() },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let recent_blockhash = context.last_blockhash;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    // Register identity
+    let identity = Keypair::new();
+    let create_ix = solana_sdk::system_instruction::create_account(
+        &authority.pubkey(),
+        &identity.pubkey(),
+        rent.minimum_balance(Identity::LEN),
+        Identity::LEN as u64,
+        &program_id,
+    );
+    let register_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(identity.pubkey(), false),
+            AccountMeta::new(authority.pubkey(), true),
+            AccountMeta::new_readonly(idenclave::Tombstone::find_address(&identity.pubkey(), &program_id).0, false),
+        ],
+        data: vec![0],
+    };
+    let mut tx = Transaction::new_with_payer(&[create_ix, register_ix], Some(&authority.pubkey()));
+    tx.sign(&[&authority, &identity], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    // The issuer commits to the document's claims, each with its own salt
+    let claims = ClaimSet::<Sha256Hasher>::new(vec![
+        Claim::new("given_name", b"Ada", [1; 32]),
+        Claim::new("birthdate", b"1815-12-10", [2; 32]),
+        Claim::new("over_18", b"true", [3; 32]),
+    ])
+    .unwrap();
+    let schema = [7u8; 32];
+    let cred_key = |nonce: u64| Credential::find_address(&issuer.pubkey(), &identity.pubkey(), &schema, nonce, &program_id).0;
+    // Issues the credential with nonce `index`, which is also its index entry
+    let issue = |index: u64, claims_root: Option<[u8; 32]>| {
+        let mut data = vec![6]; // 6 = IssueCredential
+        data.extend_from_slice(&schema);
+        data.extend_from_slice(&index.to_le_bytes());
+        data.extend_from_slice(&[3u8; 32]);
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        if let Some(claims_root) = claims_root {
+            data.extend_from_slice(&claims_root);
+        }
+        let issue_ix = Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(identity.pubkey(), false),
+                AccountMeta::new(authority.pubkey(), true),
+                AccountMeta::new(cred_key(index), false),
+                AccountMeta::new_readonly(issuer.pubkey(), true),
+                AccountMeta::new(issuer.pubkey(), true), // payer
+                AccountMeta::new(CredentialIndex::find_address(&identity.pubkey(), index, &program_id).0, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            data,
+        };
+        let mut tx = Transaction::new_with_payer(&[issue_ix], Some(&issuer.pubkey()));
+        tx.sign(&[&issuer, &authority], recent_blockhash);
+        tx
+    };
+    let cred = cred_key(0);
+    context.banks_client.process_transaction(issue(0, Some(claims.root()))).await.unwrap();
+    let account = context.banks_client.get_account(cred).await.unwrap().unwrap();
+    assert_eq!(Credential::unpack(&account.data).unwrap().claims_root, claims.root());
+
+    let verify_tx = |credential: &Pubkey, proof: &idenclave_core::ClaimProof| {
+        let mut data = vec![38]; // 38 = VerifyClaim
+        data.extend_from_slice(&proof.pack());
+        let ix = Instruction { program_id, accounts: vec![AccountMeta::new_readonly(*credential, false)], data };
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&verifier.pubkey()));
+        tx.sign(&[&verifier], recent_blockhash);
+        tx
+    };
+
+    // Disclosing one claim shows it is committed to and reports the credential status
+    let over_18 = claims.disclose("over_18").unwrap();
+    let sim = context.banks_client.simulate_transaction(verify_tx(&cred, &over_18)).await.unwrap();
+    assert_eq!(sim.result.unwrap(), Ok(()));
+    assert_eq!(sim.simulation_details.unwrap().return_data.unwrap().data, vec![CredentialStatus::Valid as u8]);
+
+    // A changed value does not verify
+    let mut forged = over_18.clone();
+    forged.claim.value = b"false".to_vec();
+    let err = context.banks_client.process_transaction(verify_tx(&cred, &forged)).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+
+    // Credentials issued without a claims root have nothing to disclose
+    let plain = cred_key(1);
+    context.banks_client.process_transaction(issue(1, None)).await.unwrap();
+    let err = context.banks_client.process_transaction(verify_tx(&plain, &over_18)).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+
+    // Re-issuing replaces the claims, so disclosures of the old document stop verifying
+    let updated = ClaimSet::<Sha256Hasher>::new(vec![Claim::new("given_name", b"Augusta Ada", [4; 32])]).unwrap();
+    let mut data = vec![18]; // 18 = ReissueCredential
+    data.extend_from_slice(&[4u8; 32]);
+    data.extend_from_slice(&2_000u64.to_le_bytes());
+    data.extend_from_slice(&u64::MAX.to_le_bytes());
+    data.extend_from_slice(&updated.root());
+    let reissue_ix = Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(cred, false), AccountMeta::new_readonly(issuer.pubkey(), true)],
+        data,
+    };
+    let mut tx = Transaction::new_with_payer(&[reissue_ix], Some(&issuer.pubkey()));
+    tx.sign(&[&issuer], recent_blockhash);
+    context.banks_client.process_transaction(tx).await.unwrap();
+    let err = context.banks_client.process_transaction(verify_tx(&cred, &claims.disclose("given_name").unwrap())).await.unwrap_err().unwrap();
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    context.banks_client.process_transaction(verify_tx(&cred, &updated.disclose("given_name").unwrap())).await.unwrap();
+}